@@ -0,0 +1,15 @@
+-- Soft delete: rows are flagged with deleted_at and hard-deleted later by the purge job
+
+ALTER TABLE users ADD COLUMN deleted_at TIMESTAMPTZ NULL;
+
+-- Email uniqueness only applies among non-deleted users, so an address can be
+-- reused once its previous owner has been deleted
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_email_key;
+DROP INDEX IF EXISTS idx_users_email_lower;
+CREATE UNIQUE INDEX idx_users_email_lower ON users(LOWER(email)) WHERE deleted_at IS NULL;
+
+-- Partial index for active users (planned in 002)
+CREATE INDEX IF NOT EXISTS idx_users_active ON users(id) WHERE deleted_at IS NULL;
+
+-- Lets the purge job find expired rows without scanning live users
+CREATE INDEX IF NOT EXISTS idx_users_deleted_at ON users(deleted_at) WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,4 @@
+-- Optimistic concurrency: every stored change bumps version, and guarded
+-- writes only apply while the version is the one the writer read
+
+ALTER TABLE users ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
@@ -0,0 +1,17 @@
+-- Idempotency keys: the first response to a request is kept for a while so
+-- that retries with the same key replay it instead of running again.
+-- `response_status` stays NULL while the first request is running.
+
+CREATE TABLE idempotency_keys (
+    scope TEXT NOT NULL,
+    idempotency_key TEXT NOT NULL,
+    fingerprint CHAR(64) NOT NULL,
+    response_status SMALLINT,
+    response_headers TEXT,
+    response_body TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (scope, idempotency_key)
+);
+
+CREATE INDEX idx_idempotency_keys_expires_at ON idempotency_keys(expires_at);
@@ -0,0 +1,17 @@
+-- Audit log: one row per change to a user, written in the same transaction
+-- as the change. Rows outlive their user, so purged users keep their
+-- history; `changes` is a JSON array of {field, before, after}.
+
+CREATE TABLE audit_log (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL,
+    actor_id UUID,
+    action VARCHAR(16) NOT NULL,
+    changes TEXT NOT NULL,
+    request_id TEXT,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_audit_log_user_id ON audit_log(user_id, created_at);
+CREATE INDEX idx_audit_log_actor_id ON audit_log(actor_id, created_at);
+CREATE INDEX idx_audit_log_created_at ON audit_log(created_at);
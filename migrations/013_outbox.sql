@@ -0,0 +1,20 @@
+-- Transactional outbox: every user event is enqueued in the same transaction
+-- as the change it describes, then delivered by the outbox relay. `seq`
+-- orders the events of one user; `available_at` is when a row may next be
+-- claimed, pushed forward by claim leases and retry backoff.
+
+CREATE TABLE outbox (
+    seq BIGSERIAL PRIMARY KEY,
+    event_id UUID NOT NULL UNIQUE,
+    aggregate_id UUID NOT NULL,
+    event_type VARCHAR(32) NOT NULL,
+    payload TEXT NOT NULL,
+    occurred_at TIMESTAMPTZ NOT NULL,
+    available_at TIMESTAMPTZ NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    sent_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_outbox_pending ON outbox(aggregate_id, seq) WHERE sent_at IS NULL;
+CREATE INDEX idx_outbox_sent_at ON outbox(sent_at) WHERE sent_at IS NOT NULL;
@@ -0,0 +1,17 @@
+-- Create users table (SQLite)
+-- UUIDs are stored as 16-byte blobs and timestamps as RFC 3339 text,
+-- matching how sqlx encodes uuid::Uuid and chrono::DateTime<Utc> for SQLite
+
+CREATE TABLE users (
+    id BLOB PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    email TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+-- Create index on email for faster lookups
+CREATE INDEX idx_users_email ON users(email);
+
+-- Create index on created_at for sorting
+CREATE INDEX idx_users_created_at ON users(created_at);
@@ -0,0 +1,17 @@
+-- Performance optimization indexes (SQLite equivalent of the Postgres migration)
+
+-- Add index on name for searches (case-insensitive)
+CREATE INDEX IF NOT EXISTS idx_users_name_lower ON users(LOWER(name));
+
+-- Add composite index for pagination queries (created_at + id for stable ordering)
+CREATE INDEX IF NOT EXISTS idx_users_created_at_id ON users(created_at DESC, id);
+
+-- Add index on updated_at for filtering recently updated users
+CREATE INDEX IF NOT EXISTS idx_users_updated_at ON users(updated_at DESC);
+
+-- Improve the existing email index by making it case-insensitive
+DROP INDEX IF EXISTS idx_users_email;
+CREATE UNIQUE INDEX idx_users_email_lower ON users(LOWER(email));
+
+-- Update statistics to help query planner
+ANALYZE users;
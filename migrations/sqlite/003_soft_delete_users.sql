@@ -0,0 +1,28 @@
+-- Soft delete: rows are flagged with deleted_at and hard-deleted later by the purge job
+-- SQLite cannot drop the inline UNIQUE constraint on email, so the table is rebuilt
+
+CREATE TABLE users_new (
+    id BLOB PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    email TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    deleted_at TEXT NULL
+);
+
+INSERT INTO users_new (id, name, email, created_at, updated_at)
+SELECT id, name, email, created_at, updated_at FROM users;
+
+DROP TABLE users;
+ALTER TABLE users_new RENAME TO users;
+
+CREATE INDEX idx_users_created_at ON users(created_at);
+CREATE INDEX idx_users_name_lower ON users(LOWER(name));
+CREATE INDEX idx_users_created_at_id ON users(created_at DESC, id);
+CREATE INDEX idx_users_updated_at ON users(updated_at DESC);
+
+-- Email uniqueness only applies among non-deleted users
+CREATE UNIQUE INDEX idx_users_email_lower ON users(LOWER(email)) WHERE deleted_at IS NULL;
+
+CREATE INDEX idx_users_active ON users(id) WHERE deleted_at IS NULL;
+CREATE INDEX idx_users_deleted_at ON users(deleted_at) WHERE deleted_at IS NOT NULL;
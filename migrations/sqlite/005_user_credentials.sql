@@ -0,0 +1,8 @@
+-- Password credentials live apart from users so listings never read secrets.
+-- Rows go away with the user when the purge job hard-deletes it.
+
+CREATE TABLE user_credentials (
+    user_id BLOB PRIMARY KEY NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    password_hash TEXT NOT NULL,
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
@@ -0,0 +1,6 @@
+-- Roles drive the authorization policy; existing users become regular users.
+-- Promote the first admin by hand, e.g.
+--   UPDATE users SET role = 'admin' WHERE email = 'you@example.com';
+
+ALTER TABLE users ADD COLUMN role TEXT NOT NULL DEFAULT 'user'
+    CHECK (role IN ('admin', 'user', 'readonly'));
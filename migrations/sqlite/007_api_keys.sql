@@ -0,0 +1,17 @@
+-- API keys for service-to-service access. Only a SHA-256 of the key is kept;
+-- scopes are stored space-separated. Keys go away with their owner.
+
+CREATE TABLE api_keys (
+    id BLOB PRIMARY KEY NOT NULL,
+    owner_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    prefix TEXT NOT NULL,
+    key_hash TEXT NOT NULL UNIQUE,
+    scopes TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    expires_at TEXT,
+    last_used_at TEXT,
+    revoked_at TEXT
+);
+
+CREATE INDEX idx_api_keys_owner_id ON api_keys(owner_id);
@@ -0,0 +1,17 @@
+-- Refresh-token sessions. Each refresh rotates the token within its family;
+-- only a SHA-256 of the token is kept. Sessions go away with their user.
+
+CREATE TABLE sessions (
+    id BLOB PRIMARY KEY NOT NULL,
+    family_id BLOB NOT NULL,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token_hash TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL,
+    rotated_at TEXT,
+    revoked_at TEXT
+);
+
+CREATE INDEX idx_sessions_family_id ON sessions(family_id);
+CREATE INDEX idx_sessions_user_id ON sessions(user_id);
+CREATE INDEX idx_sessions_expires_at ON sessions(expires_at);
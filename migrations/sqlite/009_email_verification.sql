@@ -0,0 +1,17 @@
+-- Email verification. A user's address counts as verified once a token sent
+-- to it is confirmed; tokens are single-use and only their SHA-256 is kept.
+
+ALTER TABLE users ADD COLUMN email_verified_at TEXT;
+
+CREATE TABLE email_verification_tokens (
+    id BLOB PRIMARY KEY NOT NULL,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    email TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL,
+    used_at TEXT
+);
+
+CREATE INDEX idx_email_verification_tokens_user_id ON email_verification_tokens(user_id);
+CREATE INDEX idx_email_verification_tokens_expires_at ON email_verification_tokens(expires_at);
@@ -0,0 +1,44 @@
+-- Outgoing webhooks: partner endpoints subscribed to user events, one
+-- delivery per event and subscription, and a log of every HTTP attempt.
+-- Event types are stored space-separated. A replay is a new delivery of the
+-- same event pointing at the one it re-sends.
+
+CREATE TABLE webhook_subscriptions (
+    id BLOB PRIMARY KEY NOT NULL,
+    url TEXT NOT NULL,
+    event_types TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    active BOOLEAN NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE TABLE webhook_deliveries (
+    id BLOB PRIMARY KEY NOT NULL,
+    subscription_id BLOB NOT NULL REFERENCES webhook_subscriptions(id) ON DELETE CASCADE,
+    event_id BLOB NOT NULL,
+    event_type TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    status TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TEXT NOT NULL,
+    last_response_status INTEGER,
+    last_error TEXT,
+    replay_of BLOB,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE UNIQUE INDEX idx_webhook_deliveries_event
+    ON webhook_deliveries(subscription_id, event_id) WHERE replay_of IS NULL;
+CREATE INDEX idx_webhook_deliveries_due ON webhook_deliveries(next_attempt_at) WHERE status = 'pending';
+CREATE INDEX idx_webhook_deliveries_subscription ON webhook_deliveries(subscription_id, created_at);
+
+CREATE TABLE webhook_attempts (
+    delivery_id BLOB NOT NULL REFERENCES webhook_deliveries(id) ON DELETE CASCADE,
+    attempt INTEGER NOT NULL,
+    response_status INTEGER,
+    error TEXT,
+    attempted_at TEXT NOT NULL,
+    PRIMARY KEY (delivery_id, attempt)
+);
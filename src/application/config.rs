@@ -0,0 +1,503 @@
+use std::str::FromStr;
+
+use crate::domain::{Password, UserError};
+
+/// An environment variable the application cannot start with
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("{0} must be set")]
+    Missing(String),
+    #[error("{name} must be {expected}, got '{value}'")]
+    Invalid {
+        name: String,
+        expected: &'static str,
+        value: String,
+    },
+}
+
+impl ConfigError {
+    pub fn invalid(name: &str, expected: &'static str, value: &str) -> Self {
+        Self::Invalid {
+            name: name.to_string(),
+            expected,
+            value: value.to_string(),
+        }
+    }
+}
+
+// Readers for the `from_env` constructors. A variable that is unset or blank
+// takes the default; one that is set must be valid, or startup fails.
+
+/// Trimmed value of the environment variable `name`, unless unset or blank
+pub fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().map(|value| value.trim().to_string()).filter(|value| !value.is_empty())
+}
+
+/// `name` parsed as a `T`, described to the user as `expected`
+pub fn parse_var<T: FromStr>(name: &str, expected: &'static str) -> Result<Option<T>, ConfigError> {
+    env_var(name)
+        .map(|value| value.parse().map_err(|_| ConfigError::invalid(name, expected, &value)))
+        .transpose()
+}
+
+/// `name` parsed as a number greater than zero
+pub fn positive_var<T: FromStr + PartialOrd + Default>(name: &str) -> Result<Option<T>, ConfigError> {
+    env_var(name)
+        .map(|value| {
+            value
+                .parse()
+                .ok()
+                .filter(|parsed| *parsed > T::default())
+                .ok_or_else(|| ConfigError::invalid(name, "a positive number", &value))
+        })
+        .transpose()
+}
+
+/// `name` as a positive count of some unit, e.g. `chrono::Duration::try_seconds`
+pub fn duration_var<D>(name: &str, unit: impl Fn(i64) -> Option<D>) -> Result<Option<D>, ConfigError> {
+    env_var(name)
+        .map(|value| {
+            value
+                .parse()
+                .ok()
+                .filter(|count| *count > 0)
+                .and_then(&unit)
+                .ok_or_else(|| ConfigError::invalid(name, "a positive whole number in range", &value))
+        })
+        .transpose()
+}
+
+/// `name` as a flag: `true`, `yes` or `1`, or `false`, `no` or `0`
+pub fn flag_var(name: &str) -> Result<Option<bool>, ConfigError> {
+    env_var(name)
+        .map(|value| match value.to_lowercase().as_str() {
+            "1" | "true" | "yes" => Ok(true),
+            "0" | "false" | "no" => Ok(false),
+            _ => Err(ConfigError::invalid(name, "true or false", &value)),
+        })
+        .transpose()
+}
+
+/// Limits applied to list endpoints
+#[derive(Debug, Clone)]
+pub struct PaginationConfig {
+    pub default_limit: i64,
+    pub max_limit: i64,
+    /// Above this many rows (by estimate) totals come from planner statistics
+    pub exact_count_threshold: i64,
+}
+
+impl Default for PaginationConfig {
+    fn default() -> Self {
+        Self {
+            default_limit: 10,
+            max_limit: 100,
+            exact_count_threshold: 100_000,
+        }
+    }
+}
+
+impl PaginationConfig {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let defaults = Self::default();
+
+        let default_limit = positive_var("PAGINATION_DEFAULT_LIMIT")?.unwrap_or(defaults.default_limit);
+        let max_limit = positive_var("PAGINATION_MAX_LIMIT")?.unwrap_or(defaults.max_limit);
+        let exact_count_threshold = parse_var("PAGINATION_EXACT_COUNT_THRESHOLD", "a whole number")?
+            .unwrap_or(defaults.exact_count_threshold);
+
+        Ok(Self {
+            default_limit: default_limit.min(max_limit),
+            max_limit,
+            exact_count_threshold,
+        })
+    }
+
+    /// The requested page size, or the default; `InvalidPagination` outside the configured bounds
+    pub fn checked_limit(&self, limit: Option<i64>) -> Result<i64, UserError> {
+        let limit = limit.unwrap_or(self.default_limit);
+        if limit < 1 || limit > self.max_limit {
+            return Err(UserError::InvalidPagination(format!(
+                "limit must be between 1 and {}",
+                self.max_limit
+            )));
+        }
+        Ok(limit)
+    }
+}
+
+/// Retention of soft-deleted users before they are purged for good
+#[derive(Debug, Clone)]
+pub struct SoftDeleteConfig {
+    /// How long a deleted user stays restorable
+    pub retention: chrono::Duration,
+    /// How often the purge task runs
+    pub purge_interval: std::time::Duration,
+}
+
+impl Default for SoftDeleteConfig {
+    fn default() -> Self {
+        Self {
+            retention: chrono::Duration::days(30),
+            purge_interval: std::time::Duration::from_secs(3600),
+        }
+    }
+}
+
+impl SoftDeleteConfig {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let defaults = Self::default();
+
+        let retention = duration_var("SOFT_DELETE_RETENTION_DAYS", chrono::Duration::try_days)?
+            .unwrap_or(defaults.retention);
+        let purge_interval = positive_var("SOFT_DELETE_PURGE_INTERVAL_SECS")?
+            .map(std::time::Duration::from_secs)
+            .unwrap_or(defaults.purge_interval);
+
+        Ok(Self {
+            retention,
+            purge_interval,
+        })
+    }
+}
+
+/// Access token signing and which routes skip authentication
+#[derive(Debug, Clone)]
+pub struct AuthConfig {
+    pub jwt_secret: String,
+    pub jwt_issuer: String,
+    pub access_token_ttl: chrono::Duration,
+    pub refresh_token_ttl: chrono::Duration,
+    /// `METHOD /path` or `/path` for any method; a trailing `*` matches any suffix
+    pub public_routes: Vec<String>,
+}
+
+impl AuthConfig {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let jwt_secret = env_var("JWT_SECRET")
+            .unwrap_or_else(|| {
+                tracing::warn!("JWT_SECRET is not set: using a random secret, tokens will not survive a restart");
+                format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+            });
+
+        let jwt_issuer = env_var("JWT_ISSUER").unwrap_or_else(|| "rust-nexus".to_string());
+
+        let access_token_ttl = duration_var("JWT_ACCESS_TOKEN_TTL_SECS", chrono::Duration::try_seconds)?
+            .unwrap_or(chrono::Duration::minutes(15));
+        let refresh_token_ttl = duration_var("JWT_REFRESH_TOKEN_TTL_SECS", chrono::Duration::try_seconds)?
+            .unwrap_or(chrono::Duration::days(30));
+
+        let public_routes = std::env::var("AUTH_PUBLIC_ROUTES")
+            .map(|v| v.split(',').map(|r| r.trim().to_string()).filter(|r| !r.is_empty()).collect())
+            .unwrap_or_else(|_| Self::default_public_routes());
+
+        Ok(Self {
+            jwt_secret,
+            jwt_issuer,
+            access_token_ttl,
+            refresh_token_ttl,
+            public_routes,
+        })
+    }
+
+    /// Health checks, sign-up, the session and password reset endpoints and
+    /// email confirmation (mailed tokens are the credential), and the
+    /// WebSocket upgrade, whose clients may authenticate in a message instead
+    pub fn default_public_routes() -> Vec<String> {
+        [
+            "GET /health",
+            "POST /api/auth/login",
+            "POST /api/auth/refresh",
+            "POST /api/auth/logout",
+            "POST /api/auth/password-reset/request",
+            "POST /api/auth/password-reset/confirm",
+            "POST /api/users",
+            "POST /api/verify-email/confirm",
+            "GET /ws",
+        ]
+            .map(String::from)
+            .to_vec()
+    }
+}
+
+/// Email verification tokens and the link mailed with them
+#[derive(Debug, Clone)]
+pub struct EmailVerificationConfig {
+    pub token_ttl: chrono::Duration,
+    /// The token is appended to this, e.g. `https://app.example.com/verify-email?token=`
+    pub confirm_url: Option<String>,
+}
+
+impl Default for EmailVerificationConfig {
+    fn default() -> Self {
+        Self {
+            token_ttl: chrono::Duration::hours(24),
+            confirm_url: None,
+        }
+    }
+}
+
+impl EmailVerificationConfig {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let defaults = Self::default();
+
+        let token_ttl = duration_var("EMAIL_VERIFICATION_TTL_SECS", chrono::Duration::try_seconds)?
+            .unwrap_or(defaults.token_ttl);
+        let confirm_url = env_var("EMAIL_VERIFICATION_URL");
+
+        Ok(Self {
+            token_ttl,
+            confirm_url,
+        })
+    }
+}
+
+/// Rules a new password must meet on top of the 8 to 128 characters every
+/// password needs
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            require_uppercase: false,
+            require_lowercase: false,
+            require_digit: false,
+            require_symbol: false,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let defaults = Self::default();
+        let flag = |name: &str, default: bool| Ok::<_, ConfigError>(flag_var(name)?.unwrap_or(default));
+
+        Ok(Self {
+            min_length: positive_var("PASSWORD_MIN_LENGTH")?.unwrap_or(defaults.min_length),
+            require_uppercase: flag("PASSWORD_REQUIRE_UPPERCASE", defaults.require_uppercase)?,
+            require_lowercase: flag("PASSWORD_REQUIRE_LOWERCASE", defaults.require_lowercase)?,
+            require_digit: flag("PASSWORD_REQUIRE_DIGIT", defaults.require_digit)?,
+            require_symbol: flag("PASSWORD_REQUIRE_SYMBOL", defaults.require_symbol)?,
+        })
+    }
+
+    /// `InvalidPassword` naming every rule the password breaks
+    pub fn check(&self, password: &Password) -> Result<(), UserError> {
+        let password = password.as_str();
+        let mut missing = Vec::new();
+        if password.chars().count() < self.min_length {
+            missing.push(format!("at least {} characters", self.min_length));
+        }
+        if self.require_uppercase && !password.chars().any(char::is_uppercase) {
+            missing.push("an uppercase letter".to_string());
+        }
+        if self.require_lowercase && !password.chars().any(char::is_lowercase) {
+            missing.push("a lowercase letter".to_string());
+        }
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            missing.push("a digit".to_string());
+        }
+        if self.require_symbol && !password.chars().any(|c| !c.is_alphanumeric() && !c.is_whitespace()) {
+            missing.push("a symbol".to_string());
+        }
+
+        if missing.is_empty() {
+            return Ok(());
+        }
+        Err(UserError::InvalidPassword(format!("Password must contain {}", missing.join(", "))))
+    }
+}
+
+/// Password reset tokens and the link mailed with them
+#[derive(Debug, Clone)]
+pub struct PasswordResetConfig {
+    pub token_ttl: chrono::Duration,
+    /// The token is appended to this, e.g. `https://app.example.com/reset-password?token=`
+    pub reset_url: Option<String>,
+}
+
+impl Default for PasswordResetConfig {
+    fn default() -> Self {
+        Self {
+            token_ttl: chrono::Duration::hours(1),
+            reset_url: None,
+        }
+    }
+}
+
+impl PasswordResetConfig {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let defaults = Self::default();
+
+        let token_ttl = duration_var("PASSWORD_RESET_TTL_SECS", chrono::Duration::try_seconds)?
+            .unwrap_or(defaults.token_ttl);
+        let reset_url = env_var("PASSWORD_RESET_URL");
+
+        Ok(Self {
+            token_ttl,
+            reset_url,
+        })
+    }
+}
+
+/// How long responses to `Idempotency-Key` requests are kept
+#[derive(Debug, Clone)]
+pub struct IdempotencyConfig {
+    /// Retries within this window replay the first response
+    pub ttl: chrono::Duration,
+    /// A first request still unfinished after this long is presumed lost,
+    /// e.g. to a crash, and a retry may run it again
+    pub abandon_after: chrono::Duration,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            ttl: chrono::Duration::hours(24),
+            abandon_after: chrono::Duration::minutes(1),
+        }
+    }
+}
+
+impl IdempotencyConfig {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let defaults = Self::default();
+
+        Ok(Self {
+            ttl: secs_var("IDEMPOTENCY_TTL_SECS")?.unwrap_or(defaults.ttl),
+            abandon_after: secs_var("IDEMPOTENCY_ABANDON_AFTER_SECS")?.unwrap_or(defaults.abandon_after),
+        })
+    }
+}
+
+/// How the outbox relay delivers events; `OutboxTail` polls on the same
+/// interval and batch size
+#[derive(Debug, Clone)]
+pub struct OutboxConfig {
+    /// Pause between polls while the outbox is drained
+    pub poll_interval: std::time::Duration,
+    /// Messages claimed per poll
+    pub batch_size: i64,
+    /// A claimed message not marked within this long is delivered again
+    pub lease: chrono::Duration,
+    /// Wait before the first retry of a failed delivery; doubles per attempt
+    pub retry_base: chrono::Duration,
+    /// Longest wait between retries
+    pub retry_max: chrono::Duration,
+    /// Delivered messages are kept this long, then purged
+    pub retention: chrono::Duration,
+}
+
+impl Default for OutboxConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval: std::time::Duration::from_millis(500),
+            batch_size: 100,
+            lease: chrono::Duration::seconds(30),
+            retry_base: chrono::Duration::seconds(1),
+            retry_max: chrono::Duration::minutes(5),
+            retention: chrono::Duration::days(1),
+        }
+    }
+}
+
+impl OutboxConfig {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let defaults = Self::default();
+
+        Ok(Self {
+            poll_interval: positive_var("OUTBOX_POLL_INTERVAL_MS")?
+                .map(std::time::Duration::from_millis)
+                .unwrap_or(defaults.poll_interval),
+            batch_size: positive_var("OUTBOX_BATCH_SIZE")?.unwrap_or(defaults.batch_size),
+            lease: secs_var("OUTBOX_LEASE_SECS")?.unwrap_or(defaults.lease),
+            retry_base: secs_var("OUTBOX_RETRY_BASE_SECS")?.unwrap_or(defaults.retry_base),
+            retry_max: secs_var("OUTBOX_RETRY_MAX_SECS")?.unwrap_or(defaults.retry_max),
+            retention: duration_var("OUTBOX_RETENTION_HOURS", chrono::Duration::try_hours)?
+                .unwrap_or(defaults.retention),
+        })
+    }
+
+    /// Wait before retrying a message that failed on its `attempts`th delivery
+    pub fn retry_delay(&self, attempts: i32) -> chrono::Duration {
+        backoff(self.retry_base, self.retry_max, attempts)
+    }
+}
+
+/// How webhook deliveries are sent and retried
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    /// Pause between polls for due deliveries
+    pub poll_interval: std::time::Duration,
+    /// Deliveries claimed per poll
+    pub batch_size: i64,
+    /// A claimed delivery whose attempt is not recorded within this long is
+    /// attempted again; must exceed `timeout`
+    pub lease: chrono::Duration,
+    /// Longest wait for a receiver's response
+    pub timeout: std::time::Duration,
+    /// Attempts before a delivery is dead-lettered
+    pub max_attempts: i32,
+    /// Wait before the first retry of a failed attempt; doubles per attempt
+    pub retry_base: chrono::Duration,
+    /// Longest wait between retries
+    pub retry_max: chrono::Duration,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval: std::time::Duration::from_secs(1),
+            batch_size: 50,
+            lease: chrono::Duration::minutes(1),
+            timeout: std::time::Duration::from_secs(10),
+            max_attempts: 8,
+            retry_base: chrono::Duration::seconds(10),
+            retry_max: chrono::Duration::hours(1),
+        }
+    }
+}
+
+impl WebhookConfig {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let defaults = Self::default();
+
+        Ok(Self {
+            poll_interval: positive_var("WEBHOOK_POLL_INTERVAL_MS")?
+                .map(std::time::Duration::from_millis)
+                .unwrap_or(defaults.poll_interval),
+            batch_size: positive_var("WEBHOOK_BATCH_SIZE")?.unwrap_or(defaults.batch_size),
+            lease: secs_var("WEBHOOK_LEASE_SECS")?.unwrap_or(defaults.lease),
+            timeout: positive_var("WEBHOOK_TIMEOUT_SECS")?
+                .map(std::time::Duration::from_secs)
+                .unwrap_or(defaults.timeout),
+            max_attempts: positive_var("WEBHOOK_MAX_ATTEMPTS")?.unwrap_or(defaults.max_attempts),
+            retry_base: secs_var("WEBHOOK_RETRY_BASE_SECS")?.unwrap_or(defaults.retry_base),
+            retry_max: secs_var("WEBHOOK_RETRY_MAX_SECS")?.unwrap_or(defaults.retry_max),
+        })
+    }
+
+    /// Wait before retrying a delivery whose `attempts`th attempt failed
+    pub fn retry_delay(&self, attempts: i32) -> chrono::Duration {
+        backoff(self.retry_base, self.retry_max, attempts)
+    }
+}
+
+fn secs_var(name: &str) -> Result<Option<chrono::Duration>, ConfigError> {
+    duration_var(name, chrono::Duration::try_seconds)
+}
+
+/// `base` doubled per attempt after the first, up to `max`; a delay too
+/// large to represent is `max` as well
+fn backoff(base: chrono::Duration, max: chrono::Duration, attempts: i32) -> chrono::Duration {
+    let doublings = attempts.saturating_sub(1).clamp(0, 20) as u32;
+    base.checked_mul(2i32.pow(doublings)).map_or(max, |delay| delay.min(max))
+}
@@ -0,0 +1,57 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::ApiKey;
+
+/// DTO for minting an API key
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyDto {
+    /// Label to recognise the key by, e.g. the calling service
+    pub name: String,
+    /// User the key acts as; defaults to the caller
+    pub owner_id: Option<Uuid>,
+    /// e.g. `["users:read"]`
+    pub scopes: Vec<String>,
+    /// Never expires when omitted
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// DTO for listing API keys, without the secret
+#[derive(Debug, Serialize)]
+pub struct ApiKeyResponseDto {
+    pub id: Uuid,
+    pub name: String,
+    pub owner_id: Uuid,
+    pub prefix: String,
+    pub scopes: Vec<&'static str>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// DTO for a freshly minted key; the only time the secret is shown
+#[derive(Debug, Serialize)]
+pub struct CreatedApiKeyDto {
+    pub key: String,
+    #[serde(flatten)]
+    pub api_key: ApiKeyResponseDto,
+}
+
+impl From<&ApiKey> for ApiKeyResponseDto {
+    fn from(key: &ApiKey) -> Self {
+        Self {
+            id: key.id,
+            name: key.name.clone(),
+            owner_id: key.owner_id.as_uuid(),
+            prefix: key.prefix.clone(),
+            scopes: key.scopes.iter().map(|scope| scope.as_str()).collect(),
+            created_at: key.created_at,
+            expires_at: key.expires_at,
+            last_used_at: key.last_used_at,
+            revoked_at: key.revoked_at,
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    application::dto::PaginationMeta,
+    domain::{AuditAction, AuditEntry, AuditQuery, FieldChange, UserError, UserId},
+};
+
+/// DTO for audit log filters, as given in the query string
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditFilterDto {
+    pub user_id: Option<Uuid>,
+    pub actor_id: Option<Uuid>,
+    /// `create`, `update`, `delete` or `restore`
+    pub action: Option<String>,
+    pub request_id: Option<String>,
+    /// Entries at or after this time
+    pub from: Option<DateTime<Utc>>,
+    /// Entries before this time
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// DTO for one audit log entry
+#[derive(Debug, Serialize)]
+pub struct AuditEntryDto {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// `null` for anonymous sign-up
+    pub actor_id: Option<Uuid>,
+    pub action: String,
+    pub changes: Vec<FieldChange>,
+    pub request_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// DTO for one page of audit log entries
+#[derive(Debug)]
+pub struct AuditPageDto {
+    pub entries: Vec<AuditEntryDto>,
+    pub pagination: PaginationMeta,
+}
+
+impl AuditFilterDto {
+    /// Convert DTO to the domain query criteria
+    pub fn into_domain(self) -> Result<AuditQuery, UserError> {
+        Ok(AuditQuery {
+            user_id: self.user_id.map(UserId::from_uuid),
+            actor: self.actor_id.map(UserId::from_uuid),
+            action: self.action.as_deref().map(AuditAction::parse).transpose()?,
+            request_id: self.request_id.filter(|id| !id.trim().is_empty()),
+            from: self.from,
+            to: self.to,
+        })
+    }
+}
+
+impl From<&AuditEntry> for AuditEntryDto {
+    fn from(entry: &AuditEntry) -> Self {
+        Self {
+            id: entry.id,
+            user_id: entry.user_id.as_uuid(),
+            actor_id: entry.actor.as_ref().map(UserId::as_uuid),
+            action: entry.action.to_string(),
+            changes: entry.changes.clone(),
+            request_id: entry.request_id.clone(),
+            created_at: entry.created_at,
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::AccessToken;
+
+/// DTO for password login
+#[derive(Debug, Deserialize)]
+pub struct LoginDto {
+    pub email: String,
+    pub password: String,
+}
+
+/// DTO for refreshing or ending a session
+#[derive(Debug, Deserialize)]
+pub struct RefreshTokenDto {
+    pub refresh_token: String,
+}
+
+/// DTO for an issued access token, with the session's next refresh token
+#[derive(Debug, Serialize)]
+pub struct TokenResponseDto {
+    pub access_token: String,
+    /// Always `Bearer`
+    pub token_type: &'static str,
+    pub expires_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_expires_at: Option<DateTime<Utc>>,
+}
+
+impl TokenResponseDto {
+    pub fn with_refresh_token(self, refresh_token: String, expires_at: DateTime<Utc>) -> Self {
+        Self {
+            refresh_token: Some(refresh_token),
+            refresh_expires_at: Some(expires_at),
+            ..self
+        }
+    }
+}
+
+impl From<AccessToken> for TokenResponseDto {
+    fn from(token: AccessToken) -> Self {
+        Self {
+            access_token: token.token,
+            token_type: "Bearer",
+            expires_at: token.expires_at,
+            refresh_token: None,
+            refresh_expires_at: None,
+        }
+    }
+}
+
+/// DTO for asking for a password reset mail
+#[derive(Debug, Deserialize)]
+pub struct PasswordResetRequestDto {
+    pub email: String,
+}
+
+/// DTO for setting a new password with a mailed token
+#[derive(Debug, Deserialize)]
+pub struct PasswordResetConfirmDto {
+    pub token: String,
+    pub new_password: String,
+}
@@ -0,0 +1,16 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// DTO for confirming an email address
+#[derive(Debug, Deserialize)]
+pub struct ConfirmEmailDto {
+    pub token: String,
+}
+
+/// DTO for a mailed verification token; the token itself is only in the mail
+#[derive(Debug, Serialize)]
+pub struct VerificationSentDto {
+    /// Address the token was sent to
+    pub email: String,
+    pub expires_at: DateTime<Utc>,
+}
@@ -0,0 +1,46 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    application::dto::UserResponseDto,
+    domain::{FieldChange, UserEvent},
+};
+
+/// Query of the user event stream
+#[derive(Debug, Default, Deserialize)]
+pub struct EventStreamFilterDto {
+    /// Only changes to this user
+    pub user_id: Option<Uuid>,
+}
+
+/// DTO for a user event, as sent to webhook receivers and event streams
+#[derive(Debug, Serialize)]
+pub struct UserEventDto {
+    /// Same for every delivery of the event, to drop duplicates by
+    pub id: Uuid,
+    /// `user.created`, `user.updated`, `user.deleted` or `user.restored`
+    #[serde(rename = "type")]
+    pub event_type: &'static str,
+    pub occurred_at: DateTime<Utc>,
+    /// The user after the change; before it for deletes
+    pub data: UserResponseDto,
+    /// Changed fields, for updates only
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<FieldChange>,
+}
+
+impl From<&UserEvent> for UserEventDto {
+    fn from(event: &UserEvent) -> Self {
+        Self {
+            id: event.id(),
+            event_type: event.event_type(),
+            occurred_at: event.occurred_at(),
+            data: UserResponseDto::from(event.user()),
+            changes: match event {
+                UserEvent::Updated(updated) => updated.changes.clone(),
+                _ => Vec::new(),
+            },
+        }
+    }
+}
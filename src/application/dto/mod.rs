@@ -1,3 +1,19 @@
-pub mod user_dto;
-
-pub use user_dto::*;
+pub mod api_key_dto;
+pub mod audit_dto;
+pub mod auth_dto;
+pub mod email_verification_dto;
+pub mod event_dto;
+pub mod pagination_dto;
+pub mod patch_dto;
+pub mod user_dto;
+pub mod webhook_dto;
+
+pub use api_key_dto::*;
+pub use audit_dto::*;
+pub use auth_dto::*;
+pub use email_verification_dto::*;
+pub use event_dto::*;
+pub use pagination_dto::*;
+pub use patch_dto::*;
+pub use user_dto::*;
+pub use webhook_dto::*;
@@ -0,0 +1,135 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::domain::{SortDirection, SortKey, UserCursor, UserError, UserId, UserSort, UserSortField};
+
+/// Pagination metadata attached to list responses
+#[derive(Debug, Serialize)]
+pub struct PaginationMeta {
+    /// Zero-based page number; absent for keyset (`after`) requests
+    pub page: Option<i64>,
+    pub limit: i64,
+    pub total: i64,
+    /// True when `total` comes from planner statistics rather than a full count
+    pub total_estimated: bool,
+    pub has_more: bool,
+    /// Opaque cursor for `?after=`; absent on the last page
+    pub next_cursor: Option<String>,
+    /// Link to the next page, if any
+    pub next: Option<String>,
+    /// Link to the previous page; only page/offset listings can go back
+    pub prev: Option<String>,
+}
+
+impl PaginationMeta {
+    /// Fill `next`/`prev` with links relative to `base_path`.
+    /// `carried_query` (already URL-encoded, e.g. filters and sort) is kept
+    /// on both links.
+    pub fn with_links(mut self, base_path: &str, carried_query: &str) -> Self {
+        let link = |position: String| {
+            if carried_query.is_empty() {
+                format!("{}?{}&limit={}", base_path, position, self.limit)
+            } else {
+                format!("{}?{}&{}&limit={}", base_path, carried_query, position, self.limit)
+            }
+        };
+
+        self.next = match (self.page, &self.next_cursor) {
+            _ if !self.has_more => None,
+            (Some(page), _) => Some(link(format!("page={}", page + 1))),
+            (None, Some(cursor)) => Some(link(format!("after={}", cursor))),
+            (None, None) => None,
+        };
+        self.prev = match self.page {
+            Some(page) if page > 0 => Some(link(format!("page={}", page - 1))),
+            _ => None,
+        };
+        self
+    }
+}
+
+/// Encode a keyset position as an opaque, URL-safe token
+pub fn encode_cursor(cursor: &UserCursor) -> String {
+    let key = match &cursor.key {
+        SortKey::Timestamp(t) => format!("t{}", t.to_rfc3339()),
+        SortKey::Text(s) => format!("s{}", s),
+    };
+    let raw = format!("{}|{}|{}", format_sort(&cursor.sort), cursor.id.as_uuid(), key);
+    URL_SAFE_NO_PAD.encode(raw)
+}
+
+/// Decode a token produced by `encode_cursor`; it must belong to `sort`
+pub fn decode_cursor(token: &str, sort: &UserSort) -> Result<UserCursor, UserError> {
+    let invalid = || UserError::InvalidPagination("malformed cursor".to_string());
+
+    let bytes = URL_SAFE_NO_PAD.decode(token).map_err(|_| invalid())?;
+    let raw = String::from_utf8(bytes).map_err(|_| invalid())?;
+    // The key goes last because text keys may themselves contain '|'
+    let mut parts = raw.splitn(3, '|');
+    let (cursor_sort, id, key) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(s), Some(i), Some(k)) => (parse_sort(s).map_err(|_| invalid())?, i, k),
+        _ => return Err(invalid()),
+    };
+    if cursor_sort != *sort {
+        return Err(UserError::InvalidPagination(
+            "cursor was issued for a different sort order".to_string(),
+        ));
+    }
+
+    let key = match key.split_at_checked(1) {
+        Some(("t", t)) => SortKey::Timestamp(
+            DateTime::parse_from_rfc3339(t)
+                .map_err(|_| invalid())?
+                .with_timezone(&Utc),
+        ),
+        Some(("s", s)) => SortKey::Text(s.to_string()),
+        _ => return Err(invalid()),
+    };
+
+    Ok(UserCursor {
+        sort: cursor_sort,
+        key,
+        id: UserId::from_uuid(Uuid::parse_str(id).map_err(|_| invalid())?),
+    })
+}
+
+/// Parse `field[:asc|:desc]`; the direction defaults to ascending
+pub fn parse_sort(value: &str) -> Result<UserSort, UserError> {
+    let invalid = || {
+        UserError::InvalidQuery(format!(
+            "unsupported sort '{}': use created_at, updated_at, name or email, optionally with :asc or :desc",
+            value
+        ))
+    };
+
+    let (field, direction) = value.split_once(':').unwrap_or((value, "asc"));
+    let field = match field {
+        "created_at" => UserSortField::CreatedAt,
+        "updated_at" => UserSortField::UpdatedAt,
+        "name" => UserSortField::Name,
+        "email" => UserSortField::Email,
+        _ => return Err(invalid()),
+    };
+    let direction = match direction {
+        "asc" => SortDirection::Asc,
+        "desc" => SortDirection::Desc,
+        _ => return Err(invalid()),
+    };
+    Ok(UserSort { field, direction })
+}
+
+fn format_sort(sort: &UserSort) -> String {
+    let field = match sort.field {
+        UserSortField::CreatedAt => "created_at",
+        UserSortField::UpdatedAt => "updated_at",
+        UserSortField::Name => "name",
+        UserSortField::Email => "email",
+    };
+    let direction = match sort.direction {
+        SortDirection::Asc => "asc",
+        SortDirection::Desc => "desc",
+    };
+    format!("{}:{}", field, direction)
+}
@@ -0,0 +1,158 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value};
+
+use crate::{
+    application::dto::{UpdateUserDto, UserResponseDto},
+    domain::{Email, Role, UserError, UserName},
+};
+
+/// Media type of an RFC 7396 JSON Merge Patch
+pub const MERGE_PATCH_CONTENT_TYPE: &str = "application/merge-patch+json";
+/// Media type of an RFC 6902 JSON Patch
+pub const JSON_PATCH_CONTENT_TYPE: &str = "application/json-patch+json";
+
+/// Fields clients may change; everything else in the representation is read-only
+const WRITABLE_FIELDS: [&str; 3] = ["name", "email", "role"];
+/// Nullable fields clients may clear, but not set
+const CLEARABLE_FIELDS: [&str; 1] = ["email_verified_at"];
+
+/// Changes a patch makes, validated like a `PUT`
+#[derive(Debug)]
+pub struct PatchedUser {
+    pub name: UserName,
+    pub email: Email,
+    pub role: Role,
+    /// Whether the patch cleared `email_verified_at`
+    pub clear_email_verification: bool,
+}
+
+/// Clearable fields of a patched document. Each is `None` when the patch
+/// removed it, `Some(None)` when it is `null` and `Some(Some(_))` when set.
+#[derive(Deserialize)]
+struct ClearableFields {
+    #[serde(default, deserialize_with = "present")]
+    email_verified_at: Option<Option<DateTime<Utc>>>,
+}
+
+/// DTO for a partial update, in one of the supported patch formats
+#[derive(Debug)]
+pub enum UserPatchDto {
+    /// RFC 7396: an object whose members replace, or with `null` remove, fields
+    Merge(Value),
+    /// RFC 6902: a list of operations, applied all or nothing
+    Json(json_patch::Patch),
+}
+
+impl UserPatchDto {
+    /// Parse a request body according to its `Content-Type` (parameters ignored)
+    pub fn parse(content_type: &str, body: &[u8]) -> Result<Self, UserError> {
+        let media_type = content_type.split(';').next().unwrap_or_default().trim();
+        if media_type.eq_ignore_ascii_case(MERGE_PATCH_CONTENT_TYPE) {
+            serde_json::from_slice(body).map(Self::Merge).map_err(malformed)
+        } else if media_type.eq_ignore_ascii_case(JSON_PATCH_CONTENT_TYPE) {
+            serde_json::from_slice(body).map(Self::Json).map_err(malformed)
+        } else {
+            Err(UserError::UnsupportedPatchFormat(format!(
+                "expected {} or {}, got `{}`",
+                MERGE_PATCH_CONTENT_TYPE, JSON_PATCH_CONTENT_TYPE, media_type
+            )))
+        }
+    }
+
+    /// Apply the patch to a user's representation and validate the result.
+    /// Writable fields go through the same domain validation as a `PUT`;
+    /// removing one, or touching any read-only field, is rejected. Clearable
+    /// fields may be removed or set to `null`, but not to another value.
+    pub fn apply(self, current: &UserResponseDto) -> Result<PatchedUser, UserError> {
+        let original = serde_json::to_value(current)
+            .map_err(|e| UserError::InvalidPatch(e.to_string()))?;
+        let mut document = original.clone();
+        match self {
+            Self::Merge(patch) => json_patch::merge(&mut document, &patch),
+            Self::Json(patch) => json_patch::patch(&mut document, &patch)
+                .map_err(|e| UserError::InvalidPatch(e.to_string()))?,
+        }
+
+        let Value::Object(mut patched) = document else {
+            return Err(UserError::InvalidPatch("the user must remain an object".to_string()));
+        };
+        let Value::Object(mut read_only) = original else {
+            unreachable!("a user serializes to an object");
+        };
+
+        for field in WRITABLE_FIELDS.into_iter().chain(CLEARABLE_FIELDS) {
+            read_only.remove(field);
+        }
+        let name = take_writable(&mut patched, "name")?;
+        let email = take_writable(&mut patched, "email")?;
+        let role = take_writable(&mut patched, "role")?;
+        let clearable: ClearableFields = take_clearable(&mut patched)?;
+        if let Some(field) = changed_field(&read_only, &patched) {
+            return Err(UserError::InvalidPatch(format!("`{}` is read-only", field)));
+        }
+
+        let clear_email_verification = match clearable.email_verified_at {
+            None | Some(None) => current.email_verified_at.is_some(),
+            Some(verified_at) if verified_at == current.email_verified_at => false,
+            Some(Some(_)) => {
+                return Err(UserError::InvalidPatch("`email_verified_at` can only be cleared".to_string()));
+            }
+        };
+        let (name, email, role) = UpdateUserDto {
+            name,
+            email,
+            role,
+        }
+        .into_domain()?;
+        Ok(PatchedUser {
+            name,
+            email,
+            role,
+            clear_email_verification,
+        })
+    }
+}
+
+/// Remove a writable field from the patched document; it must still be a
+/// string, since none of them is optional
+fn take_writable(fields: &mut Map<String, Value>, field: &str) -> Result<String, UserError> {
+    match fields.remove(field) {
+        Some(Value::String(value)) => Ok(value),
+        Some(Value::Null) | None => {
+            Err(UserError::InvalidPatch(format!("`{}` is required and cannot be removed", field)))
+        }
+        Some(_) => Err(UserError::InvalidPatch(format!("`{}` must be a string", field))),
+    }
+}
+
+/// Remove the clearable fields from the patched document
+fn take_clearable(fields: &mut Map<String, Value>) -> Result<ClearableFields, UserError> {
+    let clearable = CLEARABLE_FIELDS
+        .into_iter()
+        .filter_map(|field| fields.remove_entry(field))
+        .collect();
+    serde_json::from_value(Value::Object(clearable)).map_err(|e| UserError::InvalidPatch(e.to_string()))
+}
+
+/// Tell a `null` field from an absent one, which `#[serde(default)]` leaves `None`
+fn present<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+fn malformed(err: serde_json::Error) -> UserError {
+    UserError::InvalidPatch(format!("malformed patch document: {}", err))
+}
+
+/// First field that differs between two objects, if any
+fn changed_field<'a>(before: &'a Map<String, Value>, after: &'a Map<String, Value>) -> Option<&'a str> {
+    before
+        .iter()
+        .find(|(field, value)| after.get(*field) != Some(value))
+        .map(|(field, _)| field.as_str())
+        .or_else(|| after.keys().find(|field| !before.contains_key(*field)).map(String::as_str))
+}
@@ -1,95 +1,211 @@
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
-use uuid::Uuid;
-
-use crate::domain::{User, UserName, Email, UserError};
-
-/// DTO for creating a user
-#[derive(Debug, Deserialize)]
-pub struct CreateUserDto {
-    pub name: String,
-    pub email: String,
-}
-
-/// DTO for updating a user
-#[derive(Debug, Deserialize)]
-pub struct UpdateUserDto {
-    pub name: Option<String>,
-    pub email: Option<String>,
-}
-
-/// DTO for user response
-#[derive(Debug, Serialize)]
-pub struct UserResponseDto {
-    pub id: Uuid,
-    pub name: String,
-    pub email: String,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
-}
-
-/// DTO for API responses
-#[derive(Debug, Serialize)]
-pub struct ApiResponse<T> {
-    pub success: bool,
-    pub data: Option<T>,
-    pub error: Option<String>,
-}
-
-impl CreateUserDto {
-    /// Convert DTO to domain value objects
-    pub fn to_domain(self) -> Result<(UserName, Email), UserError> {
-        let name = UserName::new(self.name)?;
-        let email = Email::new(self.email)?;
-        Ok((name, email))
-    }
-}
-
-impl UpdateUserDto {
-    /// Convert DTO to domain value objects
-    pub fn to_domain(self) -> Result<(Option<UserName>, Option<Email>), UserError> {
-        let name = if let Some(n) = self.name {
-            Some(UserName::new(n)?)
-        } else {
-            None
-        };
-        
-        let email = if let Some(e) = self.email {
-            Some(Email::new(e)?)
-        } else {
-            None
-        };
-        
-        Ok((name, email))
-    }
-}
-
-impl From<&User> for UserResponseDto {
-    fn from(user: &User) -> Self {
-        Self {
-            id: user.id().as_uuid(),
-            name: user.name().as_str().to_string(),
-            email: user.email().as_str().to_string(),
-            created_at: user.created_at(),
-            updated_at: user.updated_at(),
-        }
-    }
-}
-
-impl<T> ApiResponse<T> {
-    pub fn success(data: T) -> Self {
-        Self {
-            success: true,
-            data: Some(data),
-            error: None,
-        }
-    }
-
-    pub fn error(message: String) -> Self {
-        Self {
-            success: false,
-            data: None,
-            error: Some(message),
-        }
-    }
-}
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    application::dto::{PaginationMeta, parse_sort},
+    domain::{Role, User, UserName, Email, Password, UserError, UserQuery, UserSort},
+};
+
+/// DTO for creating a user
+#[derive(Debug, Deserialize)]
+pub struct CreateUserDto {
+    pub name: String,
+    pub email: String,
+    /// Defaults to `user`; only admins may assign another role
+    pub role: Option<String>,
+    /// Enables password login when given
+    pub password: Option<String>,
+}
+
+/// What identifies a `CreateUserDto` for idempotent retries. Only whether a
+/// password was sent counts, so not even a hash of it is stored.
+#[derive(Debug, Serialize)]
+pub struct CreateUserFingerprint<'a> {
+    name: &'a str,
+    email: &'a str,
+    role: Option<&'a str>,
+    password: bool,
+}
+
+/// DTO for replacing a user's writable fields (`PUT`); every field is required
+#[derive(Debug, Deserialize)]
+pub struct UpdateUserDto {
+    pub name: String,
+    pub email: String,
+    /// Restating the current role is not a role change
+    pub role: String,
+}
+
+/// DTO for user list filters and sort, as given in the query string
+#[derive(Debug, Default, Deserialize)]
+pub struct UserFilterDto {
+    pub name_prefix: Option<String>,
+    pub name_contains: Option<String>,
+    pub email: Option<String>,
+    pub email_domain: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub updated_after: Option<DateTime<Utc>>,
+    pub updated_before: Option<DateTime<Utc>>,
+    /// Also list soft-deleted users
+    pub include_deleted: Option<bool>,
+    /// `field[:asc|:desc]`, e.g. `name:asc`
+    pub sort: Option<String>,
+}
+
+/// DTO for user response
+#[derive(Debug, Serialize)]
+pub struct UserResponseDto {
+    pub id: Uuid,
+    pub name: String,
+    pub email: String,
+    /// `null` until the current address is verified
+    pub email_verified_at: Option<DateTime<Utc>>,
+    pub role: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Set only for soft-deleted users
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Optimistic concurrency version, also served as the `ETag`
+    pub version: i64,
+}
+
+/// DTO for one page of a user listing
+#[derive(Debug, Serialize)]
+pub struct UserPageDto {
+    pub users: Vec<UserResponseDto>,
+    pub pagination: PaginationMeta,
+}
+
+/// DTO for API responses
+#[derive(Debug, Serialize)]
+pub struct ApiResponse<T> {
+    pub success: bool,
+    pub data: Option<T>,
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retryable: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pagination: Option<PaginationMeta>,
+}
+
+impl CreateUserDto {
+    /// Convert DTO to domain value objects
+    pub fn into_domain(self) -> Result<(UserName, Email, Option<Role>, Option<Password>), UserError> {
+        let name = UserName::new(self.name)?;
+        let email = Email::new(self.email)?;
+        let role = self.role.as_deref().map(Role::parse).transpose()?;
+        let password = self.password.map(Password::new).transpose()?;
+        Ok((name, email, role, password))
+    }
+
+    /// The fields to fingerprint for `Idempotency-Key` requests
+    pub fn fingerprint_fields(&self) -> CreateUserFingerprint<'_> {
+        CreateUserFingerprint {
+            name: &self.name,
+            email: &self.email,
+            role: self.role.as_deref(),
+            password: self.password.is_some(),
+        }
+    }
+}
+
+impl UpdateUserDto {
+    /// Convert DTO to domain value objects
+    pub fn into_domain(self) -> Result<(UserName, Email, Role), UserError> {
+        let name = UserName::new(self.name)?;
+        let email = Email::new(self.email)?;
+        let role = Role::parse(&self.role)?;
+        Ok((name, email, role))
+    }
+}
+
+impl UserFilterDto {
+    /// Convert DTO to the domain query criteria
+    pub fn into_domain(self) -> Result<UserQuery, UserError> {
+        let email = self.email.map(Email::new).transpose()?;
+        let email_domain = self
+            .email_domain
+            .map(|d| d.trim().trim_start_matches('@').to_lowercase())
+            .filter(|d| !d.is_empty());
+        let sort = match self.sort {
+            Some(sort) => parse_sort(&sort)?,
+            None => UserSort::default(),
+        };
+        let non_empty = |v: Option<String>| v.filter(|v| !v.trim().is_empty());
+
+        Ok(UserQuery {
+            name_prefix: non_empty(self.name_prefix),
+            name_contains: non_empty(self.name_contains),
+            email,
+            email_domain,
+            created_after: self.created_after,
+            created_before: self.created_before,
+            updated_after: self.updated_after,
+            updated_before: self.updated_before,
+            include_deleted: self.include_deleted.unwrap_or(false),
+            sort,
+        })
+    }
+}
+
+impl From<&User> for UserResponseDto {
+    fn from(user: &User) -> Self {
+        Self {
+            id: user.id().as_uuid(),
+            name: user.name().as_str().to_string(),
+            email: user.email().as_str().to_string(),
+            email_verified_at: user.email_verified_at(),
+            role: user.role().to_string(),
+            created_at: user.created_at(),
+            updated_at: user.updated_at(),
+            deleted_at: user.deleted_at(),
+            version: user.version(),
+        }
+    }
+}
+
+impl<T> ApiResponse<T> {
+    pub fn success(data: T) -> Self {
+        Self {
+            success: true,
+            data: Some(data),
+            error: None,
+            error_code: None,
+            retryable: None,
+            pagination: None,
+        }
+    }
+
+    pub fn error(message: String) -> Self {
+        Self {
+            success: false,
+            data: None,
+            error: Some(message),
+            error_code: None,
+            retryable: None,
+            pagination: None,
+        }
+    }
+
+    /// Success response for one page of a listing
+    pub fn paginated(data: T, pagination: PaginationMeta) -> Self {
+        Self {
+            pagination: Some(pagination),
+            ..Self::success(data)
+        }
+    }
+
+    /// Error response carrying the stable code and retry hint of a domain error
+    pub fn from_user_error(err: &UserError, message: String) -> Self {
+        Self {
+            error_code: Some(err.code().to_string()),
+            retryable: Some(err.is_retryable()),
+            ..Self::error(message)
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    application::dto::PaginationMeta,
+    domain::{DeliveryStatus, WebhookAttempt, WebhookDelivery, WebhookSubscription},
+};
+
+/// DTO for subscribing an endpoint to user events
+#[derive(Debug, Deserialize)]
+pub struct CreateWebhookDto {
+    pub url: String,
+    /// e.g. `["user.created"]`; every user event when omitted
+    pub event_types: Option<Vec<String>>,
+    /// Signing secret; generated when omitted
+    pub secret: Option<String>,
+}
+
+/// DTO for changing a subscription; omitted fields stay as they are
+#[derive(Debug, Deserialize)]
+pub struct UpdateWebhookDto {
+    pub url: Option<String>,
+    pub event_types: Option<Vec<String>>,
+    pub secret: Option<String>,
+    pub active: Option<bool>,
+}
+
+/// DTO for delivery log filters, as given in the query string
+#[derive(Debug, Default, Deserialize)]
+pub struct DeliveryFilterDto {
+    /// `pending`, `succeeded` or `dead_lettered`
+    pub status: Option<String>,
+}
+
+/// DTO for a subscription, without its secret
+#[derive(Debug, Serialize)]
+pub struct WebhookResponseDto {
+    pub id: Uuid,
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// DTO for a subscription whose secret was just set; the only time it is shown
+#[derive(Debug, Serialize)]
+pub struct CreatedWebhookDto {
+    pub secret: String,
+    #[serde(flatten)]
+    pub webhook: WebhookResponseDto,
+}
+
+/// DTO for one entry of the delivery log
+#[derive(Debug, Serialize)]
+pub struct WebhookDeliveryDto {
+    pub id: Uuid,
+    pub subscription_id: Uuid,
+    pub event_id: Uuid,
+    pub event_type: String,
+    pub status: &'static str,
+    pub attempts: i32,
+    /// Set only while the delivery is pending
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub last_response_status: Option<i32>,
+    pub last_error: Option<String>,
+    /// Set only for replays
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replay_of: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// DTO for one HTTP attempt of a delivery
+#[derive(Debug, Serialize)]
+pub struct WebhookAttemptDto {
+    pub attempt: i32,
+    pub response_status: Option<i32>,
+    pub error: Option<String>,
+    pub attempted_at: DateTime<Utc>,
+}
+
+/// DTO for a delivery with the body it sends and every attempt so far
+#[derive(Debug, Serialize)]
+pub struct WebhookDeliveryDetailDto {
+    #[serde(flatten)]
+    pub delivery: WebhookDeliveryDto,
+    pub payload: serde_json::Value,
+    pub attempt_log: Vec<WebhookAttemptDto>,
+}
+
+/// DTO for one page of the delivery log
+#[derive(Debug)]
+pub struct WebhookDeliveryPageDto {
+    pub deliveries: Vec<WebhookDeliveryDto>,
+    pub pagination: PaginationMeta,
+}
+
+impl From<&WebhookSubscription> for WebhookResponseDto {
+    fn from(subscription: &WebhookSubscription) -> Self {
+        Self {
+            id: subscription.id,
+            url: subscription.url.clone(),
+            event_types: subscription.event_types.clone(),
+            active: subscription.active,
+            created_at: subscription.created_at,
+            updated_at: subscription.updated_at,
+        }
+    }
+}
+
+impl From<&WebhookDelivery> for WebhookDeliveryDto {
+    fn from(delivery: &WebhookDelivery) -> Self {
+        Self {
+            id: delivery.id,
+            subscription_id: delivery.subscription_id,
+            event_id: delivery.event_id,
+            event_type: delivery.event_type.clone(),
+            status: delivery.status.as_str(),
+            attempts: delivery.attempts,
+            next_attempt_at: (delivery.status == DeliveryStatus::Pending).then_some(delivery.next_attempt_at),
+            last_response_status: delivery.last_response_status,
+            last_error: delivery.last_error.clone(),
+            replay_of: delivery.replay_of,
+            created_at: delivery.created_at,
+            updated_at: delivery.updated_at,
+        }
+    }
+}
+
+impl From<&WebhookAttempt> for WebhookAttemptDto {
+    fn from(attempt: &WebhookAttempt) -> Self {
+        Self {
+            attempt: attempt.attempt,
+            response_status: attempt.response_status,
+            error: attempt.error.clone(),
+            attempted_at: attempt.attempted_at,
+        }
+    }
+}
@@ -1,5 +1,10 @@
-pub mod dto;
-pub mod services;
-
-pub use dto::*;
-pub use services::*;
+pub mod config;
+pub mod dto;
+pub mod services;
+
+pub use config::{
+    AuthConfig, ConfigError, EmailVerificationConfig, IdempotencyConfig, OutboxConfig, PaginationConfig, PasswordPolicy,
+    PasswordResetConfig, SoftDeleteConfig, WebhookConfig,
+};
+pub use dto::*;
+pub use services::*;
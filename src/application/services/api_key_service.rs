@@ -0,0 +1,134 @@
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        dto::{ApiKeyResponseDto, CreateApiKeyDto, CreatedApiKeyDto},
+        services::secret_token,
+    },
+    domain::{
+        current_timestamp, ApiKey, ApiKeyRepositoryPort, Principal, Role, Scope, UserError, UserId,
+        UserRepositoryPort,
+    },
+};
+
+/// Marks a string as one of our API keys
+const KEY_PREFIX: &str = "rnx_";
+/// Characters of the key kept in clear, after `KEY_PREFIX`
+const VISIBLE_CHARS: usize = 8;
+const MAX_NAME_LENGTH: usize = 100;
+/// `last_used_at` moves forward at most this often, so a busy key does not
+/// cost a write on every request
+const LAST_USED_RESOLUTION: chrono::Duration = chrono::Duration::minutes(5);
+
+/// Application service for API keys: minting, listing and revoking them
+/// (admins only) and resolving a presented key to the principal it acts as.
+#[derive(Clone)]
+pub struct ApiKeyService<R: UserRepositoryPort> {
+    users: R,
+    keys: Arc<dyn ApiKeyRepositoryPort>,
+}
+
+impl<R: UserRepositoryPort> ApiKeyService<R> {
+    pub fn new(users: R, keys: Arc<dyn ApiKeyRepositoryPort>) -> Self {
+        Self { users, keys }
+    }
+
+    /// Mint a key for an existing user; the secret is returned only here
+    pub async fn create_key(&self, actor: &Principal, dto: CreateApiKeyDto) -> Result<CreatedApiKeyDto, UserError> {
+        require_key_admin(actor)?;
+
+        let name = dto.name.trim().to_string();
+        if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+            return Err(UserError::InvalidApiKey(format!(
+                "name must be between 1 and {} characters",
+                MAX_NAME_LENGTH
+            )));
+        }
+        let mut scopes = dto.scopes.iter().map(|scope| Scope::parse(scope)).collect::<Result<Vec<_>, _>>()?;
+        scopes.sort_by_key(|scope| scope.as_str());
+        scopes.dedup();
+        if scopes.is_empty() {
+            return Err(UserError::InvalidApiKey("at least one scope is required".to_string()));
+        }
+        let now = current_timestamp();
+        if dto.expires_at.is_some_and(|expires_at| expires_at <= now) {
+            return Err(UserError::InvalidApiKey("expires_at must be in the future".to_string()));
+        }
+
+        let owner_id = dto.owner_id.map(UserId::from_uuid).unwrap_or_else(|| actor.user_id.clone());
+        if self.users.find_by_id(&owner_id).await?.is_none() {
+            return Err(UserError::NotFound);
+        }
+
+        let secret = secret_token::generate(KEY_PREFIX);
+        let api_key = ApiKey {
+            id: Uuid::new_v4(),
+            owner_id,
+            name,
+            prefix: secret[..KEY_PREFIX.len() + VISIBLE_CHARS].to_string(),
+            key_hash: secret_token::hash(&secret),
+            scopes,
+            created_at: now,
+            expires_at: dto.expires_at,
+            last_used_at: None,
+            revoked_at: None,
+        };
+        self.keys.save(&api_key).await?;
+
+        Ok(CreatedApiKeyDto {
+            key: secret,
+            api_key: ApiKeyResponseDto::from(&api_key),
+        })
+    }
+
+    /// Keys of one owner, or every key, newest first
+    pub async fn list_keys(&self, actor: &Principal, owner_id: Option<Uuid>) -> Result<Vec<ApiKeyResponseDto>, UserError> {
+        require_key_admin(actor)?;
+        let owner_id = owner_id.map(UserId::from_uuid);
+        let keys = self.keys.find_all(owner_id.as_ref()).await?;
+        Ok(keys.iter().map(ApiKeyResponseDto::from).collect())
+    }
+
+    /// Revoke a key; it stops authenticating immediately
+    pub async fn revoke_key(&self, actor: &Principal, id: Uuid) -> Result<(), UserError> {
+        require_key_admin(actor)?;
+        self.keys.revoke(id, current_timestamp()).await
+    }
+
+    /// Principal behind a presented key: its owner, with the owner's current
+    /// role, limited to the key's scopes
+    pub async fn authenticate(&self, key: &str) -> Result<Principal, UserError> {
+        let rejected = || UserError::Unauthenticated("invalid, expired or revoked API key".to_string());
+        let now = current_timestamp();
+        let api_key = self.keys.find_by_hash(&secret_token::hash(key)).await?
+            .filter(|api_key| api_key.is_active(now))
+            .ok_or_else(rejected)?;
+        let owner = self.users.find_by_id(&api_key.owner_id).await?
+            .ok_or_else(rejected)?;
+
+        // Bookkeeping only; a failure here must not fail the request
+        let stale = api_key.last_used_at.is_none_or(|last_used_at| now - last_used_at >= LAST_USED_RESOLUTION);
+        if stale && let Err(err) = self.keys.record_use(api_key.id, now).await {
+            tracing::warn!(error = %err, key_id = %api_key.id, "Could not record API key use");
+        }
+
+        Ok(Principal {
+            user_id: owner.id().clone(),
+            role: owner.role(),
+            scopes: Some(api_key.scopes),
+            expires_at: api_key.expires_at,
+        })
+    }
+}
+
+/// Keys are managed from an admin session, so a leaked key cannot mint more
+fn require_key_admin(actor: &Principal) -> Result<(), UserError> {
+    if actor.role != Role::Admin || actor.scopes.is_some() {
+        return Err(UserError::Forbidden(
+            "only admins signed in with a session can manage API keys".to_string(),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,207 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        config::PasswordPolicy,
+        dto::{LoginDto, RefreshTokenDto, TokenResponseDto},
+        services::secret_token,
+    },
+    domain::{
+        current_timestamp, AccessTokenPort, CredentialRepositoryPort, Email, Password, PasswordHash, PasswordHasherPort,
+        Principal, Session, SessionRepositoryPort, User, UserError, UserId, UserRepositoryPort,
+    },
+};
+
+/// Marks a string as one of our refresh tokens
+const REFRESH_TOKEN_PREFIX: &str = "rnr_";
+
+/// Application service for authentication use cases.
+/// Credentials, hashing, token signing and sessions are separate ports, held
+/// as trait objects so the service stays generic over the user repository only.
+#[derive(Clone)]
+pub struct AuthService<R: UserRepositoryPort> {
+    users: R,
+    credentials: Arc<dyn CredentialRepositoryPort>,
+    hasher: Arc<dyn PasswordHasherPort>,
+    tokens: Arc<dyn AccessTokenPort>,
+    sessions: Arc<dyn SessionRepositoryPort>,
+    refresh_token_ttl: Duration,
+    password_policy: PasswordPolicy,
+}
+
+impl<R: UserRepositoryPort> AuthService<R> {
+    pub fn new(
+        users: R,
+        credentials: Arc<dyn CredentialRepositoryPort>,
+        hasher: Arc<dyn PasswordHasherPort>,
+        tokens: Arc<dyn AccessTokenPort>,
+        sessions: Arc<dyn SessionRepositoryPort>,
+    ) -> Self {
+        Self {
+            users,
+            credentials,
+            hasher,
+            tokens,
+            sessions,
+            refresh_token_ttl: Duration::days(30),
+            password_policy: PasswordPolicy::default(),
+        }
+    }
+
+    /// Override how long an unused refresh token stays valid
+    pub fn with_refresh_token_ttl(mut self, ttl: Duration) -> Self {
+        self.refresh_token_ttl = ttl;
+        self
+    }
+
+    /// Override the rules new passwords must meet
+    pub fn with_password_policy(mut self, password_policy: PasswordPolicy) -> Self {
+        self.password_policy = password_policy;
+        self
+    }
+
+    /// Exchange email and password for an access token and a refresh token
+    /// starting a new session.
+    /// Every failure is the same `InvalidCredentials`, so callers cannot
+    /// probe which emails exist or have a password.
+    pub async fn login(&self, dto: LoginDto) -> Result<TokenResponseDto, UserError> {
+        let (Ok(email), Ok(password)) = (Email::new(dto.email), Password::new(dto.password)) else {
+            return Err(UserError::InvalidCredentials);
+        };
+        let user = self.users.find_by_email(&email).await?
+            .ok_or(UserError::InvalidCredentials)?;
+        let hash = self.credentials.find_password_hash(user.id()).await?
+            .ok_or(UserError::InvalidCredentials)?;
+
+        if !self.hasher.verify(&password, &hash).await? {
+            return Err(UserError::InvalidCredentials);
+        }
+
+        self.issue_tokens(&user, Uuid::new_v4(), current_timestamp()).await
+    }
+
+    /// Exchange a refresh token for new tokens. The presented token is used
+    /// up; presenting it again later is treated as theft and revokes every
+    /// token of its session.
+    pub async fn refresh(&self, dto: RefreshTokenDto) -> Result<TokenResponseDto, UserError> {
+        let rejected = || UserError::Unauthenticated("invalid or expired refresh token".to_string());
+        let session = self.sessions.find_by_token_hash(&secret_token::hash(&dto.refresh_token)).await?
+            .ok_or_else(rejected)?;
+        let now = current_timestamp();
+
+        if session.rotated_at.is_some() {
+            return Err(self.reuse_detected(&session, now).await);
+        }
+        if !session.is_active(now) {
+            return Err(rejected());
+        }
+        // Lost a race with a concurrent refresh of the same token
+        if !self.sessions.mark_rotated(session.id, now).await? {
+            return Err(self.reuse_detected(&session, now).await);
+        }
+
+        let Some(user) = self.users.find_by_id(&session.user_id).await? else {
+            self.sessions.revoke_family(session.family_id, now).await?;
+            return Err(rejected());
+        };
+        self.issue_tokens(&user, session.family_id, now).await
+    }
+
+    /// End the session a refresh token belongs to. Unknown tokens are
+    /// ignored, so logging out twice is harmless.
+    pub async fn logout(&self, dto: RefreshTokenDto) -> Result<(), UserError> {
+        let session = self.sessions.find_by_token_hash(&secret_token::hash(&dto.refresh_token)).await?;
+        if let Some(session) = session {
+            self.sessions.revoke_family(session.family_id, current_timestamp()).await?;
+        }
+        Ok(())
+    }
+
+    /// Revoke every session of a user; access tokens already issued stay
+    /// valid until they expire
+    pub async fn revoke_sessions(&self, user_id: &UserId) -> Result<u64, UserError> {
+        self.sessions.revoke_all_for_user(user_id, current_timestamp()).await
+    }
+
+    /// Whether the user is still signed in somewhere
+    pub async fn has_active_session(&self, user_id: &UserId) -> Result<bool, UserError> {
+        self.sessions.has_active(user_id, current_timestamp()).await
+    }
+
+    /// Drop sessions whose refresh token has expired
+    pub async fn purge_expired_sessions(&self) -> Result<u64, UserError> {
+        self.sessions.purge_expired(current_timestamp()).await
+    }
+
+    async fn issue_tokens(&self, user: &User, family_id: Uuid, now: DateTime<Utc>) -> Result<TokenResponseDto, UserError> {
+        let principal = Principal {
+            user_id: user.id().clone(),
+            role: user.role(),
+            scopes: None,
+            expires_at: None,
+        };
+        let access_token = self.tokens.issue(&principal)?;
+
+        let refresh_token = secret_token::generate(REFRESH_TOKEN_PREFIX);
+        let session = Session {
+            id: Uuid::new_v4(),
+            family_id,
+            user_id: user.id().clone(),
+            token_hash: secret_token::hash(&refresh_token),
+            created_at: now,
+            expires_at: now + self.refresh_token_ttl,
+            rotated_at: None,
+            revoked_at: None,
+        };
+        self.sessions.save(&session).await?;
+
+        Ok(TokenResponseDto::from(access_token).with_refresh_token(refresh_token, session.expires_at))
+    }
+
+    async fn reuse_detected(&self, session: &Session, now: DateTime<Utc>) -> UserError {
+        tracing::warn!(
+            user_id = %session.user_id.as_uuid(),
+            family_id = %session.family_id,
+            "Refresh token reused; revoking its session"
+        );
+        if let Err(err) = self.sessions.revoke_family(session.family_id, now).await {
+            return err;
+        }
+        UserError::Unauthenticated("refresh token was already used; the session has been revoked".to_string())
+    }
+
+    /// Identify the caller behind an access token
+    pub fn authenticate(&self, token: &str) -> Result<Principal, UserError> {
+        self.tokens.verify(token)
+    }
+
+    /// Token port, for request guards that validate without a repository
+    pub fn tokens(&self) -> Arc<dyn AccessTokenPort> {
+        Arc::clone(&self.tokens)
+    }
+
+    /// Whether `password` meets the password policy
+    pub fn check_password(&self, password: &Password) -> Result<(), UserError> {
+        self.password_policy.check(password)
+    }
+
+    /// Set or replace a user's password, if it meets the password policy
+    pub async fn set_password(&self, user_id: &UserId, password: &Password) -> Result<(), UserError> {
+        let hash = self.hash_password(password).await?;
+        self.store_password_hash(user_id, &hash).await
+    }
+
+    /// Hash `password` if it meets the password policy, without storing it
+    pub async fn hash_password(&self, password: &Password) -> Result<PasswordHash, UserError> {
+        self.check_password(password)?;
+        self.hasher.hash(password).await
+    }
+
+    /// Set or replace a user's password with one from `hash_password`
+    pub async fn store_password_hash(&self, user_id: &UserId, hash: &PasswordHash) -> Result<(), UserError> {
+        self.credentials.set_password_hash(user_id, hash).await
+    }
+}
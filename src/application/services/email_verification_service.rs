@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        config::EmailVerificationConfig,
+        dto::{ConfirmEmailDto, UserResponseDto, VerificationSentDto},
+        services::secret_token,
+    },
+    domain::{
+        current_timestamp, AuditAction, AuditContext, AuditEntry, EmailVerificationRepositoryPort,
+        EmailVerificationToken, MailMessage, MailerPort, UserError, UserEvent, UserId, UserRepositoryPort,
+    },
+};
+
+/// Marks a string as one of our email verification tokens
+const TOKEN_PREFIX: &str = "rnv_";
+
+/// Application service proving users own their email address: it mails a
+/// single-use token and marks the address verified once the token comes back.
+/// Who may request a token is decided by `UserApplicationService`.
+#[derive(Clone)]
+pub struct EmailVerificationService<R: UserRepositoryPort> {
+    users: R,
+    tokens: Arc<dyn EmailVerificationRepositoryPort>,
+    mailer: Arc<dyn MailerPort>,
+    config: EmailVerificationConfig,
+}
+
+impl<R: UserRepositoryPort> EmailVerificationService<R> {
+    pub fn new(users: R, tokens: Arc<dyn EmailVerificationRepositoryPort>, mailer: Arc<dyn MailerPort>) -> Self {
+        Self {
+            users,
+            tokens,
+            mailer,
+            config: EmailVerificationConfig::default(),
+        }
+    }
+
+    /// Override the token lifetime and confirmation link
+    pub fn with_config(mut self, config: EmailVerificationConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Mail a fresh token to the user's current address. Earlier tokens stay
+    /// valid until they expire.
+    pub async fn send(&self, user_id: &UserId) -> Result<VerificationSentDto, UserError> {
+        let user = self.users.find_by_id(user_id).await?
+            .ok_or(UserError::NotFound)?;
+        if user.email_verified_at().is_some() {
+            return Err(UserError::EmailAlreadyVerified);
+        }
+
+        let secret = secret_token::generate(TOKEN_PREFIX);
+        let now = current_timestamp();
+        let token = EmailVerificationToken {
+            id: Uuid::new_v4(),
+            user_id: user_id.clone(),
+            email: user.email().clone(),
+            token_hash: secret_token::hash(&secret),
+            created_at: now,
+            expires_at: now + self.config.token_ttl,
+            used_at: None,
+        };
+        self.tokens.save(&token).await?;
+
+        let link = match &self.config.confirm_url {
+            Some(url) => format!("Open {}{} or use", url, secret),
+            None => "Use".to_string(),
+        };
+        self.mailer
+            .send(&MailMessage {
+                to: token.email.clone(),
+                subject: "Verify your email address".to_string(),
+                body: format!(
+                    "Hello {},\n\n{} this token to verify {}:\n\n{}\n\nIt expires at {}.\n",
+                    user.name().as_str(),
+                    link,
+                    token.email.as_str(),
+                    secret,
+                    token.expires_at.to_rfc3339(),
+                ),
+            })
+            .await?;
+
+        Ok(VerificationSentDto {
+            email: token.email.as_str().to_string(),
+            expires_at: token.expires_at,
+        })
+    }
+
+    /// Mark the address a token was sent to as verified. The token is used
+    /// up, and is worthless once the user has changed its address. The change
+    /// is audited as made by the user, who proved to own the address.
+    pub async fn confirm(&self, dto: ConfirmEmailDto, request_id: Option<&str>) -> Result<UserResponseDto, UserError> {
+        let rejected = || UserError::InvalidToken("invalid or expired verification token".to_string());
+        let now = current_timestamp();
+        let token = self.tokens.find_by_token_hash(&secret_token::hash(&dto.token)).await?
+            .filter(|token| token.is_usable(now))
+            .ok_or_else(rejected)?;
+        let mut user = self.users.find_by_id(&token.user_id).await?
+            .filter(|user| *user.email() == token.email)
+            .ok_or_else(rejected)?;
+
+        if user.email_verified_at().is_none() {
+            let before = user.clone();
+            user.mark_email_verified(now);
+            let context = AuditContext {
+                actor: Some(token.user_id.clone()),
+                request_id: request_id.map(str::to_string),
+            };
+            let audit = AuditEntry::record(&context, AuditAction::Update, Some(&before), Some(&user));
+            let event = UserEvent::updated(&before, &user);
+            self.users.update_audited(&user, &audit, &event).await?;
+        }
+        // Verifying again with a raced copy of the token is harmless, so a
+        // lost race here needs no rollback
+        self.tokens.mark_used(token.id, now).await?;
+
+        Ok(UserResponseDto::from(&user))
+    }
+
+    /// Drop tokens past their expiry
+    pub async fn purge_expired_tokens(&self) -> Result<u64, UserError> {
+        self.tokens.purge_expired(current_timestamp()).await
+    }
+}
@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    application::config::IdempotencyConfig,
+    domain::{
+        current_timestamp, IdempotencyRecord, IdempotencyRepositoryPort, InfrastructureError, Principal,
+        StoredResponse, UserError,
+    },
+};
+
+/// Longest accepted `Idempotency-Key`
+const MAX_KEY_LENGTH: usize = 255;
+
+/// How a request with an idempotency key should proceed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdempotentStart {
+    /// First time: run the request, then `complete` or `release` the key
+    Run,
+    /// Repeated: answer with the first response
+    Replay(StoredResponse),
+}
+
+/// Application service for `Idempotency-Key` requests: a key is claimed
+/// before the request runs and keeps its response for the configured TTL,
+/// so retries get the same answer instead of running twice.
+#[derive(Clone)]
+pub struct IdempotencyService {
+    records: Arc<dyn IdempotencyRepositoryPort>,
+    config: IdempotencyConfig,
+}
+
+impl IdempotencyService {
+    pub fn new(records: Arc<dyn IdempotencyRepositoryPort>) -> Self {
+        Self {
+            records,
+            config: IdempotencyConfig::default(),
+        }
+    }
+
+    /// Override how long keys are kept
+    pub fn with_config(mut self, config: IdempotencyConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Key space of a caller. Anonymous callers cannot be told apart, so
+    /// they share one; clients should use random keys.
+    pub fn scope(actor: Option<&Principal>) -> String {
+        match actor {
+            Some(principal) => format!("user:{}", principal.user_id.as_uuid()),
+            None => "anonymous".to_string(),
+        }
+    }
+
+    /// Hex SHA-256 of a request body. Hashing the parsed DTO rather than the
+    /// raw bytes makes formatting and key order irrelevant.
+    pub fn fingerprint<T: Serialize>(request: &T) -> Result<String, UserError> {
+        let bytes = serde_json::to_vec(request)
+            .map_err(|e| UserError::Internal(InfrastructureError::new("fingerprint request", e)))?;
+        Ok(format!("{:x}", Sha256::digest(&bytes)))
+    }
+
+    /// Claim `key` for a request, or find the response to replay.
+    /// `IdempotencyKeyReused` if the key came with a different request,
+    /// `IdempotencyKeyInProgress` while its first request is still running.
+    pub async fn start(&self, scope: &str, key: &str, fingerprint: &str) -> Result<IdempotentStart, UserError> {
+        validate_key(key)?;
+        let now = current_timestamp();
+        let record = IdempotencyRecord {
+            scope: scope.to_string(),
+            key: key.to_string(),
+            fingerprint: fingerprint.to_string(),
+            response: None,
+            created_at: now,
+            expires_at: now + self.config.ttl,
+        };
+        if self.records.claim(&record, now - self.config.abandon_after).await? {
+            return Ok(IdempotentStart::Run);
+        }
+
+        match self.records.find(scope, key).await? {
+            Some(existing) if existing.fingerprint != fingerprint => Err(UserError::IdempotencyKeyReused),
+            Some(IdempotencyRecord {
+                response: Some(response),
+                ..
+            }) => Ok(IdempotentStart::Replay(response)),
+            // Still running, or purged in between; either way the client retries
+            _ => Err(UserError::IdempotencyKeyInProgress),
+        }
+    }
+
+    /// Keep the response of a claimed key for replays
+    pub async fn complete(&self, scope: &str, key: &str, response: &StoredResponse) -> Result<(), UserError> {
+        self.records.complete(scope, key, response).await
+    }
+
+    /// Free a claimed key whose request failed transiently, so a retry runs it
+    pub async fn release(&self, scope: &str, key: &str) -> Result<(), UserError> {
+        self.records.release(scope, key).await
+    }
+
+    /// Drop keys past their TTL
+    pub async fn purge_expired(&self) -> Result<u64, UserError> {
+        self.records.purge_expired(current_timestamp()).await
+    }
+}
+
+fn validate_key(key: &str) -> Result<(), UserError> {
+    if key.is_empty() || key.len() > MAX_KEY_LENGTH {
+        return Err(UserError::InvalidIdempotencyKey(format!(
+            "must be 1 to {} characters",
+            MAX_KEY_LENGTH
+        )));
+    }
+    if !key.chars().all(|c| c.is_ascii_graphic()) {
+        return Err(UserError::InvalidIdempotencyKey(
+            "must be printable ASCII without spaces".to_string(),
+        ));
+    }
+    Ok(())
+}
@@ -1,3 +1,20 @@
-pub mod user_app_service;
-
-pub use user_app_service::UserApplicationService;
+pub mod api_key_service;
+pub mod auth_service;
+pub mod email_verification_service;
+pub mod idempotency_service;
+pub mod outbox_relay;
+pub mod outbox_tail;
+pub mod password_reset_service;
+mod secret_token;
+pub mod user_app_service;
+pub mod webhook_service;
+
+pub use api_key_service::ApiKeyService;
+pub use auth_service::AuthService;
+pub use email_verification_service::EmailVerificationService;
+pub use idempotency_service::{IdempotencyService, IdempotentStart};
+pub use outbox_relay::{OutboxRelay, RelayReport};
+pub use outbox_tail::OutboxTail;
+pub use password_reset_service::PasswordResetService;
+pub use user_app_service::UserApplicationService;
+pub use webhook_service::{DispatchReport, WebhookService, webhook_signature};
@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use crate::{
+    application::config::OutboxConfig,
+    domain::{current_timestamp, EventPublisherPort, OutboxRepositoryPort, UserError},
+};
+
+/// Outcome of one relay pass
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RelayReport {
+    pub claimed: usize,
+    pub delivered: usize,
+    pub failed: usize,
+}
+
+/// Application service moving events from the transactional outbox to a
+/// sink. A message is marked sent only after the sink accepted it, so a
+/// crash in between delivers it again: consumers see every event at least
+/// once, and the events of one user in the order they were stored.
+#[derive(Clone)]
+pub struct OutboxRelay {
+    outbox: Arc<dyn OutboxRepositoryPort>,
+    sink: Arc<dyn EventPublisherPort>,
+    config: OutboxConfig,
+}
+
+impl OutboxRelay {
+    pub fn new(outbox: Arc<dyn OutboxRepositoryPort>, sink: Arc<dyn EventPublisherPort>) -> Self {
+        Self {
+            outbox,
+            sink,
+            config: OutboxConfig::default(),
+        }
+    }
+
+    /// Override batch sizes, leases and retry timing
+    pub fn with_config(mut self, config: OutboxConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn config(&self) -> &OutboxConfig {
+        &self.config
+    }
+
+    /// Claim one batch of due messages and hand each to the sink.
+    /// Failed deliveries are retried with exponential backoff; until then
+    /// later events of the same user wait behind them.
+    pub async fn relay_batch(&self) -> Result<RelayReport, UserError> {
+        let now = current_timestamp();
+        let messages = self.outbox.claim(now, self.config.lease, self.config.batch_size).await?;
+        let mut report = RelayReport {
+            claimed: messages.len(),
+            ..RelayReport::default()
+        };
+
+        for message in messages {
+            match self.sink.publish(&message.event).await {
+                Ok(()) => {
+                    self.outbox.mark_sent(message.seq, current_timestamp()).await?;
+                    report.delivered += 1;
+                }
+                Err(err) => {
+                    let retry_at = current_timestamp() + self.config.retry_delay(message.attempts);
+                    tracing::warn!(
+                        error = %err,
+                        seq = message.seq,
+                        event = message.event.event_type(),
+                        attempts = message.attempts,
+                        %retry_at,
+                        "Outbox delivery failed"
+                    );
+                    self.outbox.mark_failed(message.seq, &err.to_string(), retry_at).await?;
+                    report.failed += 1;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Delete delivered messages older than the retention window
+    pub async fn purge_sent(&self) -> Result<u64, UserError> {
+        self.outbox.purge_sent(current_timestamp() - self.config.retention).await
+    }
+}
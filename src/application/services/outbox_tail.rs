@@ -0,0 +1,123 @@
+use std::{
+    collections::BTreeSet,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    application::config::OutboxConfig,
+    domain::{EventPublisherPort, OutboxRepositoryPort, UserError},
+};
+
+/// A hole in `seq` still open after this long belongs to a transaction that
+/// rolled back, and is no longer waited for
+const HOLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Application service following the outbox for one process. Unlike
+/// `OutboxRelay` it claims nothing, so every instance hands every event to
+/// its sink; it feeds sinks that only reach this process, such as the streams
+/// to its clients. Events are passed on once stored, delivered or not, in
+/// `seq` order. Nothing is retried: a sink that fails misses the event.
+pub struct OutboxTail {
+    outbox: Arc<dyn OutboxRepositoryPort>,
+    sink: Arc<dyn EventPublisherPort>,
+    config: OutboxConfig,
+    /// Every message up to here was passed on; `None` until the first read,
+    /// which starts after the newest message
+    position: Option<i64>,
+    /// Messages passed on beyond `position`, behind a hole in `seq`.
+    /// Postgres hands out `seq` on insert, not on commit, so a message may
+    /// become visible after later ones.
+    ahead: BTreeSet<i64>,
+    /// Start of the hole after `position`, and when it was first seen
+    hole: Option<(i64, Instant)>,
+}
+
+impl OutboxTail {
+    pub fn new(outbox: Arc<dyn OutboxRepositoryPort>, sink: Arc<dyn EventPublisherPort>) -> Self {
+        Self {
+            outbox,
+            sink,
+            config: OutboxConfig::default(),
+            position: None,
+            ahead: BTreeSet::new(),
+            hole: None,
+        }
+    }
+
+    /// Override the poll interval and batch size
+    pub fn with_config(mut self, config: OutboxConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Start with the messages after `seq` instead of after the newest one
+    pub fn starting_after(mut self, seq: i64) -> Self {
+        self.position = Some(seq);
+        self
+    }
+
+    pub fn config(&self) -> &OutboxConfig {
+        &self.config
+    }
+
+    /// Pass on the messages stored since the last call, up to a batch of new
+    /// ones plus any that filled a hole; returns how many
+    pub async fn tail_batch(&mut self) -> Result<usize, UserError> {
+        let position = match self.position {
+            Some(position) => position,
+            None => *self.position.insert(self.outbox.last_seq().await?),
+        };
+        let newest = self.ahead.last().copied().unwrap_or(position);
+
+        let mut messages = Vec::new();
+        if newest > position {
+            let filled = self.outbox.find_after(position, newest - position).await?;
+            messages.extend(filled.into_iter().filter(|message| !self.ahead.contains(&message.seq)));
+        }
+        messages.extend(self.outbox.find_after(newest, self.config.batch_size).await?);
+
+        for message in &messages {
+            if let Err(err) = self.sink.publish(&message.event).await {
+                tracing::warn!(
+                    error = %err,
+                    seq = message.seq,
+                    event = message.event.event_type(),
+                    "Passing on an outbox message failed"
+                );
+            }
+            self.ahead.insert(message.seq);
+        }
+        self.advance(Instant::now());
+        Ok(messages.len())
+    }
+
+    /// Move `position` over the messages passed on without a hole before
+    /// them, and over holes that stayed open too long
+    fn advance(&mut self, now: Instant) {
+        let mut position = self.position.unwrap_or_default();
+        loop {
+            while self.ahead.first() == Some(&(position + 1)) {
+                self.ahead.pop_first();
+                position += 1;
+            }
+            let Some(&next) = self.ahead.first() else {
+                self.hole = None;
+                break;
+            };
+            match self.hole {
+                Some((start, since)) if start == position + 1 && now.duration_since(since) >= HOLE_TIMEOUT => {
+                    tracing::debug!(from = start, to = next - 1, "Skipping outbox seq values never committed");
+                    position = next - 1;
+                    self.hole = None;
+                }
+                Some((start, _)) if start == position + 1 => break,
+                _ => {
+                    self.hole = Some((position + 1, now));
+                    break;
+                }
+            }
+        }
+        self.position = Some(position);
+    }
+}
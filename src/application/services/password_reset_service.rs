@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        config::PasswordResetConfig,
+        dto::{PasswordResetConfirmDto, PasswordResetRequestDto},
+        services::{AuthService, secret_token},
+    },
+    domain::{
+        current_timestamp, Email, MailMessage, MailerPort, Password, PasswordResetRepositoryPort,
+        PasswordResetToken, UserError, UserRepositoryPort,
+    },
+};
+
+/// Marks a string as one of our password reset tokens
+const TOKEN_PREFIX: &str = "rnp_";
+
+/// Application service for self-service password resets: it mails a
+/// single-use token to a user's address and sets a new password when the
+/// token comes back, signing the user out everywhere.
+#[derive(Clone)]
+pub struct PasswordResetService<R: UserRepositoryPort> {
+    users: R,
+    auth: AuthService<R>,
+    tokens: Arc<dyn PasswordResetRepositoryPort>,
+    mailer: Arc<dyn MailerPort>,
+    config: PasswordResetConfig,
+}
+
+impl<R: UserRepositoryPort> PasswordResetService<R> {
+    pub fn new(
+        users: R,
+        auth: AuthService<R>,
+        tokens: Arc<dyn PasswordResetRepositoryPort>,
+        mailer: Arc<dyn MailerPort>,
+    ) -> Self {
+        Self {
+            users,
+            auth,
+            tokens,
+            mailer,
+            config: PasswordResetConfig::default(),
+        }
+    }
+
+    /// Override the token lifetime and reset link
+    pub fn with_config(mut self, config: PasswordResetConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Mail a reset token if the address belongs to a user. Unknown or
+    /// malformed addresses succeed silently, so callers cannot probe which
+    /// emails exist; only storage and mail failures are errors.
+    pub async fn request_reset(&self, dto: PasswordResetRequestDto) -> Result<(), UserError> {
+        let Ok(email) = Email::new(dto.email) else {
+            return Ok(());
+        };
+        let Some(user) = self.users.find_by_email(&email).await? else {
+            tracing::debug!("Password reset requested for an unknown email");
+            return Ok(());
+        };
+
+        let secret = secret_token::generate(TOKEN_PREFIX);
+        let now = current_timestamp();
+        let token = PasswordResetToken {
+            id: Uuid::new_v4(),
+            user_id: user.id().clone(),
+            token_hash: secret_token::hash(&secret),
+            created_at: now,
+            expires_at: now + self.config.token_ttl,
+            used_at: None,
+        };
+        self.tokens.save(&token).await?;
+
+        let link = match &self.config.reset_url {
+            Some(url) => format!("Open {}{} or use", url, secret),
+            None => "Use".to_string(),
+        };
+        self.mailer
+            .send(&MailMessage {
+                to: user.email().clone(),
+                subject: "Reset your password".to_string(),
+                body: format!(
+                    "Hello {},\n\n{} this token to choose a new password:\n\n{}\n\n\
+                     It expires at {}. If you did not ask for this, ignore this mail.\n",
+                    user.name().as_str(),
+                    link,
+                    secret,
+                    token.expires_at.to_rfc3339(),
+                ),
+            })
+            .await
+    }
+
+    /// Set a new password with a mailed token. The password is checked
+    /// before the token is used up, so a rejected password can be retried.
+    /// Every other reset token and every session of the user is revoked.
+    pub async fn confirm_reset(&self, dto: PasswordResetConfirmDto) -> Result<(), UserError> {
+        let rejected = || UserError::InvalidToken("invalid or expired password reset token".to_string());
+        let now = current_timestamp();
+        let token = self
+            .tokens
+            .find_by_token_hash(&secret_token::hash(&dto.token))
+            .await?
+            .filter(|token| token.is_usable(now))
+            .ok_or_else(rejected)?;
+        if self.users.find_by_id(&token.user_id).await?.is_none() {
+            return Err(rejected());
+        }
+
+        let password = Password::new(dto.new_password)?;
+        self.auth.check_password(&password)?;
+        if !self.tokens.mark_used(token.id, now).await? {
+            return Err(rejected());
+        }
+
+        self.auth.set_password(&token.user_id, &password).await?;
+        self.tokens.mark_all_used_for_user(&token.user_id, now).await?;
+        let revoked = self.auth.revoke_sessions(&token.user_id).await?;
+        tracing::info!(user_id = %token.user_id.as_uuid(), revoked_sessions = revoked, "Password reset");
+        Ok(())
+    }
+
+    /// Drop tokens past their expiry
+    pub async fn purge_expired_tokens(&self) -> Result<u64, UserError> {
+        self.tokens.purge_expired(current_timestamp()).await
+    }
+}
@@ -0,0 +1,21 @@
+//! Random secrets handed to clients (API keys, refresh tokens).
+//! They carry 256 random bits, so a fast SHA-256 is enough to store them.
+
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use password_hash::rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+
+/// Random bytes behind each secret
+const SECRET_BYTES: usize = 32;
+
+/// New secret, e.g. `rnx_...`, recognisable by its prefix
+pub(crate) fn generate(prefix: &str) -> String {
+    let mut bytes = [0u8; SECRET_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    format!("{}{}", prefix, URL_SAFE_NO_PAD.encode(bytes))
+}
+
+/// Hex SHA-256 of a secret, as stored and looked up
+pub(crate) fn hash(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
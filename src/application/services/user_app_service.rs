@@ -1,75 +1,641 @@
-use uuid::Uuid;
-
-use crate::{
-    application::dto::{CreateUserDto, UpdateUserDto, UserResponseDto},
-    domain::{UserDomainService, UserRepositoryPort, UserId, UserError},
-};
-
-/// Application service for User use cases
-/// Orchestrates domain services and handles cross-cutting concerns
-#[derive(Clone)]
-pub struct UserApplicationService<R: UserRepositoryPort> {
-    domain_service: UserDomainService<R>,
-    repository: R,
-}
-
-impl<R: UserRepositoryPort> UserApplicationService<R> {
-    pub fn new(repository: R) -> Self {
-        let domain_service = UserDomainService::new(repository.clone());
-        Self {
-            domain_service,
-            repository,
-        }
-    }
-
-    /// Create a new user
-    pub async fn create_user(&self, dto: CreateUserDto) -> Result<UserResponseDto, UserError> {
-        let (name, email) = dto.to_domain()?;
-        let user = self.domain_service.create_user(name, email).await?;
-        Ok(UserResponseDto::from(&user))
-    }
-
-    /// Get user by ID
-    pub async fn get_user_by_id(&self, id: Uuid) -> Result<Option<UserResponseDto>, UserError> {
-        let user_id = UserId::from_uuid(id);
-        if let Some(user) = self.repository.find_by_id(&user_id).await? {
-            Ok(Some(UserResponseDto::from(&user)))
-        } else {
-            Ok(None)
-        }
-    }
-
-    /// Update user
-    pub async fn update_user(&self, id: Uuid, dto: UpdateUserDto) -> Result<UserResponseDto, UserError> {
-        let user_id = UserId::from_uuid(id);
-        let mut user = self.repository.find_by_id(&user_id).await?
-            .ok_or(UserError::NotFound)?;
-        
-        let (name, email) = dto.to_domain()?;
-        self.domain_service.update_user(&mut user, name, email).await?;
-        
-        Ok(UserResponseDto::from(&user))
-    }
-
-    /// Delete user
-    pub async fn delete_user(&self, id: Uuid) -> Result<(), UserError> {
-        let user_id = UserId::from_uuid(id);
-        
-        // Check if user exists
-        if self.repository.find_by_id(&user_id).await?.is_none() {
-            return Err(UserError::NotFound);
-        }
-        
-        self.repository.delete(&user_id).await
-    }
-
-    /// Get all users with pagination
-    pub async fn get_all_users(&self, page: Option<i64>, limit: Option<i64>) -> Result<Vec<UserResponseDto>, UserError> {
-        let page = page.unwrap_or(0);
-        let limit = limit.unwrap_or(10);
-        let offset = page * limit;
-        
-        let users = self.repository.find_all(offset, limit).await?;
-        Ok(users.iter().map(UserResponseDto::from).collect())
-    }
-}
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        config::{PaginationConfig, SoftDeleteConfig},
+        services::{AuthService, EmailVerificationService, IdempotencyService, PasswordResetService},
+        dto::{
+            AuditEntryDto, AuditFilterDto, AuditPageDto, CreateUserDto, PaginationMeta, UpdateUserDto, UserFilterDto,
+            UserPageDto, UserPatchDto, UserResponseDto, VerificationSentDto, decode_cursor, encode_cursor,
+        },
+    },
+    domain::{
+        current_timestamp, AuditAction, AuditContext, AuditEntry, AuditLogRepositoryPort, AuditQuery, CountAccuracy,
+        Email, InfrastructureError, Principal, Role, Scope, User, UserCursor, UserEvent, UserQuery,
+        UserDomainService, UserRepositoryPort, UserId, UserName, UserError,
+    },
+};
+
+/// Application service for User use cases
+/// Orchestrates domain services and handles cross-cutting concerns.
+/// Every use case on behalf of a caller is checked against the role policy
+/// (see `authorize`) before anything is read or written.
+#[derive(Clone)]
+pub struct UserApplicationService<R: UserRepositoryPort> {
+    domain_service: UserDomainService<R>,
+    repository: R,
+    pagination: PaginationConfig,
+    soft_delete: SoftDeleteConfig,
+    auth: Option<AuthService<R>>,
+    email_verification: Option<EmailVerificationService<R>>,
+    password_reset: Option<PasswordResetService<R>>,
+    idempotency: Option<IdempotencyService>,
+    audit_log: Option<Arc<dyn AuditLogRepositoryPort>>,
+}
+
+impl<R: UserRepositoryPort> UserApplicationService<R> {
+    pub fn new(repository: R) -> Self {
+        let domain_service = UserDomainService::new(repository.clone());
+        Self {
+            domain_service,
+            repository,
+            pagination: PaginationConfig::default(),
+            soft_delete: SoftDeleteConfig::default(),
+            auth: None,
+            email_verification: None,
+            password_reset: None,
+            idempotency: None,
+            audit_log: None,
+        }
+    }
+
+    /// Override the default list limits
+    pub fn with_pagination(mut self, pagination: PaginationConfig) -> Self {
+        self.pagination = pagination;
+        self
+    }
+
+    /// Override the default retention of soft-deleted users
+    pub fn with_soft_delete(mut self, soft_delete: SoftDeleteConfig) -> Self {
+        self.soft_delete = soft_delete;
+        self
+    }
+
+    /// Enable passwords on new users
+    pub fn with_auth(mut self, auth: AuthService<R>) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Enable mailing email verification tokens
+    pub fn with_email_verification(mut self, email_verification: EmailVerificationService<R>) -> Self {
+        self.email_verification = Some(email_verification);
+        self
+    }
+
+    /// Enable purging expired password reset tokens
+    pub fn with_password_reset(mut self, password_reset: PasswordResetService<R>) -> Self {
+        self.password_reset = Some(password_reset);
+        self
+    }
+
+    /// Enable purging expired idempotency keys
+    pub fn with_idempotency(mut self, idempotency: IdempotencyService) -> Self {
+        self.idempotency = Some(idempotency);
+        self
+    }
+
+    /// Enable reading the audit log; changes are recorded either way
+    pub fn with_audit_log(mut self, audit_log: Arc<dyn AuditLogRepositoryPort>) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
+
+    /// Create a new user, with a password if one is given.
+    /// Sign-up needs no caller, but only an admin may create a user with a
+    /// role other than `user`. The password is validated before the user is stored.
+    /// Every change is audited under the caller and `request_id`.
+    pub async fn create_user(
+        &self,
+        actor: Option<&Principal>,
+        dto: CreateUserDto,
+        request_id: Option<&str>,
+    ) -> Result<UserResponseDto, UserError> {
+        let (name, email, role, password) = dto.into_domain()?;
+        let role = role.unwrap_or_default();
+        if role != Role::default() {
+            match actor {
+                Some(actor) => authorize(actor, UserAction::ChangeRole)?,
+                None => return Err(UserError::Forbidden("only admins can assign roles".to_string())),
+            }
+        }
+        // Hash up front, so only storing the hash can fail once the user exists
+        let password_hash = match (&password, &self.auth) {
+            (Some(password), Some(auth)) => Some(auth.hash_password(password).await?),
+            (Some(_), None) => {
+                return Err(UserError::InvalidPassword("password login is not enabled".to_string()));
+            }
+            (None, _) => None,
+        };
+
+        let context = AuditContext::new(actor, request_id);
+        let user = self.domain_service.create_user(name, email, role, &context).await?;
+        if let (Some(hash), Some(auth)) = (password_hash, &self.auth)
+            && let Err(err) = auth.store_password_hash(user.id(), &hash).await
+        {
+            // An account without its password cannot sign in; delete it so
+            // the sign-up can be retried with the same email
+            if let Err(cleanup) = self.domain_service.delete_user(&user, Some(user.version()), &context).await {
+                tracing::error!(error = %cleanup, user_id = %user.id().as_uuid(), "Could not undo a failed sign-up");
+            }
+            return Err(err);
+        }
+        Ok(UserResponseDto::from(&user))
+    }
+
+    /// Get user by ID; soft-deleted users are only returned when asked for
+    pub async fn get_user_by_id(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+        include_deleted: bool,
+    ) -> Result<Option<UserResponseDto>, UserError> {
+        let user_id = UserId::from_uuid(id);
+        authorize(actor, UserAction::Read(&user_id))?;
+        if include_deleted {
+            authorize(actor, UserAction::ViewDeleted)?;
+        }
+
+        let user = if include_deleted {
+            self.repository.find_by_id_including_deleted(&user_id).await?
+        } else {
+            self.repository.find_by_id(&user_id).await?
+        };
+        if let Some(user) = user {
+            Ok(Some(UserResponseDto::from(&user)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// The caller's own user, whatever its role
+    pub async fn get_current_user(&self, actor: &Principal) -> Result<Option<UserResponseDto>, UserError> {
+        let user = self.repository.find_by_id(&actor.user_id).await?;
+        Ok(user.as_ref().map(UserResponseDto::from))
+    }
+
+    /// Replace a user's writable fields (`PUT`).
+    /// With `expected_version` (from `If-Match`) the update only applies while
+    /// the user is still at that version, otherwise `PreconditionFailed`.
+    pub async fn update_user(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+        dto: UpdateUserDto,
+        expected_version: Option<i64>,
+        request_id: Option<&str>,
+    ) -> Result<UserResponseDto, UserError> {
+        let user = self.load_for_write(actor, id, expected_version).await?;
+        let (name, email, role) = dto.into_domain()?;
+        self.write_user(actor, user, (name, email, role), false, expected_version, request_id).await
+    }
+
+    /// Partially update a user with a merge patch or JSON patch (`PATCH`).
+    /// The patch applies to the current representation, and the result is
+    /// validated like a full replacement. It may also clear the user's
+    /// email verification.
+    pub async fn patch_user(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+        patch: UserPatchDto,
+        expected_version: Option<i64>,
+        request_id: Option<&str>,
+    ) -> Result<UserResponseDto, UserError> {
+        let user = self.load_for_write(actor, id, expected_version).await?;
+        let patched = patch.apply(&UserResponseDto::from(&user))?;
+        let changes = (patched.name, patched.email, patched.role);
+        self.write_user(actor, user, changes, patched.clear_email_verification, expected_version, request_id)
+            .await
+    }
+
+    async fn load_for_write(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+        expected_version: Option<i64>,
+    ) -> Result<User, UserError> {
+        let user_id = UserId::from_uuid(id);
+        authorize(actor, UserAction::Update(&user_id))?;
+        let user = self.repository.find_by_id(&user_id).await?
+            .ok_or(UserError::NotFound)?;
+        if let Some(expected) = expected_version {
+            check_version(expected, user.version())?;
+        }
+        Ok(user)
+    }
+
+    async fn write_user(
+        &self,
+        actor: &Principal,
+        mut user: User,
+        (name, email, role): (UserName, Email, Role),
+        clear_email_verification: bool,
+        expected_version: Option<i64>,
+        request_id: Option<&str>,
+    ) -> Result<UserResponseDto, UserError> {
+        // Restating the current role is not a change
+        let role = Some(role).filter(|role| *role != user.role());
+        if role.is_some() {
+            authorize(actor, UserAction::ChangeRole)?;
+        }
+
+        let context = AuditContext::new(Some(actor), request_id);
+        self.domain_service
+            .update_user(&mut user, Some(name), Some(email), role, clear_email_verification, &context)
+            .await
+            .map_err(|err| precondition_or(err, expected_version))?;
+        
+        Ok(UserResponseDto::from(&user))
+    }
+
+    /// Delete user, optionally only while it is at `expected_version`
+    pub async fn delete_user(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+        expected_version: Option<i64>,
+        request_id: Option<&str>,
+    ) -> Result<(), UserError> {
+        authorize(actor, UserAction::Delete)?;
+        // Check if user exists
+        let user = self.load_for_write(actor, id, expected_version).await?;
+        
+        let context = AuditContext::new(Some(actor), request_id);
+        self.domain_service
+            .delete_user(&user, expected_version, &context)
+            .await
+            .map_err(|err| precondition_or(err, expected_version))?;
+
+        // Refreshing already fails for deleted users; revoking keeps the
+        // session table honest. The delete stands even if this fails.
+        if let Some(auth) = &self.auth
+            && let Err(err) = auth.revoke_sessions(user.id()).await
+        {
+            tracing::warn!(error = %err, user_id = %id, "Could not revoke sessions of deleted user");
+        }
+        Ok(())
+    }
+
+    /// Sign a user out everywhere by revoking all of their refresh tokens.
+    /// Returns how many sessions were still live.
+    pub async fn revoke_sessions(&self, actor: &Principal, id: Uuid) -> Result<u64, UserError> {
+        authorize(actor, UserAction::RevokeSessions)?;
+        let user_id = UserId::from_uuid(id);
+        if self.repository.find_by_id_including_deleted(&user_id).await?.is_none() {
+            return Err(UserError::NotFound);
+        }
+        match &self.auth {
+            Some(auth) => auth.revoke_sessions(&user_id).await,
+            None => Ok(0),
+        }
+    }
+
+    /// Undo a soft delete while the user is still within retention
+    pub async fn restore_user(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+        request_id: Option<&str>,
+    ) -> Result<UserResponseDto, UserError> {
+        authorize(actor, UserAction::Restore)?;
+        let user_id = UserId::from_uuid(id);
+        let deleted = self.repository.find_by_id_including_deleted(&user_id).await?
+            .filter(User::is_deleted)
+            .ok_or(UserError::NotFound)?;
+
+        let context = AuditContext::new(Some(actor), request_id);
+        let audit = AuditEntry::record(&context, AuditAction::Restore, None, Some(&deleted));
+        let mut restored = deleted.clone();
+        let restored_at = current_timestamp();
+        restored.restore(restored_at);
+        let event = UserEvent::restored(&restored);
+        self.repository.restore_audited(&user_id, restored_at, &audit, &event).await?;
+
+        let user = self.repository.find_by_id(&user_id).await?
+            .ok_or(UserError::NotFound)?;
+        Ok(UserResponseDto::from(&user))
+    }
+
+    /// Permanently remove users deleted longer ago than the retention window
+    pub async fn purge_deleted_users(&self) -> Result<u64, UserError> {
+        let cutoff = current_timestamp() - self.soft_delete.retention;
+        self.repository.purge_deleted(cutoff).await
+    }
+
+    /// Drop sessions whose refresh token has expired
+    pub async fn purge_expired_sessions(&self) -> Result<u64, UserError> {
+        match &self.auth {
+            Some(auth) => auth.purge_expired_sessions().await,
+            None => Ok(0),
+        }
+    }
+
+    /// Mail a verification token to the user's current address
+    pub async fn send_email_verification(&self, actor: &Principal, id: Uuid) -> Result<VerificationSentDto, UserError> {
+        let user_id = UserId::from_uuid(id);
+        authorize(actor, UserAction::VerifyEmail(&user_id))?;
+        let Some(email_verification) = &self.email_verification else {
+            return Err(UserError::Internal(InfrastructureError::new(
+                "send verification email",
+                "email verification is not configured",
+            )));
+        };
+        email_verification.send(&user_id).await
+    }
+
+    /// Drop email verification tokens past their expiry
+    pub async fn purge_expired_verification_tokens(&self) -> Result<u64, UserError> {
+        match &self.email_verification {
+            Some(email_verification) => email_verification.purge_expired_tokens().await,
+            None => Ok(0),
+        }
+    }
+
+    /// Drop password reset tokens past their expiry
+    pub async fn purge_expired_password_reset_tokens(&self) -> Result<u64, UserError> {
+        match &self.password_reset {
+            Some(password_reset) => password_reset.purge_expired_tokens().await,
+            None => Ok(0),
+        }
+    }
+
+    /// Drop idempotency keys past their TTL
+    pub async fn purge_expired_idempotency_keys(&self) -> Result<u64, UserError> {
+        match &self.idempotency {
+            Some(idempotency) => idempotency.purge_expired().await,
+            None => Ok(0),
+        }
+    }
+
+    /// Changes to one user, newest first. Users may read their own history.
+    /// Entries outlive the user, so the history of a purged user stays readable.
+    pub async fn get_user_history(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+        page: Option<i64>,
+        limit: Option<i64>,
+    ) -> Result<AuditPageDto, UserError> {
+        let user_id = UserId::from_uuid(id);
+        authorize(actor, UserAction::History(&user_id))?;
+        let query = AuditQuery {
+            user_id: Some(user_id),
+            ..AuditQuery::default()
+        };
+        self.audit_page(&query, page, limit).await
+    }
+
+    /// Audit log entries across all users matching `filter`, newest first
+    pub async fn query_audit_log(
+        &self,
+        actor: &Principal,
+        filter: AuditFilterDto,
+        page: Option<i64>,
+        limit: Option<i64>,
+    ) -> Result<AuditPageDto, UserError> {
+        authorize(actor, UserAction::Audit)?;
+        let query = filter.into_domain()?;
+        self.audit_page(&query, page, limit).await
+    }
+
+    async fn audit_page(
+        &self,
+        query: &AuditQuery,
+        page: Option<i64>,
+        limit: Option<i64>,
+    ) -> Result<AuditPageDto, UserError> {
+        let Some(audit_log) = &self.audit_log else {
+            return Err(UserError::Internal(InfrastructureError::new(
+                "read audit log",
+                "the audit log is not configured",
+            )));
+        };
+        let limit = self.pagination.checked_limit(limit)?;
+        let page = page.unwrap_or(0);
+        let offset = offset_of(page, limit)?;
+
+        // Fetch one extra entry to learn whether another page follows
+        let mut entries = audit_log.find(query, offset, limit + 1).await?;
+        let has_more = entries.len() as i64 > limit;
+        entries.truncate(limit as usize);
+        let total = audit_log.count(query).await?;
+
+        Ok(AuditPageDto {
+            entries: entries.iter().map(AuditEntryDto::from).collect(),
+            pagination: PaginationMeta {
+                page: Some(page),
+                limit,
+                total,
+                total_estimated: false,
+                has_more,
+                next_cursor: None,
+                next: None,
+                prev: None,
+            },
+        })
+    }
+
+    /// Get all users with pagination.
+    /// `after` switches from page/offset to keyset pagination; every page
+    /// carries a `next_cursor` so clients can continue with `after`.
+    pub async fn get_all_users(
+        &self,
+        actor: &Principal,
+        filter: UserFilterDto,
+        page: Option<i64>,
+        limit: Option<i64>,
+        after: Option<String>,
+    ) -> Result<UserPageDto, UserError> {
+        authorize(actor, UserAction::List)?;
+        let query = filter.into_domain()?;
+        if query.include_deleted {
+            authorize(actor, UserAction::ViewDeleted)?;
+        }
+        let limit = self.pagination.checked_limit(limit)?;
+
+        // Fetch one extra row to learn whether another page follows
+        let (page, mut users) = match after {
+            Some(token) => {
+                if page.is_some() {
+                    return Err(UserError::InvalidPagination(
+                        "`after` cannot be combined with `page`".to_string(),
+                    ));
+                }
+                let cursor = decode_cursor(&token, &query.sort)?;
+                (None, self.repository.find_after(&query, Some(&cursor), limit + 1).await?)
+            }
+            None => {
+                let page = page.unwrap_or(0);
+                let offset = offset_of(page, limit)?;
+                (Some(page), self.repository.find_all(&query, offset, limit + 1).await?)
+            }
+        };
+
+        let has_more = users.len() as i64 > limit;
+        users.truncate(limit as usize);
+        let next_cursor = match users.last() {
+            Some(last) if has_more => Some(encode_cursor(&UserCursor::after(last, query.sort))),
+            _ => None,
+        };
+        let (total, total_estimated) = self.count_users(&query).await?;
+
+        Ok(UserPageDto {
+            users: users.iter().map(UserResponseDto::from).collect(),
+            pagination: PaginationMeta {
+                page,
+                limit,
+                total,
+                total_estimated,
+                has_more,
+                next_cursor,
+                next: None,
+                prev: None,
+            },
+        })
+    }
+
+    /// Check that the caller may watch changes to every user, or only to
+    /// `user_id`; the events themselves come from the event stream adapter
+    pub fn authorize_watch(&self, actor: &Principal, user_id: Option<Uuid>) -> Result<(), UserError> {
+        let user_id = user_id.map(UserId::from_uuid);
+        authorize(actor, UserAction::Watch(user_id.as_ref()))
+    }
+
+    /// Check that `principal` still stands for what it did when it was
+    /// authenticated, for connections that outlive a request: the user is
+    /// not deleted, has the same role and, for a user session, was not signed
+    /// out everywhere since
+    pub async fn confirm_principal(&self, principal: &Principal) -> Result<(), UserError> {
+        let ended = || UserError::Unauthenticated("the credentials are no longer valid".to_string());
+        let user = self.repository.find_by_id(&principal.user_id).await?
+            .ok_or_else(ended)?;
+        if user.role() != principal.role {
+            return Err(ended());
+        }
+        if principal.scopes.is_none()
+            && let Some(auth) = &self.auth
+            && !auth.has_active_session(&principal.user_id).await?
+        {
+            return Err(ended());
+        }
+        Ok(())
+    }
+
+    /// Exact total for small tables, planner estimate once it gets large
+    async fn count_users(&self, query: &UserQuery) -> Result<(i64, bool), UserError> {
+        let estimate = self.repository.count(query, CountAccuracy::Estimated).await?;
+        if estimate > self.pagination.exact_count_threshold {
+            return Ok((estimate, true));
+        }
+        Ok((self.repository.count(query, CountAccuracy::Exact).await?, false))
+    }
+}
+
+/// Operations the role policy tells apart
+#[derive(Debug, Clone, Copy)]
+enum UserAction<'a> {
+    List,
+    Read(&'a UserId),
+    Update(&'a UserId),
+    ChangeRole,
+    Delete,
+    Restore,
+    ViewDeleted,
+    RevokeSessions,
+    VerifyEmail(&'a UserId),
+    History(&'a UserId),
+    Audit,
+    /// Changes to one user, or to all of them
+    Watch(Option<&'a UserId>),
+}
+
+impl UserAction<'_> {
+    fn describe(&self) -> &'static str {
+        match self {
+            Self::List => "list users",
+            Self::Read(_) => "read this user",
+            Self::Update(_) => "update this user",
+            Self::ChangeRole => "change roles",
+            Self::Delete => "delete users",
+            Self::Restore => "restore users",
+            Self::ViewDeleted => "see deleted users",
+            Self::RevokeSessions => "revoke sessions",
+            Self::VerifyEmail(_) => "verify this user's email",
+            Self::History(_) => "read this user's history",
+            Self::Audit => "query the audit log",
+            Self::Watch(Some(_)) => "watch this user's changes",
+            Self::Watch(None) => "watch user changes",
+        }
+    }
+
+    /// Scope an API key needs for the action
+    fn required_scope(&self) -> Scope {
+        match self {
+            Self::List | Self::Read(_) | Self::ViewDeleted | Self::History(_) | Self::Audit | Self::Watch(_) => {
+                Scope::UsersRead
+            }
+            Self::Update(_)
+            | Self::ChangeRole
+            | Self::Delete
+            | Self::Restore
+            | Self::RevokeSessions
+            | Self::VerifyEmail(_) => Scope::UsersWrite,
+        }
+    }
+}
+
+/// The role policy: admins may do anything, users may only read, update,
+/// verify the email of, read the history of and watch themselves (without
+/// changing their role), read-only accounts may only list and watch.
+/// Callers using an API key are further limited to the key's scopes.
+fn authorize(actor: &Principal, action: UserAction<'_>) -> Result<(), UserError> {
+    let allowed = match (actor.role, action) {
+        (Role::Admin, _) => true,
+        (
+            Role::User,
+            UserAction::Read(id) | UserAction::Update(id) | UserAction::VerifyEmail(id) | UserAction::History(id),
+        ) => {
+            *id == actor.user_id
+        }
+        (Role::User, UserAction::Watch(Some(id))) => *id == actor.user_id,
+        (Role::ReadOnly, UserAction::List | UserAction::Watch(_)) => true,
+        _ => false,
+    };
+    if !allowed {
+        return Err(UserError::Forbidden(format!(
+            "{} accounts may not {}",
+            actor.role,
+            action.describe()
+        )));
+    }
+
+    let scope = action.required_scope();
+    if !actor.has_scope(scope) {
+        return Err(UserError::Forbidden(format!(
+            "the API key lacks the {} scope needed to {}",
+            scope.as_str(),
+            action.describe()
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) fn offset_of(page: i64, limit: i64) -> Result<i64, UserError> {
+    page.checked_mul(limit)
+        .filter(|_| page >= 0)
+        .ok_or_else(|| UserError::InvalidPagination("page must be a non-negative number".to_string()))
+}
+
+fn check_version(expected: i64, current: i64) -> Result<(), UserError> {
+    if expected != current {
+        return Err(UserError::PreconditionFailed(format!(
+            "expected version {} but the user is at version {}",
+            expected, current
+        )));
+    }
+    Ok(())
+}
+
+/// A write conditioned on a client-supplied version that lost a race is a
+/// failed precondition, not a retryable conflict
+fn precondition_or(err: UserError, expected_version: Option<i64>) -> UserError {
+    match err {
+        UserError::Conflict(_) if expected_version.is_some() => UserError::PreconditionFailed(
+            "the user was modified concurrently".to_string(),
+        ),
+        other => other,
+    }
+}
@@ -0,0 +1,363 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        config::{PaginationConfig, WebhookConfig},
+        dto::{
+            CreateWebhookDto, CreatedWebhookDto, DeliveryFilterDto, PaginationMeta, UpdateWebhookDto, UserEventDto,
+            WebhookAttemptDto, WebhookDeliveryDetailDto, WebhookDeliveryDto, WebhookDeliveryPageDto,
+            WebhookResponseDto,
+        },
+        services::{secret_token, user_app_service::offset_of},
+    },
+    domain::{
+        current_timestamp, DeliveryStatus, EventPublisherPort, InfrastructureError, Principal, Role, UserError,
+        UserEvent, WebhookAttempt, WebhookDelivery, WebhookRepositoryPort, WebhookRequest, WebhookSenderPort,
+        WebhookSubscription,
+    },
+};
+
+/// Marks a string as one of our webhook signing secrets
+const SECRET_PREFIX: &str = "whsec_";
+
+/// `t=<unix seconds>,v1=<hex HMAC-SHA256 of "<t>.<body>">`
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+/// Id of the delivery; a replay has an id of its own
+pub const DELIVERY_HEADER: &str = "X-Webhook-Delivery";
+/// Type of the event in the body, such as `user.created`
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+
+/// Outcome of one dispatch pass
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DispatchReport {
+    pub claimed: usize,
+    pub succeeded: usize,
+    /// Failed attempts that will be retried
+    pub retrying: usize,
+    pub dead_lettered: usize,
+}
+
+/// Application service for outgoing webhooks: managing subscriptions and
+/// their delivery log (admins only), queueing a delivery per subscribed
+/// event, and sending deliveries signed with the subscription's secret.
+/// Failed attempts are retried with exponential backoff until the
+/// configured maximum, then dead-lettered until replayed.
+#[derive(Clone)]
+pub struct WebhookService {
+    webhooks: Arc<dyn WebhookRepositoryPort>,
+    sender: Arc<dyn WebhookSenderPort>,
+    config: WebhookConfig,
+    pagination: PaginationConfig,
+}
+
+impl WebhookService {
+    pub fn new(webhooks: Arc<dyn WebhookRepositoryPort>, sender: Arc<dyn WebhookSenderPort>) -> Self {
+        Self {
+            webhooks,
+            sender,
+            config: WebhookConfig::default(),
+            pagination: PaginationConfig::default(),
+        }
+    }
+
+    /// Override batch sizes, leases and retry timing
+    pub fn with_config(mut self, config: WebhookConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Override the default delivery log limits
+    pub fn with_pagination(mut self, pagination: PaginationConfig) -> Self {
+        self.pagination = pagination;
+        self
+    }
+
+    pub fn config(&self) -> &WebhookConfig {
+        &self.config
+    }
+
+    /// Subscribe an endpoint; the secret is returned only here
+    pub async fn create_webhook(
+        &self,
+        actor: &Principal,
+        dto: CreateWebhookDto,
+    ) -> Result<CreatedWebhookDto, UserError> {
+        require_webhook_admin(actor)?;
+        let event_types = dto
+            .event_types
+            .unwrap_or_else(|| UserEvent::TYPES.iter().map(|event_type| event_type.to_string()).collect());
+        let secret = dto.secret.unwrap_or_else(|| secret_token::generate(SECRET_PREFIX));
+
+        let subscription = WebhookSubscription::new(&dto.url, &event_types, secret)?;
+        self.webhooks.save_subscription(&subscription).await?;
+
+        Ok(CreatedWebhookDto {
+            secret: subscription.secret.clone(),
+            webhook: WebhookResponseDto::from(&subscription),
+        })
+    }
+
+    /// Every subscription, oldest first
+    pub async fn list_webhooks(&self, actor: &Principal) -> Result<Vec<WebhookResponseDto>, UserError> {
+        require_webhook_admin(actor)?;
+        let subscriptions = self.webhooks.find_subscriptions().await?;
+        Ok(subscriptions.iter().map(WebhookResponseDto::from).collect())
+    }
+
+    pub async fn get_webhook(&self, actor: &Principal, id: Uuid) -> Result<WebhookResponseDto, UserError> {
+        require_webhook_admin(actor)?;
+        Ok(WebhookResponseDto::from(&self.subscription(id).await?))
+    }
+
+    /// Change a subscription; deliveries already queued keep their payload
+    pub async fn update_webhook(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+        dto: UpdateWebhookDto,
+    ) -> Result<WebhookResponseDto, UserError> {
+        require_webhook_admin(actor)?;
+        let mut subscription = self.subscription(id).await?;
+        subscription.update(dto.url.as_deref(), dto.event_types.as_deref(), dto.secret, dto.active)?;
+        self.webhooks.update_subscription(&subscription).await?;
+        Ok(WebhookResponseDto::from(&subscription))
+    }
+
+    /// Delete a subscription together with its delivery log
+    pub async fn delete_webhook(&self, actor: &Principal, id: Uuid) -> Result<(), UserError> {
+        require_webhook_admin(actor)?;
+        self.webhooks.delete_subscription(id).await
+    }
+
+    /// One page of a subscription's delivery log, newest first
+    pub async fn list_deliveries(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+        filter: DeliveryFilterDto,
+        page: Option<i64>,
+        limit: Option<i64>,
+    ) -> Result<WebhookDeliveryPageDto, UserError> {
+        require_webhook_admin(actor)?;
+        let status = filter.status.as_deref().map(DeliveryStatus::parse).transpose()?;
+        let limit = self.pagination.checked_limit(limit)?;
+        let page = page.unwrap_or(0);
+        let offset = offset_of(page, limit)?;
+        self.subscription(id).await?;
+
+        // Fetch one extra delivery to learn whether another page follows
+        let mut deliveries = self.webhooks.find_deliveries(id, status, offset, limit + 1).await?;
+        let has_more = deliveries.len() as i64 > limit;
+        deliveries.truncate(limit as usize);
+        let total = self.webhooks.count_deliveries(id, status).await?;
+
+        Ok(WebhookDeliveryPageDto {
+            deliveries: deliveries.iter().map(WebhookDeliveryDto::from).collect(),
+            pagination: PaginationMeta {
+                page: Some(page),
+                limit,
+                total,
+                total_estimated: false,
+                has_more,
+                next_cursor: None,
+                next: None,
+                prev: None,
+            },
+        })
+    }
+
+    /// A delivery with its payload and every attempt
+    pub async fn get_delivery(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+        delivery_id: Uuid,
+    ) -> Result<WebhookDeliveryDetailDto, UserError> {
+        require_webhook_admin(actor)?;
+        let delivery = self.delivery(id, delivery_id).await?;
+        let attempts = self.webhooks.find_attempts(delivery.id).await?;
+        let payload = serde_json::from_str(&delivery.payload)
+            .map_err(|e| UserError::Internal(InfrastructureError::new("decode webhook payload", e)))?;
+
+        Ok(WebhookDeliveryDetailDto {
+            delivery: WebhookDeliveryDto::from(&delivery),
+            payload,
+            attempt_log: attempts.iter().map(WebhookAttemptDto::from).collect(),
+        })
+    }
+
+    /// Send a delivery again, whatever its status, as a new pending delivery
+    /// with the same payload
+    pub async fn replay_delivery(
+        &self,
+        actor: &Principal,
+        id: Uuid,
+        delivery_id: Uuid,
+    ) -> Result<WebhookDeliveryDto, UserError> {
+        require_webhook_admin(actor)?;
+        let replay = self.delivery(id, delivery_id).await?.replay();
+        self.webhooks.enqueue_delivery(&replay).await?;
+        Ok(WebhookDeliveryDto::from(&replay))
+    }
+
+    /// Claim one batch of due deliveries and attempt each
+    pub async fn dispatch_batch(&self) -> Result<DispatchReport, UserError> {
+        let now = current_timestamp();
+        let deliveries = self.webhooks.claim_deliveries(now, self.config.lease, self.config.batch_size).await?;
+        let mut report = DispatchReport {
+            claimed: deliveries.len(),
+            ..DispatchReport::default()
+        };
+
+        for delivery in deliveries {
+            match self.attempt(delivery).await? {
+                Some(DeliveryStatus::Succeeded) => report.succeeded += 1,
+                Some(DeliveryStatus::Pending) => report.retrying += 1,
+                Some(DeliveryStatus::DeadLettered) => report.dead_lettered += 1,
+                None => {}
+            }
+        }
+        Ok(report)
+    }
+
+    /// POST a claimed delivery and record the outcome; `None` when its
+    /// subscription was deleted in the meantime
+    async fn attempt(&self, mut delivery: WebhookDelivery) -> Result<Option<DeliveryStatus>, UserError> {
+        let Some(subscription) = self.webhooks.find_subscription(delivery.subscription_id).await? else {
+            return Ok(None);
+        };
+
+        let request = signed_request(&subscription, &delivery, current_timestamp().timestamp());
+        let (response_status, error) = match self.sender.send(&request).await {
+            Ok(status) if (200..300).contains(&status) => (Some(status as i32), None),
+            Ok(status) => (Some(status as i32), Some(format!("receiver answered with status {}", status))),
+            Err(err) => (None, Some(error_chain(&err))),
+        };
+
+        let attempted_at = current_timestamp();
+        delivery.status = match &error {
+            None => DeliveryStatus::Succeeded,
+            Some(_) if delivery.attempts >= self.config.max_attempts => DeliveryStatus::DeadLettered,
+            Some(_) => DeliveryStatus::Pending,
+        };
+        if delivery.status == DeliveryStatus::Pending {
+            delivery.next_attempt_at = attempted_at + self.config.retry_delay(delivery.attempts);
+        }
+        delivery.last_response_status = response_status;
+        delivery.last_error = error.clone();
+        delivery.updated_at = attempted_at;
+
+        if let Some(error) = &error {
+            tracing::warn!(
+                error = %error,
+                delivery_id = %delivery.id,
+                subscription_id = %subscription.id,
+                attempts = delivery.attempts,
+                status = delivery.status.as_str(),
+                "Webhook delivery failed"
+            );
+        }
+
+        let attempt = WebhookAttempt {
+            delivery_id: delivery.id,
+            attempt: delivery.attempts,
+            response_status,
+            error,
+            attempted_at,
+        };
+        self.webhooks.record_attempt(&delivery, &attempt).await?;
+        Ok(Some(delivery.status))
+    }
+
+    async fn subscription(&self, id: Uuid) -> Result<WebhookSubscription, UserError> {
+        self.webhooks.find_subscription(id).await?.ok_or(UserError::WebhookNotFound)
+    }
+
+    /// A delivery of subscription `id`
+    async fn delivery(&self, id: Uuid, delivery_id: Uuid) -> Result<WebhookDelivery, UserError> {
+        self.subscription(id).await?;
+        self.webhooks
+            .find_delivery(delivery_id)
+            .await?
+            .filter(|delivery| delivery.subscription_id == id)
+            .ok_or(UserError::WebhookDeliveryNotFound)
+    }
+}
+
+/// Queues one delivery of the event per active subscription that wants it.
+/// Queueing the same event twice adds nothing, so this is safe as the sink
+/// of an at-least-once relay.
+#[async_trait]
+impl EventPublisherPort for WebhookService {
+    async fn publish(&self, event: &UserEvent) -> Result<(), UserError> {
+        let subscriptions = self.webhooks.find_subscriptions().await?;
+        let subscribed: Vec<_> = subscriptions.iter().filter(|subscription| subscription.wants(event)).collect();
+        if subscribed.is_empty() {
+            return Ok(());
+        }
+
+        let payload = serde_json::to_string(&UserEventDto::from(event))
+            .map_err(|e| UserError::Internal(InfrastructureError::new("encode webhook payload", e)))?;
+        for subscription in subscribed {
+            match self.webhooks.enqueue_delivery(&WebhookDelivery::new(subscription.id, event, payload.clone())).await {
+                // Deleted since it was listed
+                Ok(_) | Err(UserError::WebhookNotFound) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Value of `SIGNATURE_HEADER` for `body` sent at `timestamp` (Unix seconds).
+/// Receivers recompute it with their copy of the secret, and should reject
+/// old timestamps to stop replayed requests.
+pub fn webhook_signature(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    format!("t={},v1={:x}", timestamp, mac.finalize().into_bytes())
+}
+
+fn signed_request(subscription: &WebhookSubscription, delivery: &WebhookDelivery, timestamp: i64) -> WebhookRequest {
+    WebhookRequest {
+        url: subscription.url.clone(),
+        headers: vec![
+            ("Content-Type".to_string(), "application/json".to_string()),
+            (EVENT_HEADER.to_string(), delivery.event_type.clone()),
+            (DELIVERY_HEADER.to_string(), delivery.id.to_string()),
+            (
+                SIGNATURE_HEADER.to_string(),
+                webhook_signature(&subscription.secret, timestamp, &delivery.payload),
+            ),
+        ],
+        body: delivery.payload.clone(),
+    }
+}
+
+/// Message of `err` followed by those of its sources
+fn error_chain(err: &dyn std::error::Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    message
+}
+
+/// Subscriptions hold signing secrets and reach out to any URL, so they are
+/// managed from an admin session, like API keys
+fn require_webhook_admin(actor: &Principal) -> Result<(), UserError> {
+    if actor.role != Role::Admin || actor.scopes.is_some() {
+        return Err(UserError::Forbidden(
+            "only admins signed in with a session can manage webhooks".to_string(),
+        ));
+    }
+    Ok(())
+}
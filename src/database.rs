@@ -1,19 +1,79 @@
 use anyhow::Result;
 use sqlx::{PgPool, Postgres, migrate::MigrateDatabase, postgres::PgPoolOptions};
+#[cfg(feature = "sqlite")]
+use sqlx::{
+    SqlitePool,
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions},
+};
 
 // Database optimization modules
 pub mod config;
 
-pub use config::DatabaseConfig;
+pub use config::{DatabaseConfig, RepositoryBackend};
+
+/// Connection pool for the backend selected by the `DATABASE_URL` scheme
+#[derive(Debug, Clone)]
+pub enum DatabasePool {
+    Postgres(PgPool),
+    #[cfg(feature = "sqlite")]
+    Sqlite(SqlitePool),
+}
 
 /// Sets up the database connection with optimized pool configuration
 /// Returns a ready-to-use database connection pool
-pub async fn setup_database() -> Result<PgPool> {
-    let config = DatabaseConfig::from_env();
+pub async fn setup_database() -> Result<DatabasePool> {
+    let config = DatabaseConfig::from_env()?;
+
+    if is_sqlite_url(&config.url) {
+        return setup_sqlite(&config).await;
+    }
+
     create_database_if_not_exists(&config.url).await?;
     let pool = create_optimized_connection_pool(&config).await?;
     run_migrations(&pool).await?;
-    Ok(pool)
+    Ok(DatabasePool::Postgres(pool))
+}
+
+fn is_sqlite_url(url: &str) -> bool {
+    url.starts_with("sqlite:")
+}
+
+#[cfg(feature = "sqlite")]
+async fn setup_sqlite(config: &DatabaseConfig) -> Result<DatabasePool> {
+    use std::str::FromStr;
+
+    tracing::info!(
+        "Creating SQLite pool: max_connections={}, acquire_timeout={:?}",
+        config.max_connections,
+        config.acquire_timeout
+    );
+
+    // WAL lets readers proceed while a write is in progress; the busy timeout
+    // makes writers wait for the lock instead of failing straight away
+    let options = SqliteConnectOptions::from_str(&config.url)?
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .busy_timeout(config.acquire_timeout);
+
+    let pool = SqlitePoolOptions::new()
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .acquire_timeout(config.acquire_timeout)
+        .idle_timeout(Some(config.idle_timeout))
+        .max_lifetime(Some(config.max_lifetime))
+        .connect_with(options)
+        .await?;
+
+    tracing::info!("Running SQLite migrations...");
+    sqlx::migrate!("./migrations/sqlite").run(&pool).await?;
+    tracing::info!("Migrations completed successfully");
+
+    Ok(DatabasePool::Sqlite(pool))
+}
+
+#[cfg(not(feature = "sqlite"))]
+async fn setup_sqlite(_config: &DatabaseConfig) -> Result<DatabasePool> {
+    anyhow::bail!("DATABASE_URL points to SQLite, but this build lacks the `sqlite` feature")
 }
 
 /// Creates an optimized connection pool with performance tuning
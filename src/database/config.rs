@@ -1,5 +1,7 @@
 use std::time::Duration;
 
+use crate::application::config::{ConfigError, env_var, parse_var, positive_var};
+
 /// Database configuration for optimal performance
 #[derive(Debug, Clone)]
 pub struct DatabaseConfig {
@@ -13,36 +15,20 @@ pub struct DatabaseConfig {
 }
 
 impl DatabaseConfig {
-    pub fn from_env() -> Self {
-        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let url = env_var("DATABASE_URL").ok_or_else(|| ConfigError::Missing("DATABASE_URL".to_string()))?;
         
         // Parse environment variables with sensible defaults for high load
-        let max_connections = std::env::var("DB_MAX_CONNECTIONS")
-            .unwrap_or_else(|_| "100".to_string())  // Increased from 50 to 100
-            .parse()
-            .unwrap_or(100);
-            
-        let min_connections = std::env::var("DB_MIN_CONNECTIONS")
-            .unwrap_or_else(|_| "10".to_string())   // Increased from 5 to 10
-            .parse()
-            .unwrap_or(10);
-            
-        let acquire_timeout_secs = std::env::var("DB_ACQUIRE_TIMEOUT_SECS")
-            .unwrap_or_else(|_| "5".to_string())    // Increased from 3 to 5 seconds
-            .parse()
-            .unwrap_or(5);
-            
-        let idle_timeout_secs = std::env::var("DB_IDLE_TIMEOUT_SECS")
-            .unwrap_or_else(|_| "600".to_string())
-            .parse()
-            .unwrap_or(600);
-            
-        let max_lifetime_secs = std::env::var("DB_MAX_LIFETIME_SECS")
-            .unwrap_or_else(|_| "1800".to_string())
-            .parse()
-            .unwrap_or(1800);
+        let max_connections = positive_var("DB_MAX_CONNECTIONS")?
+            .unwrap_or(100); // Increased from 50 to 100
+        let min_connections = parse_var("DB_MIN_CONNECTIONS", "a whole number")?
+            .unwrap_or(10); // Increased from 5 to 10
+        let acquire_timeout_secs = positive_var("DB_ACQUIRE_TIMEOUT_SECS")?
+            .unwrap_or(5); // Increased from 3 to 5 seconds
+        let idle_timeout_secs = positive_var("DB_IDLE_TIMEOUT_SECS")?.unwrap_or(600);
+        let max_lifetime_secs = positive_var("DB_MAX_LIFETIME_SECS")?.unwrap_or(1800);
 
-        Self {
+        Ok(Self {
             url,
             max_connections,
             min_connections,
@@ -50,6 +36,25 @@ impl DatabaseConfig {
             idle_timeout: Duration::from_secs(idle_timeout_secs),
             max_lifetime: Duration::from_secs(max_lifetime_secs),
             test_before_acquire: true,
+        })
+    }
+}
+
+/// Storage backend behind the user repository port
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepositoryBackend {
+    /// SQL database selected by the `DATABASE_URL` scheme (Postgres or SQLite)
+    Database,
+    Memory,
+}
+
+impl RepositoryBackend {
+    /// Read `REPOSITORY_BACKEND` (`database` by default, or `memory`)
+    pub fn from_env() -> Result<Self, ConfigError> {
+        match env_var("REPOSITORY_BACKEND").map(|value| value.to_lowercase()).as_deref() {
+            Some("memory" | "in-memory" | "inmemory") => Ok(Self::Memory),
+            None | Some("database" | "postgres" | "postgresql" | "sqlite") => Ok(Self::Database),
+            Some(other) => Err(ConfigError::invalid("REPOSITORY_BACKEND", "'database' or 'memory'", other)),
         }
     }
 }
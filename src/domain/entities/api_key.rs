@@ -0,0 +1,69 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::domain::entities::{UserError, UserId};
+
+/// Permission granted to an API key; sessions are not limited by scopes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scope {
+    /// List and read users
+    UsersRead,
+    /// Create, update, delete and restore users
+    UsersWrite,
+}
+
+/// Key for service-to-service access. Only a hash of the secret is kept;
+/// the key acts as its owner, limited to its scopes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub owner_id: UserId,
+    /// Label chosen when the key was minted
+    pub name: String,
+    /// First characters of the key, to recognise it in listings
+    pub prefix: String,
+    /// SHA-256 of the full key, hex encoded
+    pub key_hash: String,
+    pub scopes: Vec<Scope>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl Scope {
+    pub fn parse(scope: &str) -> Result<Self, UserError> {
+        match scope.trim() {
+            "users:read" => Ok(Self::UsersRead),
+            "users:write" => Ok(Self::UsersWrite),
+            other => Err(UserError::InvalidApiKey(format!(
+                "unknown scope `{}`, expected users:read or users:write",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::UsersRead => "users:read",
+            Self::UsersWrite => "users:write",
+        }
+    }
+
+    /// Parse a space-separated scope list, as stored
+    pub fn parse_list(scopes: &str) -> Result<Vec<Self>, UserError> {
+        scopes.split_whitespace().map(Self::parse).collect()
+    }
+
+    /// Space-separated scope list, as stored
+    pub fn join(scopes: &[Self]) -> String {
+        scopes.iter().map(Self::as_str).collect::<Vec<_>>().join(" ")
+    }
+}
+
+impl ApiKey {
+    /// Whether the key may still authenticate at `now`
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|expires_at| expires_at > now)
+    }
+}
@@ -0,0 +1,131 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::entities::{Principal, User, UserError, UserId, current_timestamp};
+
+/// Kind of change an audit entry records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+    Restore,
+}
+
+/// One field as it was before and after a change; `None` on the side where
+/// the user did not exist (before a create, after a delete)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// Who made a change and as part of which request
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditContext {
+    /// `None` for anonymous sign-up
+    pub actor: Option<UserId>,
+    pub request_id: Option<String>,
+}
+
+/// Record of one change to a user, written in the same transaction as the
+/// change itself
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    pub id: Uuid,
+    pub user_id: UserId,
+    pub actor: Option<UserId>,
+    pub action: AuditAction,
+    /// Fields that differ between the states before and after the change
+    pub changes: Vec<FieldChange>,
+    pub request_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditAction {
+    pub fn parse(action: &str) -> Result<Self, UserError> {
+        match action.trim().to_lowercase().as_str() {
+            "create" => Ok(Self::Create),
+            "update" => Ok(Self::Update),
+            "delete" => Ok(Self::Delete),
+            "restore" => Ok(Self::Restore),
+            other => Err(UserError::InvalidQuery(format!(
+                "unknown audit action `{}`, expected create, update, delete or restore",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Create => "create",
+            Self::Update => "update",
+            Self::Delete => "delete",
+            Self::Restore => "restore",
+        }
+    }
+}
+
+impl std::fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl AuditContext {
+    pub fn new(actor: Option<&Principal>, request_id: Option<&str>) -> Self {
+        Self {
+            actor: actor.map(|principal| principal.user_id.clone()),
+            request_id: request_id.map(str::to_string),
+        }
+    }
+}
+
+impl AuditEntry {
+    /// Entry for `action` on the user described by `before` and `after`;
+    /// at least one of them must be given
+    pub fn record(context: &AuditContext, action: AuditAction, before: Option<&User>, after: Option<&User>) -> Self {
+        let user_id = after
+            .or(before)
+            .map(|user| user.id().clone())
+            .expect("an audited change has a user before or after it");
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            actor: context.actor.clone(),
+            action,
+            changes: FieldChange::between(before, after),
+            request_id: context.request_id.clone(),
+            created_at: current_timestamp(),
+        }
+    }
+}
+
+impl FieldChange {
+    /// Audited fields that differ between two states of a user.
+    /// Versions and timestamps other than the verification are left out,
+    /// since every change moves them.
+    pub fn between(before: Option<&User>, after: Option<&User>) -> Vec<Self> {
+        let fields = |user: Option<&User>| -> [(&'static str, Option<String>); 4] {
+            [
+                ("name", user.map(|u| u.name().as_str().to_string())),
+                ("email", user.map(|u| u.email().as_str().to_string())),
+                ("role", user.map(|u| u.role().as_str().to_string())),
+                ("email_verified_at", user.and_then(|u| u.email_verified_at()).map(|at| at.to_rfc3339())),
+            ]
+        };
+
+        fields(before)
+            .into_iter()
+            .zip(fields(after))
+            .filter(|((_, before), (_, after))| before != after)
+            .map(|((field, before), (_, after))| Self {
+                field: field.to_string(),
+                before,
+                after,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,84 @@
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+
+use crate::domain::entities::{Role, Scope, UserError, UserId};
+
+const MIN_PASSWORD_LENGTH: usize = 8;
+const MAX_PASSWORD_LENGTH: usize = 128;
+
+/// Value object for a plaintext password with validation.
+/// Only ever held transiently; `Debug` never prints it.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Password(String);
+
+/// Value object for a stored password hash (PHC string format)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordHash(String);
+
+/// Identity of an authenticated caller
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    pub user_id: UserId,
+    /// Role at the time the token was issued
+    pub role: Role,
+    /// Scopes of the API key the caller used; `None` for user sessions,
+    /// which are limited by role only
+    pub scopes: Option<Vec<Scope>>,
+    /// When the credentials stop being accepted; `None` if they never expire
+    /// or were not checked yet
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Signed access token handed to a client after login
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessToken {
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl Password {
+    pub fn new(password: String) -> Result<Self, UserError> {
+        let length = password.chars().count();
+        if length < MIN_PASSWORD_LENGTH {
+            return Err(UserError::InvalidPassword(format!(
+                "Password must be at least {} characters",
+                MIN_PASSWORD_LENGTH
+            )));
+        }
+        if length > MAX_PASSWORD_LENGTH {
+            return Err(UserError::InvalidPassword(format!(
+                "Password cannot exceed {} characters",
+                MAX_PASSWORD_LENGTH
+            )));
+        }
+        Ok(Self(password))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Password {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Password(<redacted>)")
+    }
+}
+
+impl Principal {
+    /// Whether the caller's credentials allow `scope`
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        self.scopes.as_ref().is_none_or(|scopes| scopes.contains(&scope))
+    }
+}
+
+impl PasswordHash {
+    pub fn new(hash: String) -> Self {
+        Self(hash)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
@@ -0,0 +1,26 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::domain::entities::{Email, UserId};
+
+/// Single-use proof of email ownership, mailed to the address it verifies.
+/// Confirming it only counts while the user still has that address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmailVerificationToken {
+    pub id: Uuid,
+    pub user_id: UserId,
+    /// Address the token was sent to
+    pub email: Email,
+    /// SHA-256 of the token, hex encoded
+    pub token_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+impl EmailVerificationToken {
+    /// Whether the token may still be confirmed at `now`
+    pub fn is_usable(&self, now: DateTime<Utc>) -> bool {
+        self.used_at.is_none() && self.expires_at > now
+    }
+}
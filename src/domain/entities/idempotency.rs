@@ -0,0 +1,26 @@
+use chrono::{DateTime, Utc};
+
+/// A request made with an `Idempotency-Key`, and once it finished, the
+/// response to replay when the request is repeated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdempotencyRecord {
+    /// Whose key it is, e.g. `user:<id>` or `anonymous`; keys of different
+    /// callers never collide
+    pub scope: String,
+    pub key: String,
+    /// SHA-256 of the request, hex encoded
+    pub fingerprint: String,
+    /// `None` while the first request is still running
+    pub response: Option<StoredResponse>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Response as sent the first time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredResponse {
+    pub status: u16,
+    /// Headers worth replaying, e.g. `content-type` and `etag`
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
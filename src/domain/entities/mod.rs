@@ -1,3 +1,21 @@
-pub mod user;
-
-pub use user::{User, UserId, UserName, Email, UserError};
+pub mod api_key;
+pub mod audit;
+pub mod auth;
+pub mod email_verification;
+pub mod idempotency;
+pub mod password_reset;
+pub mod session;
+pub mod user;
+pub mod user_event;
+pub mod webhook;
+
+pub use api_key::{ApiKey, Scope};
+pub use audit::{AuditAction, AuditContext, AuditEntry, FieldChange};
+pub use auth::{AccessToken, Password, PasswordHash, Principal};
+pub use email_verification::EmailVerificationToken;
+pub use idempotency::{IdempotencyRecord, StoredResponse};
+pub use password_reset::PasswordResetToken;
+pub use session::Session;
+pub use user::{current_timestamp, Role, User, UserId, UserName, Email, UserError, InfrastructureError};
+pub use user_event::{UserCreated, UserDeleted, UserEvent, UserRestored, UserUpdated};
+pub use webhook::{DeliveryStatus, WebhookAttempt, WebhookDelivery, WebhookSubscription};
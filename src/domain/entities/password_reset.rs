@@ -0,0 +1,23 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::domain::entities::UserId;
+
+/// Single-use permission to set a new password, mailed to the user's address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordResetToken {
+    pub id: Uuid,
+    pub user_id: UserId,
+    /// SHA-256 of the token, hex encoded
+    pub token_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+impl PasswordResetToken {
+    /// Whether the token may still be redeemed at `now`
+    pub fn is_usable(&self, now: DateTime<Utc>) -> bool {
+        self.used_at.is_none() && self.expires_at > now
+    }
+}
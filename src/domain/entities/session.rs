@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::domain::entities::UserId;
+
+/// One refresh token of a login session. Every refresh rotates the token:
+/// the used one is marked `rotated_at` and a successor joins the same
+/// `family_id`, so presenting a rotated token again reveals theft.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub id: Uuid,
+    /// Shared by every token descended from one login
+    pub family_id: Uuid,
+    pub user_id: UserId,
+    /// SHA-256 of the refresh token, hex encoded
+    pub token_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// When the token was exchanged for its successor
+    pub rotated_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl Session {
+    /// Whether the token may still be exchanged at `now`
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.rotated_at.is_none() && self.revoked_at.is_none() && self.expires_at > now
+    }
+}
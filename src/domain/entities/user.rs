@@ -1,156 +1,415 @@
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
-use uuid::Uuid;
-
-/// Domain entity representing a User
-/// This is the core business entity, free from infrastructure concerns
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct User {
-    id: UserId,
-    name: UserName,
-    email: Email,
-    created_at: DateTime<Utc>,
-    updated_at: DateTime<Utc>,
-}
-
-/// Value object for User ID
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub struct UserId(Uuid);
-
-/// Value object for User Name with validation
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct UserName(String);
-
-/// Value object for Email with validation
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Email(String);
-
-impl User {
-    /// Create a new User (factory method)
-    pub fn new(name: UserName, email: Email) -> Self {
-        let now = Utc::now();
-        Self {
-            id: UserId::new(),
-            name,
-            email,
-            created_at: now,
-            updated_at: now,
-        }
-    }
-
-    /// Reconstruct User from persistence (used by adapters)
-    pub fn from_persistence(
-        id: UserId,
-        name: UserName,
-        email: Email,
-        created_at: DateTime<Utc>,
-        updated_at: DateTime<Utc>,
-    ) -> Self {
-        Self {
-            id,
-            name,
-            email,
-            created_at,
-            updated_at,
-        }
-    }
-
-    /// Update user information
-    pub fn update(&mut self, name: Option<UserName>, email: Option<Email>) -> Result<(), UserError> {
-        if let Some(new_name) = name {
-            self.name = new_name;
-        }
-        if let Some(new_email) = email {
-            self.email = new_email;
-        }
-        self.updated_at = Utc::now();
-        Ok(())
-    }
-
-    // Getters
-    pub fn id(&self) -> &UserId {
-        &self.id
-    }
-
-    pub fn name(&self) -> &UserName {
-        &self.name
-    }
-
-    pub fn email(&self) -> &Email {
-        &self.email
-    }
-
-    pub fn created_at(&self) -> DateTime<Utc> {
-        self.created_at
-    }
-
-    pub fn updated_at(&self) -> DateTime<Utc> {
-        self.updated_at
-    }
-}
-
-impl UserId {
-    pub fn new() -> Self {
-        Self(Uuid::new_v4())
-    }
-
-    pub fn from_uuid(uuid: Uuid) -> Self {
-        Self(uuid)
-    }
-
-    pub fn as_uuid(&self) -> Uuid {
-        self.0
-    }
-}
-
-impl Default for UserId {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl UserName {
-    pub fn new(name: String) -> Result<Self, UserError> {
-        if name.trim().is_empty() {
-            return Err(UserError::InvalidName("Name cannot be empty".to_string()));
-        }
-        if name.len() > 100 {
-            return Err(UserError::InvalidName("Name cannot exceed 100 characters".to_string()));
-        }
-        Ok(Self(name.trim().to_string()))
-    }
-
-    pub fn as_str(&self) -> &str {
-        &self.0
-    }
-}
-
-impl Email {
-    pub fn new(email: String) -> Result<Self, UserError> {
-        let email = email.trim().to_lowercase();
-        if email.is_empty() {
-            return Err(UserError::InvalidEmail("Email cannot be empty".to_string()));
-        }
-        if !email.contains('@') || !email.contains('.') {
-            return Err(UserError::InvalidEmail("Invalid email format".to_string()));
-        }
-        Ok(Self(email))
-    }
-
-    pub fn as_str(&self) -> &str {
-        &self.0
-    }
-}
-
-/// Domain errors for User operations
-#[derive(Debug, thiserror::Error)]
-pub enum UserError {
-    #[error("Invalid name: {0}")]
-    InvalidName(String),
-    #[error("Invalid email: {0}")]
-    InvalidEmail(String),
-    #[error("User not found")]
-    NotFound,
-    #[error("Email already exists")]
-    EmailAlreadyExists,
-}
+use chrono::{DateTime, SubsecRound, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Domain entity representing a User
+/// This is the core business entity, free from infrastructure concerns
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct User {
+    id: UserId,
+    name: UserName,
+    email: Email,
+    email_verified_at: Option<DateTime<Utc>>,
+    role: Role,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    deleted_at: Option<DateTime<Utc>>,
+    version: i64,
+}
+
+/// Version of a freshly created user
+const INITIAL_VERSION: i64 = 1;
+
+/// Value object for User ID
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct UserId(Uuid);
+
+/// Value object for User Name with validation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserName(String);
+
+/// Value object for Email with validation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Email(String);
+
+/// What a user may do; enforced by the application service's policy
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Role {
+    /// Lists, reads, updates and deletes any user
+    Admin,
+    /// Reads and updates only itself
+    #[default]
+    User,
+    /// Service account that can only list users
+    ReadOnly,
+}
+
+impl User {
+    /// Create a new User (factory method)
+    pub fn new(name: UserName, email: Email) -> Self {
+        let now = current_timestamp();
+        Self {
+            id: UserId::new(),
+            name,
+            email,
+            email_verified_at: None,
+            role: Role::default(),
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+            version: INITIAL_VERSION,
+        }
+    }
+
+    /// Reconstruct User from persistence (used by adapters).
+    /// Takes every stored column, hence the long argument list.
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_persistence(
+        id: UserId,
+        name: UserName,
+        email: Email,
+        email_verified_at: Option<DateTime<Utc>>,
+        role: Role,
+        created_at: DateTime<Utc>,
+        updated_at: DateTime<Utc>,
+        deleted_at: Option<DateTime<Utc>>,
+        version: i64,
+    ) -> Self {
+        Self {
+            id,
+            name,
+            email,
+            email_verified_at,
+            role,
+            created_at,
+            updated_at,
+            deleted_at,
+            version,
+        }
+    }
+
+    /// Update user information.
+    /// Bumps the version, so repositories only accept the change while the
+    /// stored version is still the one this entity was loaded at.
+    /// A different email address has to be verified again.
+    pub fn update(&mut self, name: Option<UserName>, email: Option<Email>) -> Result<(), UserError> {
+        if let Some(new_name) = name {
+            self.name = new_name;
+        }
+        if let Some(new_email) = email {
+            if new_email != self.email {
+                self.email_verified_at = None;
+            }
+            self.email = new_email;
+        }
+        self.updated_at = current_timestamp();
+        self.version += 1;
+        Ok(())
+    }
+
+    /// Record proof that the user owns its current email address.
+    /// Bumps the version like `update`.
+    pub fn mark_email_verified(&mut self, at: DateTime<Utc>) {
+        self.email_verified_at = Some(at);
+        self.updated_at = at;
+        self.version += 1;
+    }
+
+    /// Undo a soft delete, as `UserRepositoryPort::restore_audited` stores it.
+    /// Bumps the version like `update`.
+    pub fn restore(&mut self, at: DateTime<Utc>) {
+        self.deleted_at = None;
+        self.updated_at = at;
+        self.version += 1;
+    }
+
+    /// Forget that the current email address was verified; stored together
+    /// with the next `update`
+    pub fn clear_email_verification(&mut self) {
+        self.email_verified_at = None;
+    }
+
+    /// Change the user's role; stored together with the next `update`
+    pub fn set_role(&mut self, role: Role) {
+        self.role = role;
+    }
+
+    // Getters
+    pub fn id(&self) -> &UserId {
+        &self.id
+    }
+
+    pub fn name(&self) -> &UserName {
+        &self.name
+    }
+
+    pub fn email(&self) -> &Email {
+        &self.email
+    }
+
+    /// When the current email address was verified, if it was
+    pub fn email_verified_at(&self) -> Option<DateTime<Utc>> {
+        self.email_verified_at
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    pub fn updated_at(&self) -> DateTime<Utc> {
+        self.updated_at
+    }
+
+    /// When the user was soft-deleted, if it was
+    pub fn deleted_at(&self) -> Option<DateTime<Utc>> {
+        self.deleted_at
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    /// Optimistic concurrency version, incremented by every stored change
+    pub fn version(&self) -> i64 {
+        self.version
+    }
+}
+
+/// Current time at the microsecond precision every storage adapter preserves,
+/// so an entity compares equal to itself after a persistence round trip
+pub fn current_timestamp() -> DateTime<Utc> {
+    Utc::now().trunc_subsecs(6)
+}
+
+impl UserId {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+
+    pub fn from_uuid(uuid: Uuid) -> Self {
+        Self(uuid)
+    }
+
+    pub fn as_uuid(&self) -> Uuid {
+        self.0
+    }
+}
+
+impl Default for UserId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UserName {
+    pub fn new(name: String) -> Result<Self, UserError> {
+        if name.trim().is_empty() {
+            return Err(UserError::InvalidName("Name cannot be empty".to_string()));
+        }
+        if name.len() > 100 {
+            return Err(UserError::InvalidName("Name cannot exceed 100 characters".to_string()));
+        }
+        Ok(Self(name.trim().to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Email {
+    pub fn new(email: String) -> Result<Self, UserError> {
+        let email = email.trim().to_lowercase();
+        if email.is_empty() {
+            return Err(UserError::InvalidEmail("Email cannot be empty".to_string()));
+        }
+        if !email.contains('@') || !email.contains('.') {
+            return Err(UserError::InvalidEmail("Invalid email format".to_string()));
+        }
+        Ok(Self(email))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Role {
+    pub fn parse(role: &str) -> Result<Self, UserError> {
+        match role.trim().to_lowercase().as_str() {
+            "admin" => Ok(Self::Admin),
+            "user" => Ok(Self::User),
+            "readonly" => Ok(Self::ReadOnly),
+            other => Err(UserError::InvalidRole(format!(
+                "unknown role `{}`, expected admin, user or readonly",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Admin => "admin",
+            Self::User => "user",
+            Self::ReadOnly => "readonly",
+        }
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Domain errors for User operations
+#[derive(Debug, thiserror::Error)]
+pub enum UserError {
+    #[error("Invalid name: {0}")]
+    InvalidName(String),
+    #[error("Invalid email: {0}")]
+    InvalidEmail(String),
+    #[error("User not found")]
+    NotFound,
+    #[error("Email already exists")]
+    EmailAlreadyExists,
+    #[error("Invalid pagination: {0}")]
+    InvalidPagination(String),
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+    #[error("Invalid role: {0}")]
+    InvalidRole(String),
+    #[error("Invalid API key: {0}")]
+    InvalidApiKey(String),
+    #[error("Invalid patch: {0}")]
+    InvalidPatch(String),
+    #[error("Unsupported patch format: {0}")]
+    UnsupportedPatchFormat(String),
+    #[error("Invalid message: {0}")]
+    InvalidMessage(String),
+    #[error("Precondition failed: {0}")]
+    PreconditionFailed(String),
+    #[error("Invalid password: {0}")]
+    InvalidPassword(String),
+    #[error("Invalid email or password")]
+    InvalidCredentials,
+    #[error("Unauthenticated: {0}")]
+    Unauthenticated(String),
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+    #[error("API key not found")]
+    ApiKeyNotFound,
+    #[error("Invalid webhook: {0}")]
+    InvalidWebhook(String),
+    #[error("Webhook not found")]
+    WebhookNotFound,
+    #[error("Webhook delivery not found")]
+    WebhookDeliveryNotFound,
+    #[error("Invalid token: {0}")]
+    InvalidToken(String),
+    #[error("Email already verified")]
+    EmailAlreadyVerified,
+    #[error("Invalid idempotency key: {0}")]
+    InvalidIdempotencyKey(String),
+    #[error("Idempotency key was already used for a different request")]
+    IdempotencyKeyReused,
+    #[error("A request with this idempotency key is still in progress")]
+    IdempotencyKeyInProgress,
+    #[error("Rate limit exceeded, retry in {retry_after_secs}s")]
+    RateLimited { retry_after_secs: u64 },
+    #[error("Mail delivery unavailable: {0}")]
+    MailUnavailable(#[source] InfrastructureError),
+    #[error("Webhook receiver unreachable: {0}")]
+    WebhookUnavailable(#[source] InfrastructureError),
+    #[error("Storage unavailable: {0}")]
+    Unavailable(#[source] InfrastructureError),
+    #[error("Storage operation timed out: {0}")]
+    Timeout(#[source] InfrastructureError),
+    #[error("Conflicting concurrent modification: {0}")]
+    Conflict(#[source] InfrastructureError),
+    #[error("Constraint violation: {0}")]
+    ConstraintViolation(#[source] InfrastructureError),
+    #[error("Internal error: {0}")]
+    Internal(#[source] InfrastructureError),
+}
+
+impl UserError {
+    /// Stable, machine-readable code for API clients
+    pub fn code(&self) -> &'static str {
+        match self {
+            UserError::InvalidName(_) => "INVALID_NAME",
+            UserError::InvalidEmail(_) => "INVALID_EMAIL",
+            UserError::NotFound => "USER_NOT_FOUND",
+            UserError::EmailAlreadyExists => "EMAIL_ALREADY_EXISTS",
+            UserError::InvalidPagination(_) => "INVALID_PAGINATION",
+            UserError::InvalidQuery(_) => "INVALID_QUERY",
+            UserError::InvalidRole(_) => "INVALID_ROLE",
+            UserError::InvalidApiKey(_) => "INVALID_API_KEY",
+            UserError::InvalidPatch(_) => "INVALID_PATCH",
+            UserError::UnsupportedPatchFormat(_) => "UNSUPPORTED_PATCH_FORMAT",
+            UserError::InvalidMessage(_) => "INVALID_MESSAGE",
+            UserError::PreconditionFailed(_) => "PRECONDITION_FAILED",
+            UserError::InvalidPassword(_) => "INVALID_PASSWORD",
+            UserError::InvalidCredentials => "INVALID_CREDENTIALS",
+            UserError::Unauthenticated(_) => "UNAUTHENTICATED",
+            UserError::Forbidden(_) => "FORBIDDEN",
+            UserError::ApiKeyNotFound => "API_KEY_NOT_FOUND",
+            UserError::InvalidWebhook(_) => "INVALID_WEBHOOK",
+            UserError::WebhookNotFound => "WEBHOOK_NOT_FOUND",
+            UserError::WebhookDeliveryNotFound => "WEBHOOK_DELIVERY_NOT_FOUND",
+            UserError::InvalidToken(_) => "INVALID_TOKEN",
+            UserError::EmailAlreadyVerified => "EMAIL_ALREADY_VERIFIED",
+            UserError::InvalidIdempotencyKey(_) => "INVALID_IDEMPOTENCY_KEY",
+            UserError::IdempotencyKeyReused => "IDEMPOTENCY_KEY_REUSED",
+            UserError::IdempotencyKeyInProgress => "IDEMPOTENCY_KEY_IN_PROGRESS",
+            UserError::RateLimited { .. } => "RATE_LIMITED",
+            UserError::MailUnavailable(_) => "MAIL_UNAVAILABLE",
+            UserError::WebhookUnavailable(_) => "WEBHOOK_UNAVAILABLE",
+            UserError::Unavailable(_) => "STORAGE_UNAVAILABLE",
+            UserError::Timeout(_) => "STORAGE_TIMEOUT",
+            UserError::Conflict(_) => "CONCURRENT_MODIFICATION",
+            UserError::ConstraintViolation(_) => "CONSTRAINT_VIOLATION",
+            UserError::Internal(_) => "INTERNAL_ERROR",
+        }
+    }
+
+    /// Whether repeating the same request may succeed without changing it
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            UserError::Unavailable(_)
+                | UserError::Timeout(_)
+                | UserError::Conflict(_)
+                | UserError::MailUnavailable(_)
+                | UserError::WebhookUnavailable(_)
+                | UserError::RateLimited { .. }
+                | UserError::IdempotencyKeyInProgress
+        )
+    }
+}
+
+/// Infrastructure failure reported by an adapter, after classification.
+/// Keeps the operation that failed and the original error as its source.
+#[derive(Debug, thiserror::Error)]
+#[error("{operation} failed")]
+pub struct InfrastructureError {
+    operation: &'static str,
+    #[source]
+    source: Box<dyn std::error::Error + Send + Sync>,
+}
+
+impl InfrastructureError {
+    pub fn new(
+        operation: &'static str,
+        source: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+    ) -> Self {
+        Self {
+            operation,
+            source: source.into(),
+        }
+    }
+}
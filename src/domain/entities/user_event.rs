@@ -0,0 +1,132 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::domain::entities::{FieldChange, User, UserId, current_timestamp};
+
+/// A user was stored for the first time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserCreated {
+    pub id: Uuid,
+    pub user: User,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// A stored user changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserUpdated {
+    pub id: Uuid,
+    /// State after the change
+    pub user: User,
+    /// Fields that differ from the state before the change
+    pub changes: Vec<FieldChange>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// A user was (soft) deleted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserDeleted {
+    pub id: Uuid,
+    /// Last state before the delete
+    pub user: User,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// A soft-deleted user was restored
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserRestored {
+    pub id: Uuid,
+    /// State after the restore
+    pub user: User,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// Domain event about one user, published once the change is stored.
+/// Every event has its own `id`, so consumers can drop duplicates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserEvent {
+    Created(UserCreated),
+    Updated(UserUpdated),
+    Deleted(UserDeleted),
+    Restored(UserRestored),
+}
+
+impl UserEvent {
+    /// Every value `event_type` can take
+    pub const TYPES: [&'static str; 4] = ["user.created", "user.updated", "user.deleted", "user.restored"];
+
+    pub fn created(user: &User) -> Self {
+        Self::Created(UserCreated {
+            id: Uuid::new_v4(),
+            user: user.clone(),
+            occurred_at: current_timestamp(),
+        })
+    }
+
+    /// Update from `before` to `after`
+    pub fn updated(before: &User, after: &User) -> Self {
+        Self::Updated(UserUpdated {
+            id: Uuid::new_v4(),
+            user: after.clone(),
+            changes: FieldChange::between(Some(before), Some(after)),
+            occurred_at: current_timestamp(),
+        })
+    }
+
+    pub fn deleted(user: &User) -> Self {
+        Self::Deleted(UserDeleted {
+            id: Uuid::new_v4(),
+            user: user.clone(),
+            occurred_at: current_timestamp(),
+        })
+    }
+
+    pub fn restored(user: &User) -> Self {
+        Self::Restored(UserRestored {
+            id: Uuid::new_v4(),
+            user: user.clone(),
+            occurred_at: current_timestamp(),
+        })
+    }
+
+    pub fn id(&self) -> Uuid {
+        match self {
+            Self::Created(event) => event.id,
+            Self::Updated(event) => event.id,
+            Self::Deleted(event) => event.id,
+            Self::Restored(event) => event.id,
+        }
+    }
+
+    /// The user the event is about, in its latest known state
+    pub fn user(&self) -> &User {
+        match self {
+            Self::Created(event) => &event.user,
+            Self::Updated(event) => &event.user,
+            Self::Deleted(event) => &event.user,
+            Self::Restored(event) => &event.user,
+        }
+    }
+
+    pub fn user_id(&self) -> &UserId {
+        self.user().id()
+    }
+
+    pub fn occurred_at(&self) -> DateTime<Utc> {
+        match self {
+            Self::Created(event) => event.occurred_at,
+            Self::Updated(event) => event.occurred_at,
+            Self::Deleted(event) => event.occurred_at,
+            Self::Restored(event) => event.occurred_at,
+        }
+    }
+
+    /// Stable name of the event type, such as `user.created`
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            Self::Created(_) => "user.created",
+            Self::Updated(_) => "user.updated",
+            Self::Deleted(_) => "user.deleted",
+            Self::Restored(_) => "user.restored",
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::domain::entities::{UserError, UserEvent, current_timestamp};
+
+const MAX_URL_LENGTH: usize = 2048;
+/// Shortest secret a subscriber may choose; generated ones are longer
+const MIN_SECRET_LENGTH: usize = 16;
+
+/// Partner endpoint receiving signed HTTP callbacks for user events.
+/// The secret is kept in clear: it signs every delivery.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookSubscription {
+    pub id: Uuid,
+    /// `http` or `https` URL the events are POSTed to
+    pub url: String,
+    /// Subset of `UserEvent::TYPES`
+    pub event_types: Vec<String>,
+    pub secret: String,
+    /// Inactive subscriptions get no new deliveries
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Where a delivery stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeliveryStatus {
+    /// Waiting for its first attempt or a retry
+    Pending,
+    /// The receiver answered with a 2xx status
+    Succeeded,
+    /// Every attempt failed; only a replay sends it again
+    DeadLettered,
+}
+
+/// One event to be POSTed to one subscription, with the outcome of its
+/// latest attempt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub subscription_id: Uuid,
+    /// Id of the `UserEvent` delivered
+    pub event_id: Uuid,
+    pub event_type: String,
+    /// JSON body, fixed when the delivery is created so retries and replays
+    /// send the same bytes
+    pub payload: String,
+    pub status: DeliveryStatus,
+    /// Attempts made so far, counting the one in flight
+    pub attempts: i32,
+    /// When a pending delivery may next be attempted
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_response_status: Option<i32>,
+    pub last_error: Option<String>,
+    /// The delivery this one re-sends, for replays
+    pub replay_of: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Record of one HTTP attempt of a delivery
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookAttempt {
+    pub delivery_id: Uuid,
+    /// 1 for the first attempt
+    pub attempt: i32,
+    /// `None` when no response arrived
+    pub response_status: Option<i32>,
+    pub error: Option<String>,
+    pub attempted_at: DateTime<Utc>,
+}
+
+impl WebhookSubscription {
+    /// New active subscription; fails on an invalid URL, event type or secret
+    pub fn new(url: &str, event_types: &[String], secret: String) -> Result<Self, UserError> {
+        let now = current_timestamp();
+        Ok(Self {
+            id: Uuid::new_v4(),
+            url: Self::parse_url(url)?,
+            event_types: Self::parse_event_types(event_types)?,
+            secret: Self::check_secret(secret)?,
+            active: true,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    /// Apply the given changes; fields left `None` stay as they are
+    pub fn update(
+        &mut self,
+        url: Option<&str>,
+        event_types: Option<&[String]>,
+        secret: Option<String>,
+        active: Option<bool>,
+    ) -> Result<(), UserError> {
+        let url = url.map(Self::parse_url).transpose()?;
+        let event_types = event_types.map(Self::parse_event_types).transpose()?;
+        let secret = secret.map(Self::check_secret).transpose()?;
+
+        if let Some(url) = url {
+            self.url = url;
+        }
+        if let Some(event_types) = event_types {
+            self.event_types = event_types;
+        }
+        if let Some(secret) = secret {
+            self.secret = secret;
+        }
+        if let Some(active) = active {
+            self.active = active;
+        }
+        self.updated_at = current_timestamp();
+        Ok(())
+    }
+
+    /// Whether `event` should be delivered to this subscription
+    pub fn wants(&self, event: &UserEvent) -> bool {
+        self.active && self.event_types.iter().any(|event_type| event_type == event.event_type())
+    }
+
+    /// Space-separated event types, as stored
+    pub fn join_event_types(&self) -> String {
+        self.event_types.join(" ")
+    }
+
+    fn parse_url(url: &str) -> Result<String, UserError> {
+        let invalid = |reason: &str| UserError::InvalidWebhook(format!("url {}", reason));
+        let url = url.trim();
+        if url.len() > MAX_URL_LENGTH {
+            return Err(invalid(&format!("must be at most {} characters", MAX_URL_LENGTH)));
+        }
+        let parsed = url::Url::parse(url).map_err(|e| invalid(&format!("is not valid: {}", e)))?;
+        if !matches!(parsed.scheme(), "http" | "https") || parsed.host().is_none() {
+            return Err(invalid("must be an absolute http or https URL"));
+        }
+        Ok(parsed.to_string())
+    }
+
+    fn parse_event_types(event_types: &[String]) -> Result<Vec<String>, UserError> {
+        let mut parsed = Vec::with_capacity(event_types.len());
+        for event_type in event_types {
+            let event_type = event_type.trim();
+            if !UserEvent::TYPES.contains(&event_type) {
+                return Err(UserError::InvalidWebhook(format!(
+                    "unknown event type `{}`, expected one of {}",
+                    event_type,
+                    UserEvent::TYPES.join(", ")
+                )));
+            }
+            parsed.push(event_type.to_string());
+        }
+        parsed.sort();
+        parsed.dedup();
+        if parsed.is_empty() {
+            return Err(UserError::InvalidWebhook("at least one event type is required".to_string()));
+        }
+        Ok(parsed)
+    }
+
+    fn check_secret(secret: String) -> Result<String, UserError> {
+        if secret.chars().count() < MIN_SECRET_LENGTH || secret.chars().any(char::is_whitespace) {
+            return Err(UserError::InvalidWebhook(format!(
+                "secret must be at least {} characters without whitespace",
+                MIN_SECRET_LENGTH
+            )));
+        }
+        Ok(secret)
+    }
+}
+
+impl DeliveryStatus {
+    pub fn parse(status: &str) -> Result<Self, UserError> {
+        match status.trim() {
+            "pending" => Ok(Self::Pending),
+            "succeeded" => Ok(Self::Succeeded),
+            "dead_lettered" => Ok(Self::DeadLettered),
+            other => Err(UserError::InvalidQuery(format!(
+                "unknown delivery status `{}`, expected pending, succeeded or dead_lettered",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Succeeded => "succeeded",
+            Self::DeadLettered => "dead_lettered",
+        }
+    }
+}
+
+impl WebhookDelivery {
+    /// Pending delivery of `event`, serialized as `payload`, due right away
+    pub fn new(subscription_id: Uuid, event: &UserEvent, payload: String) -> Self {
+        let now = current_timestamp();
+        Self {
+            id: Uuid::new_v4(),
+            subscription_id,
+            event_id: event.id(),
+            event_type: event.event_type().to_string(),
+            payload,
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            next_attempt_at: now,
+            last_response_status: None,
+            last_error: None,
+            replay_of: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Fresh pending copy of this delivery, sending the same payload again
+    pub fn replay(&self) -> Self {
+        let now = current_timestamp();
+        Self {
+            id: Uuid::new_v4(),
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            next_attempt_at: now,
+            last_response_status: None,
+            last_error: None,
+            replay_of: Some(self.id),
+            created_at: now,
+            updated_at: now,
+            ..self.clone()
+        }
+    }
+}
@@ -0,0 +1,10 @@
+use crate::domain::entities::{AccessToken, Principal, UserError};
+
+/// Port for issuing and validating stateless access tokens
+pub trait AccessTokenPort: Send + Sync {
+    /// Sign a token identifying `principal`
+    fn issue(&self, principal: &Principal) -> Result<AccessToken, UserError>;
+
+    /// Validate signature and expiry; `Unauthenticated` if the token is not acceptable
+    fn verify(&self, token: &str) -> Result<Principal, UserError>;
+}
@@ -0,0 +1,24 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::domain::entities::{ApiKey, UserError, UserId};
+
+/// Port for API keys. Keys are looked up by the hash of their secret;
+/// revoked and expired keys are still returned and filtered by the caller.
+#[async_trait]
+pub trait ApiKeyRepositoryPort: Send + Sync {
+    /// Store a new key; `NotFound` if its owner does not exist
+    async fn save(&self, key: &ApiKey) -> Result<(), UserError>;
+
+    async fn find_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>, UserError>;
+
+    /// Keys of one owner, or all keys, newest first
+    async fn find_all(&self, owner_id: Option<&UserId>) -> Result<Vec<ApiKey>, UserError>;
+
+    /// Mark a key revoked; `ApiKeyNotFound` if it does not exist or already is
+    async fn revoke(&self, id: Uuid, revoked_at: DateTime<Utc>) -> Result<(), UserError>;
+
+    /// Record a successful authentication
+    async fn record_use(&self, id: Uuid, used_at: DateTime<Utc>) -> Result<(), UserError>;
+}
@@ -0,0 +1,29 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::domain::entities::{AuditAction, AuditEntry, UserError, UserId};
+
+/// Criteria for audit log queries; every set field must match
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditQuery {
+    pub user_id: Option<UserId>,
+    pub actor: Option<UserId>,
+    pub action: Option<AuditAction>,
+    pub request_id: Option<String>,
+    /// Inclusive lower bound on `created_at`
+    pub from: Option<DateTime<Utc>>,
+    /// Exclusive upper bound on `created_at`
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// Port for reading the audit log. Entries are only ever written together
+/// with the change they record, through the `*_audited` methods of
+/// `UserRepositoryPort`.
+#[async_trait]
+pub trait AuditLogRepositoryPort: Send + Sync {
+    /// Entries matching `query`, newest first
+    async fn find(&self, query: &AuditQuery, offset: i64, limit: i64) -> Result<Vec<AuditEntry>, UserError>;
+
+    /// Count entries matching `query`
+    async fn count(&self, query: &AuditQuery) -> Result<i64, UserError>;
+}
@@ -0,0 +1,14 @@
+use async_trait::async_trait;
+
+use crate::domain::entities::{PasswordHash, UserError, UserId};
+
+/// Port for password credentials, stored apart from the user record so
+/// listing and updating users never touches secrets
+#[async_trait]
+pub trait CredentialRepositoryPort: Send + Sync {
+    /// Set or replace the password hash of an existing user
+    async fn set_password_hash(&self, user_id: &UserId, hash: &PasswordHash) -> Result<(), UserError>;
+
+    /// Password hash of a user; `None` if the user has no password
+    async fn find_password_hash(&self, user_id: &UserId) -> Result<Option<PasswordHash>, UserError>;
+}
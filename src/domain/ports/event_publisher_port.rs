@@ -0,0 +1,11 @@
+use async_trait::async_trait;
+
+use crate::domain::entities::{UserError, UserEvent};
+
+/// Port for handing domain events to whoever reacts to them.
+/// Events are published after the change is stored, so a failure here
+/// cannot undo it; callers log it instead.
+#[async_trait]
+pub trait EventPublisherPort: Send + Sync {
+    async fn publish(&self, event: &UserEvent) -> Result<(), UserError>;
+}
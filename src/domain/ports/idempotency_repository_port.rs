@@ -0,0 +1,26 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::domain::entities::{IdempotencyRecord, StoredResponse, UserError};
+
+/// Port for idempotency keys and the responses stored with them
+#[async_trait]
+pub trait IdempotencyRepositoryPort: Send + Sync {
+    /// Store `record`, without response, unless its key is taken. A key is
+    /// free again once its record expired, or when its request started
+    /// before `abandoned_before` and never completed. Returns whether the
+    /// key was claimed.
+    async fn claim(&self, record: &IdempotencyRecord, abandoned_before: DateTime<Utc>) -> Result<bool, UserError>;
+
+    /// Record of a key, whatever its state
+    async fn find(&self, scope: &str, key: &str) -> Result<Option<IdempotencyRecord>, UserError>;
+
+    /// Attach the response to a claimed key
+    async fn complete(&self, scope: &str, key: &str, response: &StoredResponse) -> Result<(), UserError>;
+
+    /// Free a claimed key whose request did not complete, so it can be retried
+    async fn release(&self, scope: &str, key: &str) -> Result<(), UserError>;
+
+    /// Delete records that expired before `expired_before`; returns how many
+    async fn purge_expired(&self, expired_before: DateTime<Utc>) -> Result<u64, UserError>;
+}
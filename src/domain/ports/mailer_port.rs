@@ -0,0 +1,18 @@
+use async_trait::async_trait;
+
+use crate::domain::entities::{Email, UserError};
+
+/// A plain-text email to one recipient
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MailMessage {
+    pub to: Email,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Port for outgoing mail.
+/// Adapters report delivery failures as `MailUnavailable`.
+#[async_trait]
+pub trait MailerPort: Send + Sync {
+    async fn send(&self, message: &MailMessage) -> Result<(), UserError>;
+}
@@ -1,3 +1,33 @@
-pub mod user_repository_port;
-
-pub use user_repository_port::UserRepositoryPort;
+pub mod access_token_port;
+pub mod api_key_repository_port;
+pub mod audit_log_repository_port;
+pub mod credential_repository_port;
+pub mod email_verification_repository_port;
+pub mod event_publisher_port;
+pub mod idempotency_repository_port;
+pub mod mailer_port;
+pub mod outbox_repository_port;
+pub mod password_hasher_port;
+pub mod password_reset_repository_port;
+pub mod session_repository_port;
+pub mod user_query;
+pub mod user_repository_port;
+pub mod webhook_repository_port;
+pub mod webhook_sender_port;
+
+pub use access_token_port::AccessTokenPort;
+pub use api_key_repository_port::ApiKeyRepositoryPort;
+pub use audit_log_repository_port::{AuditLogRepositoryPort, AuditQuery};
+pub use credential_repository_port::CredentialRepositoryPort;
+pub use email_verification_repository_port::EmailVerificationRepositoryPort;
+pub use event_publisher_port::EventPublisherPort;
+pub use idempotency_repository_port::IdempotencyRepositoryPort;
+pub use mailer_port::{MailMessage, MailerPort};
+pub use outbox_repository_port::{OutboxMessage, OutboxRepositoryPort};
+pub use password_hasher_port::PasswordHasherPort;
+pub use password_reset_repository_port::PasswordResetRepositoryPort;
+pub use session_repository_port::SessionRepositoryPort;
+pub use user_query::{SortDirection, SortKey, UserQuery, UserSort, UserSortField};
+pub use user_repository_port::{CountAccuracy, UserCursor, UserRepositoryPort};
+pub use webhook_repository_port::WebhookRepositoryPort;
+pub use webhook_sender_port::{WebhookRequest, WebhookSenderPort};
@@ -0,0 +1,44 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+
+use crate::domain::entities::{UserError, UserEvent};
+
+/// An event waiting in the outbox
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutboxMessage {
+    /// Position in the outbox; orders the events of one user
+    pub seq: i64,
+    pub event: UserEvent,
+    /// Claims so far, including the current one
+    pub attempts: i32,
+}
+
+/// Port for the transactional outbox. Events are only ever enqueued together
+/// with the change they describe, through the `*_audited` methods of
+/// `UserRepositoryPort`; a relay claims them, delivers them and marks them.
+#[async_trait]
+pub trait OutboxRepositoryPort: Send + Sync {
+    /// Lease up to `limit` undelivered messages that are due at `now`, oldest
+    /// first. A claimed message is not claimable again until `now + lease`,
+    /// so one that is never marked is delivered again.
+    /// Only the oldest undelivered message of each user is claimable, which
+    /// keeps the events of one user in order.
+    async fn claim(&self, now: DateTime<Utc>, lease: Duration, limit: i64) -> Result<Vec<OutboxMessage>, UserError>;
+
+    /// Record the delivery of message `seq`
+    async fn mark_sent(&self, seq: i64, sent_at: DateTime<Utc>) -> Result<(), UserError>;
+
+    /// Record a failed delivery of message `seq`, to be retried from `retry_at`
+    async fn mark_failed(&self, seq: i64, error: &str, retry_at: DateTime<Utc>) -> Result<(), UserError>;
+
+    /// Delete messages delivered before `sent_before`; returns how many
+    async fn purge_sent(&self, sent_before: DateTime<Utc>) -> Result<u64, UserError>;
+
+    /// Up to `limit` messages after `after_seq`, delivered or not, in `seq`
+    /// order. Reading claims nothing, so any number of readers may follow
+    /// the outbox side by side.
+    async fn find_after(&self, after_seq: i64, limit: i64) -> Result<Vec<OutboxMessage>, UserError>;
+
+    /// Highest `seq` handed out so far, 0 for an empty outbox
+    async fn last_seq(&self) -> Result<i64, UserError>;
+}
@@ -0,0 +1,13 @@
+use async_trait::async_trait;
+
+use crate::domain::entities::{Password, PasswordHash, UserError};
+
+/// Port for one-way password hashing
+#[async_trait]
+pub trait PasswordHasherPort: Send + Sync {
+    /// Hash with a fresh salt
+    async fn hash(&self, password: &Password) -> Result<PasswordHash, UserError>;
+
+    /// Whether `password` matches `hash`; a malformed hash never matches
+    async fn verify(&self, password: &Password, hash: &PasswordHash) -> Result<bool, UserError>;
+}
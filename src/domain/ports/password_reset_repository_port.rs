@@ -0,0 +1,25 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::domain::entities::{PasswordResetToken, UserError, UserId};
+
+/// Port for password reset tokens
+#[async_trait]
+pub trait PasswordResetRepositoryPort: Send + Sync {
+    /// Store a new token; `NotFound` if its user does not exist
+    async fn save(&self, token: &PasswordResetToken) -> Result<(), UserError>;
+
+    /// Token with this hash, whatever its state
+    async fn find_by_token_hash(&self, token_hash: &str) -> Result<Option<PasswordResetToken>, UserError>;
+
+    /// Mark a token used, only if it is not used yet. Returns whether this
+    /// call did it, so a token cannot be redeemed twice.
+    async fn mark_used(&self, id: Uuid, used_at: DateTime<Utc>) -> Result<bool, UserError>;
+
+    /// Mark every unused token of a user used; returns how many
+    async fn mark_all_used_for_user(&self, user_id: &UserId, used_at: DateTime<Utc>) -> Result<u64, UserError>;
+
+    /// Delete tokens that expired before `expired_before`; returns how many
+    async fn purge_expired(&self, expired_before: DateTime<Utc>) -> Result<u64, UserError>;
+}
@@ -0,0 +1,32 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::domain::entities::{Session, UserError, UserId};
+
+/// Port for refresh-token sessions
+#[async_trait]
+pub trait SessionRepositoryPort: Send + Sync {
+    /// Store a new session; `NotFound` if its user does not exist
+    async fn save(&self, session: &Session) -> Result<(), UserError>;
+
+    /// Session of a refresh token, whatever its state
+    async fn find_by_token_hash(&self, token_hash: &str) -> Result<Option<Session>, UserError>;
+
+    /// Mark a session rotated, only if it is neither rotated nor revoked yet.
+    /// Returns whether this call did it, so two concurrent refreshes with the
+    /// same token cannot both succeed.
+    async fn mark_rotated(&self, id: Uuid, rotated_at: DateTime<Utc>) -> Result<bool, UserError>;
+
+    /// Revoke every live session of a token family; returns how many
+    async fn revoke_family(&self, family_id: Uuid, revoked_at: DateTime<Utc>) -> Result<u64, UserError>;
+
+    /// Revoke every live session of a user; returns how many
+    async fn revoke_all_for_user(&self, user_id: &UserId, revoked_at: DateTime<Utc>) -> Result<u64, UserError>;
+
+    /// Whether the user has a session that is active at `now`
+    async fn has_active(&self, user_id: &UserId, now: DateTime<Utc>) -> Result<bool, UserError>;
+
+    /// Delete sessions that expired before `expired_before`; returns how many
+    async fn purge_expired(&self, expired_before: DateTime<Utc>) -> Result<u64, UserError>;
+}
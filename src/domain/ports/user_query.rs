@@ -0,0 +1,109 @@
+use chrono::{DateTime, Utc};
+
+use crate::domain::entities::{Email, User};
+
+/// Typed criteria for listing users.
+/// Adapters translate it into their own query language; every field is
+/// optional and all present filters must match.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserQuery {
+    /// Case-insensitive name prefix
+    pub name_prefix: Option<String>,
+    /// Case-insensitive name substring
+    pub name_contains: Option<String>,
+    pub email: Option<Email>,
+    /// Lowercase domain part, matched exactly (`example.com`)
+    pub email_domain: Option<String>,
+    /// Inclusive lower bound
+    pub created_after: Option<DateTime<Utc>>,
+    /// Exclusive upper bound
+    pub created_before: Option<DateTime<Utc>>,
+    /// Inclusive lower bound
+    pub updated_after: Option<DateTime<Utc>>,
+    /// Exclusive upper bound
+    pub updated_before: Option<DateTime<Utc>>,
+    /// Also list soft-deleted users
+    pub include_deleted: bool,
+    pub sort: UserSort,
+}
+
+/// Whitelisted sort columns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserSortField {
+    CreatedAt,
+    UpdatedAt,
+    /// Case-insensitive
+    Name,
+    Email,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+/// Listing order; ties are always broken by ascending id so pages are stable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UserSort {
+    pub field: UserSortField,
+    pub direction: SortDirection,
+}
+
+impl Default for UserSort {
+    /// Newest first
+    fn default() -> Self {
+        Self {
+            field: UserSortField::CreatedAt,
+            direction: SortDirection::Desc,
+        }
+    }
+}
+
+/// Value of the sort column at a keyset position
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SortKey {
+    Timestamp(DateTime<Utc>),
+    Text(String),
+}
+
+impl UserSortField {
+    /// The value this field sorts `user` by
+    pub fn key_of(&self, user: &User) -> SortKey {
+        match self {
+            UserSortField::CreatedAt => SortKey::Timestamp(user.created_at()),
+            UserSortField::UpdatedAt => SortKey::Timestamp(user.updated_at()),
+            UserSortField::Name => SortKey::Text(user.name().as_str().to_lowercase()),
+            UserSortField::Email => SortKey::Text(user.email().as_str().to_string()),
+        }
+    }
+}
+
+impl UserQuery {
+    /// Whether `user` satisfies every filter, including deleted visibility
+    /// (the sort is ignored)
+    pub fn matches(&self, user: &User) -> bool {
+        let name = user.name().as_str().to_lowercase();
+        let email = user.email().as_str();
+
+        (self.include_deleted || !user.is_deleted())
+            && self.name_prefix.as_ref().is_none_or(|p| name.starts_with(&p.to_lowercase()))
+            && self.name_contains.as_ref().is_none_or(|p| name.contains(&p.to_lowercase()))
+            && self.email.as_ref().is_none_or(|e| e == user.email())
+            && self.email_domain.as_ref().is_none_or(|d| {
+                email.rsplit_once('@').is_some_and(|(_, domain)| domain == d.to_lowercase())
+            })
+            && self.created_after.is_none_or(|t| user.created_at() >= t)
+            && self.created_before.is_none_or(|t| user.created_at() < t)
+            && self.updated_after.is_none_or(|t| user.updated_at() >= t)
+            && self.updated_before.is_none_or(|t| user.updated_at() < t)
+    }
+
+    /// True when no filter is set, so totals can come from table statistics
+    pub fn is_unfiltered(&self) -> bool {
+        Self {
+            sort: self.sort,
+            ..Self::default()
+        } == *self
+    }
+}
@@ -1,26 +1,105 @@
-use async_trait::async_trait;
-
-use crate::domain::entities::{User, UserId, Email, UserError};
-
-/// Port (interface) for User repository operations
-/// This defines the contract that infrastructure adapters must implement
-#[async_trait]
-pub trait UserRepositoryPort: Send + Sync + Clone {
-    /// Save a new user
-    async fn save(&self, user: &User) -> Result<(), UserError>;
-    
-    /// Find user by ID
-    async fn find_by_id(&self, id: &UserId) -> Result<Option<User>, UserError>;
-    
-    /// Update an existing user
-    async fn update(&self, user: &User) -> Result<(), UserError>;
-    
-    /// Delete a user by ID
-    async fn delete(&self, id: &UserId) -> Result<(), UserError>;
-    
-    /// Get all users with pagination
-    async fn find_all(&self, offset: i64, limit: i64) -> Result<Vec<User>, UserError>;
-    
-    /// Check if user exists by email
-    async fn exists_by_email(&self, email: &Email) -> Result<bool, UserError>;
-}
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::domain::{
+    entities::{AuditEntry, User, UserEvent, UserId, Email, UserError},
+    ports::user_query::{SortKey, UserQuery, UserSort},
+};
+
+/// Position in a sorted listing, used for keyset pagination.
+/// Only valid with the sort it was taken from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserCursor {
+    pub sort: UserSort,
+    pub key: SortKey,
+    pub id: UserId,
+}
+
+impl UserCursor {
+    /// Cursor pointing just past `user` in `sort` order
+    pub fn after(user: &User, sort: UserSort) -> Self {
+        Self {
+            sort,
+            key: sort.field.key_of(user),
+            id: user.id().clone(),
+        }
+    }
+}
+
+/// How precise a user count must be
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CountAccuracy {
+    Exact,
+    /// Adapters may answer from planner statistics instead of scanning
+    Estimated,
+}
+
+/// Port (interface) for User repository operations
+/// This defines the contract that infrastructure adapters must implement.
+/// Every write is audited: it also appends `audit` to the audit log and
+/// enqueues `event` in the outbox, in the same transaction, so either all
+/// are stored or none is.
+#[async_trait]
+pub trait UserRepositoryPort: Send + Sync + Clone {
+    /// Save a new user, recording `audit` and enqueueing `event`
+    async fn save_audited(&self, user: &User, audit: &AuditEntry, event: &UserEvent) -> Result<(), UserError>;
+    
+    /// Find user by ID; soft-deleted users are not returned
+    async fn find_by_id(&self, id: &UserId) -> Result<Option<User>, UserError>;
+
+    /// Find a non-deleted user by email
+    async fn find_by_email(&self, email: &Email) -> Result<Option<User>, UserError>;
+
+    /// Find user by ID, including soft-deleted users
+    async fn find_by_id_including_deleted(&self, id: &UserId) -> Result<Option<User>, UserError>;
+    
+    /// Update an existing, non-deleted user and store `user.version()`,
+    /// recording `audit` and enqueueing `event`.
+    /// Fails with `Conflict` unless the stored version is exactly one behind,
+    /// i.e. nobody else has written since the entity was loaded.
+    async fn update_audited(&self, user: &User, audit: &AuditEntry, event: &UserEvent) -> Result<(), UserError>;
+    
+    /// Soft-delete a user by ID, recording `audit` and enqueueing `event`;
+    /// `NotFound` if it is missing or already deleted, `Conflict` if
+    /// `expected_version` is given and no longer current
+    async fn delete_audited(
+        &self,
+        id: &UserId,
+        expected_version: Option<i64>,
+        audit: &AuditEntry,
+        event: &UserEvent,
+    ) -> Result<(), UserError>;
+
+    /// Undo a soft delete as of `restored_at`, the `updated_at` the event
+    /// carries, recording `audit` and enqueueing `event`; `NotFound` unless
+    /// the user is currently deleted, `EmailAlreadyExists` if an active user
+    /// has taken the email since. Deleting and restoring both bump the version.
+    async fn restore_audited(
+        &self,
+        id: &UserId,
+        restored_at: DateTime<Utc>,
+        audit: &AuditEntry,
+        event: &UserEvent,
+    ) -> Result<(), UserError>;
+
+    /// Hard-delete users soft-deleted before `deleted_before`; returns how many
+    async fn purge_deleted(&self, deleted_before: DateTime<Utc>) -> Result<u64, UserError>;
+    
+    /// Get users matching `query` with pagination, in `query.sort` order
+    async fn find_all(&self, query: &UserQuery, offset: i64, limit: i64) -> Result<Vec<User>, UserError>;
+
+    /// Get up to `limit` users matching `query` that follow `after` in
+    /// `query.sort` order (keyset pagination); `None` starts from the top
+    async fn find_after(
+        &self,
+        query: &UserQuery,
+        after: Option<&UserCursor>,
+        limit: i64,
+    ) -> Result<Vec<User>, UserError>;
+    
+    /// Count users matching `query`; `Estimated` allows a cheap approximation for huge tables
+    async fn count(&self, query: &UserQuery, accuracy: CountAccuracy) -> Result<i64, UserError>;
+
+    /// Check if a non-deleted user exists by email
+    async fn exists_by_email(&self, email: &Email) -> Result<bool, UserError>;
+}
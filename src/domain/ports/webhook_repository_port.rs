@@ -0,0 +1,60 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use crate::domain::entities::{DeliveryStatus, UserError, WebhookAttempt, WebhookDelivery, WebhookSubscription};
+
+/// Port for webhook subscriptions and their delivery log. Deliveries go
+/// away with their subscription.
+#[async_trait]
+pub trait WebhookRepositoryPort: Send + Sync {
+    async fn save_subscription(&self, subscription: &WebhookSubscription) -> Result<(), UserError>;
+
+    /// Store changed fields; `WebhookNotFound` if it does not exist
+    async fn update_subscription(&self, subscription: &WebhookSubscription) -> Result<(), UserError>;
+
+    /// Delete a subscription and its deliveries; `WebhookNotFound` if it does not exist
+    async fn delete_subscription(&self, id: Uuid) -> Result<(), UserError>;
+
+    async fn find_subscription(&self, id: Uuid) -> Result<Option<WebhookSubscription>, UserError>;
+
+    /// Every subscription, oldest first
+    async fn find_subscriptions(&self) -> Result<Vec<WebhookSubscription>, UserError>;
+
+    /// Store a new delivery. Returns false, storing nothing, when the event
+    /// already has a delivery (other than a replay) for that subscription;
+    /// `WebhookNotFound` if the subscription is gone.
+    async fn enqueue_delivery(&self, delivery: &WebhookDelivery) -> Result<bool, UserError>;
+
+    /// Lease up to `limit` pending deliveries due at `now`, oldest first,
+    /// counting an attempt on each. A claimed delivery is not claimable again
+    /// until `now + lease`, so one whose attempt is never recorded is
+    /// attempted again.
+    async fn claim_deliveries(
+        &self,
+        now: DateTime<Utc>,
+        lease: Duration,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>, UserError>;
+
+    /// Store the outcome of `attempt`: the status, schedule and last
+    /// response of `delivery`, plus the attempt itself. A delivery that is
+    /// no longer pending is left unchanged.
+    async fn record_attempt(&self, delivery: &WebhookDelivery, attempt: &WebhookAttempt) -> Result<(), UserError>;
+
+    async fn find_delivery(&self, id: Uuid) -> Result<Option<WebhookDelivery>, UserError>;
+
+    /// Deliveries of one subscription, optionally with one status, newest first
+    async fn find_deliveries(
+        &self,
+        subscription_id: Uuid,
+        status: Option<DeliveryStatus>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>, UserError>;
+
+    async fn count_deliveries(&self, subscription_id: Uuid, status: Option<DeliveryStatus>) -> Result<i64, UserError>;
+
+    /// Attempts of one delivery, oldest first
+    async fn find_attempts(&self, delivery_id: Uuid) -> Result<Vec<WebhookAttempt>, UserError>;
+}
@@ -0,0 +1,19 @@
+use async_trait::async_trait;
+
+use crate::domain::entities::UserError;
+
+/// A signed webhook call: a JSON body POSTed to `url`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookRequest {
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+/// Port for sending webhook calls.
+/// Any HTTP response, whatever its status, is returned as its status code;
+/// adapters report calls that got no response as `WebhookUnavailable`.
+#[async_trait]
+pub trait WebhookSenderPort: Send + Sync {
+    async fn send(&self, request: &WebhookRequest) -> Result<u16, UserError>;
+}
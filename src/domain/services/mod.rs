@@ -1,3 +1,3 @@
-pub mod user_service;
-
-pub use user_service::UserDomainService;
+pub mod user_service;
+
+pub use user_service::UserDomainService;
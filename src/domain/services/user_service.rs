@@ -1,58 +1,100 @@
-use crate::domain::{
-    entities::{User, UserName, Email, UserError},
-    ports::UserRepositoryPort,
-};
-
-/// Domain service for User business logic
-/// Contains business rules that don't naturally fit in entities
-#[derive(Clone)]
-pub struct UserDomainService<R: UserRepositoryPort> {
-    user_repository: R,
-}
-
-impl<R: UserRepositoryPort> UserDomainService<R> {
-    pub fn new(user_repository: R) -> Self {
-        Self { user_repository }
-    }
-
-    /// Create a new user with business validation
-    pub async fn create_user(&self, name: UserName, email: Email) -> Result<User, UserError> {
-        // Business rule: Check if email already exists
-        if self.user_repository.exists_by_email(&email).await? {
-            return Err(UserError::EmailAlreadyExists);
-        }
-
-        // Create the user entity
-        let user = User::new(name, email);
-        
-        // Save the user
-        self.user_repository.save(&user).await?;
-        
-        Ok(user)
-    }
-
-    /// Update user with business validation
-    pub async fn update_user(
-        &self,
-        user: &mut User,
-        new_name: Option<UserName>,
-        new_email: Option<Email>,
-    ) -> Result<(), UserError> {
-        // Business rule: If email is being changed, check uniqueness
-        if let Some(ref email) = new_email {
-            if email != user.email() {
-                if self.user_repository.exists_by_email(email).await? {
-                    return Err(UserError::EmailAlreadyExists);
-                }
-            }
-        }
-
-        // Update the entity
-        user.update(new_name, new_email)?;
-        
-        // Persist changes
-        self.user_repository.update(user).await?;
-        
-        Ok(())
-    }
-}
+use crate::domain::{
+    entities::{AuditAction, AuditContext, AuditEntry, Role, User, UserEvent, UserName, Email, UserError},
+    ports::UserRepositoryPort,
+};
+
+/// Domain service for User business logic
+/// Contains business rules that don't naturally fit in entities.
+/// Every stored change enqueues a `UserEvent` in the outbox, in the same
+/// transaction; the outbox relay and tails publish it from there.
+#[derive(Clone)]
+pub struct UserDomainService<R: UserRepositoryPort> {
+    user_repository: R,
+}
+
+impl<R: UserRepositoryPort> UserDomainService<R> {
+    pub fn new(user_repository: R) -> Self {
+        Self { user_repository }
+    }
+
+    /// Create a new user with business validation, recording the change
+    /// in the audit log on behalf of `context`
+    pub async fn create_user(
+        &self,
+        name: UserName,
+        email: Email,
+        role: Role,
+        context: &AuditContext,
+    ) -> Result<User, UserError> {
+        // Business rule: Check if email already exists
+        if self.user_repository.exists_by_email(&email).await? {
+            return Err(UserError::EmailAlreadyExists);
+        }
+
+        // Create the user entity
+        let mut user = User::new(name, email);
+        user.set_role(role);
+        
+        // Save the user together with its audit entry and event
+        let audit = AuditEntry::record(context, AuditAction::Create, None, Some(&user));
+        let event = UserEvent::created(&user);
+        self.user_repository.save_audited(&user, &audit, &event).await?;
+        
+        Ok(user)
+    }
+
+    /// Update user with business validation, recording the change in the
+    /// audit log on behalf of `context`. `clear_email_verification` drops
+    /// the proof of address ownership even if the email stays the same.
+    pub async fn update_user(
+        &self,
+        user: &mut User,
+        new_name: Option<UserName>,
+        new_email: Option<Email>,
+        new_role: Option<Role>,
+        clear_email_verification: bool,
+        context: &AuditContext,
+    ) -> Result<(), UserError> {
+        // Business rule: If email is being changed, check uniqueness
+        if let Some(ref email) = new_email
+            && email != user.email()
+            && self.user_repository.exists_by_email(email).await?
+        {
+            return Err(UserError::EmailAlreadyExists);
+        }
+
+        // Update the entity
+        let before = user.clone();
+        if let Some(role) = new_role {
+            user.set_role(role);
+        }
+        if clear_email_verification {
+            user.clear_email_verification();
+        }
+        user.update(new_name, new_email)?;
+        
+        // Persist changes together with their audit entry and event
+        let audit = AuditEntry::record(context, AuditAction::Update, Some(&before), Some(user));
+        let event = UserEvent::updated(&before, user);
+        self.user_repository.update_audited(user, &audit, &event).await?;
+        
+        Ok(())
+    }
+
+    /// Soft delete `user`, optionally only while it is at `expected_version`,
+    /// recording the change in the audit log on behalf of `context`
+    pub async fn delete_user(
+        &self,
+        user: &User,
+        expected_version: Option<i64>,
+        context: &AuditContext,
+    ) -> Result<(), UserError> {
+        let audit = AuditEntry::record(context, AuditAction::Delete, Some(user), None);
+        let event = UserEvent::deleted(user);
+        self.user_repository
+            .delete_audited(user.id(), expected_version, &audit, &event)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+use argon2::{
+    Argon2,
+    password_hash::{self, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng},
+};
+use async_trait::async_trait;
+
+use crate::domain::{InfrastructureError, Password, PasswordHash, PasswordHasherPort, UserError};
+
+/// Argon2id adapter implementing PasswordHasherPort.
+/// Hashing is deliberately slow, so it runs on the blocking thread pool.
+#[derive(Clone, Default)]
+pub struct Argon2PasswordHasher {
+    argon2: Argon2<'static>,
+}
+
+impl Argon2PasswordHasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    async fn run_blocking<T: Send + 'static>(
+        &self,
+        operation: &'static str,
+        work: impl FnOnce(Argon2<'static>) -> Result<T, UserError> + Send + 'static,
+    ) -> Result<T, UserError> {
+        let argon2 = self.argon2.clone();
+        tokio::task::spawn_blocking(move || work(argon2))
+            .await
+            .map_err(|e| UserError::Internal(InfrastructureError::new(operation, e)))?
+    }
+}
+
+#[async_trait]
+impl PasswordHasherPort for Argon2PasswordHasher {
+    async fn hash(&self, password: &Password) -> Result<PasswordHash, UserError> {
+        let password = password.clone();
+        self.run_blocking("hash password", move |argon2| {
+            let salt = SaltString::generate(&mut OsRng);
+            argon2
+                .hash_password(password.as_str().as_bytes(), &salt)
+                .map(|hash| PasswordHash::new(hash.to_string()))
+                .map_err(|e| UserError::Internal(InfrastructureError::new("hash password", e.to_string())))
+        })
+        .await
+    }
+
+    async fn verify(&self, password: &Password, hash: &PasswordHash) -> Result<bool, UserError> {
+        let password = password.clone();
+        let hash = hash.clone();
+        self.run_blocking("verify password", move |argon2| {
+            let Ok(parsed) = password_hash::PasswordHash::new(hash.as_str()) else {
+                tracing::warn!("Stored password hash is malformed");
+                return Ok(false);
+            };
+            Ok(argon2.verify_password(password.as_str().as_bytes(), &parsed).is_ok())
+        })
+        .await
+    }
+}
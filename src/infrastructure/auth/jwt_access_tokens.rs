@@ -0,0 +1,80 @@
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::{
+    AccessToken, AccessTokenPort, InfrastructureError, Principal, Role, UserError, UserId,
+};
+
+/// HS256 JWT adapter implementing AccessTokenPort
+#[derive(Clone)]
+pub struct JwtAccessTokens {
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    validation: Validation,
+    issuer: String,
+    ttl: Duration,
+}
+
+/// Claims carried by access tokens: the registered ones plus the role
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    sub: Uuid,
+    role: String,
+    iss: String,
+    iat: i64,
+    exp: i64,
+}
+
+impl JwtAccessTokens {
+    pub fn new(secret: &[u8], issuer: impl Into<String>, ttl: Duration) -> Self {
+        let issuer = issuer.into();
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.set_issuer(&[issuer.as_str()]);
+        validation.set_required_spec_claims(&["exp", "iss", "sub"]);
+
+        Self {
+            encoding_key: EncodingKey::from_secret(secret),
+            decoding_key: DecodingKey::from_secret(secret),
+            validation,
+            issuer,
+            ttl,
+        }
+    }
+}
+
+impl AccessTokenPort for JwtAccessTokens {
+    fn issue(&self, principal: &Principal) -> Result<AccessToken, UserError> {
+        let issued_at = Utc::now();
+        let expires_at = issued_at + self.ttl;
+        let claims = Claims {
+            sub: principal.user_id.as_uuid(),
+            role: principal.role.as_str().to_string(),
+            iss: self.issuer.clone(),
+            iat: issued_at.timestamp(),
+            exp: expires_at.timestamp(),
+        };
+
+        let token = encode(&Header::new(Algorithm::HS256), &claims, &self.encoding_key)
+            .map_err(|e| UserError::Internal(InfrastructureError::new("sign access token", e)))?;
+        Ok(AccessToken { token, expires_at })
+    }
+
+    fn verify(&self, token: &str) -> Result<Principal, UserError> {
+        let data = decode::<Claims>(token, &self.decoding_key, &self.validation).map_err(|e| {
+            tracing::debug!("Rejected access token: {}", e);
+            UserError::Unauthenticated("invalid or expired access token".to_string())
+        })?;
+
+        let role = Role::parse(&data.claims.role)
+            .map_err(|_| UserError::Unauthenticated("invalid or expired access token".to_string()))?;
+
+        Ok(Principal {
+            user_id: UserId::from_uuid(data.claims.sub),
+            role,
+            scopes: None,
+            expires_at: DateTime::from_timestamp(data.claims.exp, 0),
+        })
+    }
+}
@@ -0,0 +1,5 @@
+pub mod argon2_password_hasher;
+pub mod jwt_access_tokens;
+
+pub use argon2_password_hasher::Argon2PasswordHasher;
+pub use jwt_access_tokens::JwtAccessTokens;
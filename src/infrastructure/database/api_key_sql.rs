@@ -0,0 +1,159 @@
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::domain::{ApiKey, Scope, UserError, UserId};
+
+/// Columns selected for the API key model
+pub(crate) const API_KEY_COLUMNS: &str =
+    "id, owner_id, name, prefix, key_hash, scopes, created_at, expires_at, last_used_at, revoked_at";
+
+/// Database model for ApiKey (infrastructure concern), shared by the SQL adapters
+#[derive(Debug, FromRow)]
+pub(crate) struct ApiKeyDbModel {
+    id: Uuid,
+    owner_id: Uuid,
+    name: String,
+    prefix: String,
+    key_hash: String,
+    scopes: String,
+    created_at: DateTime<Utc>,
+    expires_at: Option<DateTime<Utc>>,
+    last_used_at: Option<DateTime<Utc>>,
+    revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKeyDbModel {
+    pub(crate) fn into_domain(self) -> Result<ApiKey, UserError> {
+        Ok(ApiKey {
+            id: self.id,
+            owner_id: UserId::from_uuid(self.owner_id),
+            name: self.name,
+            prefix: self.prefix,
+            key_hash: self.key_hash,
+            scopes: Scope::parse_list(&self.scopes)?,
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+            last_used_at: self.last_used_at,
+            revoked_at: self.revoked_at,
+        })
+    }
+}
+
+/// Adapter implementing ApiKeyRepositoryPort over a sqlx pool. The SQL runs
+/// unchanged on every backend, so each one only names its pool type.
+macro_rules! api_key_repository {
+    ($(#[$attr:meta])* $name:ident($pool:ty)) => {
+        use async_trait::async_trait;
+        use chrono::{DateTime, Utc};
+        use uuid::Uuid;
+
+        use $crate::{
+            domain::{ApiKey, ApiKeyRepositoryPort, Scope, UserError, UserId},
+            infrastructure::database::{
+                api_key_sql::{API_KEY_COLUMNS, ApiKeyDbModel},
+                error::{is_foreign_key_violation, map_sqlx_error},
+            },
+        };
+
+        $(#[$attr])*
+        #[derive(Clone)]
+        pub struct $name {
+            pool: $pool,
+        }
+
+        impl $name {
+            pub fn new(pool: $pool) -> Self {
+                Self { pool }
+            }
+        }
+
+        #[async_trait]
+        impl ApiKeyRepositoryPort for $name {
+            async fn save(&self, key: &ApiKey) -> Result<(), UserError> {
+                sqlx::query(
+                    r#"
+                    INSERT INTO api_keys
+                        (id, owner_id, name, prefix, key_hash, scopes, created_at, expires_at, last_used_at, revoked_at)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                    "#,
+                )
+                .bind(key.id)
+                .bind(key.owner_id.as_uuid())
+                .bind(&key.name)
+                .bind(&key.prefix)
+                .bind(&key.key_hash)
+                .bind(Scope::join(&key.scopes))
+                .bind(key.created_at)
+                .bind(key.expires_at)
+                .bind(key.last_used_at)
+                .bind(key.revoked_at)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| {
+                    if is_foreign_key_violation(&e) {
+                        UserError::NotFound
+                    } else {
+                        map_sqlx_error("save api key", e)
+                    }
+                })?;
+
+                Ok(())
+            }
+
+            async fn find_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>, UserError> {
+                let result = sqlx::query_as::<_, ApiKeyDbModel>(&format!(
+                    "SELECT {} FROM api_keys WHERE key_hash = $1",
+                    API_KEY_COLUMNS
+                ))
+                .bind(key_hash)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("find api key", e))?;
+
+                result.map(ApiKeyDbModel::into_domain).transpose()
+            }
+
+            async fn find_all(&self, owner_id: Option<&UserId>) -> Result<Vec<ApiKey>, UserError> {
+                let results = sqlx::query_as::<_, ApiKeyDbModel>(&format!(
+                    "SELECT {} FROM api_keys WHERE ($1 IS NULL OR owner_id = $1) ORDER BY created_at DESC, id DESC",
+                    API_KEY_COLUMNS
+                ))
+                .bind(owner_id.map(UserId::as_uuid))
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("list api keys", e))?;
+
+                results.into_iter().map(ApiKeyDbModel::into_domain).collect()
+            }
+
+            async fn revoke(&self, id: Uuid, revoked_at: DateTime<Utc>) -> Result<(), UserError> {
+                let result = sqlx::query("UPDATE api_keys SET revoked_at = $2 WHERE id = $1 AND revoked_at IS NULL")
+                    .bind(id)
+                    .bind(revoked_at)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(|e| map_sqlx_error("revoke api key", e))?;
+
+                if result.rows_affected() == 0 {
+                    return Err(UserError::ApiKeyNotFound);
+                }
+
+                Ok(())
+            }
+
+            async fn record_use(&self, id: Uuid, used_at: DateTime<Utc>) -> Result<(), UserError> {
+                sqlx::query("UPDATE api_keys SET last_used_at = $2 WHERE id = $1")
+                    .bind(id)
+                    .bind(used_at)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(|e| map_sqlx_error("record api key use", e))?;
+
+                Ok(())
+            }
+        }
+    };
+}
+
+pub(crate) use api_key_repository;
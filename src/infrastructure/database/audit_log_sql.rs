@@ -0,0 +1,133 @@
+use chrono::{DateTime, Utc};
+use sqlx::{Database, Encode, FromRow, QueryBuilder, Type};
+use uuid::Uuid;
+
+use crate::domain::{AuditAction, AuditEntry, AuditQuery, FieldChange, InfrastructureError, UserError, UserId};
+
+/// Columns selected for the audit entry model
+pub(crate) const AUDIT_COLUMNS: &str = "id, user_id, actor_id, action, changes, request_id, created_at";
+
+/// Database model for AuditEntry (infrastructure concern), shared by the SQL adapters
+#[derive(Debug, FromRow)]
+pub(crate) struct AuditEntryDbModel {
+    id: Uuid,
+    user_id: Uuid,
+    actor_id: Option<Uuid>,
+    action: String,
+    /// JSON array of `{field, before, after}`
+    changes: String,
+    request_id: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+impl AuditEntryDbModel {
+    pub(crate) fn into_domain(self) -> Result<AuditEntry, UserError> {
+        let changes = serde_json::from_str(&self.changes)
+            .map_err(|e| UserError::Internal(InfrastructureError::new("decode audit changes", e)))?;
+
+        Ok(AuditEntry {
+            id: self.id,
+            user_id: UserId::from_uuid(self.user_id),
+            actor: self.actor_id.map(UserId::from_uuid),
+            action: AuditAction::parse(&self.action)?,
+            changes,
+            request_id: self.request_id,
+            created_at: self.created_at,
+        })
+    }
+}
+
+pub(crate) fn encode_changes(changes: &[FieldChange]) -> Result<String, UserError> {
+    serde_json::to_string(changes)
+        .map_err(|e| UserError::Internal(InfrastructureError::new("encode audit changes", e)))
+}
+
+/// Push ` WHERE ...` with every criterion of `query`, values as bind parameters
+pub(crate) fn push_audit_where<'args, DB>(qb: &mut QueryBuilder<'args, DB>, query: &AuditQuery)
+where
+    DB: Database,
+    String: Encode<'args, DB> + Type<DB>,
+    DateTime<Utc>: Encode<'args, DB> + Type<DB>,
+    Uuid: Encode<'args, DB> + Type<DB>,
+{
+    qb.push(" WHERE 1 = 1");
+    if let Some(user_id) = &query.user_id {
+        qb.push(" AND user_id = ").push_bind(user_id.as_uuid());
+    }
+    if let Some(actor) = &query.actor {
+        qb.push(" AND actor_id = ").push_bind(actor.as_uuid());
+    }
+    if let Some(action) = query.action {
+        qb.push(" AND action = ").push_bind(action.as_str().to_string());
+    }
+    if let Some(request_id) = &query.request_id {
+        qb.push(" AND request_id = ").push_bind(request_id.clone());
+    }
+    if let Some(from) = query.from {
+        qb.push(" AND created_at >= ").push_bind(from);
+    }
+    if let Some(to) = query.to {
+        qb.push(" AND created_at < ").push_bind(to);
+    }
+}
+
+/// Adapter implementing AuditLogRepositoryPort over a sqlx pool of the
+/// database `$db`. Only the query builder needs to know the backend.
+macro_rules! audit_log_repository {
+    ($(#[$attr:meta])* $name:ident($pool:ty, $db:ty)) => {
+        use async_trait::async_trait;
+        use sqlx::QueryBuilder;
+
+        use $crate::{
+            domain::{AuditEntry, AuditLogRepositoryPort, AuditQuery, UserError},
+            infrastructure::database::{
+                audit_log_sql::{AUDIT_COLUMNS, AuditEntryDbModel, push_audit_where},
+                error::map_sqlx_error,
+            },
+        };
+
+        $(#[$attr])*
+        #[derive(Clone)]
+        pub struct $name {
+            pool: $pool,
+        }
+
+        impl $name {
+            pub fn new(pool: $pool) -> Self {
+                Self { pool }
+            }
+        }
+
+        #[async_trait]
+        impl AuditLogRepositoryPort for $name {
+            async fn find(&self, query: &AuditQuery, offset: i64, limit: i64) -> Result<Vec<AuditEntry>, UserError> {
+                let mut qb = QueryBuilder::<$db>::new(format!("SELECT {} FROM audit_log", AUDIT_COLUMNS));
+                push_audit_where(&mut qb, query);
+                qb.push(" ORDER BY created_at DESC, id DESC");
+                qb.push(" LIMIT ").push_bind(limit).push(" OFFSET ").push_bind(offset);
+
+                let results = qb
+                    .build_query_as::<AuditEntryDbModel>()
+                    .fetch_all(&self.pool)
+                    .await
+                    .map_err(|e| map_sqlx_error("find audit entries", e))?;
+
+                results.into_iter().map(AuditEntryDbModel::into_domain).collect()
+            }
+
+            async fn count(&self, query: &AuditQuery) -> Result<i64, UserError> {
+                let mut qb = QueryBuilder::<$db>::new("SELECT COUNT(*) FROM audit_log");
+                push_audit_where(&mut qb, query);
+                let (count,): (i64,) = qb
+                    .build_query_as()
+                    .fetch_one(&self.pool)
+                    .await
+                    .map_err(|e| map_sqlx_error("count audit entries", e))?;
+
+                Ok(count)
+            }
+        }
+    };
+}
+
+pub(crate) use audit_log_repository;
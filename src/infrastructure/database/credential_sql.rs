@@ -0,0 +1,65 @@
+/// Adapter implementing CredentialRepositoryPort over a sqlx pool. The SQL
+/// runs unchanged on every backend, so each one only names its pool type.
+macro_rules! credential_repository {
+    ($(#[$attr:meta])* $name:ident($pool:ty)) => {
+        use async_trait::async_trait;
+
+        use $crate::{
+            domain::{current_timestamp, CredentialRepositoryPort, PasswordHash, UserError, UserId},
+            infrastructure::database::error::{is_foreign_key_violation, map_sqlx_error},
+        };
+
+        $(#[$attr])*
+        #[derive(Clone)]
+        pub struct $name {
+            pool: $pool,
+        }
+
+        impl $name {
+            pub fn new(pool: $pool) -> Self {
+                Self { pool }
+            }
+        }
+
+        #[async_trait]
+        impl CredentialRepositoryPort for $name {
+            async fn set_password_hash(&self, user_id: &UserId, hash: &PasswordHash) -> Result<(), UserError> {
+                sqlx::query(
+                    r#"
+                    INSERT INTO user_credentials (user_id, password_hash, updated_at)
+                    VALUES ($1, $2, $3)
+                    ON CONFLICT (user_id)
+                    DO UPDATE SET password_hash = excluded.password_hash, updated_at = excluded.updated_at
+                    "#,
+                )
+                .bind(user_id.as_uuid())
+                .bind(hash.as_str())
+                .bind(current_timestamp())
+                .execute(&self.pool)
+                .await
+                .map_err(|e| {
+                    if is_foreign_key_violation(&e) {
+                        UserError::NotFound
+                    } else {
+                        map_sqlx_error("set password hash", e)
+                    }
+                })?;
+
+                Ok(())
+            }
+
+            async fn find_password_hash(&self, user_id: &UserId) -> Result<Option<PasswordHash>, UserError> {
+                let row: Option<(String,)> =
+                    sqlx::query_as("SELECT password_hash FROM user_credentials WHERE user_id = $1")
+                        .bind(user_id.as_uuid())
+                        .fetch_optional(&self.pool)
+                        .await
+                        .map_err(|e| map_sqlx_error("find password hash", e))?;
+
+                Ok(row.map(|(hash,)| PasswordHash::new(hash)))
+            }
+        }
+    };
+}
+
+pub(crate) use credential_repository;
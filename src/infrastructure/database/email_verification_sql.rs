@@ -0,0 +1,133 @@
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::domain::{Email, EmailVerificationToken, UserError, UserId};
+
+/// Columns selected for the email verification token model
+pub(crate) const TOKEN_COLUMNS: &str = "id, user_id, email, token_hash, created_at, expires_at, used_at";
+
+/// Database model for EmailVerificationToken (infrastructure concern), shared by the SQL adapters
+#[derive(Debug, FromRow)]
+pub(crate) struct EmailVerificationTokenDbModel {
+    id: Uuid,
+    user_id: Uuid,
+    email: String,
+    token_hash: String,
+    created_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    used_at: Option<DateTime<Utc>>,
+}
+
+impl EmailVerificationTokenDbModel {
+    pub(crate) fn into_domain(self) -> Result<EmailVerificationToken, UserError> {
+        Ok(EmailVerificationToken {
+            id: self.id,
+            user_id: UserId::from_uuid(self.user_id),
+            email: Email::new(self.email)?,
+            token_hash: self.token_hash,
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+            used_at: self.used_at,
+        })
+    }
+}
+
+/// Adapter implementing EmailVerificationRepositoryPort over a sqlx pool. The
+/// SQL runs unchanged on every backend, so each one only names its pool type.
+macro_rules! email_verification_repository {
+    ($(#[$attr:meta])* $name:ident($pool:ty)) => {
+        use async_trait::async_trait;
+        use chrono::{DateTime, Utc};
+        use uuid::Uuid;
+
+        use $crate::{
+            domain::{EmailVerificationRepositoryPort, EmailVerificationToken, UserError},
+            infrastructure::database::{
+                email_verification_sql::{TOKEN_COLUMNS, EmailVerificationTokenDbModel},
+                error::{is_foreign_key_violation, map_sqlx_error},
+            },
+        };
+
+        $(#[$attr])*
+        #[derive(Clone)]
+        pub struct $name {
+            pool: $pool,
+        }
+
+        impl $name {
+            pub fn new(pool: $pool) -> Self {
+                Self { pool }
+            }
+        }
+
+        #[async_trait]
+        impl EmailVerificationRepositoryPort for $name {
+            async fn save(&self, token: &EmailVerificationToken) -> Result<(), UserError> {
+                sqlx::query(
+                    r#"
+                    INSERT INTO email_verification_tokens
+                        (id, user_id, email, token_hash, created_at, expires_at, used_at)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7)
+                    "#,
+                )
+                .bind(token.id)
+                .bind(token.user_id.as_uuid())
+                .bind(token.email.as_str())
+                .bind(&token.token_hash)
+                .bind(token.created_at)
+                .bind(token.expires_at)
+                .bind(token.used_at)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| {
+                    if is_foreign_key_violation(&e) {
+                        UserError::NotFound
+                    } else {
+                        map_sqlx_error("save email verification token", e)
+                    }
+                })?;
+
+                Ok(())
+            }
+
+            async fn find_by_token_hash(&self, token_hash: &str) -> Result<Option<EmailVerificationToken>, UserError> {
+                let result = sqlx::query_as::<_, EmailVerificationTokenDbModel>(&format!(
+                    "SELECT {} FROM email_verification_tokens WHERE token_hash = $1",
+                    TOKEN_COLUMNS
+                ))
+                .bind(token_hash)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("find email verification token", e))?;
+
+                result.map(EmailVerificationTokenDbModel::into_domain).transpose()
+            }
+
+            async fn mark_used(&self, id: Uuid, used_at: DateTime<Utc>) -> Result<bool, UserError> {
+                let result = sqlx::query(
+                    "UPDATE email_verification_tokens SET used_at = $2 WHERE id = $1 AND used_at IS NULL",
+                )
+                .bind(id)
+                .bind(used_at)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("use email verification token", e))?;
+
+                Ok(result.rows_affected() == 1)
+            }
+
+            async fn purge_expired(&self, expired_before: DateTime<Utc>) -> Result<u64, UserError> {
+                let result = sqlx::query("DELETE FROM email_verification_tokens WHERE expires_at < $1")
+                    .bind(expired_before)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(|e| map_sqlx_error("purge email verification tokens", e))?;
+
+                Ok(result.rows_affected())
+            }
+        }
+    };
+}
+
+pub(crate) use email_verification_repository;
@@ -0,0 +1,114 @@
+use sqlx::error::ErrorKind;
+
+use crate::domain::{InfrastructureError, UserError};
+
+/// Unique index keeping active users' emails distinct (migration 003)
+const USERS_EMAIL_INDEX: &str = "idx_users_email_lower";
+
+/// Classify a sqlx failure into the domain error taxonomy.
+/// `operation` names what the adapter was doing, for logs and error chains.
+pub(crate) fn map_sqlx_error(operation: &'static str, err: sqlx::Error) -> UserError {
+    let mapped = classify(operation, err);
+    // Violations and conflicts are expected outcomes the caller reports
+    match &mapped {
+        UserError::Timeout(_) | UserError::Unavailable(_) | UserError::Internal(_) => {
+            tracing::error!("Failed to {}: {}", operation, mapped)
+        }
+        _ => tracing::debug!("Failed to {}: {}", operation, mapped),
+    }
+    mapped
+}
+
+fn classify(operation: &'static str, err: sqlx::Error) -> UserError {
+    match &err {
+        sqlx::Error::PoolTimedOut => UserError::Timeout(InfrastructureError::new(operation, err)),
+        sqlx::Error::PoolClosed
+        | sqlx::Error::Io(_)
+        | sqlx::Error::Tls(_)
+        | sqlx::Error::WorkerCrashed => {
+            UserError::Unavailable(InfrastructureError::new(operation, err))
+        }
+        sqlx::Error::Database(db_err) => {
+            if db_err.kind() == ErrorKind::UniqueViolation
+                && constraint(db_err.as_ref()).as_deref() == Some(USERS_EMAIL_INDEX)
+            {
+                return UserError::EmailAlreadyExists;
+            }
+
+            match db_err.kind() {
+                ErrorKind::UniqueViolation
+                | ErrorKind::ForeignKeyViolation
+                | ErrorKind::NotNullViolation
+                | ErrorKind::CheckViolation => {
+                    return UserError::ConstraintViolation(InfrastructureError::new(operation, err));
+                }
+                _ => {}
+            }
+
+            let code = db_err.code().map(|c| c.into_owned()).unwrap_or_default();
+
+            #[cfg(feature = "sqlite")]
+            if db_err.try_downcast_ref::<sqlx::sqlite::SqliteError>().is_some() {
+                return classify_sqlite_code(&code)(InfrastructureError::new(operation, err));
+            }
+
+            classify_database_code(&code)(InfrastructureError::new(operation, err))
+        }
+        _ => UserError::Internal(InfrastructureError::new(operation, err)),
+    }
+}
+
+/// Name of the violated constraint or index. SQLite does not report it
+/// separately, only as `index '<name>'` in the message.
+fn constraint(db_err: &dyn sqlx::error::DatabaseError) -> Option<String> {
+    if let Some(name) = db_err.constraint() {
+        return Some(name.to_string());
+    }
+    #[cfg(feature = "sqlite")]
+    if db_err.try_downcast_ref::<sqlx::sqlite::SqliteError>().is_some() {
+        return db_err
+            .message()
+            .split_once("index '")
+            .and_then(|(_, rest)| rest.split_once('\''))
+            .map(|(name, _)| name.to_string());
+    }
+    None
+}
+
+/// Map a backend error code (Postgres SQLSTATE) to a variant
+fn classify_database_code(code: &str) -> fn(InfrastructureError) -> UserError {
+    match code {
+        // serialization_failure, deadlock_detected
+        "40001" | "40P01" => UserError::Conflict,
+        // query_canceled (statement_timeout), lock_not_available
+        "57014" | "55P03" => UserError::Timeout,
+        // admin_shutdown, crash_shutdown, cannot_connect_now
+        "57P01" | "57P02" | "57P03" => UserError::Unavailable,
+        // connection_exception and insufficient_resources classes
+        c if c.starts_with("08") || c.starts_with("53") => UserError::Unavailable,
+        _ => UserError::Internal,
+    }
+}
+
+/// Map a SQLite (extended) result code to a variant
+#[cfg(feature = "sqlite")]
+fn classify_sqlite_code(code: &str) -> fn(InfrastructureError) -> UserError {
+    // The primary result code lives in the low byte of the extended code
+    match code.parse::<i32>().map(|c| c & 0xff) {
+        // SQLITE_BUSY, SQLITE_LOCKED
+        Ok(5) | Ok(6) => UserError::Timeout,
+        // SQLITE_IOERR, SQLITE_FULL, SQLITE_CANTOPEN
+        Ok(10) | Ok(13) | Ok(14) => UserError::Unavailable,
+        _ => UserError::Internal,
+    }
+}
+
+/// A guarded write matched no row because the stored version has moved on
+pub(crate) fn version_conflict(operation: &'static str) -> UserError {
+    UserError::Conflict(InfrastructureError::new(operation, "stored version has changed"))
+}
+
+/// Whether a write failed because a referenced row does not exist
+pub(crate) fn is_foreign_key_violation(err: &sqlx::Error) -> bool {
+    matches!(err, sqlx::Error::Database(db_err) if db_err.kind() == ErrorKind::ForeignKeyViolation)
+}
@@ -0,0 +1,179 @@
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+use crate::domain::{IdempotencyRecord, InfrastructureError, StoredResponse, UserError};
+
+/// Columns selected for the idempotency record model
+pub(crate) const RECORD_COLUMNS: &str = "scope, idempotency_key, fingerprint, response_status, response_headers, \
+     response_body, created_at, expires_at";
+
+/// Database model for IdempotencyRecord (infrastructure concern), shared by the SQL adapters
+#[derive(Debug, FromRow)]
+pub(crate) struct IdempotencyRecordDbModel {
+    scope: String,
+    idempotency_key: String,
+    fingerprint: String,
+    response_status: Option<i16>,
+    /// JSON array of `[name, value]` pairs
+    response_headers: Option<String>,
+    response_body: Option<String>,
+    created_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+}
+
+pub(crate) fn encode_headers(headers: &[(String, String)]) -> Result<String, UserError> {
+    serde_json::to_string(headers)
+        .map_err(|e| UserError::Internal(InfrastructureError::new("encode stored response headers", e)))
+}
+
+impl IdempotencyRecordDbModel {
+    pub(crate) fn into_domain(self) -> Result<IdempotencyRecord, UserError> {
+        let response = match self.response_status {
+            Some(status) => Some(StoredResponse {
+                status: status as u16,
+                headers: match self.response_headers {
+                    Some(headers) => serde_json::from_str(&headers).map_err(|e| {
+                        UserError::Internal(InfrastructureError::new("decode stored response headers", e))
+                    })?,
+                    None => Vec::new(),
+                },
+                body: self.response_body.unwrap_or_default(),
+            }),
+            None => None,
+        };
+
+        Ok(IdempotencyRecord {
+            scope: self.scope,
+            key: self.idempotency_key,
+            fingerprint: self.fingerprint,
+            response,
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+        })
+    }
+}
+
+/// Adapter implementing IdempotencyRepositoryPort over a sqlx pool. The SQL
+/// runs unchanged on every backend, so each one only names its pool type.
+macro_rules! idempotency_repository {
+    ($(#[$attr:meta])* $name:ident($pool:ty)) => {
+        use async_trait::async_trait;
+        use chrono::{DateTime, Utc};
+
+        use $crate::{
+            domain::{IdempotencyRecord, IdempotencyRepositoryPort, StoredResponse, UserError},
+            infrastructure::database::{
+                error::map_sqlx_error,
+                idempotency_sql::{RECORD_COLUMNS, IdempotencyRecordDbModel, encode_headers},
+            },
+        };
+
+        $(#[$attr])*
+        #[derive(Clone)]
+        pub struct $name {
+            pool: $pool,
+        }
+
+        impl $name {
+            pub fn new(pool: $pool) -> Self {
+                Self { pool }
+            }
+        }
+
+        #[async_trait]
+        impl IdempotencyRepositoryPort for $name {
+            async fn claim(
+                &self,
+                record: &IdempotencyRecord,
+                abandoned_before: DateTime<Utc>,
+            ) -> Result<bool, UserError> {
+                let result = sqlx::query(
+                    r#"
+                    INSERT INTO idempotency_keys (scope, idempotency_key, fingerprint, created_at, expires_at)
+                    VALUES ($1, $2, $3, $4, $5)
+                    ON CONFLICT (scope, idempotency_key) DO UPDATE
+                    SET fingerprint = EXCLUDED.fingerprint,
+                        response_status = NULL,
+                        response_headers = NULL,
+                        response_body = NULL,
+                        created_at = EXCLUDED.created_at,
+                        expires_at = EXCLUDED.expires_at
+                    WHERE idempotency_keys.expires_at <= EXCLUDED.created_at
+                       OR (idempotency_keys.response_status IS NULL AND idempotency_keys.created_at < $6)
+                    "#,
+                )
+                .bind(&record.scope)
+                .bind(&record.key)
+                .bind(&record.fingerprint)
+                .bind(record.created_at)
+                .bind(record.expires_at)
+                .bind(abandoned_before)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("claim idempotency key", e))?;
+
+                Ok(result.rows_affected() == 1)
+            }
+
+            async fn find(&self, scope: &str, key: &str) -> Result<Option<IdempotencyRecord>, UserError> {
+                let result = sqlx::query_as::<_, IdempotencyRecordDbModel>(&format!(
+                    "SELECT {} FROM idempotency_keys WHERE scope = $1 AND idempotency_key = $2",
+                    RECORD_COLUMNS
+                ))
+                .bind(scope)
+                .bind(key)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("find idempotency key", e))?;
+
+                result.map(IdempotencyRecordDbModel::into_domain).transpose()
+            }
+
+            async fn complete(&self, scope: &str, key: &str, response: &StoredResponse) -> Result<(), UserError> {
+                sqlx::query(
+                    r#"
+                    UPDATE idempotency_keys
+                    SET response_status = $3, response_headers = $4, response_body = $5
+                    WHERE scope = $1 AND idempotency_key = $2
+                    "#,
+                )
+                .bind(scope)
+                .bind(key)
+                .bind(response.status as i16)
+                .bind(encode_headers(&response.headers)?)
+                .bind(&response.body)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("complete idempotency key", e))?;
+
+                Ok(())
+            }
+
+            async fn release(&self, scope: &str, key: &str) -> Result<(), UserError> {
+                sqlx::query(
+                    "DELETE FROM idempotency_keys \
+                     WHERE scope = $1 AND idempotency_key = $2 AND response_status IS NULL",
+                )
+                .bind(scope)
+                .bind(key)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("release idempotency key", e))?;
+
+                Ok(())
+            }
+
+            async fn purge_expired(&self, expired_before: DateTime<Utc>) -> Result<u64, UserError> {
+                let result = sqlx::query("DELETE FROM idempotency_keys WHERE expires_at < $1")
+                    .bind(expired_before)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(|e| map_sqlx_error("purge idempotency keys", e))?;
+
+                Ok(result.rows_affected())
+            }
+        }
+    };
+}
+
+pub(crate) use idempotency_repository;
@@ -0,0 +1,108 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+use uuid::Uuid;
+
+use crate::{
+    domain::{ApiKey, ApiKeyRepositoryPort, InfrastructureError, UserError, UserId, UserRepositoryPort},
+    infrastructure::database::InMemoryUserRepository,
+};
+
+/// In-process adapter implementing ApiKeyRepositoryPort.
+/// Shares the user store it belongs to, so keys only exist for stored users
+/// and vanish with them, like the foreign key in the SQL adapters.
+#[derive(Clone)]
+pub struct InMemoryApiKeyRepository {
+    users: InMemoryUserRepository,
+    keys: Arc<RwLock<HashMap<Uuid, ApiKey>>>,
+}
+
+impl InMemoryApiKeyRepository {
+    pub fn new(users: InMemoryUserRepository) -> Self {
+        Self {
+            users,
+            keys: Arc::default(),
+        }
+    }
+
+    async fn owner_exists(&self, owner_id: &UserId) -> Result<bool, UserError> {
+        Ok(self.users.find_by_id_including_deleted(owner_id).await?.is_some())
+    }
+}
+
+fn poisoned() -> UserError {
+    UserError::Internal(InfrastructureError::new("access api key store", "lock poisoned"))
+}
+
+#[async_trait]
+impl ApiKeyRepositoryPort for InMemoryApiKeyRepository {
+    async fn save(&self, key: &ApiKey) -> Result<(), UserError> {
+        if !self.owner_exists(&key.owner_id).await? {
+            return Err(UserError::NotFound);
+        }
+        let mut keys = self.keys.write().map_err(|_| poisoned())?;
+        if keys.values().any(|k| k.id == key.id || k.key_hash == key.key_hash) {
+            return Err(UserError::ConstraintViolation(InfrastructureError::new(
+                "save api key",
+                "duplicate api key",
+            )));
+        }
+        keys.insert(key.id, key.clone());
+        Ok(())
+    }
+
+    async fn find_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>, UserError> {
+        let key = self
+            .keys
+            .read()
+            .map_err(|_| poisoned())?
+            .values()
+            .find(|k| k.key_hash == key_hash)
+            .cloned();
+        match key {
+            Some(key) if self.owner_exists(&key.owner_id).await? => Ok(Some(key)),
+            _ => Ok(None),
+        }
+    }
+
+    async fn find_all(&self, owner_id: Option<&UserId>) -> Result<Vec<ApiKey>, UserError> {
+        let keys: Vec<ApiKey> = self
+            .keys
+            .read()
+            .map_err(|_| poisoned())?
+            .values()
+            .filter(|k| owner_id.is_none_or(|owner| k.owner_id == *owner))
+            .cloned()
+            .collect();
+
+        let mut live = Vec::with_capacity(keys.len());
+        for key in keys {
+            if self.owner_exists(&key.owner_id).await? {
+                live.push(key);
+            }
+        }
+        live.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id)));
+        Ok(live)
+    }
+
+    async fn revoke(&self, id: Uuid, revoked_at: DateTime<Utc>) -> Result<(), UserError> {
+        let mut keys = self.keys.write().map_err(|_| poisoned())?;
+        match keys.get_mut(&id).filter(|k| k.revoked_at.is_none()) {
+            Some(key) => {
+                key.revoked_at = Some(revoked_at);
+                Ok(())
+            }
+            None => Err(UserError::ApiKeyNotFound),
+        }
+    }
+
+    async fn record_use(&self, id: Uuid, used_at: DateTime<Utc>) -> Result<(), UserError> {
+        if let Some(key) = self.keys.write().map_err(|_| poisoned())?.get_mut(&id) {
+            key.last_used_at = Some(used_at);
+        }
+        Ok(())
+    }
+}
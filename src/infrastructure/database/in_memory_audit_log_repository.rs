@@ -0,0 +1,56 @@
+use async_trait::async_trait;
+
+use crate::{
+    domain::{AuditEntry, AuditLogRepositoryPort, AuditQuery, UserError},
+    infrastructure::database::InMemoryUserRepository,
+};
+
+/// In-process adapter implementing AuditLogRepositoryPort.
+/// Reads the entries the user store appends with each audited change.
+#[derive(Clone)]
+pub struct InMemoryAuditLogRepository {
+    users: InMemoryUserRepository,
+}
+
+impl InMemoryAuditLogRepository {
+    pub fn new(users: InMemoryUserRepository) -> Self {
+        Self { users }
+    }
+
+    /// Entries matching `query`, newest first like the SQL adapters
+    fn matching(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>, UserError> {
+        let mut entries: Vec<AuditEntry> = self
+            .users
+            .audit_entries()?
+            .into_iter()
+            .filter(|entry| matches(entry, query))
+            .collect();
+        entries.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| b.id.cmp(&a.id)));
+        Ok(entries)
+    }
+}
+
+fn matches(entry: &AuditEntry, query: &AuditQuery) -> bool {
+    query.user_id.as_ref().is_none_or(|id| *id == entry.user_id)
+        && query.actor.as_ref().is_none_or(|actor| Some(actor) == entry.actor.as_ref())
+        && query.action.is_none_or(|action| action == entry.action)
+        && query.request_id.as_ref().is_none_or(|id| Some(id) == entry.request_id.as_ref())
+        && query.from.is_none_or(|from| entry.created_at >= from)
+        && query.to.is_none_or(|to| entry.created_at < to)
+}
+
+#[async_trait]
+impl AuditLogRepositoryPort for InMemoryAuditLogRepository {
+    async fn find(&self, query: &AuditQuery, offset: i64, limit: i64) -> Result<Vec<AuditEntry>, UserError> {
+        Ok(self
+            .matching(query)?
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .collect())
+    }
+
+    async fn count(&self, query: &AuditQuery) -> Result<i64, UserError> {
+        Ok(self.matching(query)?.len() as i64)
+    }
+}
@@ -0,0 +1,56 @@
+use async_trait::async_trait;
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use crate::{
+    domain::{
+        CredentialRepositoryPort, InfrastructureError, PasswordHash, UserError, UserId,
+        UserRepositoryPort,
+    },
+    infrastructure::database::InMemoryUserRepository,
+};
+
+/// In-process adapter implementing CredentialRepositoryPort.
+/// Shares the user store it belongs to, so credentials only exist for stored
+/// users and vanish with them, like the foreign key in the SQL adapters.
+#[derive(Clone)]
+pub struct InMemoryCredentialRepository {
+    users: InMemoryUserRepository,
+    hashes: Arc<RwLock<HashMap<UserId, PasswordHash>>>,
+}
+
+impl InMemoryCredentialRepository {
+    pub fn new(users: InMemoryUserRepository) -> Self {
+        Self {
+            users,
+            hashes: Arc::default(),
+        }
+    }
+}
+
+fn poisoned() -> UserError {
+    UserError::Internal(InfrastructureError::new("access credential store", "lock poisoned"))
+}
+
+#[async_trait]
+impl CredentialRepositoryPort for InMemoryCredentialRepository {
+    async fn set_password_hash(&self, user_id: &UserId, hash: &PasswordHash) -> Result<(), UserError> {
+        if self.users.find_by_id_including_deleted(user_id).await?.is_none() {
+            return Err(UserError::NotFound);
+        }
+        self.hashes
+            .write()
+            .map_err(|_| poisoned())?
+            .insert(user_id.clone(), hash.clone());
+        Ok(())
+    }
+
+    async fn find_password_hash(&self, user_id: &UserId) -> Result<Option<PasswordHash>, UserError> {
+        if self.users.find_by_id_including_deleted(user_id).await?.is_none() {
+            return Ok(None);
+        }
+        Ok(self.hashes.read().map_err(|_| poisoned())?.get(user_id).cloned())
+    }
+}
@@ -0,0 +1,73 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use crate::domain::{IdempotencyRecord, IdempotencyRepositoryPort, InfrastructureError, StoredResponse, UserError};
+
+/// In-process adapter implementing IdempotencyRepositoryPort
+#[derive(Clone, Default)]
+pub struct InMemoryIdempotencyRepository {
+    records: Arc<RwLock<HashMap<(String, String), IdempotencyRecord>>>,
+}
+
+impl InMemoryIdempotencyRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+fn poisoned() -> UserError {
+    UserError::Internal(InfrastructureError::new("access idempotency store", "lock poisoned"))
+}
+
+fn id(scope: &str, key: &str) -> (String, String) {
+    (scope.to_string(), key.to_string())
+}
+
+#[async_trait]
+impl IdempotencyRepositoryPort for InMemoryIdempotencyRepository {
+    async fn claim(&self, record: &IdempotencyRecord, abandoned_before: DateTime<Utc>) -> Result<bool, UserError> {
+        let mut records = self.records.write().map_err(|_| poisoned())?;
+        let free = records.get(&id(&record.scope, &record.key)).is_none_or(|existing| {
+            existing.expires_at <= record.created_at
+                || (existing.response.is_none() && existing.created_at < abandoned_before)
+        });
+        if free {
+            let claimed = IdempotencyRecord {
+                response: None,
+                ..record.clone()
+            };
+            records.insert(id(&record.scope, &record.key), claimed);
+        }
+        Ok(free)
+    }
+
+    async fn find(&self, scope: &str, key: &str) -> Result<Option<IdempotencyRecord>, UserError> {
+        Ok(self.records.read().map_err(|_| poisoned())?.get(&id(scope, key)).cloned())
+    }
+
+    async fn complete(&self, scope: &str, key: &str, response: &StoredResponse) -> Result<(), UserError> {
+        if let Some(record) = self.records.write().map_err(|_| poisoned())?.get_mut(&id(scope, key)) {
+            record.response = Some(response.clone());
+        }
+        Ok(())
+    }
+
+    async fn release(&self, scope: &str, key: &str) -> Result<(), UserError> {
+        let mut records = self.records.write().map_err(|_| poisoned())?;
+        if records.get(&id(scope, key)).is_some_and(|record| record.response.is_none()) {
+            records.remove(&id(scope, key));
+        }
+        Ok(())
+    }
+
+    async fn purge_expired(&self, expired_before: DateTime<Utc>) -> Result<u64, UserError> {
+        let mut records = self.records.write().map_err(|_| poisoned())?;
+        let before = records.len();
+        records.retain(|_, record| record.expires_at >= expired_before);
+        Ok((before - records.len()) as u64)
+    }
+}
@@ -0,0 +1,124 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashSet;
+
+use crate::{
+    domain::{OutboxMessage, OutboxRepositoryPort, UserError, UserEvent, UserId},
+    infrastructure::database::InMemoryUserRepository,
+};
+
+/// One row of the in-memory outbox, mirroring the `outbox` table
+#[derive(Debug, Clone)]
+pub(crate) struct OutboxRow {
+    seq: i64,
+    event: UserEvent,
+    available_at: DateTime<Utc>,
+    attempts: i32,
+    sent_at: Option<DateTime<Utc>>,
+}
+
+/// The in-memory outbox, oldest row first
+#[derive(Debug, Default)]
+pub(crate) struct OutboxRows {
+    rows: Vec<OutboxRow>,
+    last_seq: i64,
+}
+
+impl OutboxRows {
+    pub(crate) fn push(&mut self, event: &UserEvent) {
+        self.last_seq += 1;
+        self.rows.push(OutboxRow {
+            seq: self.last_seq,
+            event: event.clone(),
+            available_at: event.occurred_at(),
+            attempts: 0,
+            sent_at: None,
+        });
+    }
+
+    fn pending(&mut self, seq: i64) -> Option<&mut OutboxRow> {
+        self.rows.iter_mut().find(|row| row.seq == seq && row.sent_at.is_none())
+    }
+}
+
+/// In-process adapter implementing OutboxRepositoryPort.
+/// Works on the events the user store enqueues with each audited change.
+#[derive(Clone)]
+pub struct InMemoryOutboxRepository {
+    users: InMemoryUserRepository,
+}
+
+impl InMemoryOutboxRepository {
+    pub fn new(users: InMemoryUserRepository) -> Self {
+        Self { users }
+    }
+}
+
+#[async_trait]
+impl OutboxRepositoryPort for InMemoryOutboxRepository {
+    async fn claim(&self, now: DateTime<Utc>, lease: Duration, limit: i64) -> Result<Vec<OutboxMessage>, UserError> {
+        let mut outbox = self.users.outbox()?;
+
+        // Rows are in `seq` order, so the first undelivered row of each user
+        // is the only one that may be claimed
+        let mut seen: HashSet<UserId> = HashSet::new();
+        let mut claimed = Vec::new();
+        for row in outbox.rows.iter_mut().filter(|row| row.sent_at.is_none()) {
+            if claimed.len() as i64 >= limit {
+                break;
+            }
+            if !seen.insert(row.event.user_id().clone()) || row.available_at > now {
+                continue;
+            }
+            row.available_at = now + lease;
+            row.attempts += 1;
+            claimed.push(OutboxMessage {
+                seq: row.seq,
+                event: row.event.clone(),
+                attempts: row.attempts,
+            });
+        }
+        Ok(claimed)
+    }
+
+    async fn mark_sent(&self, seq: i64, sent_at: DateTime<Utc>) -> Result<(), UserError> {
+        if let Some(row) = self.users.outbox()?.pending(seq) {
+            row.sent_at = Some(sent_at);
+        }
+        Ok(())
+    }
+
+    /// Only the retry time is kept; there is no table to inspect errors in
+    async fn mark_failed(&self, seq: i64, _error: &str, retry_at: DateTime<Utc>) -> Result<(), UserError> {
+        if let Some(row) = self.users.outbox()?.pending(seq) {
+            row.available_at = retry_at;
+        }
+        Ok(())
+    }
+
+    async fn purge_sent(&self, sent_before: DateTime<Utc>) -> Result<u64, UserError> {
+        let mut outbox = self.users.outbox()?;
+        let before = outbox.rows.len();
+        outbox.rows.retain(|row| row.sent_at.is_none_or(|at| at >= sent_before));
+        Ok((before - outbox.rows.len()) as u64)
+    }
+
+    async fn find_after(&self, after_seq: i64, limit: i64) -> Result<Vec<OutboxMessage>, UserError> {
+        let outbox = self.users.outbox()?;
+        Ok(outbox
+            .rows
+            .iter()
+            .filter(|row| row.seq > after_seq)
+            .take(limit.max(0) as usize)
+            .map(|row| OutboxMessage {
+                seq: row.seq,
+                event: row.event.clone(),
+                attempts: row.attempts,
+            })
+            .collect())
+    }
+
+    async fn last_seq(&self) -> Result<i64, UserError> {
+        Ok(self.users.outbox()?.last_seq)
+    }
+}
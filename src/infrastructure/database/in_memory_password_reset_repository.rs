@@ -0,0 +1,100 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        InfrastructureError, PasswordResetRepositoryPort, PasswordResetToken, UserError, UserId, UserRepositoryPort,
+    },
+    infrastructure::database::InMemoryUserRepository,
+};
+
+/// In-process adapter implementing PasswordResetRepositoryPort.
+/// Shares the user store it belongs to, so tokens only exist for stored
+/// users and vanish with them, like the foreign key in the SQL adapters.
+#[derive(Clone)]
+pub struct InMemoryPasswordResetRepository {
+    users: InMemoryUserRepository,
+    tokens: Arc<RwLock<HashMap<Uuid, PasswordResetToken>>>,
+}
+
+impl InMemoryPasswordResetRepository {
+    pub fn new(users: InMemoryUserRepository) -> Self {
+        Self {
+            users,
+            tokens: Arc::default(),
+        }
+    }
+
+    async fn user_exists(&self, user_id: &UserId) -> Result<bool, UserError> {
+        Ok(self.users.find_by_id_including_deleted(user_id).await?.is_some())
+    }
+}
+
+fn poisoned() -> UserError {
+    UserError::Internal(InfrastructureError::new("access password reset store", "lock poisoned"))
+}
+
+#[async_trait]
+impl PasswordResetRepositoryPort for InMemoryPasswordResetRepository {
+    async fn save(&self, token: &PasswordResetToken) -> Result<(), UserError> {
+        if !self.user_exists(&token.user_id).await? {
+            return Err(UserError::NotFound);
+        }
+        let mut tokens = self.tokens.write().map_err(|_| poisoned())?;
+        if tokens.values().any(|t| t.id == token.id || t.token_hash == token.token_hash) {
+            return Err(UserError::ConstraintViolation(InfrastructureError::new(
+                "save password reset token",
+                "duplicate password reset token",
+            )));
+        }
+        tokens.insert(token.id, token.clone());
+        Ok(())
+    }
+
+    async fn find_by_token_hash(&self, token_hash: &str) -> Result<Option<PasswordResetToken>, UserError> {
+        let token = self
+            .tokens
+            .read()
+            .map_err(|_| poisoned())?
+            .values()
+            .find(|t| t.token_hash == token_hash)
+            .cloned();
+        match token {
+            Some(token) if self.user_exists(&token.user_id).await? => Ok(Some(token)),
+            _ => Ok(None),
+        }
+    }
+
+    async fn mark_used(&self, id: Uuid, used_at: DateTime<Utc>) -> Result<bool, UserError> {
+        let mut tokens = self.tokens.write().map_err(|_| poisoned())?;
+        match tokens.get_mut(&id).filter(|t| t.used_at.is_none()) {
+            Some(token) => {
+                token.used_at = Some(used_at);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn mark_all_used_for_user(&self, user_id: &UserId, used_at: DateTime<Utc>) -> Result<u64, UserError> {
+        let mut tokens = self.tokens.write().map_err(|_| poisoned())?;
+        let mut used = 0;
+        for token in tokens.values_mut().filter(|t| t.user_id == *user_id && t.used_at.is_none()) {
+            token.used_at = Some(used_at);
+            used += 1;
+        }
+        Ok(used)
+    }
+
+    async fn purge_expired(&self, expired_before: DateTime<Utc>) -> Result<u64, UserError> {
+        let mut tokens = self.tokens.write().map_err(|_| poisoned())?;
+        let before = tokens.len();
+        tokens.retain(|_, t| t.expires_at >= expired_before);
+        Ok((before - tokens.len()) as u64)
+    }
+}
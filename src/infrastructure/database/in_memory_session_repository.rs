@@ -0,0 +1,112 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+use uuid::Uuid;
+
+use crate::{
+    domain::{InfrastructureError, Session, SessionRepositoryPort, UserError, UserId, UserRepositoryPort},
+    infrastructure::database::InMemoryUserRepository,
+};
+
+/// In-process adapter implementing SessionRepositoryPort.
+/// Shares the user store it belongs to, so sessions only exist for stored
+/// users and vanish with them, like the foreign key in the SQL adapters.
+#[derive(Clone)]
+pub struct InMemorySessionRepository {
+    users: InMemoryUserRepository,
+    sessions: Arc<RwLock<HashMap<Uuid, Session>>>,
+}
+
+impl InMemorySessionRepository {
+    pub fn new(users: InMemoryUserRepository) -> Self {
+        Self {
+            users,
+            sessions: Arc::default(),
+        }
+    }
+
+    async fn user_exists(&self, user_id: &UserId) -> Result<bool, UserError> {
+        Ok(self.users.find_by_id_including_deleted(user_id).await?.is_some())
+    }
+
+    /// Set `revoked_at` on every live session matching `filter`
+    fn revoke_where(&self, revoked_at: DateTime<Utc>, filter: impl Fn(&Session) -> bool) -> Result<u64, UserError> {
+        let mut sessions = self.sessions.write().map_err(|_| poisoned())?;
+        let mut revoked = 0;
+        for session in sessions.values_mut().filter(|s| s.revoked_at.is_none() && filter(s)) {
+            session.revoked_at = Some(revoked_at);
+            revoked += 1;
+        }
+        Ok(revoked)
+    }
+}
+
+fn poisoned() -> UserError {
+    UserError::Internal(InfrastructureError::new("access session store", "lock poisoned"))
+}
+
+#[async_trait]
+impl SessionRepositoryPort for InMemorySessionRepository {
+    async fn save(&self, session: &Session) -> Result<(), UserError> {
+        if !self.user_exists(&session.user_id).await? {
+            return Err(UserError::NotFound);
+        }
+        let mut sessions = self.sessions.write().map_err(|_| poisoned())?;
+        if sessions.values().any(|s| s.id == session.id || s.token_hash == session.token_hash) {
+            return Err(UserError::ConstraintViolation(InfrastructureError::new(
+                "save session",
+                "duplicate session",
+            )));
+        }
+        sessions.insert(session.id, session.clone());
+        Ok(())
+    }
+
+    async fn find_by_token_hash(&self, token_hash: &str) -> Result<Option<Session>, UserError> {
+        let session = self
+            .sessions
+            .read()
+            .map_err(|_| poisoned())?
+            .values()
+            .find(|s| s.token_hash == token_hash)
+            .cloned();
+        match session {
+            Some(session) if self.user_exists(&session.user_id).await? => Ok(Some(session)),
+            _ => Ok(None),
+        }
+    }
+
+    async fn mark_rotated(&self, id: Uuid, rotated_at: DateTime<Utc>) -> Result<bool, UserError> {
+        let mut sessions = self.sessions.write().map_err(|_| poisoned())?;
+        match sessions.get_mut(&id).filter(|s| s.rotated_at.is_none() && s.revoked_at.is_none()) {
+            Some(session) => {
+                session.rotated_at = Some(rotated_at);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn revoke_family(&self, family_id: Uuid, revoked_at: DateTime<Utc>) -> Result<u64, UserError> {
+        self.revoke_where(revoked_at, |s| s.family_id == family_id)
+    }
+
+    async fn revoke_all_for_user(&self, user_id: &UserId, revoked_at: DateTime<Utc>) -> Result<u64, UserError> {
+        self.revoke_where(revoked_at, |s| s.user_id == *user_id)
+    }
+
+    async fn has_active(&self, user_id: &UserId, now: DateTime<Utc>) -> Result<bool, UserError> {
+        let sessions = self.sessions.read().map_err(|_| poisoned())?;
+        Ok(sessions.values().any(|s| s.user_id == *user_id && s.is_active(now)))
+    }
+
+    async fn purge_expired(&self, expired_before: DateTime<Utc>) -> Result<u64, UserError> {
+        let mut sessions = self.sessions.write().map_err(|_| poisoned())?;
+        let before = sessions.len();
+        sessions.retain(|_, s| s.expires_at >= expired_before);
+        Ok((before - sessions.len()) as u64)
+    }
+}
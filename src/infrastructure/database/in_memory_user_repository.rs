@@ -0,0 +1,287 @@
+use async_trait::async_trait;
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    sync::{Arc, RwLock, RwLockWriteGuard},
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    domain::{
+        current_timestamp, AuditEntry, User, UserEvent, UserId, Email, UserError, InfrastructureError,
+        ports::{
+            CountAccuracy, SortDirection, SortKey, UserCursor, UserQuery, UserRepositoryPort, UserSort,
+        },
+    },
+    infrastructure::database::{error::version_conflict, in_memory_outbox_repository::OutboxRows},
+};
+
+/// In-process adapter implementing UserRepositoryPort.
+/// Mirrors the Postgres adapter's semantics so it can stand in for local runs,
+/// demos and tests; all data is lost when the process exits.
+#[derive(Clone, Default)]
+pub struct InMemoryUserRepository {
+    users: Arc<RwLock<HashMap<UserId, User>>>,
+    audit_log: Arc<RwLock<Vec<AuditEntry>>>,
+    outbox: Arc<RwLock<OutboxRows>>,
+}
+
+type Users = HashMap<UserId, User>;
+
+impl InMemoryUserRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Entries appended by the `*_audited` methods, oldest first
+    pub(crate) fn audit_entries(&self) -> Result<Vec<AuditEntry>, UserError> {
+        Ok(self.audit_log.read().map_err(|_| poisoned())?.clone())
+    }
+
+    /// Append `entry` while the caller still holds the user store's write
+    /// lock, so readers never see a change without its entry
+    fn append(&self, entry: &AuditEntry) -> Result<(), UserError> {
+        self.audit_log.write().map_err(|_| poisoned())?.push(entry.clone());
+        Ok(())
+    }
+
+    /// Events enqueued by the `*_audited` methods
+    pub(crate) fn outbox(&self) -> Result<RwLockWriteGuard<'_, OutboxRows>, UserError> {
+        self.outbox.write().map_err(|_| poisoned())
+    }
+
+    /// Enqueue `event`, like `append`, under the user store's write lock
+    fn enqueue(&self, event: &UserEvent) -> Result<(), UserError> {
+        self.outbox()?.push(event);
+        Ok(())
+    }
+
+    fn read(&self) -> Result<std::sync::RwLockReadGuard<'_, HashMap<UserId, User>>, UserError> {
+        self.users.read().map_err(|_| poisoned())
+    }
+
+    fn write(&self) -> Result<std::sync::RwLockWriteGuard<'_, HashMap<UserId, User>>, UserError> {
+        self.users.write().map_err(|_| poisoned())
+    }
+}
+
+fn poisoned() -> UserError {
+    UserError::Internal(InfrastructureError::new("access user store", "lock poisoned"))
+}
+
+/// Case-insensitive email match among active users, like the partial
+/// `LOWER(email)` unique index
+fn email_taken_by_other(users: &HashMap<UserId, User>, email: &Email, except: Option<&UserId>) -> bool {
+    users.values().any(|u| {
+        !u.is_deleted()
+            && Some(u.id()) != except
+            && u.email().as_str().eq_ignore_ascii_case(email.as_str())
+    })
+}
+
+/// Copy of `user` with a new deletion state, touching updated_at and the version
+fn with_deleted_at(user: &User, deleted_at: Option<DateTime<Utc>>, now: DateTime<Utc>) -> User {
+    User::from_persistence(
+        user.id().clone(),
+        user.name().clone(),
+        user.email().clone(),
+        user.email_verified_at(),
+        user.role(),
+        user.created_at(),
+        now,
+        deleted_at,
+        user.version() + 1,
+    )
+}
+
+/// Users matching `query`, in the order the SQL adapters list them
+fn sorted_matches<'a>(users: &'a HashMap<UserId, User>, query: &UserQuery) -> Vec<&'a User> {
+    let mut matches: Vec<&User> = users.values().filter(|u| query.matches(u)).collect();
+    matches.sort_by(|a, b| {
+        compare_position(
+            &query.sort,
+            (&query.sort.field.key_of(a), a.id()),
+            (&query.sort.field.key_of(b), b.id()),
+        )
+    });
+    matches
+}
+
+/// Order two (sort key, id) positions; ties are broken by ascending id
+fn compare_position(sort: &UserSort, a: (&SortKey, &UserId), b: (&SortKey, &UserId)) -> Ordering {
+    let by_key = match sort.direction {
+        SortDirection::Asc => a.0.cmp(b.0),
+        SortDirection::Desc => b.0.cmp(a.0),
+    };
+    by_key.then_with(|| a.1.as_uuid().cmp(&b.1.as_uuid()))
+}
+
+fn comes_after(user: &User, cursor: &UserCursor) -> bool {
+    let key = cursor.sort.field.key_of(user);
+    compare_position(&cursor.sort, (&key, user.id()), (&cursor.key, &cursor.id)) == Ordering::Greater
+}
+
+fn insert_user(users: &mut Users, user: &User) -> Result<(), UserError> {
+    if users.contains_key(user.id()) {
+        return Err(UserError::ConstraintViolation(InfrastructureError::new(
+            "save user",
+            "duplicate user id",
+        )));
+    }
+    if email_taken_by_other(users, user.email(), None) {
+        return Err(UserError::EmailAlreadyExists);
+    }
+    users.insert(user.id().clone(), user.clone());
+    Ok(())
+}
+
+fn update_user(users: &mut Users, user: &User) -> Result<(), UserError> {
+    let Some(stored) = users.get(user.id()).filter(|u| !u.is_deleted()) else {
+        return Err(UserError::NotFound);
+    };
+    if stored.version() != user.version() - 1 {
+        return Err(version_conflict("update user"));
+    }
+    let created_at = stored.created_at();
+    if email_taken_by_other(users, user.email(), Some(user.id())) {
+        return Err(UserError::EmailAlreadyExists);
+    }
+
+    // Only mutable columns change; created_at stays as originally stored
+    let updated = User::from_persistence(
+        user.id().clone(),
+        user.name().clone(),
+        user.email().clone(),
+        user.email_verified_at(),
+        user.role(),
+        created_at,
+        user.updated_at(),
+        None,
+        user.version(),
+    );
+    users.insert(user.id().clone(), updated);
+    Ok(())
+}
+
+fn delete_user(users: &mut Users, id: &UserId, expected_version: Option<i64>) -> Result<(), UserError> {
+    let Some(stored) = users.get(id).filter(|u| !u.is_deleted()) else {
+        return Err(UserError::NotFound);
+    };
+    if expected_version.is_some_and(|v| v != stored.version()) {
+        return Err(version_conflict("delete user"));
+    }
+    let now = current_timestamp();
+    let deleted = with_deleted_at(stored, Some(now), now);
+    users.insert(id.clone(), deleted);
+    Ok(())
+}
+
+fn restore_user(users: &mut Users, id: &UserId, restored_at: DateTime<Utc>) -> Result<(), UserError> {
+    let Some(stored) = users.get(id).filter(|u| u.is_deleted()) else {
+        return Err(UserError::NotFound);
+    };
+    if email_taken_by_other(users, stored.email(), Some(id)) {
+        return Err(UserError::EmailAlreadyExists);
+    }
+    let restored = with_deleted_at(stored, None, restored_at);
+    users.insert(id.clone(), restored);
+    Ok(())
+}
+
+#[async_trait]
+impl UserRepositoryPort for InMemoryUserRepository {
+    async fn save_audited(&self, user: &User, audit: &AuditEntry, event: &UserEvent) -> Result<(), UserError> {
+        let mut users = self.write()?;
+        insert_user(&mut users, user)?;
+        self.append(audit)?;
+        self.enqueue(event)
+    }
+
+    async fn find_by_id(&self, id: &UserId) -> Result<Option<User>, UserError> {
+        Ok(self.read()?.get(id).filter(|u| !u.is_deleted()).cloned())
+    }
+
+    async fn find_by_email(&self, email: &Email) -> Result<Option<User>, UserError> {
+        Ok(self
+            .read()?
+            .values()
+            .find(|u| !u.is_deleted() && u.email().as_str().eq_ignore_ascii_case(email.as_str()))
+            .cloned())
+    }
+
+    async fn find_by_id_including_deleted(&self, id: &UserId) -> Result<Option<User>, UserError> {
+        Ok(self.read()?.get(id).cloned())
+    }
+
+    async fn update_audited(&self, user: &User, audit: &AuditEntry, event: &UserEvent) -> Result<(), UserError> {
+        let mut users = self.write()?;
+        update_user(&mut users, user)?;
+        self.append(audit)?;
+        self.enqueue(event)
+    }
+
+    async fn delete_audited(
+        &self,
+        id: &UserId,
+        expected_version: Option<i64>,
+        audit: &AuditEntry,
+        event: &UserEvent,
+    ) -> Result<(), UserError> {
+        let mut users = self.write()?;
+        delete_user(&mut users, id, expected_version)?;
+        self.append(audit)?;
+        self.enqueue(event)
+    }
+
+    async fn restore_audited(
+        &self,
+        id: &UserId,
+        restored_at: DateTime<Utc>,
+        audit: &AuditEntry,
+        event: &UserEvent,
+    ) -> Result<(), UserError> {
+        let mut users = self.write()?;
+        restore_user(&mut users, id, restored_at)?;
+        self.append(audit)?;
+        self.enqueue(event)
+    }
+
+    async fn purge_deleted(&self, deleted_before: DateTime<Utc>) -> Result<u64, UserError> {
+        let mut users = self.write()?;
+        let before = users.len();
+        users.retain(|_, u| u.deleted_at().is_none_or(|t| t >= deleted_before));
+        Ok((before - users.len()) as u64)
+    }
+
+    async fn find_all(&self, query: &UserQuery, offset: i64, limit: i64) -> Result<Vec<User>, UserError> {
+        Ok(sorted_matches(&*self.read()?, query)
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn find_after(
+        &self,
+        query: &UserQuery,
+        after: Option<&UserCursor>,
+        limit: i64,
+    ) -> Result<Vec<User>, UserError> {
+        Ok(sorted_matches(&*self.read()?, query)
+            .into_iter()
+            .filter(|u| after.is_none_or(|cursor| comes_after(u, cursor)))
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn count(&self, query: &UserQuery, _accuracy: CountAccuracy) -> Result<i64, UserError> {
+        Ok(self.read()?.values().filter(|u| query.matches(u)).count() as i64)
+    }
+
+    async fn exists_by_email(&self, email: &Email) -> Result<bool, UserError> {
+        Ok(email_taken_by_other(&*self.read()?, email, None))
+    }
+}
@@ -0,0 +1,209 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use std::sync::{Arc, RwLock};
+use uuid::Uuid;
+
+use crate::domain::{
+    DeliveryStatus, InfrastructureError, UserError, WebhookAttempt, WebhookDelivery, WebhookRepositoryPort,
+    WebhookSubscription,
+};
+
+/// The in-memory webhook tables, each in insertion order
+#[derive(Debug, Default)]
+struct WebhookStore {
+    subscriptions: Vec<WebhookSubscription>,
+    deliveries: Vec<WebhookDelivery>,
+    attempts: Vec<WebhookAttempt>,
+}
+
+/// In-process adapter implementing WebhookRepositoryPort
+#[derive(Clone, Default)]
+pub struct InMemoryWebhookRepository {
+    store: Arc<RwLock<WebhookStore>>,
+}
+
+impl InMemoryWebhookRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn read(&self) -> Result<std::sync::RwLockReadGuard<'_, WebhookStore>, UserError> {
+        self.store.read().map_err(|_| poisoned())
+    }
+
+    fn write(&self) -> Result<std::sync::RwLockWriteGuard<'_, WebhookStore>, UserError> {
+        self.store.write().map_err(|_| poisoned())
+    }
+}
+
+fn poisoned() -> UserError {
+    UserError::Internal(InfrastructureError::new("access webhook store", "lock poisoned"))
+}
+
+fn newest_first(a: &WebhookDelivery, b: &WebhookDelivery) -> std::cmp::Ordering {
+    b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id))
+}
+
+#[async_trait]
+impl WebhookRepositoryPort for InMemoryWebhookRepository {
+    async fn save_subscription(&self, subscription: &WebhookSubscription) -> Result<(), UserError> {
+        let mut store = self.write()?;
+        if store.subscriptions.iter().any(|s| s.id == subscription.id) {
+            return Err(UserError::ConstraintViolation(InfrastructureError::new(
+                "save webhook subscription",
+                "duplicate webhook subscription",
+            )));
+        }
+        store.subscriptions.push(subscription.clone());
+        Ok(())
+    }
+
+    async fn update_subscription(&self, subscription: &WebhookSubscription) -> Result<(), UserError> {
+        let mut store = self.write()?;
+        let stored = store
+            .subscriptions
+            .iter_mut()
+            .find(|s| s.id == subscription.id)
+            .ok_or(UserError::WebhookNotFound)?;
+        *stored = WebhookSubscription {
+            created_at: stored.created_at,
+            ..subscription.clone()
+        };
+        Ok(())
+    }
+
+    async fn delete_subscription(&self, id: Uuid) -> Result<(), UserError> {
+        let mut store = self.write()?;
+        let before = store.subscriptions.len();
+        store.subscriptions.retain(|s| s.id != id);
+        if store.subscriptions.len() == before {
+            return Err(UserError::WebhookNotFound);
+        }
+
+        // Cascade, like the foreign keys of the SQL adapters
+        let WebhookStore { deliveries, attempts, .. } = &mut *store;
+        deliveries.retain(|d| d.subscription_id != id);
+        attempts.retain(|a| deliveries.iter().any(|d| d.id == a.delivery_id));
+        Ok(())
+    }
+
+    async fn find_subscription(&self, id: Uuid) -> Result<Option<WebhookSubscription>, UserError> {
+        Ok(self.read()?.subscriptions.iter().find(|s| s.id == id).cloned())
+    }
+
+    async fn find_subscriptions(&self) -> Result<Vec<WebhookSubscription>, UserError> {
+        let mut subscriptions = self.read()?.subscriptions.clone();
+        subscriptions.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+        Ok(subscriptions)
+    }
+
+    async fn enqueue_delivery(&self, delivery: &WebhookDelivery) -> Result<bool, UserError> {
+        let mut store = self.write()?;
+        if !store.subscriptions.iter().any(|s| s.id == delivery.subscription_id) {
+            return Err(UserError::WebhookNotFound);
+        }
+        let duplicate = delivery.replay_of.is_none()
+            && store.deliveries.iter().any(|d| {
+                d.replay_of.is_none()
+                    && d.subscription_id == delivery.subscription_id
+                    && d.event_id == delivery.event_id
+            });
+        if duplicate {
+            return Ok(false);
+        }
+        store.deliveries.push(delivery.clone());
+        Ok(true)
+    }
+
+    async fn claim_deliveries(
+        &self,
+        now: DateTime<Utc>,
+        lease: Duration,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>, UserError> {
+        let mut store = self.write()?;
+        let mut due: Vec<&mut WebhookDelivery> = store
+            .deliveries
+            .iter_mut()
+            .filter(|d| d.status == DeliveryStatus::Pending && d.next_attempt_at <= now)
+            .collect();
+        due.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+
+        let mut claimed = Vec::new();
+        for delivery in due.into_iter().take(limit.max(0) as usize) {
+            delivery.next_attempt_at = now + lease;
+            delivery.attempts += 1;
+            delivery.updated_at = now;
+            claimed.push(delivery.clone());
+        }
+        Ok(claimed)
+    }
+
+    async fn record_attempt(&self, delivery: &WebhookDelivery, attempt: &WebhookAttempt) -> Result<(), UserError> {
+        let mut store = self.write()?;
+        let stored = store
+            .deliveries
+            .iter_mut()
+            .find(|d| d.id == delivery.id)
+            .ok_or(UserError::WebhookDeliveryNotFound)?;
+        if stored.status == DeliveryStatus::Pending {
+            stored.status = delivery.status;
+            stored.next_attempt_at = delivery.next_attempt_at;
+            stored.last_response_status = delivery.last_response_status;
+            stored.last_error = delivery.last_error.clone();
+            stored.updated_at = delivery.updated_at;
+        }
+        if !store.attempts.iter().any(|a| a.delivery_id == attempt.delivery_id && a.attempt == attempt.attempt) {
+            store.attempts.push(attempt.clone());
+        }
+        Ok(())
+    }
+
+    async fn find_delivery(&self, id: Uuid) -> Result<Option<WebhookDelivery>, UserError> {
+        Ok(self.read()?.deliveries.iter().find(|d| d.id == id).cloned())
+    }
+
+    async fn find_deliveries(
+        &self,
+        subscription_id: Uuid,
+        status: Option<DeliveryStatus>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>, UserError> {
+        let mut deliveries: Vec<WebhookDelivery> = self
+            .read()?
+            .deliveries
+            .iter()
+            .filter(|d| d.subscription_id == subscription_id && status.is_none_or(|status| d.status == status))
+            .cloned()
+            .collect();
+        deliveries.sort_by(newest_first);
+        Ok(deliveries
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .collect())
+    }
+
+    async fn count_deliveries(&self, subscription_id: Uuid, status: Option<DeliveryStatus>) -> Result<i64, UserError> {
+        let count = self
+            .read()?
+            .deliveries
+            .iter()
+            .filter(|d| d.subscription_id == subscription_id && status.is_none_or(|status| d.status == status))
+            .count();
+        Ok(count as i64)
+    }
+
+    async fn find_attempts(&self, delivery_id: Uuid) -> Result<Vec<WebhookAttempt>, UserError> {
+        let mut attempts: Vec<WebhookAttempt> = self
+            .read()?
+            .attempts
+            .iter()
+            .filter(|a| a.delivery_id == delivery_id)
+            .cloned()
+            .collect();
+        attempts.sort_by_key(|a| a.attempt);
+        Ok(attempts)
+    }
+}
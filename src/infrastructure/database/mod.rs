@@ -1,3 +1,93 @@
-pub mod postgres_user_repository;
-
-pub use postgres_user_repository::PostgresUserRepository;
+mod api_key_sql;
+mod audit_log_sql;
+mod credential_sql;
+mod email_verification_sql;
+mod error;
+mod idempotency_sql;
+pub mod in_memory_api_key_repository;
+pub mod in_memory_audit_log_repository;
+pub mod in_memory_credential_repository;
+pub mod in_memory_email_verification_repository;
+pub mod in_memory_idempotency_repository;
+pub mod in_memory_outbox_repository;
+pub mod in_memory_password_reset_repository;
+pub mod in_memory_session_repository;
+pub mod in_memory_user_repository;
+pub mod in_memory_webhook_repository;
+mod outbox_sql;
+mod password_reset_sql;
+pub mod postgres_api_key_repository;
+pub mod postgres_audit_log_repository;
+pub mod postgres_credential_repository;
+pub mod postgres_email_verification_repository;
+pub mod postgres_idempotency_repository;
+pub mod postgres_outbox_repository;
+pub mod postgres_password_reset_repository;
+pub mod postgres_session_repository;
+pub mod postgres_user_repository;
+pub mod postgres_webhook_repository;
+mod session_sql;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_api_key_repository;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_audit_log_repository;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_credential_repository;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_email_verification_repository;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_idempotency_repository;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_outbox_repository;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_password_reset_repository;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_session_repository;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_user_repository;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_webhook_repository;
+mod user_query_sql;
+mod user_sql;
+mod webhook_sql;
+
+pub use in_memory_api_key_repository::InMemoryApiKeyRepository;
+pub use in_memory_audit_log_repository::InMemoryAuditLogRepository;
+pub use in_memory_credential_repository::InMemoryCredentialRepository;
+pub use in_memory_email_verification_repository::InMemoryEmailVerificationRepository;
+pub use in_memory_idempotency_repository::InMemoryIdempotencyRepository;
+pub use in_memory_outbox_repository::InMemoryOutboxRepository;
+pub use in_memory_password_reset_repository::InMemoryPasswordResetRepository;
+pub use in_memory_session_repository::InMemorySessionRepository;
+pub use in_memory_user_repository::InMemoryUserRepository;
+pub use in_memory_webhook_repository::InMemoryWebhookRepository;
+pub use postgres_api_key_repository::PostgresApiKeyRepository;
+pub use postgres_audit_log_repository::PostgresAuditLogRepository;
+pub use postgres_credential_repository::PostgresCredentialRepository;
+pub use postgres_email_verification_repository::PostgresEmailVerificationRepository;
+pub use postgres_idempotency_repository::PostgresIdempotencyRepository;
+pub use postgres_outbox_repository::PostgresOutboxRepository;
+pub use postgres_password_reset_repository::PostgresPasswordResetRepository;
+pub use postgres_session_repository::PostgresSessionRepository;
+pub use postgres_user_repository::PostgresUserRepository;
+pub use postgres_webhook_repository::PostgresWebhookRepository;
+#[cfg(feature = "sqlite")]
+pub use sqlite_api_key_repository::SqliteApiKeyRepository;
+#[cfg(feature = "sqlite")]
+pub use sqlite_audit_log_repository::SqliteAuditLogRepository;
+#[cfg(feature = "sqlite")]
+pub use sqlite_credential_repository::SqliteCredentialRepository;
+#[cfg(feature = "sqlite")]
+pub use sqlite_email_verification_repository::SqliteEmailVerificationRepository;
+#[cfg(feature = "sqlite")]
+pub use sqlite_idempotency_repository::SqliteIdempotencyRepository;
+#[cfg(feature = "sqlite")]
+pub use sqlite_outbox_repository::SqliteOutboxRepository;
+#[cfg(feature = "sqlite")]
+pub use sqlite_password_reset_repository::SqlitePasswordResetRepository;
+#[cfg(feature = "sqlite")]
+pub use sqlite_session_repository::SqliteSessionRepository;
+#[cfg(feature = "sqlite")]
+pub use sqlite_user_repository::SqliteUserRepository;
+#[cfg(feature = "sqlite")]
+pub use sqlite_webhook_repository::SqliteWebhookRepository;
@@ -0,0 +1,249 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::domain::{
+    Email, FieldChange, InfrastructureError, OutboxMessage, Role, User, UserCreated, UserDeleted, UserError,
+    UserEvent, UserId, UserName, UserRestored, UserUpdated,
+};
+
+/// Columns selected for the outbox message model
+pub(crate) const OUTBOX_COLUMNS: &str = "seq, event_id, event_type, payload, occurred_at, attempts";
+
+/// Database model for OutboxMessage (infrastructure concern), shared by the SQL adapters
+#[derive(Debug, FromRow)]
+pub(crate) struct OutboxDbModel {
+    seq: i64,
+    event_id: Uuid,
+    event_type: String,
+    /// JSON, see `EventPayload`
+    payload: String,
+    occurred_at: DateTime<Utc>,
+    attempts: i32,
+}
+
+/// Body of an event in the `payload` column
+#[derive(Serialize, Deserialize)]
+struct EventPayload {
+    user: UserPayload,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    changes: Vec<FieldChange>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct UserPayload {
+    id: Uuid,
+    name: String,
+    email: String,
+    email_verified_at: Option<DateTime<Utc>>,
+    role: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    deleted_at: Option<DateTime<Utc>>,
+    version: i64,
+}
+
+impl OutboxDbModel {
+    pub(crate) fn into_domain(self) -> Result<OutboxMessage, UserError> {
+        let payload: EventPayload = serde_json::from_str(&self.payload)
+            .map_err(|e| UserError::Internal(InfrastructureError::new("decode outbox event", e)))?;
+        let (id, occurred_at) = (self.event_id, self.occurred_at);
+        let user = payload.user.into_domain()?;
+
+        let event = match self.event_type.as_str() {
+            "user.created" => UserEvent::Created(UserCreated { id, user, occurred_at }),
+            "user.updated" => UserEvent::Updated(UserUpdated {
+                id,
+                user,
+                changes: payload.changes,
+                occurred_at,
+            }),
+            "user.deleted" => UserEvent::Deleted(UserDeleted { id, user, occurred_at }),
+            "user.restored" => UserEvent::Restored(UserRestored { id, user, occurred_at }),
+            other => {
+                return Err(UserError::Internal(InfrastructureError::new(
+                    "decode outbox event",
+                    format!("unknown event type `{}`", other),
+                )));
+            }
+        };
+
+        Ok(OutboxMessage {
+            seq: self.seq,
+            event,
+            attempts: self.attempts,
+        })
+    }
+}
+
+impl UserPayload {
+    fn into_domain(self) -> Result<User, UserError> {
+        Ok(User::from_persistence(
+            UserId::from_uuid(self.id),
+            UserName::new(self.name)?,
+            Email::new(self.email)?,
+            self.email_verified_at,
+            Role::parse(&self.role)?,
+            self.created_at,
+            self.updated_at,
+            self.deleted_at,
+            self.version,
+        ))
+    }
+}
+
+/// The `payload` column for `event`
+pub(crate) fn encode_event(event: &UserEvent) -> Result<String, UserError> {
+    let user = event.user();
+    let payload = EventPayload {
+        user: UserPayload {
+            id: user.id().as_uuid(),
+            name: user.name().as_str().to_string(),
+            email: user.email().as_str().to_string(),
+            email_verified_at: user.email_verified_at(),
+            role: user.role().as_str().to_string(),
+            created_at: user.created_at(),
+            updated_at: user.updated_at(),
+            deleted_at: user.deleted_at(),
+            version: user.version(),
+        },
+        changes: match event {
+            UserEvent::Updated(updated) => updated.changes.clone(),
+            _ => Vec::new(),
+        },
+    };
+    serde_json::to_string(&payload)
+        .map_err(|e| UserError::Internal(InfrastructureError::new("encode outbox event", e)))
+}
+
+/// Adapter implementing OutboxRepositoryPort over a sqlx pool. `claim_lock`
+/// ends the subquery selecting the rows to claim: the backend's row-locking
+/// clause, or nothing where writes are serialized anyway.
+macro_rules! outbox_repository {
+    ($(#[$attr:meta])* $name:ident($pool:ty), claim_lock = $claim_lock:literal) => {
+        use async_trait::async_trait;
+        use chrono::{DateTime, Duration, Utc};
+
+        use $crate::{
+            domain::{OutboxMessage, OutboxRepositoryPort, UserError},
+            infrastructure::database::{
+                error::map_sqlx_error,
+                outbox_sql::{OUTBOX_COLUMNS, OutboxDbModel},
+            },
+        };
+
+        $(#[$attr])*
+        #[derive(Clone)]
+        pub struct $name {
+            pool: $pool,
+        }
+
+        impl $name {
+            pub fn new(pool: $pool) -> Self {
+                Self { pool }
+            }
+        }
+
+        #[async_trait]
+        impl OutboxRepositoryPort for $name {
+            async fn claim(
+                &self,
+                now: DateTime<Utc>,
+                lease: Duration,
+                limit: i64,
+            ) -> Result<Vec<OutboxMessage>, UserError> {
+                // A row is claimable when no older row of its user is undelivered;
+                // on Postgres, row locks on `users` keep `seq` in commit order per user
+                let rows = sqlx::query_as::<_, OutboxDbModel>(&format!(
+                    r#"
+                    UPDATE outbox
+                    SET available_at = $2, attempts = attempts + 1
+                    WHERE seq IN (
+                        SELECT o.seq FROM outbox o
+                        WHERE o.sent_at IS NULL AND o.available_at <= $1
+                          AND NOT EXISTS (
+                              SELECT 1 FROM outbox e
+                              WHERE e.aggregate_id = o.aggregate_id AND e.sent_at IS NULL AND e.seq < o.seq
+                          )
+                        ORDER BY o.seq
+                        LIMIT $3
+                        {}
+                    )
+                    RETURNING {}
+                    "#,
+                    $claim_lock, OUTBOX_COLUMNS
+                ))
+                .bind(now)
+                .bind(now + lease)
+                .bind(limit)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("claim outbox messages", e))?;
+
+                // RETURNING has no order of its own
+                let mut messages = rows.into_iter().map(OutboxDbModel::into_domain).collect::<Result<Vec<_>, _>>()?;
+                messages.sort_by_key(|message| message.seq);
+                Ok(messages)
+            }
+
+            async fn mark_sent(&self, seq: i64, sent_at: DateTime<Utc>) -> Result<(), UserError> {
+                sqlx::query("UPDATE outbox SET sent_at = $2, last_error = NULL WHERE seq = $1 AND sent_at IS NULL")
+                    .bind(seq)
+                    .bind(sent_at)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(|e| map_sqlx_error("mark outbox message sent", e))?;
+
+                Ok(())
+            }
+
+            async fn mark_failed(&self, seq: i64, error: &str, retry_at: DateTime<Utc>) -> Result<(), UserError> {
+                sqlx::query("UPDATE outbox SET available_at = $3, last_error = $2 WHERE seq = $1 AND sent_at IS NULL")
+                    .bind(seq)
+                    .bind(error)
+                    .bind(retry_at)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(|e| map_sqlx_error("mark outbox message failed", e))?;
+
+                Ok(())
+            }
+
+            async fn purge_sent(&self, sent_before: DateTime<Utc>) -> Result<u64, UserError> {
+                let result = sqlx::query("DELETE FROM outbox WHERE sent_at IS NOT NULL AND sent_at < $1")
+                    .bind(sent_before)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(|e| map_sqlx_error("purge sent outbox messages", e))?;
+
+                Ok(result.rows_affected())
+            }
+
+            async fn find_after(&self, after_seq: i64, limit: i64) -> Result<Vec<OutboxMessage>, UserError> {
+                let rows = sqlx::query_as::<_, OutboxDbModel>(&format!(
+                    "SELECT {} FROM outbox WHERE seq > $1 ORDER BY seq LIMIT $2",
+                    OUTBOX_COLUMNS
+                ))
+                .bind(after_seq)
+                .bind(limit)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("read outbox messages", e))?;
+
+                rows.into_iter().map(OutboxDbModel::into_domain).collect()
+            }
+
+            async fn last_seq(&self) -> Result<i64, UserError> {
+                let (last_seq,): (i64,) = sqlx::query_as("SELECT COALESCE(MAX(seq), 0) FROM outbox")
+                    .fetch_one(&self.pool)
+                    .await
+                    .map_err(|e| map_sqlx_error("read last outbox seq", e))?;
+
+                Ok(last_seq)
+            }
+        }
+    };
+}
+
+pub(crate) use outbox_repository;
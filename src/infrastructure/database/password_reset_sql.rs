@@ -0,0 +1,142 @@
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::domain::{PasswordResetToken, UserId};
+
+/// Columns selected for the password reset token model
+pub(crate) const TOKEN_COLUMNS: &str = "id, user_id, token_hash, created_at, expires_at, used_at";
+
+/// Database model for PasswordResetToken (infrastructure concern), shared by the SQL adapters
+#[derive(Debug, FromRow)]
+pub(crate) struct PasswordResetTokenDbModel {
+    id: Uuid,
+    user_id: Uuid,
+    token_hash: String,
+    created_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    used_at: Option<DateTime<Utc>>,
+}
+
+impl PasswordResetTokenDbModel {
+    pub(crate) fn into_domain(self) -> PasswordResetToken {
+        PasswordResetToken {
+            id: self.id,
+            user_id: UserId::from_uuid(self.user_id),
+            token_hash: self.token_hash,
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+            used_at: self.used_at,
+        }
+    }
+}
+
+/// Adapter implementing PasswordResetRepositoryPort over a sqlx pool. The SQL
+/// runs unchanged on every backend, so each one only names its pool type.
+macro_rules! password_reset_repository {
+    ($(#[$attr:meta])* $name:ident($pool:ty)) => {
+        use async_trait::async_trait;
+        use chrono::{DateTime, Utc};
+        use uuid::Uuid;
+
+        use $crate::{
+            domain::{PasswordResetRepositoryPort, PasswordResetToken, UserError, UserId},
+            infrastructure::database::{
+                error::{is_foreign_key_violation, map_sqlx_error},
+                password_reset_sql::{TOKEN_COLUMNS, PasswordResetTokenDbModel},
+            },
+        };
+
+        $(#[$attr])*
+        #[derive(Clone)]
+        pub struct $name {
+            pool: $pool,
+        }
+
+        impl $name {
+            pub fn new(pool: $pool) -> Self {
+                Self { pool }
+            }
+        }
+
+        #[async_trait]
+        impl PasswordResetRepositoryPort for $name {
+            async fn save(&self, token: &PasswordResetToken) -> Result<(), UserError> {
+                sqlx::query(
+                    r#"
+                    INSERT INTO password_reset_tokens (id, user_id, token_hash, created_at, expires_at, used_at)
+                    VALUES ($1, $2, $3, $4, $5, $6)
+                    "#,
+                )
+                .bind(token.id)
+                .bind(token.user_id.as_uuid())
+                .bind(&token.token_hash)
+                .bind(token.created_at)
+                .bind(token.expires_at)
+                .bind(token.used_at)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| {
+                    if is_foreign_key_violation(&e) {
+                        UserError::NotFound
+                    } else {
+                        map_sqlx_error("save password reset token", e)
+                    }
+                })?;
+
+                Ok(())
+            }
+
+            async fn find_by_token_hash(&self, token_hash: &str) -> Result<Option<PasswordResetToken>, UserError> {
+                let result = sqlx::query_as::<_, PasswordResetTokenDbModel>(&format!(
+                    "SELECT {} FROM password_reset_tokens WHERE token_hash = $1",
+                    TOKEN_COLUMNS
+                ))
+                .bind(token_hash)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("find password reset token", e))?;
+
+                Ok(result.map(PasswordResetTokenDbModel::into_domain))
+            }
+
+            async fn mark_used(&self, id: Uuid, used_at: DateTime<Utc>) -> Result<bool, UserError> {
+                let result = sqlx::query(
+                    "UPDATE password_reset_tokens SET used_at = $2 WHERE id = $1 AND used_at IS NULL",
+                )
+                .bind(id)
+                .bind(used_at)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("use password reset token", e))?;
+
+                Ok(result.rows_affected() == 1)
+            }
+
+            async fn mark_all_used_for_user(&self, user_id: &UserId, used_at: DateTime<Utc>) -> Result<u64, UserError> {
+                let result = sqlx::query(
+                    "UPDATE password_reset_tokens SET used_at = $2 WHERE user_id = $1 AND used_at IS NULL",
+                )
+                .bind(user_id.as_uuid())
+                .bind(used_at)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("use password reset tokens", e))?;
+
+                Ok(result.rows_affected())
+            }
+
+            async fn purge_expired(&self, expired_before: DateTime<Utc>) -> Result<u64, UserError> {
+                let result = sqlx::query("DELETE FROM password_reset_tokens WHERE expires_at < $1")
+                    .bind(expired_before)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(|e| map_sqlx_error("purge password reset tokens", e))?;
+
+                Ok(result.rows_affected())
+            }
+        }
+    };
+}
+
+pub(crate) use password_reset_repository;
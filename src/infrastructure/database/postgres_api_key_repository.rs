@@ -0,0 +1,8 @@
+use sqlx::PgPool;
+
+use crate::infrastructure::database::api_key_sql::api_key_repository;
+
+api_key_repository! {
+    /// Database adapter implementing ApiKeyRepositoryPort
+    PostgresApiKeyRepository(PgPool)
+}
@@ -0,0 +1,8 @@
+use sqlx::{PgPool, Postgres};
+
+use crate::infrastructure::database::audit_log_sql::audit_log_repository;
+
+audit_log_repository! {
+    /// Database adapter implementing AuditLogRepositoryPort
+    PostgresAuditLogRepository(PgPool, Postgres)
+}
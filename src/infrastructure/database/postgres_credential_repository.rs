@@ -0,0 +1,8 @@
+use sqlx::PgPool;
+
+use crate::infrastructure::database::credential_sql::credential_repository;
+
+credential_repository! {
+    /// Database adapter implementing CredentialRepositoryPort
+    PostgresCredentialRepository(PgPool)
+}
@@ -0,0 +1,8 @@
+use sqlx::PgPool;
+
+use crate::infrastructure::database::email_verification_sql::email_verification_repository;
+
+email_verification_repository! {
+    /// Database adapter implementing EmailVerificationRepositoryPort
+    PostgresEmailVerificationRepository(PgPool)
+}
@@ -0,0 +1,8 @@
+use sqlx::PgPool;
+
+use crate::infrastructure::database::idempotency_sql::idempotency_repository;
+
+idempotency_repository! {
+    /// Database adapter implementing IdempotencyRepositoryPort
+    PostgresIdempotencyRepository(PgPool)
+}
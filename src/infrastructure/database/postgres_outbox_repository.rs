@@ -0,0 +1,11 @@
+use sqlx::PgPool;
+
+use crate::infrastructure::database::outbox_sql::outbox_repository;
+
+outbox_repository! {
+    /// Database adapter implementing OutboxRepositoryPort.
+    /// Claims lock candidate rows with `FOR UPDATE SKIP LOCKED`, so concurrent
+    /// relays neither block on nor double-claim each other's messages.
+    PostgresOutboxRepository(PgPool),
+    claim_lock = "FOR UPDATE SKIP LOCKED"
+}
@@ -0,0 +1,8 @@
+use sqlx::PgPool;
+
+use crate::infrastructure::database::password_reset_sql::password_reset_repository;
+
+password_reset_repository! {
+    /// Database adapter implementing PasswordResetRepositoryPort
+    PostgresPasswordResetRepository(PgPool)
+}
@@ -0,0 +1,8 @@
+use sqlx::PgPool;
+
+use crate::infrastructure::database::session_sql::session_repository;
+
+session_repository! {
+    /// Database adapter implementing SessionRepositoryPort
+    PostgresSessionRepository(PgPool)
+}
@@ -0,0 +1,11 @@
+use sqlx::PgPool;
+
+use crate::infrastructure::database::webhook_sql::webhook_repository;
+
+webhook_repository! {
+    /// Database adapter implementing WebhookRepositoryPort.
+    /// Claims lock candidate rows with `FOR UPDATE SKIP LOCKED`, so concurrent
+    /// dispatchers neither block on nor double-claim each other's deliveries.
+    PostgresWebhookRepository(PgPool),
+    claim_lock = "FOR UPDATE SKIP LOCKED"
+}
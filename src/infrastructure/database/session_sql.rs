@@ -0,0 +1,177 @@
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::domain::{Session, UserId};
+
+/// Columns selected for the session model
+pub(crate) const SESSION_COLUMNS: &str =
+    "id, family_id, user_id, token_hash, created_at, expires_at, rotated_at, revoked_at";
+
+/// Database model for Session (infrastructure concern), shared by the SQL adapters
+#[derive(Debug, FromRow)]
+pub(crate) struct SessionDbModel {
+    id: Uuid,
+    family_id: Uuid,
+    user_id: Uuid,
+    token_hash: String,
+    created_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    rotated_at: Option<DateTime<Utc>>,
+    revoked_at: Option<DateTime<Utc>>,
+}
+
+impl SessionDbModel {
+    pub(crate) fn into_domain(self) -> Session {
+        Session {
+            id: self.id,
+            family_id: self.family_id,
+            user_id: UserId::from_uuid(self.user_id),
+            token_hash: self.token_hash,
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+            rotated_at: self.rotated_at,
+            revoked_at: self.revoked_at,
+        }
+    }
+}
+
+/// Adapter implementing SessionRepositoryPort over a sqlx pool. The SQL runs
+/// unchanged on every backend, so each one only names its pool type.
+macro_rules! session_repository {
+    ($(#[$attr:meta])* $name:ident($pool:ty)) => {
+        use async_trait::async_trait;
+        use chrono::{DateTime, Utc};
+        use uuid::Uuid;
+
+        use $crate::{
+            domain::{Session, SessionRepositoryPort, UserError, UserId},
+            infrastructure::database::{
+                error::{is_foreign_key_violation, map_sqlx_error},
+                session_sql::{SESSION_COLUMNS, SessionDbModel},
+            },
+        };
+
+        $(#[$attr])*
+        #[derive(Clone)]
+        pub struct $name {
+            pool: $pool,
+        }
+
+        impl $name {
+            pub fn new(pool: $pool) -> Self {
+                Self { pool }
+            }
+        }
+
+        #[async_trait]
+        impl SessionRepositoryPort for $name {
+            async fn save(&self, session: &Session) -> Result<(), UserError> {
+                sqlx::query(
+                    r#"
+                    INSERT INTO sessions
+                        (id, family_id, user_id, token_hash, created_at, expires_at, rotated_at, revoked_at)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                    "#,
+                )
+                .bind(session.id)
+                .bind(session.family_id)
+                .bind(session.user_id.as_uuid())
+                .bind(&session.token_hash)
+                .bind(session.created_at)
+                .bind(session.expires_at)
+                .bind(session.rotated_at)
+                .bind(session.revoked_at)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| {
+                    if is_foreign_key_violation(&e) {
+                        UserError::NotFound
+                    } else {
+                        map_sqlx_error("save session", e)
+                    }
+                })?;
+
+                Ok(())
+            }
+
+            async fn find_by_token_hash(&self, token_hash: &str) -> Result<Option<Session>, UserError> {
+                let result = sqlx::query_as::<_, SessionDbModel>(&format!(
+                    "SELECT {} FROM sessions WHERE token_hash = $1",
+                    SESSION_COLUMNS
+                ))
+                .bind(token_hash)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("find session", e))?;
+
+                Ok(result.map(SessionDbModel::into_domain))
+            }
+
+            async fn mark_rotated(&self, id: Uuid, rotated_at: DateTime<Utc>) -> Result<bool, UserError> {
+                let result = sqlx::query(
+                    "UPDATE sessions SET rotated_at = $2 WHERE id = $1 AND rotated_at IS NULL AND revoked_at IS NULL",
+                )
+                .bind(id)
+                .bind(rotated_at)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("rotate session", e))?;
+
+                Ok(result.rows_affected() == 1)
+            }
+
+            async fn revoke_family(&self, family_id: Uuid, revoked_at: DateTime<Utc>) -> Result<u64, UserError> {
+                let result = sqlx::query(
+                    "UPDATE sessions SET revoked_at = $2 WHERE family_id = $1 AND revoked_at IS NULL",
+                )
+                .bind(family_id)
+                .bind(revoked_at)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("revoke session family", e))?;
+
+                Ok(result.rows_affected())
+            }
+
+            async fn revoke_all_for_user(&self, user_id: &UserId, revoked_at: DateTime<Utc>) -> Result<u64, UserError> {
+                let result = sqlx::query(
+                    "UPDATE sessions SET revoked_at = $2 WHERE user_id = $1 AND revoked_at IS NULL",
+                )
+                .bind(user_id.as_uuid())
+                .bind(revoked_at)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("revoke user sessions", e))?;
+
+                Ok(result.rows_affected())
+            }
+
+            async fn has_active(&self, user_id: &UserId, now: DateTime<Utc>) -> Result<bool, UserError> {
+                let active: i64 = sqlx::query_scalar(
+                    "SELECT COUNT(*) FROM sessions \
+                     WHERE user_id = $1 AND rotated_at IS NULL AND revoked_at IS NULL AND expires_at > $2",
+                )
+                .bind(user_id.as_uuid())
+                .bind(now)
+                .fetch_one(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("find active sessions", e))?;
+
+                Ok(active > 0)
+            }
+
+            async fn purge_expired(&self, expired_before: DateTime<Utc>) -> Result<u64, UserError> {
+                let result = sqlx::query("DELETE FROM sessions WHERE expires_at < $1")
+                    .bind(expired_before)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(|e| map_sqlx_error("purge expired sessions", e))?;
+
+                Ok(result.rows_affected())
+            }
+        }
+    };
+}
+
+pub(crate) use session_repository;
@@ -0,0 +1,8 @@
+use sqlx::SqlitePool;
+
+use crate::infrastructure::database::api_key_sql::api_key_repository;
+
+api_key_repository! {
+    /// SQLite adapter implementing ApiKeyRepositoryPort
+    SqliteApiKeyRepository(SqlitePool)
+}
@@ -0,0 +1,8 @@
+use sqlx::{Sqlite, SqlitePool};
+
+use crate::infrastructure::database::audit_log_sql::audit_log_repository;
+
+audit_log_repository! {
+    /// SQLite adapter implementing AuditLogRepositoryPort
+    SqliteAuditLogRepository(SqlitePool, Sqlite)
+}
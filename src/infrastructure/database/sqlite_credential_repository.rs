@@ -0,0 +1,8 @@
+use sqlx::SqlitePool;
+
+use crate::infrastructure::database::credential_sql::credential_repository;
+
+credential_repository! {
+    /// SQLite adapter implementing CredentialRepositoryPort
+    SqliteCredentialRepository(SqlitePool)
+}
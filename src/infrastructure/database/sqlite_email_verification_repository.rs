@@ -0,0 +1,8 @@
+use sqlx::SqlitePool;
+
+use crate::infrastructure::database::email_verification_sql::email_verification_repository;
+
+email_verification_repository! {
+    /// SQLite adapter implementing EmailVerificationRepositoryPort
+    SqliteEmailVerificationRepository(SqlitePool)
+}
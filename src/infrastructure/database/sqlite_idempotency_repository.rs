@@ -0,0 +1,8 @@
+use sqlx::SqlitePool;
+
+use crate::infrastructure::database::idempotency_sql::idempotency_repository;
+
+idempotency_repository! {
+    /// SQLite adapter implementing IdempotencyRepositoryPort
+    SqliteIdempotencyRepository(SqlitePool)
+}
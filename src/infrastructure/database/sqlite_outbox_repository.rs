@@ -0,0 +1,11 @@
+use sqlx::SqlitePool;
+
+use crate::infrastructure::database::outbox_sql::outbox_repository;
+
+outbox_repository! {
+    /// SQLite adapter implementing OutboxRepositoryPort.
+    /// SQLite runs one write at a time, so a claim is a single UPDATE without
+    /// row locks.
+    SqliteOutboxRepository(SqlitePool),
+    claim_lock = ""
+}
@@ -0,0 +1,8 @@
+use sqlx::SqlitePool;
+
+use crate::infrastructure::database::password_reset_sql::password_reset_repository;
+
+password_reset_repository! {
+    /// SQLite adapter implementing PasswordResetRepositoryPort
+    SqlitePasswordResetRepository(SqlitePool)
+}
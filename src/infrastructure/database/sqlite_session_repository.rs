@@ -0,0 +1,8 @@
+use sqlx::SqlitePool;
+
+use crate::infrastructure::database::session_sql::session_repository;
+
+session_repository! {
+    /// SQLite adapter implementing SessionRepositoryPort
+    SqliteSessionRepository(SqlitePool)
+}
@@ -0,0 +1,10 @@
+use sqlx::{Sqlite, SqlitePool};
+
+use crate::infrastructure::database::user_sql::user_repository;
+
+user_repository! {
+    /// SQLite adapter implementing UserRepositoryPort, for small single-node deployments.
+    /// SQLite keeps no cheap row estimate, so counts are always exact.
+    SqliteUserRepository(SqlitePool, Sqlite),
+    count_estimate = None
+}
@@ -0,0 +1,11 @@
+use sqlx::SqlitePool;
+
+use crate::infrastructure::database::webhook_sql::webhook_repository;
+
+webhook_repository! {
+    /// SQLite adapter implementing WebhookRepositoryPort.
+    /// SQLite runs one write at a time, so a claim is a single UPDATE without
+    /// row locks.
+    SqliteWebhookRepository(SqlitePool),
+    claim_lock = ""
+}
@@ -0,0 +1,122 @@
+use chrono::{DateTime, Utc};
+use sqlx::{Database, Encode, QueryBuilder, Type};
+use uuid::Uuid;
+
+use crate::domain::{SortDirection, SortKey, UserCursor, UserQuery, UserSortField};
+
+/// Columns selected for the user model, shared by every listing query
+pub(crate) const USER_COLUMNS: &str = "id, name, email, email_verified_at, role, created_at, updated_at, deleted_at, version";
+
+/// Appends `UserQuery` criteria to a SQL statement.
+/// Values always go through bind parameters; only whitelisted column names
+/// and directions are written into the SQL text.
+pub(crate) struct UserQuerySql<'q> {
+    query: &'q UserQuery,
+}
+
+impl<'q> UserQuerySql<'q> {
+    pub(crate) fn new(query: &'q UserQuery) -> Self {
+        Self { query }
+    }
+
+    /// Push ` WHERE ...` with every filter plus the optional keyset condition
+    pub(crate) fn push_where<'args, DB>(&self, qb: &mut QueryBuilder<'args, DB>, after: Option<&UserCursor>)
+    where
+        DB: Database,
+        String: Encode<'args, DB> + Type<DB>,
+        DateTime<Utc>: Encode<'args, DB> + Type<DB>,
+        Uuid: Encode<'args, DB> + Type<DB>,
+    {
+        let query = self.query;
+        qb.push(" WHERE 1 = 1");
+
+        if !query.include_deleted {
+            qb.push(" AND deleted_at IS NULL");
+        }
+
+        if let Some(prefix) = &query.name_prefix {
+            qb.push(" AND LOWER(name) LIKE ")
+                .push_bind(format!("{}%", escape_like(&prefix.to_lowercase())))
+                .push(" ESCAPE '\\'");
+        }
+        if let Some(fragment) = &query.name_contains {
+            qb.push(" AND LOWER(name) LIKE ")
+                .push_bind(format!("%{}%", escape_like(&fragment.to_lowercase())))
+                .push(" ESCAPE '\\'");
+        }
+        if let Some(email) = &query.email {
+            qb.push(" AND email = ").push_bind(email.as_str().to_string());
+        }
+        if let Some(domain) = &query.email_domain {
+            qb.push(" AND email LIKE ")
+                .push_bind(format!("%@{}", escape_like(&domain.to_lowercase())))
+                .push(" ESCAPE '\\'");
+        }
+        if let Some(t) = query.created_after {
+            qb.push(" AND created_at >= ").push_bind(t);
+        }
+        if let Some(t) = query.created_before {
+            qb.push(" AND created_at < ").push_bind(t);
+        }
+        if let Some(t) = query.updated_after {
+            qb.push(" AND updated_at >= ").push_bind(t);
+        }
+        if let Some(t) = query.updated_before {
+            qb.push(" AND updated_at < ").push_bind(t);
+        }
+
+        if let Some(cursor) = after {
+            // Rows level with the cursor continue after its id; the range
+            // bound on the sort column alone lets the index do the seeking
+            let column = sort_column(cursor.sort.field);
+            let (outer, inner) = match cursor.sort.direction {
+                SortDirection::Asc => (">=", ">"),
+                SortDirection::Desc => ("<=", "<"),
+            };
+            qb.push(format!(" AND {} {} ", column, outer));
+            push_key(qb, &cursor.key);
+            qb.push(format!(" AND ({} {} ", column, inner));
+            push_key(qb, &cursor.key);
+            qb.push(" OR id > ").push_bind(cursor.id.as_uuid()).push(")");
+        }
+    }
+
+    /// Push ` ORDER BY ...` for the query's sort, ties broken by ascending id
+    pub(crate) fn push_order_by<DB: Database>(&self, qb: &mut QueryBuilder<'_, DB>) {
+        let direction = match self.query.sort.direction {
+            SortDirection::Asc => "ASC",
+            SortDirection::Desc => "DESC",
+        };
+        qb.push(format!(" ORDER BY {} {}, id", sort_column(self.query.sort.field), direction));
+    }
+}
+
+fn sort_column(field: UserSortField) -> &'static str {
+    match field {
+        UserSortField::CreatedAt => "created_at",
+        UserSortField::UpdatedAt => "updated_at",
+        // Served by idx_users_name_lower
+        UserSortField::Name => "LOWER(name)",
+        UserSortField::Email => "email",
+    }
+}
+
+fn push_key<'args, DB>(qb: &mut QueryBuilder<'args, DB>, key: &SortKey)
+where
+    DB: Database,
+    String: Encode<'args, DB> + Type<DB>,
+    DateTime<Utc>: Encode<'args, DB> + Type<DB>,
+{
+    match key {
+        SortKey::Timestamp(t) => qb.push_bind(*t),
+        SortKey::Text(s) => qb.push_bind(s.clone()),
+    };
+}
+
+/// Escape LIKE wildcards so user input only ever matches literally
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
@@ -0,0 +1,426 @@
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::domain::{Email, Role, User, UserError, UserId, UserName};
+
+/// Database model for User (infrastructure concern), shared by the SQL adapters
+#[derive(Debug, FromRow)]
+pub(crate) struct UserDbModel {
+    id: Uuid,
+    name: String,
+    email: String,
+    email_verified_at: Option<DateTime<Utc>>,
+    role: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    deleted_at: Option<DateTime<Utc>>,
+    version: i64,
+}
+
+impl UserDbModel {
+    pub(crate) fn into_domain(self) -> Result<User, UserError> {
+        let id = UserId::from_uuid(self.id);
+        let name = UserName::new(self.name)?;
+        let email = Email::new(self.email)?;
+        let role = Role::parse(&self.role)?;
+
+        Ok(User::from_persistence(
+            id,
+            name,
+            email,
+            self.email_verified_at,
+            role,
+            self.created_at,
+            self.updated_at,
+            self.deleted_at,
+            self.version,
+        ))
+    }
+}
+
+/// Adapter implementing UserRepositoryPort over a sqlx pool of the database
+/// `$db`. `count_estimate`, when given, is a query for the backend's row
+/// estimate of the whole table (negative while unknown), used for
+/// `CountAccuracy::Estimated` counts of unfiltered listings.
+macro_rules! user_repository {
+    (
+        $(#[$attr:meta])* $name:ident($pool:ty, $db:ty),
+        count_estimate = $count_estimate:expr
+    ) => {
+        use async_trait::async_trait;
+        use chrono::{DateTime, Utc};
+        use sqlx::{Database, QueryBuilder, Transaction};
+
+        use $crate::{
+            domain::{
+                AuditEntry, CountAccuracy, Email, User, UserCursor, UserError, UserEvent, UserId, UserQuery,
+                UserRepositoryPort, current_timestamp,
+            },
+            infrastructure::database::{
+                audit_log_sql::encode_changes,
+                error::{map_sqlx_error, version_conflict},
+                outbox_sql::encode_event,
+                user_query_sql::{USER_COLUMNS, UserQuerySql},
+                user_sql::UserDbModel,
+            },
+        };
+
+        type Connection = <$db as Database>::Connection;
+
+        $(#[$attr])*
+        #[derive(Clone)]
+        pub struct $name {
+            pool: $pool,
+        }
+
+        impl $name {
+            pub fn new(pool: $pool) -> Self {
+                Self { pool }
+            }
+
+            async fn begin(&self, operation: &'static str) -> Result<Transaction<'static, $db>, UserError> {
+                self.pool.begin().await.map_err(|e| map_sqlx_error(operation, e))
+            }
+        }
+
+        async fn commit(tx: Transaction<'_, $db>, operation: &'static str) -> Result<(), UserError> {
+            tx.commit().await.map_err(|e| map_sqlx_error(operation, e))
+        }
+
+        /// Why a guarded write to an active user matched no row
+        async fn missing_or_conflict(conn: &mut Connection, id: &UserId, operation: &'static str) -> UserError {
+            let exists = sqlx::query_as::<_, (bool,)>(
+                "SELECT EXISTS(SELECT 1 FROM users WHERE id = $1 AND deleted_at IS NULL)",
+            )
+            .bind(id.as_uuid())
+            .fetch_one(conn)
+            .await;
+
+            match exists {
+                Ok((true,)) => version_conflict(operation),
+                Ok((false,)) => UserError::NotFound,
+                Err(e) => map_sqlx_error(operation, e),
+            }
+        }
+
+        async fn insert_user(conn: &mut Connection, user: &User) -> Result<(), UserError> {
+            sqlx::query(
+                r#"
+                INSERT INTO users (id, name, email, role, created_at, updated_at, version, email_verified_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                "#,
+            )
+            .bind(user.id().as_uuid())
+            .bind(user.name().as_str())
+            .bind(user.email().as_str())
+            .bind(user.role().as_str())
+            .bind(user.created_at())
+            .bind(user.updated_at())
+            .bind(user.version())
+            .bind(user.email_verified_at())
+            .execute(conn)
+            .await
+            .map_err(|e| map_sqlx_error("save user", e))?;
+
+            Ok(())
+        }
+
+        async fn update_user(conn: &mut Connection, user: &User) -> Result<(), UserError> {
+            let result = sqlx::query(
+                r#"
+                UPDATE users
+                SET name = $2, email = $3, role = $4, updated_at = $5, version = $6, email_verified_at = $7
+                WHERE id = $1 AND version = $6 - 1 AND deleted_at IS NULL
+                "#,
+            )
+            .bind(user.id().as_uuid())
+            .bind(user.name().as_str())
+            .bind(user.email().as_str())
+            .bind(user.role().as_str())
+            .bind(user.updated_at())
+            .bind(user.version())
+            .bind(user.email_verified_at())
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| map_sqlx_error("update user", e))?;
+
+            if result.rows_affected() == 0 {
+                return Err(missing_or_conflict(conn, user.id(), "update user").await);
+            }
+
+            Ok(())
+        }
+
+        async fn delete_user(
+            conn: &mut Connection,
+            id: &UserId,
+            expected_version: Option<i64>,
+        ) -> Result<(), UserError> {
+            let now = current_timestamp();
+            let result = sqlx::query(
+                r#"
+                UPDATE users
+                SET deleted_at = $2, updated_at = $2, version = version + 1
+                WHERE id = $1 AND deleted_at IS NULL AND (CAST($3 AS BIGINT) IS NULL OR version = $3)
+                "#,
+            )
+            .bind(id.as_uuid())
+            .bind(now)
+            .bind(expected_version)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| map_sqlx_error("delete user", e))?;
+
+            if result.rows_affected() == 0 {
+                return Err(missing_or_conflict(conn, id, "delete user").await);
+            }
+
+            Ok(())
+        }
+
+        async fn restore_user(conn: &mut Connection, id: &UserId, restored_at: DateTime<Utc>) -> Result<(), UserError> {
+            // The partial unique email index rejects the restore if an active
+            // user has taken the address in the meantime
+            let result = sqlx::query(
+                r#"
+                UPDATE users
+                SET deleted_at = NULL, updated_at = $2, version = version + 1
+                WHERE id = $1 AND deleted_at IS NOT NULL
+                "#,
+            )
+            .bind(id.as_uuid())
+            .bind(restored_at)
+            .execute(conn)
+            .await
+            .map_err(|e| map_sqlx_error("restore user", e))?;
+
+            if result.rows_affected() == 0 {
+                return Err(UserError::NotFound);
+            }
+
+            Ok(())
+        }
+
+        async fn insert_audit_entry(conn: &mut Connection, entry: &AuditEntry) -> Result<(), UserError> {
+            sqlx::query(
+                r#"
+                INSERT INTO audit_log (id, user_id, actor_id, action, changes, request_id, created_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                "#,
+            )
+            .bind(entry.id)
+            .bind(entry.user_id.as_uuid())
+            .bind(entry.actor.as_ref().map(UserId::as_uuid))
+            .bind(entry.action.as_str())
+            .bind(encode_changes(&entry.changes)?)
+            .bind(entry.request_id.as_deref())
+            .bind(entry.created_at)
+            .execute(conn)
+            .await
+            .map_err(|e| map_sqlx_error("write audit entry", e))?;
+
+            Ok(())
+        }
+
+        async fn insert_outbox_event(conn: &mut Connection, event: &UserEvent) -> Result<(), UserError> {
+            sqlx::query(
+                r#"
+                INSERT INTO outbox (event_id, aggregate_id, event_type, payload, occurred_at, available_at)
+                VALUES ($1, $2, $3, $4, $5, $5)
+                "#,
+            )
+            .bind(event.id())
+            .bind(event.user_id().as_uuid())
+            .bind(event.event_type())
+            .bind(encode_event(event)?)
+            .bind(event.occurred_at())
+            .execute(conn)
+            .await
+            .map_err(|e| map_sqlx_error("enqueue outbox event", e))?;
+
+            Ok(())
+        }
+
+        #[async_trait]
+        impl UserRepositoryPort for $name {
+            async fn save_audited(&self, user: &User, audit: &AuditEntry, event: &UserEvent) -> Result<(), UserError> {
+                let mut tx = self.begin("save user").await?;
+                insert_user(&mut tx, user).await?;
+                insert_audit_entry(&mut tx, audit).await?;
+                insert_outbox_event(&mut tx, event).await?;
+                commit(tx, "save user").await
+            }
+
+            async fn find_by_id(&self, id: &UserId) -> Result<Option<User>, UserError> {
+                let result = sqlx::query_as::<_, UserDbModel>(&format!(
+                    "SELECT {} FROM users WHERE id = $1 AND deleted_at IS NULL",
+                    USER_COLUMNS
+                ))
+                .bind(id.as_uuid())
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("find user by id", e))?;
+
+                result.map(UserDbModel::into_domain).transpose()
+            }
+
+            async fn find_by_email(&self, email: &Email) -> Result<Option<User>, UserError> {
+                let result = sqlx::query_as::<_, UserDbModel>(&format!(
+                    "SELECT {} FROM users WHERE email = $1 AND deleted_at IS NULL",
+                    USER_COLUMNS
+                ))
+                .bind(email.as_str())
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("find user by email", e))?;
+
+                result.map(UserDbModel::into_domain).transpose()
+            }
+
+            async fn find_by_id_including_deleted(&self, id: &UserId) -> Result<Option<User>, UserError> {
+                let result = sqlx::query_as::<_, UserDbModel>(&format!(
+                    "SELECT {} FROM users WHERE id = $1",
+                    USER_COLUMNS
+                ))
+                .bind(id.as_uuid())
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("find user by id", e))?;
+
+                result.map(UserDbModel::into_domain).transpose()
+            }
+
+            async fn update_audited(
+                &self,
+                user: &User,
+                audit: &AuditEntry,
+                event: &UserEvent,
+            ) -> Result<(), UserError> {
+                let mut tx = self.begin("update user").await?;
+                update_user(&mut tx, user).await?;
+                insert_audit_entry(&mut tx, audit).await?;
+                insert_outbox_event(&mut tx, event).await?;
+                commit(tx, "update user").await
+            }
+
+            async fn delete_audited(
+                &self,
+                id: &UserId,
+                expected_version: Option<i64>,
+                audit: &AuditEntry,
+                event: &UserEvent,
+            ) -> Result<(), UserError> {
+                let mut tx = self.begin("delete user").await?;
+                delete_user(&mut tx, id, expected_version).await?;
+                insert_audit_entry(&mut tx, audit).await?;
+                insert_outbox_event(&mut tx, event).await?;
+                commit(tx, "delete user").await
+            }
+
+            async fn restore_audited(
+                &self,
+                id: &UserId,
+                restored_at: DateTime<Utc>,
+                audit: &AuditEntry,
+                event: &UserEvent,
+            ) -> Result<(), UserError> {
+                let mut tx = self.begin("restore user").await?;
+                restore_user(&mut tx, id, restored_at).await?;
+                insert_audit_entry(&mut tx, audit).await?;
+                insert_outbox_event(&mut tx, event).await?;
+                commit(tx, "restore user").await
+            }
+
+            async fn purge_deleted(&self, deleted_before: DateTime<Utc>) -> Result<u64, UserError> {
+                let result = sqlx::query("DELETE FROM users WHERE deleted_at IS NOT NULL AND deleted_at < $1")
+                    .bind(deleted_before)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(|e| map_sqlx_error("purge deleted users", e))?;
+
+                Ok(result.rows_affected())
+            }
+
+            async fn find_all(&self, query: &UserQuery, offset: i64, limit: i64) -> Result<Vec<User>, UserError> {
+                let sql = UserQuerySql::new(query);
+                let mut qb = QueryBuilder::<$db>::new(format!("SELECT {} FROM users", USER_COLUMNS));
+                sql.push_where(&mut qb, None);
+                sql.push_order_by(&mut qb);
+                qb.push(" LIMIT ").push_bind(limit).push(" OFFSET ").push_bind(offset);
+
+                let results = qb
+                    .build_query_as::<UserDbModel>()
+                    .fetch_all(&self.pool)
+                    .await
+                    .map_err(|e| map_sqlx_error("find all users", e))?;
+
+                results.into_iter().map(UserDbModel::into_domain).collect()
+            }
+
+            async fn find_after(
+                &self,
+                query: &UserQuery,
+                after: Option<&UserCursor>,
+                limit: i64,
+            ) -> Result<Vec<User>, UserError> {
+                let sql = UserQuerySql::new(query);
+                let mut qb = QueryBuilder::<$db>::new(format!("SELECT {} FROM users", USER_COLUMNS));
+                sql.push_where(&mut qb, after);
+                sql.push_order_by(&mut qb);
+                qb.push(" LIMIT ").push_bind(limit);
+
+                let results = qb
+                    .build_query_as::<UserDbModel>()
+                    .fetch_all(&self.pool)
+                    .await
+                    .map_err(|e| map_sqlx_error("find users after cursor", e))?;
+
+                results.into_iter().map(UserDbModel::into_domain).collect()
+            }
+
+            async fn count(&self, query: &UserQuery, accuracy: CountAccuracy) -> Result<i64, UserError> {
+                // Row estimates only describe the whole table, so filtered
+                // counts are always exact. The estimate also covers soft-deleted
+                // rows awaiting purge, which is within the slack of an estimate anyway
+                let estimate_sql: Option<&str> = $count_estimate;
+                if let Some(estimate_sql) = estimate_sql
+                    && accuracy == CountAccuracy::Estimated
+                    && query.is_unfiltered()
+                {
+                    let (estimate,): (i64,) = sqlx::query_as(estimate_sql)
+                        .fetch_one(&self.pool)
+                        .await
+                        .map_err(|e| map_sqlx_error("estimate user count", e))?;
+
+                    if estimate >= 0 {
+                        return Ok(estimate);
+                    }
+                }
+
+                let mut qb = QueryBuilder::<$db>::new("SELECT COUNT(*) FROM users");
+                UserQuerySql::new(query).push_where(&mut qb, None);
+                let (count,): (i64,) = qb
+                    .build_query_as()
+                    .fetch_one(&self.pool)
+                    .await
+                    .map_err(|e| map_sqlx_error("count users", e))?;
+
+                Ok(count)
+            }
+
+            async fn exists_by_email(&self, email: &Email) -> Result<bool, UserError> {
+                let result: (bool,) =
+                    sqlx::query_as("SELECT EXISTS(SELECT 1 FROM users WHERE email = $1 AND deleted_at IS NULL)")
+                        .bind(email.as_str())
+                        .fetch_one(&self.pool)
+                        .await
+                        .map_err(|e| map_sqlx_error("check email existence", e))?;
+
+                Ok(result.0)
+            }
+        }
+    };
+}
+
+pub(crate) use user_repository;
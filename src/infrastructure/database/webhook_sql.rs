@@ -0,0 +1,417 @@
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::domain::{DeliveryStatus, UserError, WebhookAttempt, WebhookDelivery, WebhookSubscription};
+
+/// Columns selected for the webhook subscription model
+pub(crate) const SUBSCRIPTION_COLUMNS: &str = "id, url, event_types, secret, active, created_at, updated_at";
+
+/// Columns selected for the webhook delivery model
+pub(crate) const DELIVERY_COLUMNS: &str = "id, subscription_id, event_id, event_type, payload, status, attempts, \
+    next_attempt_at, last_response_status, last_error, replay_of, created_at, updated_at";
+
+/// Columns selected for the webhook attempt model
+pub(crate) const ATTEMPT_COLUMNS: &str = "delivery_id, attempt, response_status, error, attempted_at";
+
+/// Database model for WebhookSubscription (infrastructure concern), shared by the SQL adapters
+#[derive(Debug, FromRow)]
+pub(crate) struct SubscriptionDbModel {
+    id: Uuid,
+    url: String,
+    /// Space-separated
+    event_types: String,
+    secret: String,
+    active: bool,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+/// Database model for WebhookDelivery (infrastructure concern), shared by the SQL adapters
+#[derive(Debug, FromRow)]
+pub(crate) struct DeliveryDbModel {
+    id: Uuid,
+    subscription_id: Uuid,
+    event_id: Uuid,
+    event_type: String,
+    payload: String,
+    status: String,
+    attempts: i32,
+    next_attempt_at: DateTime<Utc>,
+    last_response_status: Option<i32>,
+    last_error: Option<String>,
+    replay_of: Option<Uuid>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+/// Database model for WebhookAttempt (infrastructure concern), shared by the SQL adapters
+#[derive(Debug, FromRow)]
+pub(crate) struct AttemptDbModel {
+    delivery_id: Uuid,
+    attempt: i32,
+    response_status: Option<i32>,
+    error: Option<String>,
+    attempted_at: DateTime<Utc>,
+}
+
+impl SubscriptionDbModel {
+    pub(crate) fn into_domain(self) -> WebhookSubscription {
+        WebhookSubscription {
+            id: self.id,
+            url: self.url,
+            event_types: self.event_types.split_whitespace().map(str::to_string).collect(),
+            secret: self.secret,
+            active: self.active,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        }
+    }
+}
+
+impl DeliveryDbModel {
+    pub(crate) fn into_domain(self) -> Result<WebhookDelivery, UserError> {
+        Ok(WebhookDelivery {
+            id: self.id,
+            subscription_id: self.subscription_id,
+            event_id: self.event_id,
+            event_type: self.event_type,
+            payload: self.payload,
+            status: DeliveryStatus::parse(&self.status)?,
+            attempts: self.attempts,
+            next_attempt_at: self.next_attempt_at,
+            last_response_status: self.last_response_status,
+            last_error: self.last_error,
+            replay_of: self.replay_of,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        })
+    }
+}
+
+impl AttemptDbModel {
+    pub(crate) fn into_domain(self) -> WebhookAttempt {
+        WebhookAttempt {
+            delivery_id: self.delivery_id,
+            attempt: self.attempt,
+            response_status: self.response_status,
+            error: self.error,
+            attempted_at: self.attempted_at,
+        }
+    }
+}
+
+/// Adapter implementing WebhookRepositoryPort over a sqlx pool. `claim_lock`
+/// ends the subquery selecting the deliveries to claim: the backend's
+/// row-locking clause, or nothing where writes are serialized anyway.
+macro_rules! webhook_repository {
+    ($(#[$attr:meta])* $name:ident($pool:ty), claim_lock = $claim_lock:literal) => {
+        use async_trait::async_trait;
+        use chrono::{DateTime, Duration, Utc};
+        use uuid::Uuid;
+
+        use $crate::{
+            domain::{
+                DeliveryStatus, UserError, WebhookAttempt, WebhookDelivery, WebhookRepositoryPort, WebhookSubscription,
+            },
+            infrastructure::database::{
+                error::{is_foreign_key_violation, map_sqlx_error},
+                webhook_sql::{
+                    ATTEMPT_COLUMNS, AttemptDbModel, DELIVERY_COLUMNS, DeliveryDbModel, SUBSCRIPTION_COLUMNS,
+                    SubscriptionDbModel,
+                },
+            },
+        };
+
+        $(#[$attr])*
+        #[derive(Clone)]
+        pub struct $name {
+            pool: $pool,
+        }
+
+        impl $name {
+            pub fn new(pool: $pool) -> Self {
+                Self { pool }
+            }
+        }
+
+        #[async_trait]
+        impl WebhookRepositoryPort for $name {
+            async fn save_subscription(&self, subscription: &WebhookSubscription) -> Result<(), UserError> {
+                sqlx::query(
+                    r#"
+                    INSERT INTO webhook_subscriptions (id, url, event_types, secret, active, created_at, updated_at)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7)
+                    "#,
+                )
+                .bind(subscription.id)
+                .bind(&subscription.url)
+                .bind(subscription.join_event_types())
+                .bind(&subscription.secret)
+                .bind(subscription.active)
+                .bind(subscription.created_at)
+                .bind(subscription.updated_at)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("save webhook subscription", e))?;
+
+                Ok(())
+            }
+
+            async fn update_subscription(&self, subscription: &WebhookSubscription) -> Result<(), UserError> {
+                let result = sqlx::query(
+                    r#"
+                    UPDATE webhook_subscriptions
+                    SET url = $2, event_types = $3, secret = $4, active = $5, updated_at = $6
+                    WHERE id = $1
+                    "#,
+                )
+                .bind(subscription.id)
+                .bind(&subscription.url)
+                .bind(subscription.join_event_types())
+                .bind(&subscription.secret)
+                .bind(subscription.active)
+                .bind(subscription.updated_at)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("update webhook subscription", e))?;
+
+                if result.rows_affected() == 0 {
+                    return Err(UserError::WebhookNotFound);
+                }
+
+                Ok(())
+            }
+
+            async fn delete_subscription(&self, id: Uuid) -> Result<(), UserError> {
+                let result = sqlx::query("DELETE FROM webhook_subscriptions WHERE id = $1")
+                    .bind(id)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(|e| map_sqlx_error("delete webhook subscription", e))?;
+
+                if result.rows_affected() == 0 {
+                    return Err(UserError::WebhookNotFound);
+                }
+
+                Ok(())
+            }
+
+            async fn find_subscription(&self, id: Uuid) -> Result<Option<WebhookSubscription>, UserError> {
+                let result = sqlx::query_as::<_, SubscriptionDbModel>(&format!(
+                    "SELECT {} FROM webhook_subscriptions WHERE id = $1",
+                    SUBSCRIPTION_COLUMNS
+                ))
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("find webhook subscription", e))?;
+
+                Ok(result.map(SubscriptionDbModel::into_domain))
+            }
+
+            async fn find_subscriptions(&self) -> Result<Vec<WebhookSubscription>, UserError> {
+                let results = sqlx::query_as::<_, SubscriptionDbModel>(&format!(
+                    "SELECT {} FROM webhook_subscriptions ORDER BY created_at, id",
+                    SUBSCRIPTION_COLUMNS
+                ))
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("list webhook subscriptions", e))?;
+
+                Ok(results.into_iter().map(SubscriptionDbModel::into_domain).collect())
+            }
+
+            async fn enqueue_delivery(&self, delivery: &WebhookDelivery) -> Result<bool, UserError> {
+                let result = sqlx::query(
+                    r#"
+                    INSERT INTO webhook_deliveries (
+                        id, subscription_id, event_id, event_type, payload, status, attempts, next_attempt_at,
+                        last_response_status, last_error, replay_of, created_at, updated_at
+                    )
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+                    ON CONFLICT (subscription_id, event_id) WHERE replay_of IS NULL DO NOTHING
+                    "#,
+                )
+                .bind(delivery.id)
+                .bind(delivery.subscription_id)
+                .bind(delivery.event_id)
+                .bind(&delivery.event_type)
+                .bind(&delivery.payload)
+                .bind(delivery.status.as_str())
+                .bind(delivery.attempts)
+                .bind(delivery.next_attempt_at)
+                .bind(delivery.last_response_status)
+                .bind(&delivery.last_error)
+                .bind(delivery.replay_of)
+                .bind(delivery.created_at)
+                .bind(delivery.updated_at)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| {
+                    if is_foreign_key_violation(&e) {
+                        UserError::WebhookNotFound
+                    } else {
+                        map_sqlx_error("enqueue webhook delivery", e)
+                    }
+                })?;
+
+                Ok(result.rows_affected() > 0)
+            }
+
+            async fn claim_deliveries(
+                &self,
+                now: DateTime<Utc>,
+                lease: Duration,
+                limit: i64,
+            ) -> Result<Vec<WebhookDelivery>, UserError> {
+                let rows = sqlx::query_as::<_, DeliveryDbModel>(&format!(
+                    r#"
+                    UPDATE webhook_deliveries
+                    SET next_attempt_at = $2, attempts = attempts + 1, updated_at = $1
+                    WHERE id IN (
+                        SELECT id FROM webhook_deliveries
+                        WHERE status = 'pending' AND next_attempt_at <= $1
+                        ORDER BY created_at, id
+                        LIMIT $3
+                        {}
+                    )
+                    RETURNING {}
+                    "#,
+                    $claim_lock, DELIVERY_COLUMNS
+                ))
+                .bind(now)
+                .bind(now + lease)
+                .bind(limit)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("claim webhook deliveries", e))?;
+
+                // RETURNING has no order of its own
+                let mut deliveries = rows.into_iter().map(DeliveryDbModel::into_domain).collect::<Result<Vec<_>, _>>()?;
+                deliveries.sort_by_key(|delivery| (delivery.created_at, delivery.id));
+                Ok(deliveries)
+            }
+
+            async fn record_attempt(
+                &self,
+                delivery: &WebhookDelivery,
+                attempt: &WebhookAttempt,
+            ) -> Result<(), UserError> {
+                let operation = "record webhook attempt";
+                let mut tx = self.pool.begin().await.map_err(|e| map_sqlx_error(operation, e))?;
+
+                sqlx::query(
+                    r#"
+                    UPDATE webhook_deliveries
+                    SET status = $2, next_attempt_at = $3, last_response_status = $4, last_error = $5, updated_at = $6
+                    WHERE id = $1 AND status = 'pending'
+                    "#,
+                )
+                .bind(delivery.id)
+                .bind(delivery.status.as_str())
+                .bind(delivery.next_attempt_at)
+                .bind(delivery.last_response_status)
+                .bind(&delivery.last_error)
+                .bind(delivery.updated_at)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| map_sqlx_error(operation, e))?;
+
+                sqlx::query(&format!(
+                    "INSERT INTO webhook_attempts ({}) VALUES ($1, $2, $3, $4, $5) ON CONFLICT DO NOTHING",
+                    ATTEMPT_COLUMNS
+                ))
+                .bind(attempt.delivery_id)
+                .bind(attempt.attempt)
+                .bind(attempt.response_status)
+                .bind(&attempt.error)
+                .bind(attempt.attempted_at)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| {
+                    if is_foreign_key_violation(&e) {
+                        UserError::WebhookDeliveryNotFound
+                    } else {
+                        map_sqlx_error(operation, e)
+                    }
+                })?;
+
+                tx.commit().await.map_err(|e| map_sqlx_error(operation, e))
+            }
+
+            async fn find_delivery(&self, id: Uuid) -> Result<Option<WebhookDelivery>, UserError> {
+                let result = sqlx::query_as::<_, DeliveryDbModel>(&format!(
+                    "SELECT {} FROM webhook_deliveries WHERE id = $1",
+                    DELIVERY_COLUMNS
+                ))
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("find webhook delivery", e))?;
+
+                result.map(DeliveryDbModel::into_domain).transpose()
+            }
+
+            async fn find_deliveries(
+                &self,
+                subscription_id: Uuid,
+                status: Option<DeliveryStatus>,
+                offset: i64,
+                limit: i64,
+            ) -> Result<Vec<WebhookDelivery>, UserError> {
+                let results = sqlx::query_as::<_, DeliveryDbModel>(&format!(
+                    r#"
+                    SELECT {} FROM webhook_deliveries
+                    WHERE subscription_id = $1 AND ($2 IS NULL OR status = $2)
+                    ORDER BY created_at DESC, id DESC
+                    LIMIT $3 OFFSET $4
+                    "#,
+                    DELIVERY_COLUMNS
+                ))
+                .bind(subscription_id)
+                .bind(status.map(|status| status.as_str()))
+                .bind(limit)
+                .bind(offset)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("list webhook deliveries", e))?;
+
+                results.into_iter().map(DeliveryDbModel::into_domain).collect()
+            }
+
+            async fn count_deliveries(
+                &self,
+                subscription_id: Uuid,
+                status: Option<DeliveryStatus>,
+            ) -> Result<i64, UserError> {
+                let (count,): (i64,) = sqlx::query_as(
+                    "SELECT COUNT(*) FROM webhook_deliveries \
+                     WHERE subscription_id = $1 AND ($2 IS NULL OR status = $2)",
+                )
+                .bind(subscription_id)
+                .bind(status.map(|status| status.as_str()))
+                .fetch_one(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("count webhook deliveries", e))?;
+
+                Ok(count)
+            }
+
+            async fn find_attempts(&self, delivery_id: Uuid) -> Result<Vec<WebhookAttempt>, UserError> {
+                let results = sqlx::query_as::<_, AttemptDbModel>(&format!(
+                    "SELECT {} FROM webhook_attempts WHERE delivery_id = $1 ORDER BY attempt",
+                    ATTEMPT_COLUMNS
+                ))
+                .bind(delivery_id)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| map_sqlx_error("list webhook attempts", e))?;
+
+                Ok(results.into_iter().map(AttemptDbModel::into_domain).collect())
+            }
+        }
+    };
+}
+
+pub(crate) use webhook_repository;
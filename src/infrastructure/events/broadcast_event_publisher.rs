@@ -0,0 +1,73 @@
+use async_trait::async_trait;
+use tokio::{
+    sync::broadcast::{self, error::RecvError},
+    task::JoinHandle,
+};
+
+use crate::{
+    application::config::{ConfigError, positive_var},
+    domain::{EventPublisherPort, UserError, UserEvent},
+};
+
+/// Events a subscriber may fall behind by before it misses some
+pub const DEFAULT_EVENT_BUS_CAPACITY: usize = 1024;
+
+/// In-process adapter implementing EventPublisherPort.
+/// Every subscriber gets every event published after it subscribed. A
+/// subscriber more than `capacity` events behind skips the oldest ones and
+/// is told how many by `RecvError::Lagged`. Nothing survives a restart.
+#[derive(Clone, Debug)]
+pub struct BroadcastEventPublisher {
+    sender: broadcast::Sender<UserEvent>,
+}
+
+impl BroadcastEventPublisher {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    /// Capacity from `EVENT_BUS_CAPACITY`, by default 1024
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let capacity = positive_var("EVENT_BUS_CAPACITY")?.unwrap_or(DEFAULT_EVENT_BUS_CAPACITY);
+        Ok(Self::new(capacity))
+    }
+
+    /// Receive every event published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<UserEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for BroadcastEventPublisher {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVENT_BUS_CAPACITY)
+    }
+}
+
+#[async_trait]
+impl EventPublisherPort for BroadcastEventPublisher {
+    async fn publish(&self, event: &UserEvent) -> Result<(), UserError> {
+        // Without subscribers there is nobody to tell, which is not an error
+        let _ = self.sender.send(event.clone());
+        Ok(())
+    }
+}
+
+/// Log every event at debug level until the publisher is dropped
+pub fn spawn_event_log(mut events: broadcast::Receiver<UserEvent>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(event) => tracing::debug!(
+                    event = event.event_type(),
+                    event_id = %event.id(),
+                    user_id = %event.user_id().as_uuid(),
+                    "User event"
+                ),
+                Err(RecvError::Lagged(skipped)) => tracing::warn!(skipped, "Event log fell behind"),
+                Err(RecvError::Closed) => break,
+            }
+        }
+    })
+}
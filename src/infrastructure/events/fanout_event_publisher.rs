@@ -0,0 +1,35 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::domain::{EventPublisherPort, UserError, UserEvent};
+
+/// Adapter implementing EventPublisherPort by handing each event to several
+/// publishers, in order. The first failure is returned right away, so when
+/// the caller retries, the publishers before it see the event again and
+/// must tolerate duplicates.
+#[derive(Clone, Default)]
+pub struct FanoutEventPublisher {
+    publishers: Vec<Arc<dyn EventPublisherPort>>,
+}
+
+impl FanoutEventPublisher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also publish to `publisher`, after the ones added before
+    pub fn with(mut self, publisher: Arc<dyn EventPublisherPort>) -> Self {
+        self.publishers.push(publisher);
+        self
+    }
+}
+
+#[async_trait]
+impl EventPublisherPort for FanoutEventPublisher {
+    async fn publish(&self, event: &UserEvent) -> Result<(), UserError> {
+        for publisher in &self.publishers {
+            publisher.publish(event).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,7 @@
+pub mod broadcast_event_publisher;
+pub mod fanout_event_publisher;
+pub mod user_event_stream;
+
+pub use broadcast_event_publisher::{BroadcastEventPublisher, spawn_event_log};
+pub use fanout_event_publisher::FanoutEventPublisher;
+pub use user_event_stream::{Resume, UserEventStream};
@@ -0,0 +1,116 @@
+use async_trait::async_trait;
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::{
+    application::config::{ConfigError, positive_var},
+    domain::{EventPublisherPort, UserError, UserEvent},
+};
+
+/// Recent events kept for clients resuming a stream
+pub const DEFAULT_EVENT_STREAM_BUFFER: usize = 1000;
+/// Pause after which an idle stream gets a heartbeat
+pub const DEFAULT_EVENT_STREAM_HEARTBEAT: Duration = Duration::from_secs(15);
+
+/// Where a new subscriber starts
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resume {
+    /// The events after the last one the client saw, oldest first; empty for
+    /// a client starting fresh
+    Replay(Vec<UserEvent>),
+    /// The last event the client saw is no longer (or never was) buffered,
+    /// so it may have missed some. Carries the newest buffered event id, from
+    /// which the client can resume once it has reloaded its state.
+    Gap(Option<Uuid>),
+}
+
+/// In-process adapter implementing EventPublisherPort for streams to
+/// clients, such as Server-Sent Events.
+/// Keeps the latest `capacity` events so a reconnecting client can resume
+/// after the last event it saw; events published again are dropped by id.
+/// Fed by `OutboxTail`, so every instance sees every event; nothing survives
+/// a restart.
+#[derive(Clone, Debug)]
+pub struct UserEventStream {
+    recent: Arc<Mutex<VecDeque<UserEvent>>>,
+    sender: broadcast::Sender<UserEvent>,
+    capacity: usize,
+    heartbeat: Duration,
+}
+
+impl UserEventStream {
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "the event stream buffer must hold at least one event");
+        let (sender, _) = broadcast::channel(capacity);
+        Self {
+            recent: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            sender,
+            capacity,
+            heartbeat: DEFAULT_EVENT_STREAM_HEARTBEAT,
+        }
+    }
+
+    /// Override how long a stream may stay silent before a heartbeat
+    pub fn with_heartbeat(mut self, heartbeat: Duration) -> Self {
+        self.heartbeat = heartbeat;
+        self
+    }
+
+    /// Buffer from `EVENT_STREAM_BUFFER` (default 1000) and heartbeat from
+    /// `EVENT_STREAM_HEARTBEAT_SECS` (default 15)
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let capacity = positive_var("EVENT_STREAM_BUFFER")?.unwrap_or(DEFAULT_EVENT_STREAM_BUFFER);
+        let heartbeat =
+            positive_var("EVENT_STREAM_HEARTBEAT_SECS")?.map_or(DEFAULT_EVENT_STREAM_HEARTBEAT, Duration::from_secs);
+        Ok(Self::new(capacity).with_heartbeat(heartbeat))
+    }
+
+    pub fn heartbeat(&self) -> Duration {
+        self.heartbeat
+    }
+
+    /// Start receiving events: first those the resume point calls for, then
+    /// every event published from now on, with none lost or repeated in
+    /// between. `last_event_id` is the id of the last event the client saw.
+    pub fn subscribe(&self, last_event_id: Option<Uuid>) -> (Resume, broadcast::Receiver<UserEvent>) {
+        // Publishing holds the lock too, so nothing slips between the two
+        let recent = self.recent.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let receiver = self.sender.subscribe();
+        let resume = match last_event_id {
+            None => Resume::Replay(Vec::new()),
+            Some(id) => match recent.iter().position(|event| event.id() == id) {
+                Some(seen) => Resume::Replay(recent.iter().skip(seen + 1).cloned().collect()),
+                None => Resume::Gap(recent.back().map(UserEvent::id)),
+            },
+        };
+        (resume, receiver)
+    }
+}
+
+impl Default for UserEventStream {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVENT_STREAM_BUFFER)
+    }
+}
+
+#[async_trait]
+impl EventPublisherPort for UserEventStream {
+    async fn publish(&self, event: &UserEvent) -> Result<(), UserError> {
+        let mut recent = self.recent.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if recent.iter().any(|seen| seen.id() == event.id()) {
+            return Ok(());
+        }
+        if recent.len() == self.capacity {
+            recent.pop_front();
+        }
+        recent.push_back(event.clone());
+        // Without subscribers there is nobody to tell, which is not an error
+        let _ = self.sender.send(event.clone());
+        Ok(())
+    }
+}
@@ -0,0 +1,9 @@
+pub mod outbox_relay;
+pub mod outbox_tail;
+pub mod purge_deleted_users;
+pub mod webhook_dispatcher;
+
+pub use outbox_relay::spawn_outbox_relay;
+pub use outbox_tail::spawn_outbox_tail;
+pub use purge_deleted_users::spawn_purge_task;
+pub use webhook_dispatcher::spawn_webhook_dispatcher;
@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+
+use crate::application::OutboxRelay;
+
+/// How often delivered outbox messages past retention are purged
+const PURGE_EVERY: Duration = Duration::from_secs(3600);
+
+/// Relay outbox messages to the sink for as long as the process runs.
+/// A full batch is followed by the next one right away; otherwise the task
+/// waits for the poll interval. Failures are logged and retried on the next
+/// poll.
+pub fn spawn_outbox_relay(relay: OutboxRelay) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut purge = tokio::time::interval(PURGE_EVERY);
+        purge.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            match relay.relay_batch().await {
+                Ok(report) if report.claimed as i64 >= relay.config().batch_size => continue,
+                Ok(_) => {}
+                Err(err) => tracing::warn!(error = ?err, code = err.code(), "Relaying the outbox failed"),
+            }
+
+            tokio::select! {
+                _ = purge.tick() => match relay.purge_sent().await {
+                    Ok(0) => {}
+                    Ok(purged) => tracing::info!(purged, "Purged delivered outbox messages"),
+                    Err(err) => tracing::warn!(error = ?err, code = err.code(), "Purging the outbox failed"),
+                },
+                _ = tokio::time::sleep(relay.config().poll_interval) => {}
+            }
+        }
+    })
+}
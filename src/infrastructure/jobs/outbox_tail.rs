@@ -0,0 +1,19 @@
+use tokio::task::JoinHandle;
+
+use crate::application::OutboxTail;
+
+/// Follow the outbox for as long as the process runs. A full batch is
+/// followed by the next one right away; otherwise the task waits for the
+/// poll interval. Failures are logged and retried on the next poll.
+pub fn spawn_outbox_tail(mut tail: OutboxTail) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            match tail.tail_batch().await {
+                Ok(passed) if passed as i64 >= tail.config().batch_size => continue,
+                Ok(_) => {}
+                Err(err) => tracing::warn!(error = ?err, code = err.code(), "Following the outbox failed"),
+            }
+            tokio::time::sleep(tail.config().poll_interval).await;
+        }
+    })
+}
@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+
+use crate::{
+    application::UserApplicationService,
+    domain::{UserError, UserRepositoryPort},
+};
+
+/// Periodically purge soft-deleted users past their retention window, along
+/// with expired sessions, email verification and password reset tokens and
+/// idempotency keys.
+/// Failures are logged and retried on the next tick, so a storage outage
+/// never stops the task.
+pub fn spawn_purge_task<R: UserRepositoryPort + 'static>(
+    app_service: UserApplicationService<R>,
+    every: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(every);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+            log_purge("soft-deleted users", app_service.purge_deleted_users().await);
+            log_purge("expired sessions", app_service.purge_expired_sessions().await);
+            log_purge(
+                "expired email verification tokens",
+                app_service.purge_expired_verification_tokens().await,
+            );
+            log_purge(
+                "expired password reset tokens",
+                app_service.purge_expired_password_reset_tokens().await,
+            );
+            log_purge("expired idempotency keys", app_service.purge_expired_idempotency_keys().await);
+        }
+    })
+}
+
+fn log_purge(what: &'static str, result: Result<u64, UserError>) {
+    match result {
+        Ok(0) => {}
+        Ok(purged) => tracing::info!(purged, "Purged {}", what),
+        Err(err) => tracing::warn!(error = ?err, code = err.code(), "Purging {} failed", what),
+    }
+}
@@ -0,0 +1,20 @@
+use tokio::task::JoinHandle;
+
+use crate::application::WebhookService;
+
+/// Send due webhook deliveries for as long as the process runs.
+/// A full batch is followed by the next one right away; otherwise the task
+/// waits for the poll interval. Failures are logged and retried on the next
+/// poll.
+pub fn spawn_webhook_dispatcher(webhooks: WebhookService) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            match webhooks.dispatch_batch().await {
+                Ok(report) if report.claimed as i64 >= webhooks.config().batch_size => continue,
+                Ok(_) => {}
+                Err(err) => tracing::warn!(error = ?err, code = err.code(), "Dispatching webhooks failed"),
+            }
+            tokio::time::sleep(webhooks.config().poll_interval).await;
+        }
+    })
+}
@@ -0,0 +1,56 @@
+use async_trait::async_trait;
+use std::path::PathBuf;
+
+use crate::domain::{InfrastructureError, MailMessage, MailerPort, UserError, current_timestamp};
+
+/// Development adapter implementing MailerPort.
+/// Logs every message and, with a drop directory, also writes it there as an
+/// `.eml` file. Messages carry secrets such as verification tokens, so this
+/// is not meant for production.
+#[derive(Clone, Debug, Default)]
+pub struct LogMailer {
+    drop_dir: Option<PathBuf>,
+}
+
+impl LogMailer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also write each message to `dir`, created on first use
+    pub fn with_drop_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.drop_dir = Some(dir.into());
+        self
+    }
+
+    async fn drop_file(&self, dir: &PathBuf, message: &MailMessage) -> std::io::Result<PathBuf> {
+        tokio::fs::create_dir_all(dir).await?;
+        let now = current_timestamp();
+        let path = dir.join(format!("{}-{}.eml", now.format("%Y%m%dT%H%M%S%.6fZ"), uuid::Uuid::new_v4().simple()));
+        let contents = format!(
+            "Date: {}\r\nTo: {}\r\nSubject: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}\r\n",
+            now.to_rfc2822(),
+            message.to.as_str(),
+            message.subject,
+            message.body.replace('\n', "\r\n"),
+        );
+        tokio::fs::write(&path, contents).await?;
+        Ok(path)
+    }
+}
+
+#[async_trait]
+impl MailerPort for LogMailer {
+    async fn send(&self, message: &MailMessage) -> Result<(), UserError> {
+        tracing::info!(to = message.to.as_str(), subject = %message.subject, body = %message.body, "Mail");
+
+        if let Some(dir) = &self.drop_dir {
+            let path = self
+                .drop_file(dir, message)
+                .await
+                .map_err(|e| UserError::MailUnavailable(InfrastructureError::new("drop mail", e)))?;
+            tracing::debug!(path = %path.display(), "Mail dropped");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+pub mod log_mailer;
+pub mod smtp_mailer;
+
+use std::sync::Arc;
+
+pub use log_mailer::LogMailer;
+pub use smtp_mailer::{SmtpMailer, SmtpTls};
+
+use crate::{
+    application::config::{ConfigError, env_var, parse_var},
+    domain::{MailerPort, UserError},
+};
+
+/// Mail adapter selected by `MAILER` (`log` by default, or `smtp`)
+#[derive(Debug, Clone)]
+pub enum MailerConfig {
+    /// `MAIL_DROP_DIR` optionally collects messages as `.eml` files
+    Log { drop_dir: Option<String> },
+    /// `SMTP_HOST`, `SMTP_PORT`, `SMTP_TLS`, `SMTP_USERNAME`, `SMTP_PASSWORD`
+    /// and the sender, `MAIL_FROM`
+    Smtp {
+        host: String,
+        port: u16,
+        tls: SmtpTls,
+        credentials: Option<(String, String)>,
+        from: String,
+    },
+}
+
+impl MailerConfig {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        match env_var("MAILER").map(|v| v.to_lowercase()).as_deref() {
+            None | Some("log") => Ok(Self::Log {
+                drop_dir: env_var("MAIL_DROP_DIR"),
+            }),
+            Some("smtp") => {
+                let tls = match env_var("SMTP_TLS").map(|v| v.to_lowercase()).as_deref() {
+                    None | Some("none") => SmtpTls::None,
+                    Some("starttls") => SmtpTls::StartTls,
+                    Some("tls") => SmtpTls::Tls,
+                    Some(other) => return Err(ConfigError::invalid("SMTP_TLS", "'none', 'starttls' or 'tls'", other)),
+                };
+                Ok(Self::Smtp {
+                    host: env_var("SMTP_HOST").unwrap_or_else(|| "localhost".to_string()),
+                    port: parse_var("SMTP_PORT", "a port number")?.unwrap_or(1025),
+                    tls,
+                    credentials: env_var("SMTP_USERNAME").zip(env_var("SMTP_PASSWORD")),
+                    from: env_var("MAIL_FROM").unwrap_or_else(|| "Rust Nexus <no-reply@localhost>".to_string()),
+                })
+            }
+            Some(other) => Err(ConfigError::invalid("MAILER", "'log' or 'smtp'", other)),
+        }
+    }
+
+    pub fn into_mailer(self) -> Result<Arc<dyn MailerPort>, UserError> {
+        Ok(match self {
+            Self::Log { drop_dir: None } => Arc::new(LogMailer::new()),
+            Self::Log { drop_dir: Some(dir) } => Arc::new(LogMailer::new().with_drop_dir(dir)),
+            Self::Smtp {
+                host,
+                port,
+                tls,
+                credentials,
+                from,
+            } => Arc::new(SmtpMailer::new(&host, port, tls, credentials, &from)?),
+        })
+    }
+}
@@ -0,0 +1,93 @@
+use async_trait::async_trait;
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+    message::{Mailbox, header::ContentType},
+    transport::smtp::authentication::Credentials,
+};
+use std::time::Duration;
+
+use crate::domain::{InfrastructureError, MailMessage, MailerPort, UserError};
+
+/// How the SMTP connection is secured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpTls {
+    /// Plain text, for local sinks such as Mailpit or MailHog
+    None,
+    /// Upgrade with STARTTLS, usually on port 587
+    StartTls,
+    /// TLS from the first byte, usually on port 465
+    Tls,
+}
+
+/// SMTP adapter implementing MailerPort.
+/// Opens one connection per message; mail volume here is low.
+#[derive(Clone)]
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    /// `from` is a mailbox such as `Rust Nexus <no-reply@example.com>`;
+    /// one that does not parse is a misconfiguration, reported at startup
+    pub fn new(
+        host: &str,
+        port: u16,
+        tls: SmtpTls,
+        credentials: Option<(String, String)>,
+        from: &str,
+    ) -> Result<Self, UserError> {
+        let from = from
+            .parse::<Mailbox>()
+            .map_err(|e| {
+                UserError::Internal(InfrastructureError::new(
+                    "configure mail sender",
+                    format!("invalid sender '{}': {}", from, e),
+                ))
+            })?;
+
+        let builder = match tls {
+            SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+            SmtpTls::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host).map_err(smtp_error)?,
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host).map_err(smtp_error)?,
+        };
+        let builder = builder.port(port).timeout(Some(Duration::from_secs(10)));
+        let builder = match credentials {
+            Some((username, password)) => builder.credentials(Credentials::new(username, password)),
+            None => builder,
+        };
+
+        Ok(Self {
+            transport: builder.build(),
+            from,
+        })
+    }
+}
+
+fn smtp_error(e: impl std::error::Error + Send + Sync + 'static) -> UserError {
+    UserError::MailUnavailable(InfrastructureError::new("send mail", e))
+}
+
+#[async_trait]
+impl MailerPort for SmtpMailer {
+    async fn send(&self, message: &MailMessage) -> Result<(), UserError> {
+        // The address was validated as an `Email` already, so a failure here
+        // is ours rather than the caller's
+        let to = message.to.as_str().parse::<Mailbox>().map_err(|e| {
+            UserError::Internal(InfrastructureError::new(
+                "address mail",
+                format!("cannot mail '{}': {}", message.to.as_str(), e),
+            ))
+        })?;
+        let email = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(&message.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(message.body.clone())
+            .map_err(|e| UserError::Internal(InfrastructureError::new("build mail", e)))?;
+
+        self.transport.send(email).await.map_err(smtp_error)?;
+        Ok(())
+    }
+}
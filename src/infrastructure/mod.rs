@@ -1,5 +1,15 @@
-pub mod database;
-pub mod web;
-
-pub use database::*;
-pub use web::*;
+pub mod auth;
+pub mod database;
+pub mod events;
+pub mod jobs;
+pub mod mail;
+pub mod web;
+pub mod webhooks;
+
+pub use auth::*;
+pub use database::*;
+pub use events::*;
+pub use jobs::*;
+pub use mail::*;
+pub use web::*;
+pub use webhooks::*;
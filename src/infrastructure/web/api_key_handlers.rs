@@ -0,0 +1,65 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    application::{ApiKeyResponseDto, ApiKeyService, ApiResponse, CreateApiKeyDto, CreatedApiKeyDto},
+    domain::UserRepositoryPort,
+    infrastructure::web::{auth::AuthenticatedUser, error::ApiError},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct ApiKeyListQuery {
+    /// Only keys acting as this user
+    owner_id: Option<Uuid>,
+}
+
+/// Mint a key; the response is the only place its secret appears
+pub async fn create_api_key<R: UserRepositoryPort + 'static>(
+    State(api_keys): State<ApiKeyService<R>>,
+    AuthenticatedUser(principal): AuthenticatedUser,
+    Json(payload): Json<CreateApiKeyDto>,
+) -> Result<(StatusCode, Json<ApiResponse<CreatedApiKeyDto>>), ApiError>
+{
+    match api_keys.create_key(&principal, payload).await {
+        Ok(created) => Ok((
+            StatusCode::CREATED,
+            Json(ApiResponse::success(created)),
+        )),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub async fn list_api_keys<R: UserRepositoryPort + 'static>(
+    State(api_keys): State<ApiKeyService<R>>,
+    AuthenticatedUser(principal): AuthenticatedUser,
+    Query(query): Query<ApiKeyListQuery>,
+) -> Result<(StatusCode, Json<ApiResponse<Vec<ApiKeyResponseDto>>>), ApiError>
+{
+    match api_keys.list_keys(&principal, query.owner_id).await {
+        Ok(keys) => Ok((
+            StatusCode::OK,
+            Json(ApiResponse::success(keys)),
+        )),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub async fn revoke_api_key<R: UserRepositoryPort + 'static>(
+    State(api_keys): State<ApiKeyService<R>>,
+    AuthenticatedUser(principal): AuthenticatedUser,
+    Path(id): Path<Uuid>,
+) -> Result<(StatusCode, Json<ApiResponse<()>>), ApiError>
+{
+    match api_keys.revoke_key(&principal, id).await {
+        Ok(()) => Ok((
+            StatusCode::NO_CONTENT,
+            Json(ApiResponse::success(())),
+        )),
+        Err(err) => Err(err.into()),
+    }
+}
@@ -0,0 +1,218 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use axum::{
+    extract::{FromRef, FromRequestParts, OptionalFromRequestParts, Request, State},
+    http::{HeaderMap, HeaderName, Method, header::AUTHORIZATION, request::Parts},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::{
+    application::ApiKeyService,
+    domain::{AccessTokenPort, Principal, UserError, UserRepositoryPort},
+    infrastructure::web::error::ApiError,
+};
+
+/// Alternative to `Authorization: ApiKey <key>`
+pub const X_API_KEY: HeaderName = HeaderName::from_static("x-api-key");
+
+/// Validates bearer tokens and API keys for the `AuthenticatedUser`
+/// extractor and the `require_authentication` middleware
+#[derive(Clone)]
+pub struct AuthGuard {
+    tokens: Arc<dyn AccessTokenPort>,
+    api_keys: Arc<dyn ApiKeyAuthenticator>,
+    public_routes: Arc<Vec<PublicRoute>>,
+}
+
+/// Resolves an API key to the principal it acts as.
+/// Lets the guard stay independent of the user repository type.
+#[async_trait]
+pub trait ApiKeyAuthenticator: Send + Sync {
+    async fn authenticate(&self, key: &str) -> Result<Principal, UserError>;
+}
+
+#[async_trait]
+impl<R: UserRepositoryPort + 'static> ApiKeyAuthenticator for ApiKeyService<R> {
+    async fn authenticate(&self, key: &str) -> Result<Principal, UserError> {
+        ApiKeyService::authenticate(self, key).await
+    }
+}
+
+/// One `METHOD /path` entry of the public route list
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PublicRoute {
+    /// `None` matches any method
+    method: Option<Method>,
+    path: String,
+    /// Whether `path` is a prefix (written with a trailing `*`)
+    prefix: bool,
+}
+
+/// Caller identified by a valid access token
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser(pub Principal);
+
+impl AuthGuard {
+    /// `public_routes` entries are `METHOD /path` or `/path`; a trailing `*`
+    /// matches any suffix. Unparseable entries are ignored with a warning.
+    pub fn new(
+        tokens: Arc<dyn AccessTokenPort>,
+        api_keys: Arc<dyn ApiKeyAuthenticator>,
+        public_routes: &[String],
+    ) -> Self {
+        let public_routes = public_routes
+            .iter()
+            .filter_map(|entry| {
+                let parsed = PublicRoute::parse(entry);
+                if parsed.is_none() {
+                    tracing::warn!(entry = %entry, "Ignoring malformed public route");
+                }
+                parsed
+            })
+            .collect();
+
+        Self {
+            tokens,
+            api_keys,
+            public_routes: Arc::new(public_routes),
+        }
+    }
+
+    fn is_public(&self, method: &Method, path: &str) -> bool {
+        self.public_routes.iter().any(|route| route.matches(method, path))
+    }
+
+    /// Principal behind the request's API key or `Authorization: Bearer` header
+    pub(crate) async fn authenticate(&self, headers: &HeaderMap) -> Result<Principal, UserError> {
+        self.authenticate_credentials(bearer_token(headers), api_key(headers)).await
+    }
+
+    /// Principal behind an API key or else an access token, however they
+    /// were sent (a WebSocket client sends them in a message)
+    pub(crate) async fn authenticate_credentials(
+        &self,
+        token: Option<&str>,
+        key: Option<&str>,
+    ) -> Result<Principal, UserError> {
+        if let Some(key) = key {
+            return self.api_keys.authenticate(key).await;
+        }
+        let token = token.ok_or_else(|| UserError::Unauthenticated("missing bearer token or API key".to_string()))?;
+        self.tokens.verify(token)
+    }
+}
+
+impl PublicRoute {
+    fn parse(entry: &str) -> Option<Self> {
+        let (method, path) = match entry.trim().split_once(char::is_whitespace) {
+            Some((method, path)) => (Some(method.to_ascii_uppercase().parse().ok()?), path.trim()),
+            None => (None, entry.trim()),
+        };
+        if !path.starts_with('/') {
+            return None;
+        }
+        let (path, prefix) = match path.strip_suffix('*') {
+            Some(prefix) => (prefix, true),
+            None => (path, false),
+        };
+
+        Some(Self {
+            method,
+            path: path.to_string(),
+            prefix,
+        })
+    }
+
+    fn matches(&self, method: &Method, path: &str) -> bool {
+        self.method.as_ref().is_none_or(|m| m == method)
+            && if self.prefix {
+                path.starts_with(&self.path)
+            } else {
+                path == self.path
+            }
+    }
+}
+
+/// Token from `Authorization: Bearer <token>`
+pub(crate) fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    authorization(headers, "bearer")
+}
+
+/// Key from `Authorization: ApiKey <key>` or `X-Api-Key: <key>`
+pub(crate) fn api_key(headers: &HeaderMap) -> Option<&str> {
+    authorization(headers, "apikey").or_else(|| {
+        let key = headers.get(X_API_KEY)?.to_str().ok()?.trim();
+        (!key.is_empty()).then_some(key)
+    })
+}
+
+/// Credentials of an `Authorization` header with the given scheme
+fn authorization<'h>(headers: &'h HeaderMap, scheme: &str) -> Option<&'h str> {
+    let value = headers.get(AUTHORIZATION)?.to_str().ok()?;
+    let (given, credentials) = value.split_once(' ')?;
+    given.eq_ignore_ascii_case(scheme).then(|| credentials.trim())
+}
+
+/// Require a valid access token or API key everywhere except the public routes.
+/// The principal is stored in the request extensions for `AuthenticatedUser`;
+/// public routes still pick it up when a valid token is sent. A principal an
+/// outer layer already resolved (see `rate_limit`) is reused as is.
+pub async fn require_authentication(
+    State(guard): State<AuthGuard>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    if request.extensions().get::<Principal>().is_some() {
+        return Ok(next.run(request).await);
+    }
+    let authenticated = guard.authenticate(request.headers()).await;
+    match authenticated {
+        Ok(principal) => {
+            request.extensions_mut().insert(principal);
+        }
+        Err(err) if !guard.is_public(request.method(), request.uri().path()) => return Err(err.into()),
+        Err(_) => {}
+    }
+
+    Ok(next.run(request).await)
+}
+
+impl<S> FromRequestParts<S> for AuthenticatedUser
+where
+    S: Send + Sync,
+    AuthGuard: FromRef<S>,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(principal) = parts.extensions.get::<Principal>() {
+            return Ok(Self(principal.clone()));
+        }
+        AuthGuard::from_ref(state)
+            .authenticate(&parts.headers)
+            .await
+            .map(Self)
+            .map_err(ApiError::from)
+    }
+}
+
+/// `Option<AuthenticatedUser>` is `None` only when no credentials were sent;
+/// an invalid token or key is still rejected
+impl<S> OptionalFromRequestParts<S> for AuthenticatedUser
+where
+    S: Send + Sync,
+    AuthGuard: FromRef<S>,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Option<Self>, Self::Rejection> {
+        if parts.extensions.get::<Principal>().is_none()
+            && bearer_token(&parts.headers).is_none()
+            && api_key(&parts.headers).is_none() {
+            return Ok(None);
+        }
+        <Self as FromRequestParts<S>>::from_request_parts(parts, state).await.map(Some)
+    }
+}
@@ -0,0 +1,157 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        ApiResponse, AuthService, ConfirmEmailDto, EmailVerificationService, LoginDto, PasswordResetConfirmDto,
+        PasswordResetRequestDto, PasswordResetService, RefreshTokenDto, TokenResponseDto, UserApplicationService,
+        UserResponseDto, VerificationSentDto,
+    },
+    domain::{UserError, UserRepositoryPort},
+    infrastructure::web::{auth::AuthenticatedUser, error::ApiError, request_id::RequestId},
+};
+
+pub async fn login<R: UserRepositoryPort + 'static>(
+    State(auth_service): State<AuthService<R>>,
+    Json(payload): Json<LoginDto>,
+) -> Result<(StatusCode, Json<ApiResponse<TokenResponseDto>>), ApiError>
+{
+    match auth_service.login(payload).await {
+        Ok(token) => Ok((
+            StatusCode::OK,
+            Json(ApiResponse::success(token)),
+        )),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Exchange a refresh token for a new access and refresh token
+pub async fn refresh<R: UserRepositoryPort + 'static>(
+    State(auth_service): State<AuthService<R>>,
+    Json(payload): Json<RefreshTokenDto>,
+) -> Result<(StatusCode, Json<ApiResponse<TokenResponseDto>>), ApiError>
+{
+    match auth_service.refresh(payload).await {
+        Ok(token) => Ok((
+            StatusCode::OK,
+            Json(ApiResponse::success(token)),
+        )),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// End the session of a refresh token
+pub async fn logout<R: UserRepositoryPort + 'static>(
+    State(auth_service): State<AuthService<R>>,
+    Json(payload): Json<RefreshTokenDto>,
+) -> Result<(StatusCode, Json<ApiResponse<()>>), ApiError>
+{
+    match auth_service.logout(payload).await {
+        Ok(()) => Ok((
+            StatusCode::NO_CONTENT,
+            Json(ApiResponse::success(())),
+        )),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Sign a user out on every device
+pub async fn revoke_user_sessions<R: UserRepositoryPort + 'static>(
+    State(app_service): State<UserApplicationService<R>>,
+    AuthenticatedUser(principal): AuthenticatedUser,
+    Path(id): Path<Uuid>,
+) -> Result<(StatusCode, Json<ApiResponse<()>>), ApiError>
+{
+    match app_service.revoke_sessions(&principal, id).await {
+        Ok(_) => Ok((
+            StatusCode::NO_CONTENT,
+            Json(ApiResponse::success(())),
+        )),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Mail a verification token to the user's address
+pub async fn send_email_verification<R: UserRepositoryPort + 'static>(
+    State(app_service): State<UserApplicationService<R>>,
+    AuthenticatedUser(principal): AuthenticatedUser,
+    Path(id): Path<Uuid>,
+) -> Result<(StatusCode, Json<ApiResponse<VerificationSentDto>>), ApiError>
+{
+    match app_service.send_email_verification(&principal, id).await {
+        Ok(sent) => Ok((
+            StatusCode::ACCEPTED,
+            Json(ApiResponse::success(sent)),
+        )),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Confirm an address with a mailed token
+pub async fn confirm_email<R: UserRepositoryPort + 'static>(
+    State(email_verification): State<EmailVerificationService<R>>,
+    request_id: RequestId,
+    Json(payload): Json<ConfirmEmailDto>,
+) -> Result<(StatusCode, Json<ApiResponse<UserResponseDto>>), ApiError>
+{
+    match email_verification.confirm(payload, Some(request_id.as_str())).await {
+        Ok(user) => Ok((
+            StatusCode::OK,
+            Json(ApiResponse::success(user)),
+        )),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Mail a password reset token if the address belongs to a user.
+/// Always answers 202 straight away: the lookup and mail run in the
+/// background, so neither the status nor the timing reveals whether the
+/// address is registered.
+pub async fn request_password_reset<R: UserRepositoryPort + 'static>(
+    State(password_reset): State<PasswordResetService<R>>,
+    Json(payload): Json<PasswordResetRequestDto>,
+) -> (StatusCode, Json<ApiResponse<()>>)
+{
+    tokio::spawn(async move {
+        if let Err(err) = password_reset.request_reset(payload).await {
+            tracing::warn!(error = ?err, code = err.code(), "Password reset request failed");
+        }
+    });
+    (StatusCode::ACCEPTED, Json(ApiResponse::success(())))
+}
+
+/// Set a new password with a mailed token
+pub async fn confirm_password_reset<R: UserRepositoryPort + 'static>(
+    State(password_reset): State<PasswordResetService<R>>,
+    Json(payload): Json<PasswordResetConfirmDto>,
+) -> Result<(StatusCode, Json<ApiResponse<()>>), ApiError>
+{
+    match password_reset.confirm_reset(payload).await {
+        Ok(()) => Ok((
+            StatusCode::NO_CONTENT,
+            Json(ApiResponse::success(())),
+        )),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// The user behind the access token
+pub async fn current_user<R: UserRepositoryPort + 'static>(
+    State(app_service): State<UserApplicationService<R>>,
+    AuthenticatedUser(principal): AuthenticatedUser,
+) -> Result<(StatusCode, Json<ApiResponse<UserResponseDto>>), ApiError>
+{
+    match app_service.get_current_user(&principal).await {
+        Ok(Some(user)) => Ok((
+            StatusCode::OK,
+            Json(ApiResponse::success(user)),
+        )),
+        // A token outliving its user no longer identifies anyone
+        Ok(None) => Err(UserError::Unauthenticated("user no longer exists".to_string()).into()),
+        Err(err) => Err(err.into()),
+    }
+}
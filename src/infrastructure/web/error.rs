@@ -0,0 +1,124 @@
+use axum::{
+    http::{StatusCode, header::{RETRY_AFTER, WWW_AUTHENTICATE}},
+    response::{IntoResponse, Json, Response},
+};
+
+use crate::{
+    application::{ApiResponse, UserResponseDto},
+    domain::UserError,
+};
+
+/// Seconds clients should wait before retrying a transient failure
+const RETRY_AFTER_SECS: &str = "1";
+
+/// HTTP representation of a domain error
+#[derive(Debug)]
+pub struct ApiError(pub UserError);
+
+impl From<UserError> for ApiError {
+    fn from(err: UserError) -> Self {
+        Self(err)
+    }
+}
+
+impl ApiError {
+    pub(crate) fn status(&self) -> StatusCode {
+        match self.0 {
+            UserError::NotFound
+            | UserError::ApiKeyNotFound
+            | UserError::WebhookNotFound
+            | UserError::WebhookDeliveryNotFound => StatusCode::NOT_FOUND,
+            UserError::EmailAlreadyExists | UserError::EmailAlreadyVerified => StatusCode::CONFLICT,
+            UserError::InvalidName(_)
+            | UserError::InvalidEmail(_)
+            | UserError::InvalidPagination(_)
+            | UserError::InvalidQuery(_)
+            | UserError::InvalidRole(_)
+            | UserError::InvalidApiKey(_)
+            | UserError::InvalidWebhook(_)
+            | UserError::InvalidMessage(_)
+            | UserError::InvalidPassword(_)
+            | UserError::InvalidToken(_)
+            | UserError::InvalidIdempotencyKey(_) => StatusCode::BAD_REQUEST,
+            UserError::InvalidCredentials | UserError::Unauthenticated(_) => StatusCode::UNAUTHORIZED,
+            UserError::Forbidden(_) => StatusCode::FORBIDDEN,
+            UserError::InvalidPatch(_) | UserError::IdempotencyKeyReused => StatusCode::UNPROCESSABLE_ENTITY,
+            UserError::IdempotencyKeyInProgress => StatusCode::CONFLICT,
+            UserError::UnsupportedPatchFormat(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            UserError::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
+            UserError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            UserError::Unavailable(_)
+            | UserError::Timeout(_)
+            | UserError::MailUnavailable(_)
+            | UserError::WebhookUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            UserError::Conflict(_) | UserError::ConstraintViolation(_) => StatusCode::CONFLICT,
+            UserError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Client-facing message; infrastructure details stay in the logs
+    pub(crate) fn message(&self) -> String {
+        match &self.0 {
+            UserError::NotFound => "User not found".to_string(),
+            UserError::EmailAlreadyExists => "Email already exists".to_string(),
+            UserError::InvalidName(msg) => format!("Invalid name: {}", msg),
+            UserError::InvalidEmail(msg) => format!("Invalid email: {}", msg),
+            UserError::InvalidPagination(msg) => format!("Invalid pagination: {}", msg),
+            UserError::InvalidQuery(msg) => format!("Invalid query: {}", msg),
+            UserError::InvalidRole(msg) => format!("Invalid role: {}", msg),
+            UserError::InvalidApiKey(msg) => format!("Invalid API key: {}", msg),
+            UserError::InvalidPatch(msg) => format!("Invalid patch: {}", msg),
+            UserError::UnsupportedPatchFormat(msg) => format!("Unsupported patch format: {}", msg),
+            UserError::InvalidMessage(msg) => format!("Invalid message: {}", msg),
+            UserError::PreconditionFailed(msg) => format!("Precondition failed: {}", msg),
+            UserError::InvalidPassword(msg) => format!("Invalid password: {}", msg),
+            UserError::InvalidCredentials => "Invalid email or password".to_string(),
+            UserError::Unauthenticated(msg) => format!("Authentication required: {}", msg),
+            UserError::Forbidden(msg) => format!("Forbidden: {}", msg),
+            UserError::ApiKeyNotFound => "API key not found".to_string(),
+            UserError::InvalidWebhook(msg) => format!("Invalid webhook: {}", msg),
+            UserError::WebhookNotFound => "Webhook not found".to_string(),
+            UserError::WebhookDeliveryNotFound => "Webhook delivery not found".to_string(),
+            UserError::InvalidToken(msg) => format!("Invalid token: {}", msg),
+            UserError::EmailAlreadyVerified => "Email already verified".to_string(),
+            UserError::InvalidIdempotencyKey(msg) => format!("Invalid idempotency key: {}", msg),
+            UserError::IdempotencyKeyReused => {
+                "Idempotency key was already used for a different request".to_string()
+            }
+            UserError::IdempotencyKeyInProgress => {
+                "A request with this idempotency key is still in progress".to_string()
+            }
+            UserError::RateLimited { retry_after_secs } => {
+                format!("Too many requests, retry in {} seconds", retry_after_secs)
+            }
+            UserError::MailUnavailable(_) => "Mail delivery temporarily unavailable".to_string(),
+            UserError::WebhookUnavailable(_) => "Webhook receiver unreachable".to_string(),
+            UserError::Unavailable(_) => "Service temporarily unavailable".to_string(),
+            UserError::Timeout(_) => "Storage operation timed out".to_string(),
+            UserError::Conflict(_) => "Conflicting concurrent modification".to_string(),
+            UserError::ConstraintViolation(_) => "Request violates a data constraint".to_string(),
+            UserError::Internal(_) => "Internal server error".to_string(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        if status.is_server_error() {
+            tracing::error!(error = ?self.0, code = self.0.code(), "Request failed");
+        }
+
+        let body = Json(ApiResponse::<UserResponseDto>::from_user_error(&self.0, self.message()));
+        match (status, &self.0) {
+            (_, UserError::RateLimited { retry_after_secs }) => {
+                (status, [(RETRY_AFTER, retry_after_secs.to_string())], body).into_response()
+            }
+            (StatusCode::SERVICE_UNAVAILABLE, _) => {
+                (status, [(RETRY_AFTER, RETRY_AFTER_SECS)], body).into_response()
+            }
+            (StatusCode::UNAUTHORIZED, _) => (status, [(WWW_AUTHENTICATE, "Bearer")], body).into_response(),
+            _ => (status, body).into_response(),
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use axum::http::{HeaderMap, header::IF_MATCH};
+
+use crate::domain::UserError;
+
+/// Strong entity tag for a user version
+pub fn etag(version: i64) -> String {
+    format!("\"{}\"", version)
+}
+
+/// Version required by `If-Match`, if any.
+/// `*` only requires the user to exist, which every handler checks anyway.
+/// Tags use strong comparison, so weak (`W/`) or foreign tags never match.
+pub fn if_match_version(headers: &HeaderMap) -> Result<Option<i64>, UserError> {
+    let Some(value) = headers.get(IF_MATCH) else {
+        return Ok(None);
+    };
+    let value = value.to_str().unwrap_or_default().trim();
+    if value == "*" {
+        return Ok(None);
+    }
+
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .and_then(|v| v.parse::<i64>().ok())
+        .map(Some)
+        .ok_or_else(|| {
+            UserError::PreconditionFailed(format!("If-Match {} does not match the current version", value))
+        })
+}
@@ -0,0 +1,158 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, HeaderName},
+    response::sse::{Event, KeepAlive, Sse},
+};
+use chrono::Utc;
+use futures_util::stream::{self, Stream, StreamExt};
+use tokio::{sync::broadcast::error::RecvError, time::Instant};
+use uuid::Uuid;
+
+use crate::{
+    application::{EventStreamFilterDto, UserApplicationService, UserEventDto},
+    domain::{Principal, UserError, UserEvent, UserRepositoryPort},
+    infrastructure::{
+        events::{Resume, UserEventStream},
+        web::{
+            auth::{AuthGuard, AuthenticatedUser, api_key, bearer_token},
+            error::ApiError,
+        },
+    },
+};
+
+/// Header a reconnecting `EventSource` sends with the id of the last event it got
+pub const LAST_EVENT_ID: HeaderName = HeaderName::from_static("last-event-id");
+/// Type of the event telling a client it may have missed changes and
+/// should reload what it shows
+pub const RESET_EVENT: &str = "reset";
+
+/// Stream user changes as Server-Sent Events, optionally of one user only.
+/// Each event has the event id as `id`, the event type as `event` and a
+/// `UserEventDto` as `data`. With `Last-Event-ID` the stream resumes after
+/// that event, or starts with a `reset` event when it is no longer buffered.
+/// The stream ends when the caller's credentials expire, or at the first
+/// heartbeat after they no longer allow watching.
+pub async fn user_events<R: UserRepositoryPort + 'static>(
+    State(users): State<UserApplicationService<R>>,
+    State(stream): State<UserEventStream>,
+    State(guard): State<AuthGuard>,
+    AuthenticatedUser(principal): AuthenticatedUser,
+    Query(filter): Query<EventStreamFilterDto>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError>
+{
+    users.authorize_watch(&principal, filter.user_id)?;
+
+    let last_event_id = headers.get(LAST_EVENT_ID).map(|value| {
+        // An id we never sent is treated like one that left the buffer
+        value
+            .to_str()
+            .ok()
+            .and_then(|value| Uuid::parse_str(value.trim()).ok())
+            .unwrap_or_else(Uuid::nil)
+    });
+    let (resume, live) = stream.subscribe(last_event_id);
+
+    let user_id = filter.user_id;
+    let wanted = move |event: &UserEvent| user_id.is_none_or(|id| event.user_id().as_uuid() == id);
+    let first: Vec<Event> = match resume {
+        Resume::Replay(events) => events.iter().filter(|event| wanted(event)).map(user_event).collect(),
+        Resume::Gap(newest) => vec![reset_event(newest)],
+    };
+    let watcher = Watcher {
+        users,
+        guard,
+        token: bearer_token(&headers).map(str::to_string),
+        api_key: api_key(&headers).map(str::to_string),
+        expires_at: principal.expires_at.map(|at| Instant::now() + (at - Utc::now()).to_std().unwrap_or_default()),
+        principal,
+        user_id,
+    };
+    let heartbeat = stream.heartbeat();
+    let checks = tokio::time::interval_at(Instant::now() + heartbeat, heartbeat);
+    let live = stream::unfold((live, watcher, checks), move |(mut live, watcher, mut checks)| async move {
+        loop {
+            tokio::select! {
+                event = live.recv() => match event {
+                    Ok(event) if wanted(&event) => return Some((user_event(&event), (live, watcher, checks))),
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!(skipped, "User event stream fell behind");
+                        return Some((reset_event(None), (live, watcher, checks)));
+                    }
+                    Err(RecvError::Closed) => return None,
+                },
+                _ = checks.tick() => match watcher.confirm().await {
+                    Ok(()) => {}
+                    Err(err @ (UserError::Unauthenticated(_) | UserError::Forbidden(_))) => {
+                        tracing::debug!(error = %err, "Ending user event stream");
+                        return None;
+                    }
+                    Err(err) => tracing::warn!(error = %err, "Could not check event stream credentials again"),
+                },
+                () = until(watcher.expires_at) => return None,
+            }
+        }
+    });
+
+    let events = stream::iter(first).chain(live).map(Ok);
+    Ok(Sse::new(events).keep_alive(KeepAlive::new().interval(stream.heartbeat())))
+}
+
+/// Who an open stream is for, and how they authenticated
+struct Watcher<R: UserRepositoryPort> {
+    users: UserApplicationService<R>,
+    guard: AuthGuard,
+    token: Option<String>,
+    api_key: Option<String>,
+    expires_at: Option<Instant>,
+    principal: Principal,
+    user_id: Option<Uuid>,
+}
+
+impl<R: UserRepositoryPort + 'static> Watcher<R> {
+    /// Check again that the credentials are still accepted, still stand for
+    /// the principal and still let it watch: keys and sessions may have been
+    /// revoked, and the user deleted or given another role since
+    async fn confirm(&self) -> Result<(), UserError> {
+        let current = self
+            .guard
+            .authenticate_credentials(self.token.as_deref(), self.api_key.as_deref())
+            .await?;
+        if current.user_id != self.principal.user_id
+            || current.role != self.principal.role
+            || current.scopes != self.principal.scopes
+        {
+            return Err(UserError::Unauthenticated("the credentials are no longer valid".to_string()));
+        }
+        self.users.confirm_principal(&self.principal).await?;
+        self.users.authorize_watch(&self.principal, self.user_id)
+    }
+}
+
+/// Resolves at `deadline`, or never without one
+async fn until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+fn user_event(event: &UserEvent) -> Event {
+    Event::default()
+        .id(event.id().to_string())
+        .event(event.event_type())
+        .json_data(UserEventDto::from(event))
+        .expect("user events serialize to JSON")
+}
+
+/// `newest`, when known, is where the client can resume once it has reloaded
+fn reset_event(newest: Option<Uuid>) -> Event {
+    let event = Event::default().event(RESET_EVENT).data("{}");
+    match newest {
+        Some(id) => event.id(id.to_string()),
+        None => event,
+    }
+}
@@ -1,106 +1,230 @@
-use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::Json,
-};
-use serde::Deserialize;
-use uuid::Uuid;
-
-use crate::{
-    application::{UserApplicationService, CreateUserDto, UpdateUserDto, UserResponseDto, ApiResponse},
-    domain::{UserError},
-    infrastructure::PostgresUserRepository,
-};
-
-#[derive(Debug, Deserialize)]
-pub struct PaginationQuery {
-    page: Option<i64>,
-    limit: Option<i64>,
-}
-
-// Concrete handlers for PostgresUserRepository
-pub async fn create_user_concrete(
-    State(app_service): State<UserApplicationService<PostgresUserRepository>>,
-    Json(payload): Json<CreateUserDto>,
-) -> Result<(StatusCode, Json<ApiResponse<UserResponseDto>>), (StatusCode, Json<ApiResponse<UserResponseDto>>)>
-{
-    match app_service.create_user(payload).await {
-        Ok(user) => Ok((
-            StatusCode::CREATED,
-            Json(ApiResponse::success(user)),
-        )),
-        Err(err) => Err(handle_user_error(err)),
-    }
-}
-
-pub async fn get_user_concrete(
-    State(app_service): State<UserApplicationService<PostgresUserRepository>>,
-    Path(id): Path<Uuid>,
-) -> Result<(StatusCode, Json<ApiResponse<UserResponseDto>>), (StatusCode, Json<ApiResponse<UserResponseDto>>)>
-{
-    match app_service.get_user_by_id(id).await {
-        Ok(Some(user)) => Ok((
-            StatusCode::OK,
-            Json(ApiResponse::success(user)),
-        )),
-        Ok(None) => Err((
-            StatusCode::NOT_FOUND,
-            Json(ApiResponse::<UserResponseDto>::error("User not found".to_string())),
-        )),
-        Err(err) => Err(handle_user_error(err)),
-    }
-}
-
-pub async fn update_user_concrete(
-    State(app_service): State<UserApplicationService<PostgresUserRepository>>,
-    Path(id): Path<Uuid>,
-    Json(payload): Json<UpdateUserDto>,
-) -> Result<(StatusCode, Json<ApiResponse<UserResponseDto>>), (StatusCode, Json<ApiResponse<UserResponseDto>>)>
-{
-    match app_service.update_user(id, payload).await {
-        Ok(user) => Ok((
-            StatusCode::OK,
-            Json(ApiResponse::success(user)),
-        )),
-        Err(err) => Err(handle_user_error(err)),
-    }
-}
-
-pub async fn delete_user_concrete(
-    State(app_service): State<UserApplicationService<PostgresUserRepository>>,
-    Path(id): Path<Uuid>,
-) -> Result<(StatusCode, Json<ApiResponse<()>>), (StatusCode, Json<ApiResponse<UserResponseDto>>)>
-{
-    match app_service.delete_user(id).await {
-        Ok(()) => Ok((
-            StatusCode::NO_CONTENT,
-            Json(ApiResponse::success(())),
-        )),
-        Err(err) => Err(handle_user_error(err)),
-    }
-}
-
-pub async fn get_users_concrete(
-    State(app_service): State<UserApplicationService<PostgresUserRepository>>,
-    Query(pagination): Query<PaginationQuery>,
-) -> Result<(StatusCode, Json<ApiResponse<Vec<UserResponseDto>>>), (StatusCode, Json<ApiResponse<UserResponseDto>>)>
-{
-    match app_service.get_all_users(pagination.page, pagination.limit).await {
-        Ok(users) => Ok((
-            StatusCode::OK,
-            Json(ApiResponse::success(users)),
-        )),
-        Err(err) => Err(handle_user_error(err)),
-    }
-}
-
-fn handle_user_error(err: UserError) -> (StatusCode, Json<ApiResponse<UserResponseDto>>) {
-    let (status, message) = match err {
-        UserError::NotFound => (StatusCode::NOT_FOUND, "User not found".to_string()),
-        UserError::EmailAlreadyExists => (StatusCode::CONFLICT, "Email already exists".to_string()),
-        UserError::InvalidName(msg) => (StatusCode::BAD_REQUEST, format!("Invalid name: {}", msg)),
-        UserError::InvalidEmail(msg) => (StatusCode::BAD_REQUEST, format!("Invalid email: {}", msg)),
-    };
-    
-    (status, Json(ApiResponse::<UserResponseDto>::error(message)))
-}
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, RawQuery, State},
+    http::{HeaderMap, HeaderName, StatusCode, header::{CONTENT_TYPE, ETAG}},
+    response::{IntoResponse, Json, Response},
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        UserApplicationService, AuditEntryDto, AuditFilterDto, CreateUserDto, UpdateUserDto, UserFilterDto,
+        UserPatchDto, UserResponseDto, ApiResponse, IdempotencyService,
+    },
+    domain::{UserError, UserRepositoryPort},
+    infrastructure::web::{
+        auth::AuthenticatedUser,
+        error::ApiError,
+        etag::{etag, if_match_version},
+        idempotency::{idempotency_key, run_idempotently},
+        request_id::RequestId,
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct PaginationQuery {
+    page: Option<i64>,
+    limit: Option<i64>,
+    /// Opaque keyset cursor from a previous page's `next_cursor`
+    after: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PageQuery {
+    pub(crate) page: Option<i64>,
+    pub(crate) limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UserLookupQuery {
+    /// Also return a soft-deleted user
+    include_deleted: Option<bool>,
+}
+
+/// A single user, tagged with its version for conditional requests
+type UserReply = (StatusCode, [(HeaderName, String); 1], Json<ApiResponse<UserResponseDto>>);
+
+fn user_reply(status: StatusCode, user: UserResponseDto) -> UserReply {
+    (status, [(ETAG, etag(user.version))], Json(ApiResponse::success(user)))
+}
+
+// Handlers are generic over the repository adapter, so the same HTTP
+// surface can be served by any UserRepositoryPort implementation.
+// Authorization is left to the application service; handlers only pass the caller on.
+/// With an `Idempotency-Key`, retries replay the first response instead of
+/// creating the user again.
+pub async fn create_user<R: UserRepositoryPort + 'static>(
+    State(app_service): State<UserApplicationService<R>>,
+    State(idempotency): State<IdempotencyService>,
+    caller: Option<AuthenticatedUser>,
+    request_id: RequestId,
+    headers: HeaderMap,
+    Json(payload): Json<CreateUserDto>,
+) -> Result<Response, ApiError>
+{
+    let actor = caller.as_ref().map(|AuthenticatedUser(principal)| principal);
+    let create = async |payload| match app_service.create_user(actor, payload, Some(request_id.as_str())).await {
+        Ok(user) => user_reply(StatusCode::CREATED, user).into_response(),
+        Err(err) => ApiError::from(err).into_response(),
+    };
+
+    let Some(key) = idempotency_key(&headers)? else {
+        return Ok(create(payload).await);
+    };
+    let fingerprint = IdempotencyService::fingerprint(&payload.fingerprint_fields())?;
+    let scope = IdempotencyService::scope(actor);
+    run_idempotently(&idempotency, &scope, key, &fingerprint, create(payload)).await
+}
+
+pub async fn get_user<R: UserRepositoryPort + 'static>(
+    State(app_service): State<UserApplicationService<R>>,
+    AuthenticatedUser(principal): AuthenticatedUser,
+    Path(id): Path<Uuid>,
+    Query(lookup): Query<UserLookupQuery>,
+) -> Result<UserReply, ApiError>
+{
+    match app_service.get_user_by_id(&principal, id, lookup.include_deleted.unwrap_or(false)).await {
+        Ok(Some(user)) => Ok(user_reply(StatusCode::OK, user)),
+        Ok(None) => Err(UserError::NotFound.into()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// `PUT` replaces every writable field: `name`, `email` and `role` are required
+pub async fn update_user<R: UserRepositoryPort + 'static>(
+    State(app_service): State<UserApplicationService<R>>,
+    AuthenticatedUser(principal): AuthenticatedUser,
+    Path(id): Path<Uuid>,
+    request_id: RequestId,
+    headers: HeaderMap,
+    Json(payload): Json<UpdateUserDto>,
+) -> Result<UserReply, ApiError>
+{
+    let expected_version = if_match_version(&headers)?;
+    match app_service.update_user(&principal, id, payload, expected_version, Some(request_id.as_str())).await {
+        Ok(user) => Ok(user_reply(StatusCode::OK, user)),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// `PATCH` takes the raw body, since the format depends on the content type.
+/// No writable field is optional, so a merge patch `null` or a JSON Patch
+/// `remove` on one is rejected rather than clearing it.
+pub async fn patch_user<R: UserRepositoryPort + 'static>(
+    State(app_service): State<UserApplicationService<R>>,
+    AuthenticatedUser(principal): AuthenticatedUser,
+    Path(id): Path<Uuid>,
+    request_id: RequestId,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<UserReply, ApiError>
+{
+    let expected_version = if_match_version(&headers)?;
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    let patch = UserPatchDto::parse(content_type, &body)?;
+
+    match app_service.patch_user(&principal, id, patch, expected_version, Some(request_id.as_str())).await {
+        Ok(user) => Ok(user_reply(StatusCode::OK, user)),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub async fn delete_user<R: UserRepositoryPort + 'static>(
+    State(app_service): State<UserApplicationService<R>>,
+    AuthenticatedUser(principal): AuthenticatedUser,
+    Path(id): Path<Uuid>,
+    request_id: RequestId,
+    headers: HeaderMap,
+) -> Result<(StatusCode, Json<ApiResponse<()>>), ApiError>
+{
+    let expected_version = if_match_version(&headers)?;
+    match app_service.delete_user(&principal, id, expected_version, Some(request_id.as_str())).await {
+        Ok(()) => Ok((
+            StatusCode::NO_CONTENT,
+            Json(ApiResponse::success(())),
+        )),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub async fn restore_user<R: UserRepositoryPort + 'static>(
+    State(app_service): State<UserApplicationService<R>>,
+    AuthenticatedUser(principal): AuthenticatedUser,
+    Path(id): Path<Uuid>,
+    request_id: RequestId,
+) -> Result<UserReply, ApiError>
+{
+    match app_service.restore_user(&principal, id, Some(request_id.as_str())).await {
+        Ok(user) => Ok(user_reply(StatusCode::OK, user)),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub async fn get_users<R: UserRepositoryPort + 'static>(
+    State(app_service): State<UserApplicationService<R>>,
+    AuthenticatedUser(principal): AuthenticatedUser,
+    Query(pagination): Query<PaginationQuery>,
+    Query(filter): Query<UserFilterDto>,
+    RawQuery(raw_query): RawQuery,
+) -> Result<(StatusCode, Json<ApiResponse<Vec<UserResponseDto>>>), ApiError>
+{
+    match app_service.get_all_users(&principal, filter, pagination.page, pagination.limit, pagination.after).await {
+        Ok(page) => {
+            let carried = carried_query(raw_query.as_deref().unwrap_or_default());
+            Ok((
+                StatusCode::OK,
+                Json(ApiResponse::paginated(page.users, page.pagination.with_links("/api/users", &carried))),
+            ))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Changes to one user, newest first
+pub async fn get_user_history<R: UserRepositoryPort + 'static>(
+    State(app_service): State<UserApplicationService<R>>,
+    AuthenticatedUser(principal): AuthenticatedUser,
+    Path(id): Path<Uuid>,
+    Query(pagination): Query<PageQuery>,
+) -> Result<(StatusCode, Json<ApiResponse<Vec<AuditEntryDto>>>), ApiError>
+{
+    let page = app_service.get_user_history(&principal, id, pagination.page, pagination.limit).await?;
+    let path = format!("/api/users/{}/history", id);
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::paginated(page.entries, page.pagination.with_links(&path, ""))),
+    ))
+}
+
+/// Audit log across all users, filtered by the query string
+pub async fn get_audit_log<R: UserRepositoryPort + 'static>(
+    State(app_service): State<UserApplicationService<R>>,
+    AuthenticatedUser(principal): AuthenticatedUser,
+    Query(pagination): Query<PageQuery>,
+    Query(filter): Query<AuditFilterDto>,
+    RawQuery(raw_query): RawQuery,
+) -> Result<(StatusCode, Json<ApiResponse<Vec<AuditEntryDto>>>), ApiError>
+{
+    let page = app_service.query_audit_log(&principal, filter, pagination.page, pagination.limit).await?;
+    let carried = carried_query(raw_query.as_deref().unwrap_or_default());
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::paginated(page.entries, page.pagination.with_links("/api/audit", &carried))),
+    ))
+}
+
+/// Query string without the pagination parameters, so links keep filters and sort
+pub(crate) fn carried_query(raw_query: &str) -> String {
+    raw_query
+        .split('&')
+        .filter(|pair| {
+            let key = pair.split('=').next().unwrap_or_default();
+            !pair.is_empty() && !matches!(key, "page" | "limit" | "after")
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
@@ -0,0 +1,110 @@
+use std::future::Future;
+
+use axum::{
+    body::{Body, to_bytes},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header::{CONTENT_TYPE, ETAG, LOCATION}},
+    response::{IntoResponse, Response},
+};
+use serde_json::Value;
+
+use crate::{
+    application::{IdempotencyService, IdempotentStart},
+    domain::{InfrastructureError, StoredResponse, UserError},
+    infrastructure::web::error::ApiError,
+};
+
+/// Client-chosen key that makes retries of a request safe
+pub const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+/// Set to `true` on responses replayed for a repeated key
+pub const IDEMPOTENT_REPLAYED: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+/// Headers kept with a response and sent again on replay
+const REPLAYED_HEADERS: [HeaderName; 3] = [CONTENT_TYPE, ETAG, LOCATION];
+
+/// Largest response body kept for replays
+const MAX_STORED_BODY: usize = 1024 * 1024;
+
+/// The request's `Idempotency-Key`, if it sent one
+pub fn idempotency_key(headers: &HeaderMap) -> Result<Option<&str>, UserError> {
+    headers
+        .get(IDEMPOTENCY_KEY)
+        .map(|value| {
+            value
+                .to_str()
+                .map(str::trim)
+                .map_err(|_| UserError::InvalidIdempotencyKey("must be printable ASCII".to_string()))
+        })
+        .transpose()
+}
+
+/// Run `request` once per key and replay its response to retries.
+/// Responses are kept unless they report a transient failure (5xx, 429 or a
+/// retryable error code); then the key is released so a retry runs again.
+pub async fn run_idempotently<F>(
+    idempotency: &IdempotencyService,
+    scope: &str,
+    key: &str,
+    fingerprint: &str,
+    request: F,
+) -> Result<Response, ApiError>
+where
+    F: Future<Output = Response>,
+{
+    if let IdempotentStart::Replay(stored) = idempotency.start(scope, key, fingerprint).await? {
+        return Ok(replay(stored));
+    }
+
+    let (parts, body) = request.await.into_parts();
+    let bytes = match to_bytes(body, MAX_STORED_BODY).await {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            release(idempotency, scope, key).await;
+            return Err(UserError::Internal(InfrastructureError::new("buffer response", err)).into());
+        }
+    };
+
+    let transient = parts.status.is_server_error()
+        || parts.status == StatusCode::TOO_MANY_REQUESTS
+        || serde_json::from_slice::<Value>(&bytes).is_ok_and(|body| body["retryable"] == Value::Bool(true));
+    if transient {
+        release(idempotency, scope, key).await;
+    } else {
+        let stored = StoredResponse {
+            status: parts.status.as_u16(),
+            headers: REPLAYED_HEADERS
+                .iter()
+                .filter_map(|name| {
+                    let value = parts.headers.get(name)?.to_str().ok()?;
+                    Some((name.to_string(), value.to_string()))
+                })
+                .collect(),
+            body: String::from_utf8_lossy(&bytes).into_owned(),
+        };
+        // The request already ran; a retry after this failure conflicts
+        // until the key is presumed abandoned
+        if let Err(err) = idempotency.complete(scope, key, &stored).await {
+            tracing::warn!(error = ?err, code = err.code(), "Storing idempotent response failed");
+        }
+    }
+
+    Ok(Response::from_parts(parts, Body::from(bytes)))
+}
+
+async fn release(idempotency: &IdempotencyService, scope: &str, key: &str) {
+    if let Err(err) = idempotency.release(scope, key).await {
+        tracing::warn!(error = ?err, code = err.code(), "Releasing idempotency key failed");
+    }
+}
+
+fn replay(stored: StoredResponse) -> Response {
+    let status = StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK);
+    let mut response = (status, stored.body).into_response();
+    let headers = response.headers_mut();
+    for (name, value) in stored.headers {
+        if let (Ok(name), Ok(value)) = (HeaderName::try_from(name), HeaderValue::try_from(value)) {
+            headers.insert(name, value);
+        }
+    }
+    headers.insert(IDEMPOTENT_REPLAYED, HeaderValue::from_static("true"));
+    response
+}
@@ -1,4 +1,17 @@
-pub mod handlers;
-pub mod routes;
-
-pub use routes::create_routes;
+pub mod api_key_handlers;
+pub mod auth;
+pub mod auth_handlers;
+pub mod error;
+pub mod etag;
+pub mod event_stream_handlers;
+pub mod handlers;
+pub mod idempotency;
+pub mod rate_limit;
+pub mod request_id;
+pub mod routes;
+pub mod webhook_handlers;
+pub mod websocket_handlers;
+
+pub use rate_limit::{Budget, RateLimitConfig, RateLimiter, rate_limit};
+pub use request_id::{RequestId, X_REQUEST_ID, request_id};
+pub use routes::{AppState, create_routes};
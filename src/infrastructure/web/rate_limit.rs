@@ -0,0 +1,310 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderMap, HeaderName, HeaderValue, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    application::config::{ConfigError, flag_var, positive_var},
+    domain::UserError,
+    infrastructure::web::{
+        auth::{AuthGuard, api_key},
+        error::ApiError,
+    },
+};
+
+/// Requests the bucket holds when full
+pub const RATELIMIT_LIMIT: HeaderName = HeaderName::from_static("ratelimit-limit");
+/// Requests left right now
+pub const RATELIMIT_REMAINING: HeaderName = HeaderName::from_static("ratelimit-remaining");
+/// Seconds until the bucket is full again
+pub const RATELIMIT_RESET: HeaderName = HeaderName::from_static("ratelimit-reset");
+
+/// Client IP as set by a reverse proxy
+const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+
+/// How often idle buckets are dropped
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Token bucket size and refill rate of one class of requests
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Budget {
+    /// Requests a client may send at once
+    pub burst: u32,
+    /// Requests regained per second
+    pub per_second: f64,
+}
+
+/// Per-client request budgets, read from `RATE_LIMIT_*`
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    /// `GET`, `HEAD` and `OPTIONS` requests
+    pub read: Budget,
+    /// Every other method
+    pub write: Budget,
+    /// Take the client IP from the last `X-Forwarded-For` entry; enable only
+    /// behind a proxy that sets it, since clients can send it themselves
+    pub trust_forwarded_for: bool,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            read: Budget {
+                burst: 100,
+                per_second: 20.0,
+            },
+            write: Budget {
+                burst: 20,
+                per_second: 5.0,
+            },
+            trust_forwarded_for: false,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let defaults = Self::default();
+        let budget = |class: &str, default: Budget| {
+            Ok::<_, ConfigError>(Budget {
+                burst: positive_var(&format!("RATE_LIMIT_{}_BURST", class))?.unwrap_or(default.burst),
+                per_second: positive_var(&format!("RATE_LIMIT_{}_PER_SEC", class))?.unwrap_or(default.per_second),
+            })
+        };
+
+        Ok(Self {
+            enabled: flag_var("RATE_LIMIT_ENABLED")?.unwrap_or(defaults.enabled),
+            read: budget("READ", defaults.read)?,
+            write: budget("WRITE", defaults.write)?,
+            trust_forwarded_for: flag_var("RATE_LIMIT_TRUST_FORWARDED_FOR")?.unwrap_or(defaults.trust_forwarded_for),
+        })
+    }
+}
+
+/// Token buckets per client and request class, shared by every clone.
+/// Clients are the user behind a valid access token, a valid API key, or
+/// otherwise the client IP; invalid credentials count against the IP.
+/// Checking an API key costs a database lookup, so it happens only while
+/// both the key's bucket and the IP's bucket have tokens left.
+#[derive(Clone)]
+pub struct RateLimiter {
+    config: Arc<RateLimitConfig>,
+    guard: Option<AuthGuard>,
+    buckets: Arc<Mutex<Buckets>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Class {
+    Read,
+    Write,
+}
+
+struct Buckets {
+    by_client: HashMap<(Class, String), Bucket>,
+    last_sweep: Instant,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Outcome of one request against its bucket
+struct Decision {
+    limit: u32,
+    remaining: u32,
+    reset_secs: u64,
+    /// Set when the request is rejected
+    retry_after_secs: Option<u64>,
+}
+
+impl Budget {
+    fn refill(&self, bucket: &mut Bucket, now: Instant) {
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.per_second).min(f64::from(self.burst));
+        bucket.updated = now;
+    }
+
+    /// Whole seconds until `tokens` more are regained
+    fn secs_for(&self, tokens: f64) -> u64 {
+        (tokens.max(0.0) / self.per_second).ceil() as u64
+    }
+}
+
+impl RateLimiter {
+    /// Limit by client IP only
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            guard: None,
+            buckets: Arc::new(Mutex::new(Buckets {
+                by_client: HashMap::new(),
+                last_sweep: Instant::now(),
+            })),
+        }
+    }
+
+    /// Limit authenticated callers by user or API key, using the same guard
+    /// as the routes; the resolved principal is passed on to them
+    pub fn with_guard(mut self, guard: AuthGuard) -> Self {
+        self.guard = Some(guard);
+        self
+    }
+
+    fn budget(&self, class: Class) -> Budget {
+        match class {
+            Class::Read => self.config.read,
+            Class::Write => self.config.write,
+        }
+    }
+
+    /// Take one token from the client's bucket, if there is one
+    fn take(&self, class: Class, client: String) -> Decision {
+        let budget = self.budget(class);
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+        if now.duration_since(buckets.last_sweep) >= SWEEP_INTERVAL {
+            self.sweep(&mut buckets, now);
+        }
+
+        let bucket = buckets.by_client.entry((class, client)).or_insert(Bucket {
+            tokens: f64::from(budget.burst),
+            updated: now,
+        });
+        budget.refill(bucket, now);
+        let retry_after_secs = if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            None
+        } else {
+            Some(budget.secs_for(1.0 - bucket.tokens).max(1))
+        };
+
+        Decision {
+            limit: budget.burst,
+            remaining: bucket.tokens.floor() as u32,
+            reset_secs: budget.secs_for(f64::from(budget.burst) - bucket.tokens),
+            retry_after_secs,
+        }
+    }
+
+    /// Drop buckets that have refilled completely; a new one starts full anyway
+    fn sweep(&self, buckets: &mut Buckets, now: Instant) {
+        buckets.by_client.retain(|(class, _), bucket| {
+            let budget = self.budget(*class);
+            budget.refill(bucket, now);
+            bucket.tokens < f64::from(budget.burst)
+        });
+        buckets.last_sweep = now;
+    }
+
+    /// Whether the client's bucket has a token left, without taking it
+    fn has_tokens(&self, class: Class, client: &str) -> bool {
+        let budget = self.budget(class);
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+        match buckets.by_client.get_mut(&(class, client.to_string())) {
+            Some(bucket) => {
+                budget.refill(bucket, Instant::now());
+                bucket.tokens >= 1.0
+            }
+            None => true,
+        }
+    }
+
+    /// Take one token from the caller's bucket. A valid principal is stored
+    /// in the request so `require_authentication` does not resolve it again.
+    async fn admit(&self, class: Class, request: &mut Request) -> Decision {
+        let ip = match self.client_ip(request) {
+            Some(ip) => format!("ip:{}", ip),
+            None => "ip:unknown".to_string(),
+        };
+        let Some(guard) = &self.guard else {
+            return self.take(class, ip);
+        };
+
+        // The key's bucket is keyed on the key as presented, so an exhausted
+        // one is rejected before the lookup; an IP out of budget gets no
+        // lookups at all, however many keys it makes up
+        let client = match api_key(request.headers()) {
+            Some(_) if !self.has_tokens(class, &ip) => return self.take(class, ip),
+            Some(key) => {
+                let client = format!("key:{:x}", Sha256::digest(key.as_bytes()));
+                let decision = self.take(class, client);
+                if decision.retry_after_secs.is_some() {
+                    return decision;
+                }
+                if let Ok(principal) = guard.authenticate(request.headers()).await {
+                    request.extensions_mut().insert(principal);
+                    return decision;
+                }
+                ip
+            }
+            // Access tokens are checked without touching the database
+            None => match guard.authenticate(request.headers()).await {
+                Ok(principal) => {
+                    let client = format!("user:{}", principal.user_id.as_uuid());
+                    request.extensions_mut().insert(principal);
+                    client
+                }
+                Err(_) => ip,
+            },
+        };
+        self.take(class, client)
+    }
+
+    fn client_ip(&self, request: &Request) -> Option<IpAddr> {
+        if self.config.trust_forwarded_for
+            && let Some(forwarded) = request.headers().get(X_FORWARDED_FOR)
+        {
+            return forwarded.to_str().ok()?.rsplit(',').next()?.trim().parse().ok();
+        }
+        request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip())
+    }
+}
+
+impl Decision {
+    fn write_headers(&self, headers: &mut HeaderMap) {
+        headers.insert(RATELIMIT_LIMIT, HeaderValue::from(self.limit));
+        headers.insert(RATELIMIT_REMAINING, HeaderValue::from(self.remaining));
+        headers.insert(RATELIMIT_RESET, HeaderValue::from(self.reset_secs));
+    }
+}
+
+/// Reject clients over their budget with `429 Too Many Requests` and
+/// `Retry-After`; every limited response carries the `RateLimit-*` headers.
+/// `/health` is never limited, so monitoring keeps working under load.
+pub async fn rate_limit(State(limiter): State<RateLimiter>, mut request: Request, next: Next) -> Response {
+    if !limiter.config.enabled || request.uri().path() == "/health" {
+        return next.run(request).await;
+    }
+
+    let class = match *request.method() {
+        Method::GET | Method::HEAD | Method::OPTIONS => Class::Read,
+        _ => Class::Write,
+    };
+    let decision = limiter.admit(class, &mut request).await;
+
+    let mut response = match decision.retry_after_secs {
+        Some(retry_after_secs) => {
+            tracing::debug!(?class, retry_after_secs, "Rate limit exceeded");
+            ApiError::from(UserError::RateLimited { retry_after_secs }).into_response()
+        }
+        None => next.run(request).await,
+    };
+    decision.write_headers(response.headers_mut());
+    response
+}
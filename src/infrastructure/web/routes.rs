@@ -1,26 +1,193 @@
-use axum::{
-    routing::{delete, get, post, put},
-    Router,
-};
-
-use crate::{
-    application::UserApplicationService,
-    infrastructure::{PostgresUserRepository, web::handlers},
-};
-
-pub fn create_routes(
-    app_service: UserApplicationService<PostgresUserRepository>,
-) -> Router {
-    Router::new()
-        .route("/health", get(health_check))
-        .route("/api/users", post(handlers::create_user_concrete))
-        .route("/api/users", get(handlers::get_users_concrete))
-        .route("/api/users/{id}", get(handlers::get_user_concrete))
-        .route("/api/users/{id}", put(handlers::update_user_concrete))
-        .route("/api/users/{id}", delete(handlers::delete_user_concrete))
-        .with_state(app_service)
-}
-
-async fn health_check() -> &'static str {
-    "OK"
-}
+use std::sync::Arc;
+
+use axum::{
+    extract::FromRef,
+    middleware,
+    routing::{delete, get, post},
+    Router,
+};
+
+use crate::{
+    application::{
+        ApiKeyService, AuthService, EmailVerificationService, IdempotencyService, PasswordResetService,
+        UserApplicationService, WebhookService,
+    },
+    domain::UserRepositoryPort,
+    infrastructure::{
+        events::UserEventStream,
+        web::{
+            auth::{AuthGuard, require_authentication},
+            api_key_handlers, auth_handlers, event_stream_handlers, handlers,
+            request_id::request_id,
+            webhook_handlers, websocket_handlers,
+        },
+    },
+};
+
+/// Shared state of every route; handlers extract the part they need
+#[derive(Clone)]
+pub struct AppState<R: UserRepositoryPort> {
+    pub users: UserApplicationService<R>,
+    pub auth: AuthService<R>,
+    pub api_keys: ApiKeyService<R>,
+    pub email_verification: EmailVerificationService<R>,
+    pub password_reset: PasswordResetService<R>,
+    pub idempotency: IdempotencyService,
+    pub webhooks: WebhookService,
+    pub event_stream: UserEventStream,
+    pub guard: AuthGuard,
+}
+
+impl<R: UserRepositoryPort + 'static> AppState<R> {
+    /// `public_routes` are reachable without credentials (see `AuthGuard::new`)
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        users: UserApplicationService<R>,
+        auth: AuthService<R>,
+        api_keys: ApiKeyService<R>,
+        email_verification: EmailVerificationService<R>,
+        password_reset: PasswordResetService<R>,
+        idempotency: IdempotencyService,
+        webhooks: WebhookService,
+        event_stream: UserEventStream,
+        public_routes: &[String],
+    ) -> Self {
+        let guard = AuthGuard::new(auth.tokens(), Arc::new(api_keys.clone()), public_routes);
+        Self {
+            users,
+            auth,
+            api_keys,
+            email_verification,
+            password_reset,
+            idempotency,
+            webhooks,
+            event_stream,
+            guard,
+        }
+    }
+}
+
+impl<R: UserRepositoryPort> FromRef<AppState<R>> for UserApplicationService<R> {
+    fn from_ref(state: &AppState<R>) -> Self {
+        state.users.clone()
+    }
+}
+
+impl<R: UserRepositoryPort> FromRef<AppState<R>> for AuthService<R> {
+    fn from_ref(state: &AppState<R>) -> Self {
+        state.auth.clone()
+    }
+}
+
+impl<R: UserRepositoryPort> FromRef<AppState<R>> for ApiKeyService<R> {
+    fn from_ref(state: &AppState<R>) -> Self {
+        state.api_keys.clone()
+    }
+}
+
+impl<R: UserRepositoryPort> FromRef<AppState<R>> for EmailVerificationService<R> {
+    fn from_ref(state: &AppState<R>) -> Self {
+        state.email_verification.clone()
+    }
+}
+
+impl<R: UserRepositoryPort> FromRef<AppState<R>> for PasswordResetService<R> {
+    fn from_ref(state: &AppState<R>) -> Self {
+        state.password_reset.clone()
+    }
+}
+
+impl<R: UserRepositoryPort> FromRef<AppState<R>> for IdempotencyService {
+    fn from_ref(state: &AppState<R>) -> Self {
+        state.idempotency.clone()
+    }
+}
+
+impl<R: UserRepositoryPort> FromRef<AppState<R>> for WebhookService {
+    fn from_ref(state: &AppState<R>) -> Self {
+        state.webhooks.clone()
+    }
+}
+
+impl<R: UserRepositoryPort> FromRef<AppState<R>> for UserEventStream {
+    fn from_ref(state: &AppState<R>) -> Self {
+        state.event_stream.clone()
+    }
+}
+
+impl<R: UserRepositoryPort> FromRef<AppState<R>> for AuthGuard {
+    fn from_ref(state: &AppState<R>) -> Self {
+        state.guard.clone()
+    }
+}
+
+/// Build the HTTP surface over any repository adapter
+pub fn create_routes<R: UserRepositoryPort + 'static>(state: AppState<R>) -> Router {
+    Router::new()
+        .route("/health", get(health_check))
+        .route("/api/auth/login", post(auth_handlers::login::<R>))
+        .route("/api/auth/refresh", post(auth_handlers::refresh::<R>))
+        .route("/api/auth/logout", post(auth_handlers::logout::<R>))
+        .route(
+            "/api/auth/password-reset/request",
+            post(auth_handlers::request_password_reset::<R>),
+        )
+        .route(
+            "/api/auth/password-reset/confirm",
+            post(auth_handlers::confirm_password_reset::<R>),
+        )
+        .route("/api/auth/me", get(auth_handlers::current_user::<R>))
+        .route(
+            "/api/users",
+            get(handlers::get_users::<R>).post(handlers::create_user::<R>),
+        )
+        .route("/api/users/events", get(event_stream_handlers::user_events::<R>))
+        .route("/ws", get(websocket_handlers::connect::<R>))
+        .route(
+            "/api/users/{id}",
+            get(handlers::get_user::<R>)
+                .put(handlers::update_user::<R>)
+                .patch(handlers::patch_user::<R>)
+                .delete(handlers::delete_user::<R>),
+        )
+        .route("/api/users/{id}/restore", post(handlers::restore_user::<R>))
+        .route("/api/users/{id}/history", get(handlers::get_user_history::<R>))
+        .route("/api/users/{id}/sessions", delete(auth_handlers::revoke_user_sessions::<R>))
+        .route(
+            "/api/users/{id}/verify-email/send",
+            post(auth_handlers::send_email_verification::<R>),
+        )
+        .route("/api/verify-email/confirm", post(auth_handlers::confirm_email::<R>))
+        .route(
+            "/api/api-keys",
+            get(api_key_handlers::list_api_keys::<R>).post(api_key_handlers::create_api_key::<R>),
+        )
+        .route("/api/api-keys/{id}", delete(api_key_handlers::revoke_api_key::<R>))
+        .route("/api/audit", get(handlers::get_audit_log::<R>))
+        .route(
+            "/api/webhooks",
+            get(webhook_handlers::list_webhooks).post(webhook_handlers::create_webhook),
+        )
+        .route(
+            "/api/webhooks/{id}",
+            get(webhook_handlers::get_webhook)
+                .patch(webhook_handlers::update_webhook)
+                .delete(webhook_handlers::delete_webhook),
+        )
+        .route("/api/webhooks/{id}/deliveries", get(webhook_handlers::list_deliveries))
+        .route(
+            "/api/webhooks/{id}/deliveries/{delivery_id}",
+            get(webhook_handlers::get_delivery),
+        )
+        .route(
+            "/api/webhooks/{id}/deliveries/{delivery_id}/replay",
+            post(webhook_handlers::replay_delivery),
+        )
+        .route_layer(middleware::from_fn_with_state(state.guard.clone(), require_authentication))
+        .layer(middleware::from_fn(request_id))
+        .with_state(state)
+}
+
+async fn health_check() -> &'static str {
+    "OK"
+}
@@ -0,0 +1,109 @@
+use axum::{
+    extract::{Path, Query, RawQuery, State},
+    http::StatusCode,
+    response::Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        ApiResponse, CreateWebhookDto, CreatedWebhookDto, DeliveryFilterDto, UpdateWebhookDto, WebhookDeliveryDetailDto,
+        WebhookDeliveryDto, WebhookResponseDto, WebhookService,
+    },
+    infrastructure::web::{
+        auth::AuthenticatedUser,
+        error::ApiError,
+        handlers::{PageQuery, carried_query},
+    },
+};
+
+/// Subscribe an endpoint; the response is the only place its secret appears
+pub async fn create_webhook(
+    State(webhooks): State<WebhookService>,
+    AuthenticatedUser(principal): AuthenticatedUser,
+    Json(payload): Json<CreateWebhookDto>,
+) -> Result<(StatusCode, Json<ApiResponse<CreatedWebhookDto>>), ApiError>
+{
+    let created = webhooks.create_webhook(&principal, payload).await?;
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(created))))
+}
+
+pub async fn list_webhooks(
+    State(webhooks): State<WebhookService>,
+    AuthenticatedUser(principal): AuthenticatedUser,
+) -> Result<(StatusCode, Json<ApiResponse<Vec<WebhookResponseDto>>>), ApiError>
+{
+    let subscriptions = webhooks.list_webhooks(&principal).await?;
+    Ok((StatusCode::OK, Json(ApiResponse::success(subscriptions))))
+}
+
+pub async fn get_webhook(
+    State(webhooks): State<WebhookService>,
+    AuthenticatedUser(principal): AuthenticatedUser,
+    Path(id): Path<Uuid>,
+) -> Result<(StatusCode, Json<ApiResponse<WebhookResponseDto>>), ApiError>
+{
+    let subscription = webhooks.get_webhook(&principal, id).await?;
+    Ok((StatusCode::OK, Json(ApiResponse::success(subscription))))
+}
+
+pub async fn update_webhook(
+    State(webhooks): State<WebhookService>,
+    AuthenticatedUser(principal): AuthenticatedUser,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateWebhookDto>,
+) -> Result<(StatusCode, Json<ApiResponse<WebhookResponseDto>>), ApiError>
+{
+    let subscription = webhooks.update_webhook(&principal, id, payload).await?;
+    Ok((StatusCode::OK, Json(ApiResponse::success(subscription))))
+}
+
+pub async fn delete_webhook(
+    State(webhooks): State<WebhookService>,
+    AuthenticatedUser(principal): AuthenticatedUser,
+    Path(id): Path<Uuid>,
+) -> Result<(StatusCode, Json<ApiResponse<()>>), ApiError>
+{
+    webhooks.delete_webhook(&principal, id).await?;
+    Ok((StatusCode::NO_CONTENT, Json(ApiResponse::success(()))))
+}
+
+/// The delivery log of a subscription; `?status=dead_lettered` lists its dead letters
+pub async fn list_deliveries(
+    State(webhooks): State<WebhookService>,
+    AuthenticatedUser(principal): AuthenticatedUser,
+    Path(id): Path<Uuid>,
+    Query(pagination): Query<PageQuery>,
+    Query(filter): Query<DeliveryFilterDto>,
+    RawQuery(raw_query): RawQuery,
+) -> Result<(StatusCode, Json<ApiResponse<Vec<WebhookDeliveryDto>>>), ApiError>
+{
+    let page = webhooks.list_deliveries(&principal, id, filter, pagination.page, pagination.limit).await?;
+    let path = format!("/api/webhooks/{}/deliveries", id);
+    let carried = carried_query(raw_query.as_deref().unwrap_or_default());
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::paginated(page.deliveries, page.pagination.with_links(&path, &carried))),
+    ))
+}
+
+pub async fn get_delivery(
+    State(webhooks): State<WebhookService>,
+    AuthenticatedUser(principal): AuthenticatedUser,
+    Path((id, delivery_id)): Path<(Uuid, Uuid)>,
+) -> Result<(StatusCode, Json<ApiResponse<WebhookDeliveryDetailDto>>), ApiError>
+{
+    let delivery = webhooks.get_delivery(&principal, id, delivery_id).await?;
+    Ok((StatusCode::OK, Json(ApiResponse::success(delivery))))
+}
+
+/// Queue a delivery again; `202 Accepted` with the new delivery
+pub async fn replay_delivery(
+    State(webhooks): State<WebhookService>,
+    AuthenticatedUser(principal): AuthenticatedUser,
+    Path((id, delivery_id)): Path<(Uuid, Uuid)>,
+) -> Result<(StatusCode, Json<ApiResponse<WebhookDeliveryDto>>), ApiError>
+{
+    let replay = webhooks.replay_delivery(&principal, id, delivery_id).await?;
+    Ok((StatusCode::ACCEPTED, Json(ApiResponse::success(replay))))
+}
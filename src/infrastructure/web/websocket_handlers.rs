@@ -0,0 +1,379 @@
+use std::{collections::BTreeSet, time::Duration};
+
+use axum::{
+    extract::{
+        State,
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade, close_code},
+    },
+    http::HeaderMap,
+    response::Response,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+use crate::{
+    application::{UserApplicationService, UserEventDto, UserFilterDto},
+    domain::{InfrastructureError, Principal, UserError, UserEvent, UserRepositoryPort},
+    infrastructure::{
+        events::UserEventStream,
+        web::{
+            auth::{AuthGuard, AuthenticatedUser, api_key, bearer_token},
+            error::ApiError,
+        },
+    },
+};
+
+/// Time a client connecting without credentials has to send `authenticate`
+const AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+/// Largest message a client may send
+const MAX_MESSAGE_SIZE: usize = 64 * 1024;
+/// Most users one connection may subscribe to by id
+const MAX_SUBSCRIBED_USERS: usize = 100;
+
+/// A client message: a request, with an optional `id` of the client's
+/// choosing (string or number) that the reply echoes
+#[derive(Debug, Deserialize)]
+struct ClientMessage {
+    id: Option<Value>,
+    #[serde(flatten)]
+    request: ClientRequest,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientRequest {
+    /// Only as the first message, when the upgrade request had no credentials
+    Authenticate {
+        token: Option<String>,
+        api_key: Option<String>,
+    },
+    /// Changes to these users, or to every user without `user_ids`
+    Subscribe { user_ids: Option<Vec<Uuid>> },
+    /// Stop what `Subscribe` with the same `user_ids` started
+    Unsubscribe { user_ids: Option<Vec<Uuid>> },
+    GetUser { user_id: Uuid },
+    /// Same parameters as `GET /api/users`
+    ListUsers {
+        page: Option<i64>,
+        limit: Option<i64>,
+        after: Option<String>,
+        #[serde(flatten)]
+        filter: UserFilterDto,
+    },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage {
+    /// Reply to a request that succeeded
+    Result { id: Option<Value>, data: Value },
+    /// Reply to a request that failed, with the fields of an HTTP error body
+    Error {
+        id: Option<Value>,
+        error: String,
+        error_code: &'static str,
+        retryable: bool,
+    },
+    /// A change the connection is subscribed to
+    Event { event: UserEventDto },
+    /// Notifications were dropped because the client read too slowly;
+    /// reload what is shown
+    Reset,
+}
+
+/// What a connection is subscribed to
+#[derive(Debug, Default, Serialize)]
+struct Subscriptions {
+    /// Every user
+    directory: bool,
+    user_ids: BTreeSet<Uuid>,
+}
+
+impl Subscriptions {
+    fn is_empty(&self) -> bool {
+        !self.directory && self.user_ids.is_empty()
+    }
+
+    fn wants(&self, event: &UserEvent) -> bool {
+        self.directory || self.user_ids.contains(&event.user_id().as_uuid())
+    }
+}
+
+/// Credentials a connection authenticated with, however they were sent
+struct Credentials {
+    token: Option<String>,
+    api_key: Option<String>,
+}
+
+/// Upgrade to a WebSocket speaking the JSON protocol in the README.
+/// Credentials on the upgrade request authenticate the connection; without
+/// them the first message must be `authenticate`. Invalid credentials are
+/// rejected before the upgrade.
+pub async fn connect<R: UserRepositoryPort + 'static>(
+    ws: WebSocketUpgrade,
+    State(users): State<UserApplicationService<R>>,
+    State(stream): State<UserEventStream>,
+    State(guard): State<AuthGuard>,
+    headers: HeaderMap,
+    caller: Option<AuthenticatedUser>,
+) -> Response {
+    let connection = Connection {
+        users,
+        guard,
+        credentials: Credentials {
+            token: bearer_token(&headers).map(str::to_string),
+            api_key: api_key(&headers).map(str::to_string),
+        },
+        principal: caller.map(|AuthenticatedUser(principal)| principal),
+        subscriptions: Subscriptions::default(),
+    };
+    ws.max_message_size(MAX_MESSAGE_SIZE)
+        .on_upgrade(move |socket| connection.run(socket, stream))
+}
+
+/// State of one WebSocket connection
+struct Connection<R: UserRepositoryPort> {
+    users: UserApplicationService<R>,
+    guard: AuthGuard,
+    credentials: Credentials,
+    principal: Option<Principal>,
+    subscriptions: Subscriptions,
+}
+
+impl<R: UserRepositoryPort + 'static> Connection<R> {
+    async fn run(mut self, mut socket: WebSocket, stream: UserEventStream) {
+        let principal = match self.principal.take() {
+            Some(principal) => principal,
+            None => match self.authenticate(&mut socket).await {
+                Some(principal) => principal,
+                None => return close(&mut socket, "authentication required").await,
+            },
+        };
+
+        let (_, mut live) = stream.subscribe(None);
+        let mut heartbeat = tokio::time::interval(stream.heartbeat());
+        heartbeat.tick().await;
+        let expiry = principal.expires_at.map(|at| (at - Utc::now()).to_std().unwrap_or_default());
+        let mut expired = std::pin::pin!(async move {
+            match expiry {
+                Some(expiry) => tokio::time::sleep(expiry).await,
+                None => std::future::pending().await,
+            }
+        });
+        loop {
+            let outgoing = tokio::select! {
+                incoming = socket.recv() => match incoming {
+                    Some(Ok(Message::Text(text))) => Some(self.reply(&principal, text.as_str()).await),
+                    Some(Ok(Message::Binary(_))) => Some(error_reply(
+                        None,
+                        UserError::InvalidMessage("messages are JSON text".to_string()),
+                    )),
+                    Some(Ok(Message::Ping(_) | Message::Pong(_))) => None,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                },
+                event = live.recv() => match event {
+                    Ok(event) if self.subscriptions.wants(&event) => Some(ServerMessage::Event {
+                        event: UserEventDto::from(&event),
+                    }),
+                    Ok(_) => None,
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!(skipped, "WebSocket connection fell behind");
+                        (!self.subscriptions.is_empty()).then_some(ServerMessage::Reset)
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = heartbeat.tick() => {
+                    if socket.send(Message::Ping(Default::default())).await.is_err() {
+                        break;
+                    }
+                    match self.confirm(&principal).await {
+                        Ok(()) => {}
+                        Err(UserError::Unauthenticated(reason)) => return close(&mut socket, &reason).await,
+                        Err(err) => tracing::warn!(error = %err, "Could not check WebSocket credentials again"),
+                    }
+                    None
+                }
+                () = &mut expired => return close(&mut socket, "credentials expired").await,
+            };
+
+            if let Some(message) = outgoing
+                && send(&mut socket, &message).await.is_err()
+            {
+                break;
+            }
+        }
+    }
+
+    /// Wait for the `authenticate` message; `None` once the client has been
+    /// told why it failed
+    async fn authenticate(&mut self, socket: &mut WebSocket) -> Option<Principal> {
+        let first = match tokio::time::timeout(AUTH_TIMEOUT, socket.recv()).await {
+            Ok(Some(Ok(Message::Text(text)))) => text,
+            Ok(Some(Ok(_))) | Err(_) => {
+                let err = UserError::Unauthenticated("send `authenticate` first".to_string());
+                let _ = send(socket, &error_reply(None, err)).await;
+                return None;
+            }
+            Ok(Some(Err(_)) | None) => return None,
+        };
+
+        let (id, outcome) = match parse(first.as_str()) {
+            Ok(ClientMessage {
+                id,
+                request: ClientRequest::Authenticate { token, api_key },
+            }) => {
+                let outcome = self
+                    .guard
+                    .authenticate_credentials(token.as_deref(), api_key.as_deref())
+                    .await;
+                self.credentials = Credentials { token, api_key };
+                (id, outcome)
+            }
+            Ok(ClientMessage { id, .. }) => {
+                let err = UserError::Unauthenticated("send `authenticate` first".to_string());
+                (id, Err(err))
+            }
+            Err((id, err)) => (id, Err(err)),
+        };
+
+        let (reply, principal) = match outcome {
+            Ok(principal) => {
+                let data = serde_json::json!({
+                    "user_id": principal.user_id.as_uuid(),
+                    "role": principal.role.as_str(),
+                });
+                (result_reply(id, data), Some(principal))
+            }
+            Err(err) => (error_reply(id, err), None),
+        };
+        send(socket, &reply).await.ok()?;
+        principal
+    }
+
+    /// Check again, with every heartbeat, that the credentials are still
+    /// accepted and still stand for `principal`: keys and sessions may have
+    /// been revoked, and the user deleted or given another role since
+    async fn confirm(&self, principal: &Principal) -> Result<(), UserError> {
+        let Credentials { token, api_key } = &self.credentials;
+        let current = self.guard.authenticate_credentials(token.as_deref(), api_key.as_deref()).await?;
+        if current.user_id != principal.user_id
+            || current.role != principal.role
+            || current.scopes != principal.scopes
+        {
+            return Err(UserError::Unauthenticated("the credentials are no longer valid".to_string()));
+        }
+        self.users.confirm_principal(principal).await
+    }
+
+    async fn reply(&mut self, principal: &Principal, text: &str) -> ServerMessage {
+        let (id, request) = match parse(text) {
+            Ok(ClientMessage { id, request }) => (id, request),
+            Err((id, err)) => return error_reply(id, err),
+        };
+        match self.handle(principal, request).await {
+            Ok(data) => result_reply(id, data),
+            Err(err) => error_reply(id, err),
+        }
+    }
+
+    async fn handle(&mut self, principal: &Principal, request: ClientRequest) -> Result<Value, UserError> {
+        match request {
+            ClientRequest::Authenticate { .. } => {
+                Err(UserError::InvalidMessage("the connection is already authenticated".to_string()))
+            }
+            ClientRequest::Subscribe { user_ids } => {
+                match &user_ids {
+                    None => self.users.authorize_watch(principal, None)?,
+                    Some(user_ids) => {
+                        let subscribed: BTreeSet<&Uuid> = self.subscriptions.user_ids.iter().chain(user_ids).collect();
+                        if subscribed.len() > MAX_SUBSCRIBED_USERS {
+                            return Err(UserError::InvalidMessage(format!(
+                                "at most {} users can be subscribed to by id",
+                                MAX_SUBSCRIBED_USERS
+                            )));
+                        }
+                        for user_id in user_ids {
+                            self.users.authorize_watch(principal, Some(*user_id))?;
+                        }
+                    }
+                }
+                match user_ids {
+                    None => self.subscriptions.directory = true,
+                    Some(user_ids) => self.subscriptions.user_ids.extend(user_ids),
+                }
+                to_value(&self.subscriptions)
+            }
+            ClientRequest::Unsubscribe { user_ids } => {
+                match user_ids {
+                    None => self.subscriptions.directory = false,
+                    Some(user_ids) => {
+                        for user_id in user_ids {
+                            self.subscriptions.user_ids.remove(&user_id);
+                        }
+                    }
+                }
+                to_value(&self.subscriptions)
+            }
+            ClientRequest::GetUser { user_id } => {
+                let user = self.users.get_user_by_id(principal, user_id, false).await?;
+                to_value(&user.ok_or(UserError::NotFound)?)
+            }
+            ClientRequest::ListUsers {
+                page,
+                limit,
+                after,
+                filter,
+            } => to_value(&self.users.get_all_users(principal, filter, page, limit, after).await?),
+        }
+    }
+}
+
+/// Parse a client message, keeping its `id` when only the request is invalid
+fn parse(text: &str) -> Result<ClientMessage, (Option<Value>, UserError)> {
+    serde_json::from_str(text).map_err(|err| {
+        let id = serde_json::from_str::<Value>(text)
+            .ok()
+            .and_then(|mut value| value.get_mut("id").map(Value::take))
+            .filter(|id| !id.is_null());
+        (id, UserError::InvalidMessage(err.to_string()))
+    })
+}
+
+fn to_value<T: Serialize>(data: &T) -> Result<Value, UserError> {
+    serde_json::to_value(data)
+        .map_err(|err| UserError::Internal(InfrastructureError::new("encode WebSocket reply", err)))
+}
+
+fn result_reply(id: Option<Value>, data: Value) -> ServerMessage {
+    ServerMessage::Result { id, data }
+}
+
+fn error_reply(id: Option<Value>, err: UserError) -> ServerMessage {
+    let err = ApiError(err);
+    if err.status().is_server_error() {
+        tracing::error!(error = ?err.0, code = err.0.code(), "WebSocket request failed");
+    }
+    ServerMessage::Error {
+        id,
+        error: err.message(),
+        error_code: err.0.code(),
+        retryable: err.0.is_retryable(),
+    }
+}
+
+/// Close the connection as a policy violation, telling the client why
+async fn close(socket: &mut WebSocket, reason: &str) {
+    let close = CloseFrame {
+        code: close_code::POLICY,
+        reason: reason.into(),
+    };
+    let _ = socket.send(Message::Close(Some(close))).await;
+}
+
+async fn send(socket: &mut WebSocket, message: &ServerMessage) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(message).expect("server messages serialize to JSON");
+    socket.send(Message::Text(text.into())).await
+}
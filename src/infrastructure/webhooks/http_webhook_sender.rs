@@ -0,0 +1,44 @@
+use async_trait::async_trait;
+use reqwest::{Client, redirect::Policy};
+use std::time::Duration;
+
+use crate::domain::{InfrastructureError, UserError, WebhookRequest, WebhookSenderPort};
+
+const USER_AGENT: &str = concat!("rust-nexus-webhooks/", env!("CARGO_PKG_VERSION"));
+
+/// HTTP adapter implementing WebhookSenderPort.
+/// Redirects are not followed: a receiver answering 3xx has not taken the
+/// delivery, and the signature covers the body, not a new destination.
+#[derive(Clone)]
+pub struct HttpWebhookSender {
+    client: Client,
+}
+
+impl HttpWebhookSender {
+    /// Calls without a response within `timeout` fail
+    pub fn new(timeout: Duration) -> Result<Self, UserError> {
+        let client = Client::builder()
+            .timeout(timeout)
+            .redirect(Policy::none())
+            .user_agent(USER_AGENT)
+            .build()
+            .map_err(|e| UserError::Internal(InfrastructureError::new("build webhook client", e)))?;
+        Ok(Self { client })
+    }
+}
+
+#[async_trait]
+impl WebhookSenderPort for HttpWebhookSender {
+    async fn send(&self, request: &WebhookRequest) -> Result<u16, UserError> {
+        let mut builder = self.client.post(&request.url).body(request.body.clone());
+        for (name, value) in &request.headers {
+            builder = builder.header(name, value);
+        }
+
+        let response = builder
+            .send()
+            .await
+            .map_err(|e| UserError::WebhookUnavailable(InfrastructureError::new("send webhook", e)))?;
+        Ok(response.status().as_u16())
+    }
+}
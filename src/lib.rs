@@ -0,0 +1,7 @@
+// Hexagonal architecture modules
+pub mod domain;
+pub mod application;
+pub mod infrastructure;
+
+// Legacy database module (still needed for setup_database function)
+pub mod database;
@@ -1,28 +1,71 @@
-// Hexagonal architecture modules
-mod domain;
-mod application;
-mod infrastructure;
-
-// Legacy database module (still needed for setup_database function)
-mod database;
-
 use anyhow::Result;
-use axum::http::{
-    HeaderValue, Method,
-    header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE},
+use axum::{
+    Router,
+    http::{
+        HeaderValue, Method,
+        header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, ETAG, IF_MATCH, RETRY_AFTER},
+    },
+    middleware,
 };
 use dotenvy::dotenv;
-use std::env;
+use std::{net::SocketAddr, sync::Arc};
 use tower::ServiceBuilder;
 use tower_http::{cors::CorsLayer, trace::TraceLayer};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use crate::{
-    database::setup_database,
-    application::UserApplicationService,
-    infrastructure::{PostgresUserRepository, create_routes},
+#[cfg(feature = "sqlite")]
+use rust_nexus::infrastructure::{
+    SqliteApiKeyRepository, SqliteAuditLogRepository, SqliteCredentialRepository, SqliteEmailVerificationRepository,
+    SqliteIdempotencyRepository, SqliteOutboxRepository, SqlitePasswordResetRepository, SqliteSessionRepository,
+    SqliteUserRepository, SqliteWebhookRepository,
+};
+use rust_nexus::{
+    database::{DatabasePool, RepositoryBackend, setup_database},
+    application::{
+        ApiKeyService, AuthConfig, AuthService, EmailVerificationConfig, EmailVerificationService,
+        IdempotencyConfig, IdempotencyService, OutboxConfig, OutboxRelay, OutboxTail, PaginationConfig, PasswordPolicy,
+        PasswordResetConfig, PasswordResetService, SoftDeleteConfig, UserApplicationService, WebhookConfig,
+        WebhookService, config::parse_var,
+    },
+    domain::{
+        ApiKeyRepositoryPort, AuditLogRepositoryPort, CredentialRepositoryPort, EmailVerificationRepositoryPort,
+        IdempotencyRepositoryPort, OutboxRepositoryPort, PasswordResetRepositoryPort, SessionRepositoryPort,
+        UserRepositoryPort, WebhookRepositoryPort,
+    },
+    infrastructure::{
+        AppState, Argon2PasswordHasher, BroadcastEventPublisher, FanoutEventPublisher, HttpWebhookSender,
+        InMemoryApiKeyRepository, InMemoryAuditLogRepository, InMemoryCredentialRepository,
+        InMemoryEmailVerificationRepository, InMemoryIdempotencyRepository, InMemoryOutboxRepository,
+        InMemoryPasswordResetRepository, InMemorySessionRepository, InMemoryUserRepository, InMemoryWebhookRepository,
+        JwtAccessTokens, MailerConfig, PostgresApiKeyRepository, PostgresAuditLogRepository,
+        PostgresCredentialRepository, PostgresEmailVerificationRepository, PostgresIdempotencyRepository,
+        PostgresOutboxRepository, PostgresPasswordResetRepository, PostgresSessionRepository, PostgresUserRepository,
+        PostgresWebhookRepository, RateLimitConfig, RateLimiter, X_REQUEST_ID, create_routes, rate_limit,
+        UserEventStream, spawn_event_log, spawn_outbox_relay, spawn_outbox_tail, spawn_purge_task,
+        spawn_webhook_dispatcher,
+        web::{
+            auth::{AuthGuard, X_API_KEY},
+            event_stream_handlers::LAST_EVENT_ID,
+            idempotency::{IDEMPOTENCY_KEY, IDEMPOTENT_REPLAYED},
+            rate_limit::{RATELIMIT_LIMIT, RATELIMIT_REMAINING, RATELIMIT_RESET},
+        },
+    },
 };
 
+/// Storage adapters of one backend
+struct Storage<R> {
+    users: R,
+    credentials: Arc<dyn CredentialRepositoryPort>,
+    api_keys: Arc<dyn ApiKeyRepositoryPort>,
+    sessions: Arc<dyn SessionRepositoryPort>,
+    email_verifications: Arc<dyn EmailVerificationRepositoryPort>,
+    password_resets: Arc<dyn PasswordResetRepositoryPort>,
+    idempotency: Arc<dyn IdempotencyRepositoryPort>,
+    audit_log: Arc<dyn AuditLogRepositoryPort>,
+    outbox: Arc<dyn OutboxRepositoryPort>,
+    webhooks: Arc<dyn WebhookRepositoryPort>,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     // Load environment variables
@@ -38,32 +81,90 @@ async fn main() -> Result<()> {
         .init();
 
     // Get configuration from environment
-    let port = env::var("PORT")
-        .unwrap_or_else(|_| "3000".to_string())
-        .parse::<u16>()
-        .expect("PORT must be a valid number");
+    let port = parse_var::<u16>("PORT", "a port number")?.unwrap_or(3000);
 
-    // Database setup with optimized pool
-    let pool = setup_database().await?;
-
-    // Create repository adapter
-    let user_repository = PostgresUserRepository::new(pool);
-    
-    // Create application service
-    let user_app_service = UserApplicationService::new(user_repository);
+    // Create repository adapter and application service for the selected backend
+    let (routes, guard) = match RepositoryBackend::from_env()? {
+        // Database setup with optimized pool; the URL scheme picks the adapter
+        RepositoryBackend::Database => match setup_database().await? {
+            DatabasePool::Postgres(pool) => build_routes(Storage {
+                users: PostgresUserRepository::new(pool.clone()),
+                credentials: Arc::new(PostgresCredentialRepository::new(pool.clone())),
+                api_keys: Arc::new(PostgresApiKeyRepository::new(pool.clone())),
+                sessions: Arc::new(PostgresSessionRepository::new(pool.clone())),
+                email_verifications: Arc::new(PostgresEmailVerificationRepository::new(pool.clone())),
+                password_resets: Arc::new(PostgresPasswordResetRepository::new(pool.clone())),
+                idempotency: Arc::new(PostgresIdempotencyRepository::new(pool.clone())),
+                audit_log: Arc::new(PostgresAuditLogRepository::new(pool.clone())),
+                outbox: Arc::new(PostgresOutboxRepository::new(pool.clone())),
+                webhooks: Arc::new(PostgresWebhookRepository::new(pool)),
+            })?,
+            #[cfg(feature = "sqlite")]
+            DatabasePool::Sqlite(pool) => build_routes(Storage {
+                users: SqliteUserRepository::new(pool.clone()),
+                credentials: Arc::new(SqliteCredentialRepository::new(pool.clone())),
+                api_keys: Arc::new(SqliteApiKeyRepository::new(pool.clone())),
+                sessions: Arc::new(SqliteSessionRepository::new(pool.clone())),
+                email_verifications: Arc::new(SqliteEmailVerificationRepository::new(pool.clone())),
+                password_resets: Arc::new(SqlitePasswordResetRepository::new(pool.clone())),
+                idempotency: Arc::new(SqliteIdempotencyRepository::new(pool.clone())),
+                audit_log: Arc::new(SqliteAuditLogRepository::new(pool.clone())),
+                outbox: Arc::new(SqliteOutboxRepository::new(pool.clone())),
+                webhooks: Arc::new(SqliteWebhookRepository::new(pool)),
+            })?,
+        },
+        RepositoryBackend::Memory => {
+            tracing::warn!("Using in-memory repository: data will be lost on shutdown");
+            let users = InMemoryUserRepository::new();
+            build_routes(Storage {
+                credentials: Arc::new(InMemoryCredentialRepository::new(users.clone())),
+                api_keys: Arc::new(InMemoryApiKeyRepository::new(users.clone())),
+                sessions: Arc::new(InMemorySessionRepository::new(users.clone())),
+                email_verifications: Arc::new(InMemoryEmailVerificationRepository::new(users.clone())),
+                password_resets: Arc::new(InMemoryPasswordResetRepository::new(users.clone())),
+                idempotency: Arc::new(InMemoryIdempotencyRepository::new()),
+                audit_log: Arc::new(InMemoryAuditLogRepository::new(users.clone())),
+                outbox: Arc::new(InMemoryOutboxRepository::new(users.clone())),
+                webhooks: Arc::new(InMemoryWebhookRepository::new()),
+                users,
+            })?
+        }
+    };
 
     // Create CORS layer
     let cors = CorsLayer::new()
         .allow_origin("http://localhost:3000".parse::<HeaderValue>().unwrap())
-        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE])
         .allow_credentials(true)
-        .allow_headers([AUTHORIZATION, ACCEPT, CONTENT_TYPE]);
+        .allow_headers([
+            AUTHORIZATION,
+            ACCEPT,
+            CONTENT_TYPE,
+            IF_MATCH,
+            X_API_KEY,
+            IDEMPOTENCY_KEY,
+            X_REQUEST_ID,
+            LAST_EVENT_ID,
+        ])
+        .expose_headers([
+            ETAG,
+            RETRY_AFTER,
+            RATELIMIT_LIMIT,
+            RATELIMIT_REMAINING,
+            RATELIMIT_RESET,
+            IDEMPOTENT_REPLAYED,
+            X_REQUEST_ID,
+        ]);
+
+    // Per-client token buckets, keyed by the same credentials the routes accept
+    let rate_limiter = RateLimiter::new(RateLimitConfig::from_env()?).with_guard(guard);
 
     // Build the application with middleware
-    let app = create_routes(user_app_service).layer(
+    let app = routes.layer(
         ServiceBuilder::new()
             .layer(TraceLayer::new_for_http())
-            .layer(cors),
+            .layer(cors)
+            .layer(middleware::from_fn_with_state(rate_limiter, rate_limit)),
     );
 
     // Create listener with TCP optimizations for high load
@@ -75,7 +176,99 @@ async fn main() -> Result<()> {
     tracing::info!("Connection pool: max={}, optimized for high-load scenarios", 100);
 
     // Start the server
-    axum::serve(listener, app).await.unwrap();
+    // Peer addresses key the rate limits of anonymous clients
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .await
+        .unwrap();
 
     Ok(())
 }
+
+/// Wire the application services, background jobs and HTTP routes over one
+/// storage backend; also returns the guard that identifies callers
+fn build_routes<R: UserRepositoryPort + 'static>(storage: Storage<R>) -> Result<(Router, AuthGuard)> {
+    let Storage {
+        users: repository,
+        credentials,
+        api_keys,
+        sessions,
+        email_verifications,
+        password_resets,
+        idempotency,
+        audit_log,
+        outbox,
+        webhooks,
+    } = storage;
+    let auth_config = AuthConfig::from_env()?;
+    let tokens = JwtAccessTokens::new(
+        auth_config.jwt_secret.as_bytes(),
+        auth_config.jwt_issuer.clone(),
+        auth_config.access_token_ttl,
+    );
+    let auth_service = AuthService::new(
+        repository.clone(),
+        credentials,
+        Arc::new(Argon2PasswordHasher::new()),
+        Arc::new(tokens),
+        sessions,
+    )
+    .with_refresh_token_ttl(auth_config.refresh_token_ttl)
+    .with_password_policy(PasswordPolicy::from_env()?);
+
+    let api_key_service = ApiKeyService::new(repository.clone(), api_keys);
+
+    let mailer = MailerConfig::from_env()?.into_mailer()?;
+    let email_verification = EmailVerificationService::new(repository.clone(), email_verifications, mailer.clone())
+        .with_config(EmailVerificationConfig::from_env()?);
+    let password_reset = PasswordResetService::new(repository.clone(), auth_service.clone(), password_resets, mailer)
+        .with_config(PasswordResetConfig::from_env()?);
+
+    let idempotency = IdempotencyService::new(idempotency).with_config(IdempotencyConfig::from_env()?);
+
+    let webhook_config = WebhookConfig::from_env()?;
+    let webhooks = WebhookService::new(webhooks, Arc::new(HttpWebhookSender::new(webhook_config.timeout)?))
+        .with_config(webhook_config)
+        .with_pagination(PaginationConfig::from_env()?);
+    spawn_webhook_dispatcher(webhooks.clone());
+
+    // The outbox relay queues webhook deliveries for user events, once across
+    // all instances. Every instance also follows the outbox on its own, to
+    // hand every event to its in-process broadcast bus and client streams.
+    let outbox_config = OutboxConfig::from_env()?;
+    spawn_outbox_relay(
+        OutboxRelay::new(outbox.clone(), Arc::new(webhooks.clone())).with_config(outbox_config.clone()),
+    );
+    let events = BroadcastEventPublisher::from_env()?;
+    spawn_event_log(events.subscribe());
+    let event_stream = UserEventStream::from_env()?;
+    let local_sinks = FanoutEventPublisher::new()
+        .with(Arc::new(events))
+        .with(Arc::new(event_stream.clone()));
+    spawn_outbox_tail(OutboxTail::new(outbox, Arc::new(local_sinks)).with_config(outbox_config));
+
+    let soft_delete = SoftDeleteConfig::from_env()?;
+    let purge_interval = soft_delete.purge_interval;
+    let app_service = UserApplicationService::new(repository)
+        .with_pagination(PaginationConfig::from_env()?)
+        .with_soft_delete(soft_delete)
+        .with_auth(auth_service.clone())
+        .with_email_verification(email_verification.clone())
+        .with_password_reset(password_reset.clone())
+        .with_idempotency(idempotency.clone())
+        .with_audit_log(audit_log);
+    spawn_purge_task(app_service.clone(), purge_interval);
+
+    let state = AppState::new(
+        app_service,
+        auth_service,
+        api_key_service,
+        email_verification,
+        password_reset,
+        idempotency,
+        webhooks,
+        event_stream,
+        &auth_config.public_routes,
+    );
+    let guard = state.guard.clone();
+    Ok((create_routes(state), guard))
+}
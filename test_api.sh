@@ -9,22 +9,30 @@ curl -X POST http://localhost:3000/api/users \
   -H "Content-Type: application/json" \
   -d '{
     "name": "John Doe",
-    "email": "john.doe@example.com"
+    "email": "john.doe@example.com",
+    "password": "correct horse battery"
   }'
 
+# Log in and keep the access token for the calls below
+TOKEN=$(curl -s -X POST http://localhost:3000/api/auth/login \
+  -H "Content-Type: application/json" \
+  -d '{"email": "john.doe@example.com", "password": "correct horse battery"}' \
+  | sed -E 's/.*"access_token":"([^"]+)".*/\1/')
+
 # Get all users
-curl -X GET http://localhost:3000/api/users
+curl -H "Authorization: Bearer $TOKEN" -X GET http://localhost:3000/api/users
 
 # Get user by ID (replace with actual UUID from create response)
-curl -X GET http://localhost:3000/api/users/YOUR_USER_ID_HERE
+curl -H "Authorization: Bearer $TOKEN" -X GET http://localhost:3000/api/users/YOUR_USER_ID_HERE
 
 # Update user (replace with actual UUID)
-curl -X PUT http://localhost:3000/api/users/YOUR_USER_ID_HERE \
+curl -H "Authorization: Bearer $TOKEN" -X PUT http://localhost:3000/api/users/YOUR_USER_ID_HERE \
   -H "Content-Type: application/json" \
   -d '{
     "name": "Jane Doe",
-    "email": "jane.doe@example.com"
+    "email": "jane.doe@example.com",
+    "role": "user"
   }'
 
 # Delete user (replace with actual UUID)
-curl -X DELETE http://localhost:3000/api/users/YOUR_USER_ID_HERE
+curl -H "Authorization: Bearer $TOKEN" -X DELETE http://localhost:3000/api/users/YOUR_USER_ID_HERE
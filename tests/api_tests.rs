@@ -0,0 +1,2317 @@
+use axum::{
+    Router,
+    body::{Body, BodyDataStream, to_bytes},
+    extract::State,
+    http::{HeaderMap, Request, StatusCode, header},
+    middleware,
+    routing::post,
+};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{Value, json};
+use tokio_tungstenite::{
+    MaybeTlsStream, WebSocketStream, connect_async,
+    tungstenite::{self, client::IntoClientRequest},
+};
+use tower::ServiceExt;
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use chrono::Duration;
+use rust_nexus::{
+    application::{
+        ApiKeyService, AuthConfig, AuthService, CreateUserDto, EmailVerificationService, IdempotencyService,
+        IdempotentStart, OutboxConfig, OutboxRelay, OutboxTail, PasswordPolicy, PasswordResetService,
+        UserApplicationService, WebhookConfig, WebhookService, webhook_signature,
+    },
+    domain::{
+        AccessTokenPort, CredentialRepositoryPort, Email, EventPublisherPort, InfrastructureError, MailMessage,
+        MailerPort, PasswordHash, Principal, Role, UserError, UserEvent, UserId, UserRepositoryPort,
+    },
+    infrastructure::{
+        AppState, Argon2PasswordHasher, BroadcastEventPublisher, FanoutEventPublisher, HttpWebhookSender,
+        InMemoryApiKeyRepository, InMemoryAuditLogRepository, InMemoryCredentialRepository,
+        InMemoryEmailVerificationRepository, InMemoryIdempotencyRepository, InMemoryOutboxRepository,
+        InMemoryPasswordResetRepository, InMemorySessionRepository, InMemoryUserRepository, InMemoryWebhookRepository,
+        JwtAccessTokens, RateLimitConfig, RateLimiter, UserEventStream, create_routes, rate_limit, spawn_outbox_tail,
+        web::auth::AuthGuard,
+    },
+};
+
+/// Router plus an admin access token that requests carry by default
+struct TestApp {
+    router: Router,
+    token: String,
+    tokens: Arc<JwtAccessTokens>,
+    outbox: Outbox,
+    guard: AuthGuard,
+    idempotency: IdempotencyService,
+    events: BroadcastEventPublisher,
+    event_outbox: InMemoryOutboxRepository,
+    webhooks: WebhookService,
+}
+
+/// Mailer keeping every message for inspection
+#[derive(Clone, Default)]
+struct Outbox(Arc<Mutex<Vec<MailMessage>>>);
+
+#[async_trait]
+impl MailerPort for Outbox {
+    async fn send(&self, message: &MailMessage) -> Result<(), UserError> {
+        self.0.lock().unwrap().push(message.clone());
+        Ok(())
+    }
+}
+
+impl Outbox {
+    /// The token (first word with `prefix`) of the latest message
+    fn last_token(&self, prefix: &str) -> String {
+        let messages = self.0.lock().unwrap();
+        let body = &messages.last().expect("no mail sent").body;
+        body.split_whitespace().find(|word| word.starts_with(prefix)).unwrap().to_string()
+    }
+
+    fn len(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
+}
+
+/// Credential store whose writes fail, as when the database goes away
+/// between storing a new user and its password
+struct BrokenCredentials;
+
+#[async_trait]
+impl CredentialRepositoryPort for BrokenCredentials {
+    async fn set_password_hash(&self, _: &UserId, _: &PasswordHash) -> Result<(), UserError> {
+        Err(UserError::Unavailable(InfrastructureError::new("store password", "database unavailable")))
+    }
+
+    async fn find_password_hash(&self, _: &UserId) -> Result<Option<PasswordHash>, UserError> {
+        Ok(None)
+    }
+}
+
+/// Event sink that rejects deliveries while `failing` is set
+#[derive(Clone, Default)]
+struct FlakySink {
+    failing: Arc<Mutex<bool>>,
+    delivered: Arc<Mutex<Vec<UserEvent>>>,
+}
+
+#[async_trait]
+impl EventPublisherPort for FlakySink {
+    async fn publish(&self, event: &UserEvent) -> Result<(), UserError> {
+        if *self.failing.lock().unwrap() {
+            return Err(UserError::Unavailable(InfrastructureError::new("publish event", "sink unavailable")));
+        }
+        self.delivered.lock().unwrap().push(event.clone());
+        Ok(())
+    }
+}
+
+/// Local webhook receiver answering with the queued statuses, then 200
+#[derive(Clone, Default)]
+struct Receiver {
+    statuses: Arc<Mutex<VecDeque<u16>>>,
+    received: Arc<Mutex<Vec<(HeaderMap, String)>>>,
+}
+
+impl Receiver {
+    /// Serve on a free local port; returns the URL to subscribe
+    async fn start(&self) -> String {
+        async fn receive(State(receiver): State<Receiver>, headers: HeaderMap, body: String) -> StatusCode {
+            receiver.received.lock().unwrap().push((headers, body));
+            let status = receiver.statuses.lock().unwrap().pop_front().unwrap_or(200);
+            StatusCode::from_u16(status).unwrap()
+        }
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hooks", listener.local_addr().unwrap());
+        let router = Router::new().route("/hooks", post(receive)).with_state(self.clone());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        url
+    }
+
+    fn answer(&self, statuses: &[u16]) {
+        self.statuses.lock().unwrap().extend(statuses);
+    }
+
+    fn last(&self) -> (HeaderMap, String) {
+        self.received.lock().unwrap().last().expect("no webhook received").clone()
+    }
+
+    fn len(&self) -> usize {
+        self.received.lock().unwrap().len()
+    }
+}
+
+impl TestApp {
+    /// `Authorization` header value for the user `id` with `role`
+    fn bearer_for(&self, id: &str, role: Role) -> String {
+        let caller = Principal {
+            user_id: UserId::from_uuid(id.parse().unwrap()),
+            role,
+            scopes: None,
+            expires_at: None,
+        };
+        format!("Bearer {}", self.tokens.issue(&caller).unwrap().token)
+    }
+}
+
+fn app() -> TestApp {
+    let repository = InMemoryUserRepository::new();
+    let tokens = Arc::new(JwtAccessTokens::new(b"test-secret", "rust-nexus-tests", Duration::minutes(5)));
+    let auth = AuthService::new(
+        repository.clone(),
+        Arc::new(InMemoryCredentialRepository::new(repository.clone())),
+        Arc::new(Argon2PasswordHasher::new()),
+        tokens.clone(),
+        Arc::new(InMemorySessionRepository::new(repository.clone())),
+    )
+    .with_password_policy(PasswordPolicy {
+        min_length: 10,
+        ..PasswordPolicy::default()
+    });
+    let api_keys = ApiKeyService::new(
+        repository.clone(),
+        Arc::new(InMemoryApiKeyRepository::new(repository.clone())),
+    );
+    let outbox = Outbox::default();
+    let email_verification = EmailVerificationService::new(
+        repository.clone(),
+        Arc::new(InMemoryEmailVerificationRepository::new(repository.clone())),
+        Arc::new(outbox.clone()),
+    );
+    let password_reset = PasswordResetService::new(
+        repository.clone(),
+        auth.clone(),
+        Arc::new(InMemoryPasswordResetRepository::new(repository.clone())),
+        Arc::new(outbox.clone()),
+    );
+    let idempotency = IdempotencyService::new(Arc::new(InMemoryIdempotencyRepository::new()));
+    let audit_log = Arc::new(InMemoryAuditLogRepository::new(repository.clone()));
+    let event_outbox = InMemoryOutboxRepository::new(repository.clone());
+    let events = BroadcastEventPublisher::default();
+    // A small buffer, and heartbeats quick enough to wait for
+    let event_stream = UserEventStream::new(8).with_heartbeat(std::time::Duration::from_millis(100));
+    // Followed from the first message, and often enough not to wait on
+    let local_sinks = FanoutEventPublisher::new()
+        .with(Arc::new(events.clone()))
+        .with(Arc::new(event_stream.clone()));
+    let tail_config = OutboxConfig {
+        poll_interval: std::time::Duration::from_millis(5),
+        ..OutboxConfig::default()
+    };
+    spawn_outbox_tail(
+        OutboxTail::new(Arc::new(event_outbox.clone()), Arc::new(local_sinks))
+            .with_config(tail_config)
+            .starting_after(0),
+    );
+    let users = UserApplicationService::new(repository)
+        .with_auth(auth.clone())
+        .with_email_verification(email_verification.clone())
+        .with_password_reset(password_reset.clone())
+        .with_audit_log(audit_log);
+    // Retries are due at once, so each dispatch makes the next attempt
+    let webhooks = WebhookService::new(
+        Arc::new(InMemoryWebhookRepository::new()),
+        Arc::new(HttpWebhookSender::new(std::time::Duration::from_secs(5)).unwrap()),
+    )
+    .with_config(WebhookConfig {
+        max_attempts: 3,
+        retry_base: Duration::zero(),
+        ..WebhookConfig::default()
+    });
+    let state = AppState::new(
+        users,
+        auth,
+        api_keys,
+        email_verification,
+        password_reset,
+        idempotency.clone(),
+        webhooks.clone(),
+        event_stream,
+        &AuthConfig::default_public_routes(),
+    );
+    let guard = state.guard.clone();
+    let router = create_routes(state);
+
+    let caller = Principal {
+        user_id: UserId::new(),
+        role: Role::Admin,
+        scopes: None,
+        expires_at: None,
+    };
+    let token = tokens.issue(&caller).unwrap().token;
+    TestApp {
+        router,
+        token,
+        tokens,
+        outbox,
+        guard,
+        idempotency,
+        events,
+        event_outbox,
+        webhooks,
+    }
+}
+
+/// The app behind the rate limiter, as installed in `main.rs`
+fn rate_limited_app(config: RateLimitConfig) -> TestApp {
+    let app = app();
+    let limiter = RateLimiter::new(config).with_guard(app.guard.clone());
+    TestApp {
+        router: app.router.clone().layer(middleware::from_fn_with_state(limiter, rate_limit)),
+        ..app
+    }
+}
+
+async fn send(app: &TestApp, method: &str, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let (status, _, json) = send_with_headers(app, method, uri, &[], body).await;
+    (status, json)
+}
+
+/// Send a request; JSON content type and the app's token are added unless given
+async fn send_with_headers(
+    app: &TestApp,
+    method: &str,
+    uri: &str,
+    headers: &[(&str, &str)],
+    body: Option<Value>,
+) -> (StatusCode, HeaderMap, Value) {
+    let mut request = Request::builder().method(method).uri(uri);
+    let has = |header: &str| headers.iter().any(|(name, _)| name.eq_ignore_ascii_case(header));
+    if !has("content-type") {
+        request = request.header("content-type", "application/json");
+    }
+    if !has("authorization") {
+        request = request.header("authorization", format!("Bearer {}", app.token));
+    }
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let request = request
+        .body(body.map_or_else(Body::empty, |b| Body::from(b.to_string())))
+        .unwrap();
+
+    let response = app.router.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+    (status, headers, json)
+}
+
+/// Reads the messages of an event stream response
+struct EventStreamReader {
+    body: BodyDataStream,
+    pending: String,
+}
+
+impl EventStreamReader {
+    /// Open `GET uri` with the app's token unless `headers` has one
+    async fn open(app: &TestApp, uri: &str, headers: &[(&str, &str)]) -> (StatusCode, Self) {
+        let mut request = Request::builder().method("GET").uri(uri);
+        if !headers.iter().any(|(name, _)| name.eq_ignore_ascii_case("authorization")) {
+            request = request.header("authorization", format!("Bearer {}", app.token));
+        }
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let response = app.router.clone().oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+        let status = response.status();
+        let reader = Self {
+            body: response.into_body().into_data_stream(),
+            pending: String::new(),
+        };
+        (status, reader)
+    }
+
+    /// The `(field, value)` lines of the next message; a heartbeat is a
+    /// comment, `("", "")`
+    async fn next(&mut self) -> Vec<(String, String)> {
+        loop {
+            if let Some(end) = self.pending.find("\n\n") {
+                let message: String = self.pending.drain(..end + 2).collect();
+                return message
+                    .lines()
+                    .filter(|line| !line.is_empty())
+                    .map(|line| {
+                        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+                        (field.to_string(), value.trim_start().to_string())
+                    })
+                    .collect();
+            }
+            let chunk = tokio::time::timeout(std::time::Duration::from_secs(5), self.body.next())
+                .await
+                .expect("no message within 5s")
+                .expect("stream ended")
+                .unwrap();
+            self.pending.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+    }
+
+    /// The next message that is not a heartbeat
+    async fn next_event(&mut self) -> Vec<(String, String)> {
+        loop {
+            let message = self.next().await;
+            if field(&message, "").is_none() {
+                return message;
+            }
+        }
+    }
+
+    /// Whether the server ends the stream within 5s, skipping what it sends first
+    async fn ends(&mut self) -> bool {
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            while let Some(chunk) = self.body.next().await {
+                chunk.unwrap();
+            }
+        })
+        .await
+        .is_ok()
+    }
+}
+
+/// Value of `field` in a stream message
+fn field<'a>(message: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    message.iter().find(|(field, _)| field == name).map(|(_, value)| value.as_str())
+}
+
+/// WebSocket client connected to the app served on a free local port
+struct WsClient {
+    socket: WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>,
+}
+
+impl WsClient {
+    /// Connect to `/ws` with `authorization`, if any; the error is the
+    /// rejected upgrade
+    async fn connect(app: &TestApp, authorization: Option<&str>) -> Result<Self, tungstenite::Error> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut request = format!("ws://{}/ws", listener.local_addr().unwrap()).into_client_request().unwrap();
+        let router = app.router.clone();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        if let Some(authorization) = authorization {
+            request.headers_mut().insert("authorization", authorization.parse().unwrap());
+        }
+        let (socket, _) = connect_async(request).await?;
+        Ok(Self { socket })
+    }
+
+    async fn send(&mut self, message: Value) {
+        self.socket.send(tungstenite::Message::text(message.to_string())).await.unwrap();
+    }
+
+    /// The next message, skipping pings; `None` once the server closed
+    async fn next(&mut self) -> Option<Value> {
+        loop {
+            let message = tokio::time::timeout(std::time::Duration::from_secs(5), self.socket.next())
+                .await
+                .expect("no message within 5s")?
+                .unwrap();
+            match message {
+                tungstenite::Message::Text(text) => return Some(serde_json::from_str(text.as_str()).unwrap()),
+                tungstenite::Message::Close(_) => return None,
+                _ => {}
+            }
+        }
+    }
+
+    /// Send `message` and return the reply
+    async fn request(&mut self, message: Value) -> Value {
+        self.send(message).await;
+        self.next().await.expect("connection closed")
+    }
+}
+
+/// Sign up a user with a password and log in; returns its id and the tokens
+async fn sign_up_and_log_in(app: &TestApp, email: &str) -> (String, Value) {
+    let no_token = [("authorization", "")];
+    let (status, _, created) = send_with_headers(
+        app,
+        "POST",
+        "/api/users",
+        &no_token,
+        Some(json!({ "name": "Session User", "email": email, "password": "correct horse" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    (created["data"]["id"].as_str().unwrap().to_string(), log_in(app, email).await)
+}
+
+/// Log in a user signed up by `sign_up_and_log_in`; returns the tokens
+async fn log_in(app: &TestApp, email: &str) -> Value {
+    let no_token = [("authorization", "")];
+    let (status, _, body) = send_with_headers(
+        app,
+        "POST",
+        "/api/auth/login",
+        &no_token,
+        Some(json!({ "email": email, "password": "correct horse" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    body["data"].clone()
+}
+
+/// Next event on the bus, which the outbox tail feeds shortly after the change
+async fn next_event(events: &mut tokio::sync::broadcast::Receiver<UserEvent>) -> UserEvent {
+    tokio::time::timeout(std::time::Duration::from_secs(5), events.recv()).await.unwrap().unwrap()
+}
+
+/// Sign up a user, make it an admin and log in again. Unlike `app.token`,
+/// the tokens stand for a stored user with a session, which WebSocket
+/// connections check again with every heartbeat.
+async fn sign_up_admin_and_log_in(app: &TestApp, email: &str) -> (String, Value) {
+    let mut events = app.events.subscribe();
+    let (id, _) = sign_up_and_log_in(app, email).await;
+    let merge = [("content-type", "application/merge-patch+json")];
+    let uri = format!("/api/users/{}", id);
+    let (status, _, _) = send_with_headers(app, "PATCH", &uri, &merge, Some(json!({ "role": "admin" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    // Both changes are streamed before the caller connects
+    next_event(&mut events).await;
+    next_event(&mut events).await;
+    (id, log_in(app, email).await)
+}
+
+/// POST a refresh token to one of the session endpoints
+async fn send_refresh_token(app: &TestApp, uri: &str, refresh_token: &Value) -> (StatusCode, Value) {
+    let (status, _, body) = send_with_headers(
+        app,
+        "POST",
+        uri,
+        &[("authorization", "")],
+        Some(json!({ "refresh_token": refresh_token })),
+    )
+    .await;
+    (status, body)
+}
+
+#[tokio::test]
+async fn test_create_and_fetch_user_without_postgres() {
+    let app = app();
+
+    let (status, created) = send(
+        &app,
+        "POST",
+        "/api/users",
+        Some(json!({ "name": "John Doe", "email": "john.doe@example.com" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let id = created["data"]["id"].as_str().unwrap().to_string();
+
+    let (status, fetched) = send(&app, "GET", &format!("/api/users/{}", id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(fetched["data"]["email"], "john.doe@example.com");
+}
+
+#[tokio::test]
+async fn test_duplicate_email_returns_conflict_with_error_code() {
+    let app = app();
+    let payload = json!({ "name": "John Doe", "email": "john.doe@example.com" });
+    send(&app, "POST", "/api/users", Some(payload)).await;
+
+    let (status, body) = send(
+        &app,
+        "POST",
+        "/api/users",
+        Some(json!({ "name": "John Clone", "email": "John.Doe@Example.com" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["error_code"], "EMAIL_ALREADY_EXISTS");
+    assert_eq!(body["retryable"], false);
+}
+
+#[tokio::test]
+async fn test_missing_user_returns_not_found() {
+    let app = app();
+    let uri = format!("/api/users/{}", uuid::Uuid::new_v4());
+
+    let (status, body) = send(&app, "GET", &uri, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error_code"], "USER_NOT_FOUND");
+
+    let (status, _) = send(&app, "DELETE", &uri, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_cursor_pagination_follows_next_cursor() {
+    let app = app();
+    for i in 0..3 {
+        let payload = json!({ "name": format!("User {}", i), "email": format!("user{}@example.com", i) });
+        send(&app, "POST", "/api/users", Some(payload)).await;
+    }
+
+    let (status, first) = send(&app, "GET", "/api/users?limit=2", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(first["data"].as_array().unwrap().len(), 2);
+    let cursor = first["pagination"]["next_cursor"].as_str().unwrap().to_string();
+
+    let (status, second) = send(&app, "GET", &format!("/api/users?after={}&limit=2", cursor), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(second["data"].as_array().unwrap().len(), 1);
+    assert!(second["pagination"]["next_cursor"].is_null());
+
+    let (status, body) = send(&app, "GET", "/api/users?after=not-a-cursor", None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error_code"], "INVALID_PAGINATION");
+}
+
+#[tokio::test]
+async fn test_page_listing_reports_totals_and_links() {
+    let app = app();
+    for i in 0..3 {
+        let payload = json!({ "name": format!("User {}", i), "email": format!("user{}@example.com", i) });
+        send(&app, "POST", "/api/users", Some(payload)).await;
+    }
+
+    let (status, body) = send(&app, "GET", "/api/users?page=1&limit=2", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let pagination = &body["pagination"];
+    assert_eq!(pagination["page"], 1);
+    assert_eq!(pagination["total"], 3);
+    assert_eq!(pagination["has_more"], false);
+    assert!(pagination["next"].is_null());
+    assert_eq!(pagination["prev"], "/api/users?page=0&limit=2");
+}
+
+#[tokio::test]
+async fn test_out_of_range_pagination_is_rejected() {
+    let app = app();
+    for uri in ["/api/users?limit=1000000", "/api/users?limit=0", "/api/users?page=-1"] {
+        let (status, body) = send(&app, "GET", uri, None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+        assert_eq!(body["error_code"], "INVALID_PAGINATION");
+    }
+}
+
+#[tokio::test]
+async fn test_filters_and_sort_are_applied_and_kept_in_links() {
+    let app = app();
+    for (name, email) in [("Alice", "alice@example.com"), ("Albert", "albert@example.com"), ("Bob", "bob@corp.io")] {
+        send(&app, "POST", "/api/users", Some(json!({ "name": name, "email": email }))).await;
+    }
+
+    let (status, body) = send(&app, "GET", "/api/users?name_prefix=al&sort=name:asc&limit=1", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["name"], "Albert");
+    assert_eq!(body["pagination"]["total"], 2);
+    assert_eq!(body["pagination"]["next"], "/api/users?name_prefix=al&sort=name:asc&page=1&limit=1");
+
+    let (status, body) = send(&app, "GET", "/api/users?sort=password", None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error_code"], "INVALID_QUERY");
+}
+
+#[tokio::test]
+async fn test_deleted_user_can_be_restored() {
+    let app = app();
+    let (_, created) = send(
+        &app,
+        "POST",
+        "/api/users",
+        Some(json!({ "name": "John Doe", "email": "john.doe@example.com" })),
+    )
+    .await;
+    let id = created["data"]["id"].as_str().unwrap().to_string();
+
+    let (status, _) = send(&app, "DELETE", &format!("/api/users/{}", id), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _) = send(&app, "GET", &format!("/api/users/{}", id), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, deleted) =
+        send(&app, "GET", &format!("/api/users/{}?include_deleted=true", id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(deleted["data"]["deleted_at"].is_string());
+
+    let (_, listed) = send(&app, "GET", "/api/users?include_deleted=true", None).await;
+    assert_eq!(listed["pagination"]["total"], 1);
+
+    let (status, restored) = send(&app, "POST", &format!("/api/users/{}/restore", id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(restored["data"].get("deleted_at").is_none());
+
+    let (status, _) = send(&app, "POST", &format!("/api/users/{}/restore", id), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_if_match_guards_updates_and_deletes() {
+    let app = app();
+    let (_, created) = send(
+        &app,
+        "POST",
+        "/api/users",
+        Some(json!({ "name": "John Doe", "email": "john.doe@example.com" })),
+    )
+    .await;
+    let uri = format!("/api/users/{}", created["data"]["id"].as_str().unwrap());
+
+    let (_, headers, _) = send_with_headers(&app, "GET", &uri, &[], None).await;
+    let etag = headers[header::ETAG].to_str().unwrap().to_string();
+    assert_eq!(etag, "\"1\"");
+
+    let rename = json!({ "name": "Jane Doe", "email": "john.doe@example.com", "role": "user" });
+    let (status, headers, updated) =
+        send_with_headers(&app, "PUT", &uri, &[("if-match", &etag)], Some(rename.clone())).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["data"]["version"], 2);
+    assert_eq!(headers[header::ETAG], "\"2\"");
+
+    // The old tag is stale now
+    let (status, _, body) =
+        send_with_headers(&app, "PUT", &uri, &[("if-match", &etag)], Some(rename)).await;
+    assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(body["error_code"], "PRECONDITION_FAILED");
+
+    let (status, _, _) = send_with_headers(&app, "DELETE", &uri, &[("if-match", &etag)], None).await;
+    assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+
+    let (status, _, _) = send_with_headers(&app, "DELETE", &uri, &[("if-match", "\"2\"")], None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn test_patch_applies_merge_and_json_patches() {
+    let app = app();
+    let (_, created) = send(
+        &app,
+        "POST",
+        "/api/users",
+        Some(json!({ "name": "John Doe", "email": "john.doe@example.com" })),
+    )
+    .await;
+    let uri = format!("/api/users/{}", created["data"]["id"].as_str().unwrap());
+    let merge = [("content-type", "application/merge-patch+json")];
+    let json_patch = [("content-type", "application/json-patch+json")];
+
+    let (status, _, patched) =
+        send_with_headers(&app, "PATCH", &uri, &merge, Some(json!({ "name": "Jane Doe" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(patched["data"]["name"], "Jane Doe");
+    assert_eq!(patched["data"]["email"], "john.doe@example.com");
+
+    let ops = json!([
+        { "op": "test", "path": "/version", "value": 2 },
+        { "op": "replace", "path": "/email", "value": "jane.doe@example.com" }
+    ]);
+    let (status, _, patched) = send_with_headers(&app, "PATCH", &uri, &json_patch, Some(ops)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(patched["data"]["email"], "jane.doe@example.com");
+
+    // Patches are validated like any other update. No writable field is
+    // optional, so none can be cleared.
+    let clearing = [(&merge, json!({ "name": null })), (&json_patch, json!([{ "op": "remove", "path": "/role" }]))];
+    for (headers, clear) in clearing {
+        let (status, _, body) = send_with_headers(&app, "PATCH", &uri, headers, Some(clear)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error_code"], "INVALID_PATCH");
+        assert!(body["error"].as_str().unwrap().contains("cannot be removed"));
+    }
+    let cases = [
+        (&merge, json!({ "id": "00000000-0000-0000-0000-000000000000" }), StatusCode::UNPROCESSABLE_ENTITY),
+        (&merge, json!({ "email": "not-an-email" }), StatusCode::BAD_REQUEST),
+        (&json_patch, json!([{ "op": "test", "path": "/version", "value": 1 }]), StatusCode::UNPROCESSABLE_ENTITY),
+    ];
+    for (headers, patch, expected) in cases {
+        let (status, _, _) = send_with_headers(&app, "PATCH", &uri, headers, Some(patch.clone())).await;
+        assert_eq!(status, expected, "{}", patch);
+    }
+
+    let (status, _, body) = send_with_headers(&app, "PATCH", &uri, &[], Some(json!({ "name": "X" }))).await;
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(body["error_code"], "UNSUPPORTED_PATCH_FORMAT");
+
+    let (_, fetched) = send(&app, "GET", &uri, None).await;
+    assert_eq!(fetched["data"]["version"], 3);
+}
+
+#[tokio::test]
+async fn test_put_replaces_the_whole_user() {
+    let app = app();
+    let (_, created) = send(
+        &app,
+        "POST",
+        "/api/users",
+        Some(json!({ "name": "John Doe", "email": "john.doe@example.com" })),
+    )
+    .await;
+    let uri = format!("/api/users/{}", created["data"]["id"].as_str().unwrap());
+
+    // Every writable field is required, the role too
+    for partial in [json!({ "name": "Jane Doe" }), json!({ "name": "Jane Doe", "email": "jane.doe@example.com" })] {
+        let (status, _) = send(&app, "PUT", &uri, Some(partial)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let (status, replaced) = send(
+        &app,
+        "PUT",
+        &uri,
+        Some(json!({ "name": "Jane Doe", "email": "jane.doe@example.com", "role": "user" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(replaced["data"]["email"], "jane.doe@example.com");
+}
+
+#[tokio::test]
+async fn test_users_require_a_valid_token() {
+    let app = app();
+
+    for authorization in ["", "Bearer not-a-jwt", "Basic dXNlcjpwYXNz"] {
+        let (status, headers, body) =
+            send_with_headers(&app, "GET", "/api/users", &[("authorization", authorization)], None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{:?}", authorization);
+        assert_eq!(headers[header::WWW_AUTHENTICATE], "Bearer");
+        assert_eq!(body["error_code"], "UNAUTHENTICATED");
+    }
+
+    // Public routes stay reachable without a token
+    let (status, _, _) = send_with_headers(&app, "GET", "/health", &[("authorization", "")], None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_sign_up_is_undone_when_the_password_cannot_be_stored() {
+    let repository = InMemoryUserRepository::new();
+    let auth = AuthService::new(
+        repository.clone(),
+        Arc::new(BrokenCredentials),
+        Arc::new(Argon2PasswordHasher::new()),
+        Arc::new(JwtAccessTokens::new(b"test-secret", "rust-nexus-tests", Duration::minutes(5))),
+        Arc::new(InMemorySessionRepository::new(repository.clone())),
+    );
+    let users = UserApplicationService::new(repository.clone()).with_auth(auth);
+    let sign_up = || CreateUserDto {
+        name: "John Doe".to_string(),
+        email: "john.doe@example.com".to_string(),
+        role: None,
+        password: Some("correct horse".to_string()),
+    };
+
+    let result = users.create_user(None, sign_up(), None).await;
+    assert!(matches!(result, Err(UserError::Unavailable(_))), "{:?}", result);
+    let email = Email::new("john.doe@example.com".to_string()).unwrap();
+    assert!(!repository.exists_by_email(&email).await.unwrap());
+    // A retry fails the same way rather than with a conflict
+    assert!(matches!(users.create_user(None, sign_up(), None).await, Err(UserError::Unavailable(_))));
+}
+
+#[tokio::test]
+async fn test_login_with_password_issues_a_working_token() {
+    let app = app();
+    let no_token = [("authorization", "")];
+    let (status, _, created) = send_with_headers(
+        &app,
+        "POST",
+        "/api/users",
+        &no_token,
+        Some(json!({ "name": "John Doe", "email": "john.doe@example.com", "password": "correct horse" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert!(created["data"].get("password").is_none());
+
+    let (status, _, body) = send_with_headers(
+        &app,
+        "POST",
+        "/api/auth/login",
+        &no_token,
+        Some(json!({ "email": "John.Doe@example.com", "password": "wrong horse" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error_code"], "INVALID_CREDENTIALS");
+
+    let (status, _, body) = send_with_headers(
+        &app,
+        "POST",
+        "/api/auth/login",
+        &no_token,
+        Some(json!({ "email": "john.doe@example.com", "password": "correct horse" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["token_type"], "Bearer");
+
+    let bearer = format!("Bearer {}", body["data"]["access_token"].as_str().unwrap());
+    let (status, _, me) =
+        send_with_headers(&app, "GET", "/api/auth/me", &[("authorization", &bearer)], None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(me["data"]["id"], created["data"]["id"]);
+}
+
+#[tokio::test]
+async fn test_short_password_is_rejected_before_the_user_is_created() {
+    let app = app();
+    let (status, body) = send(
+        &app,
+        "POST",
+        "/api/users",
+        Some(json!({ "name": "John Doe", "email": "john.doe@example.com", "password": "short" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error_code"], "INVALID_PASSWORD");
+
+    let (_, listed) = send(&app, "GET", "/api/users", None).await;
+    assert_eq!(listed["pagination"]["total"], 0);
+}
+
+#[tokio::test]
+async fn test_users_can_only_read_and_update_themselves() {
+    let app = app();
+    let (_, alice) = send(&app, "POST", "/api/users", Some(json!({ "name": "Alice", "email": "alice@example.com" }))).await;
+    let (_, bob) = send(&app, "POST", "/api/users", Some(json!({ "name": "Bob", "email": "bob@example.com" }))).await;
+    let alice_id = alice["data"]["id"].as_str().unwrap();
+    let bob_id = bob["data"]["id"].as_str().unwrap();
+    assert_eq!(alice["data"]["role"], "user");
+
+    let as_alice = app.bearer_for(alice_id, Role::User);
+    let auth = [("authorization", as_alice.as_str())];
+    let merge = [("authorization", as_alice.as_str()), ("content-type", "application/merge-patch+json")];
+
+    let (status, _, _) = send_with_headers(&app, "GET", &format!("/api/users/{}", alice_id), &auth, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _, body) = send_with_headers(&app, "GET", &format!("/api/users/{}", bob_id), &auth, None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error_code"], "FORBIDDEN");
+    let (status, _, _) = send_with_headers(&app, "GET", "/api/users", &auth, None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let uri = format!("/api/users/{}", alice_id);
+    let (status, _, body) = send_with_headers(&app, "PATCH", &uri, &merge, Some(json!({ "name": "Alice B" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["name"], "Alice B");
+    let (status, _, _) = send_with_headers(&app, "PATCH", &uri, &merge, Some(json!({ "role": "admin" }))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, _) = send_with_headers(
+        &app,
+        "PATCH",
+        &format!("/api/users/{}", bob_id),
+        &merge,
+        Some(json!({ "name": "Mallory" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, _) = send_with_headers(&app, "DELETE", &uri, &auth, None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Admins may change roles
+    let (status, _, body) = send_with_headers(
+        &app,
+        "PATCH",
+        &format!("/api/users/{}", bob_id),
+        &[("content-type", "application/merge-patch+json")],
+        Some(json!({ "role": "readonly" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["role"], "readonly");
+}
+
+#[tokio::test]
+async fn test_readonly_accounts_can_only_list() {
+    let app = app();
+    let (_, user) = send(&app, "POST", "/api/users", Some(json!({ "name": "Reporter", "email": "reports@example.com" }))).await;
+    let id = user["data"]["id"].as_str().unwrap();
+    let as_readonly = app.bearer_for(id, Role::ReadOnly);
+    let auth = [("authorization", as_readonly.as_str())];
+
+    let (status, _, body) = send_with_headers(&app, "GET", "/api/users", &auth, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["pagination"]["total"], 1);
+    let (status, _, _) = send_with_headers(&app, "GET", "/api/users?include_deleted=true", &auth, None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, _) = send_with_headers(&app, "GET", &format!("/api/users/{}", id), &auth, None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, _) = send_with_headers(
+        &app,
+        "PUT",
+        &format!("/api/users/{}", id),
+        &auth,
+        Some(json!({ "name": "Reporter", "email": "reports@example.com", "role": "readonly" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_only_admins_assign_roles_on_create() {
+    let app = app();
+    let (status, _, body) = send_with_headers(
+        &app,
+        "POST",
+        "/api/users",
+        &[("authorization", "")],
+        Some(json!({ "name": "Eve", "email": "eve@example.com", "role": "admin" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error_code"], "FORBIDDEN");
+
+    let (status, body) = send(
+        &app,
+        "POST",
+        "/api/users",
+        Some(json!({ "name": "Ops", "email": "ops@example.com", "role": "admin" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["data"]["role"], "admin");
+}
+
+#[tokio::test]
+async fn test_api_keys_act_as_their_owner_within_scopes() {
+    let app = app();
+    let (_, service) = send(
+        &app,
+        "POST",
+        "/api/users",
+        Some(json!({ "name": "Reports", "email": "reports@example.com", "role": "readonly" })),
+    )
+    .await;
+    let (_, ops) = send(
+        &app,
+        "POST",
+        "/api/users",
+        Some(json!({ "name": "Ops", "email": "ops@example.com", "role": "admin" })),
+    )
+    .await;
+    let service_id = service["data"]["id"].as_str().unwrap();
+    let ops_id = ops["data"]["id"].as_str().unwrap();
+
+    let (status, body) = send(
+        &app,
+        "POST",
+        "/api/api-keys",
+        Some(json!({ "name": "reports", "owner_id": service_id, "scopes": ["users:read"] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let reports_key = body["data"]["key"].as_str().unwrap().to_string();
+    let reports_key_id = body["data"]["id"].as_str().unwrap().to_string();
+    assert!(reports_key.starts_with(body["data"]["prefix"].as_str().unwrap()));
+
+    // Both header forms authenticate
+    let (status, _, _) =
+        send_with_headers(&app, "GET", "/api/users", &[("authorization", ""), ("x-api-key", &reports_key)], None).await;
+    assert_eq!(status, StatusCode::OK);
+    let scheme = format!("ApiKey {}", reports_key);
+    let (status, _, _) = send_with_headers(&app, "GET", "/api/users", &[("authorization", &scheme)], None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // An admin's read-only key reads anyone but cannot write or mint keys
+    let (_, body) = send(
+        &app,
+        "POST",
+        "/api/api-keys",
+        Some(json!({ "name": "ops", "owner_id": ops_id, "scopes": ["users:read"] })),
+    )
+    .await;
+    let ops_key = format!("ApiKey {}", body["data"]["key"].as_str().unwrap());
+    let ops_auth = [("authorization", ops_key.as_str())];
+    let (status, _, _) = send_with_headers(&app, "GET", &format!("/api/users/{}", service_id), &ops_auth, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _, body) = send_with_headers(
+        &app,
+        "PUT",
+        &format!("/api/users/{}", service_id),
+        &ops_auth,
+        Some(json!({ "name": "Renamed", "email": "reports@example.com", "role": "readonly" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error_code"], "FORBIDDEN");
+    let (status, _, _) = send_with_headers(
+        &app,
+        "POST",
+        "/api/api-keys",
+        &ops_auth,
+        Some(json!({ "name": "escalated", "scopes": ["users:write"] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, listed) = send(&app, "GET", &format!("/api/api-keys?owner_id={}", service_id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed["data"].as_array().unwrap().len(), 1);
+    assert!(listed["data"][0].get("key").is_none());
+    assert!(listed["data"][0]["last_used_at"].is_string());
+    // Another use right away does not move `last_used_at`
+    let (status, _, _) = send_with_headers(&app, "GET", "/api/users", &[("authorization", &scheme)], None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, relisted) = send(&app, "GET", &format!("/api/api-keys?owner_id={}", service_id), None).await;
+    assert_eq!(relisted["data"][0]["last_used_at"], listed["data"][0]["last_used_at"]);
+
+    let (status, _) = send(&app, "DELETE", &format!("/api/api-keys/{}", reports_key_id), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _, _) =
+        send_with_headers(&app, "GET", "/api/users", &[("authorization", ""), ("x-api-key", &reports_key)], None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, body) = send(&app, "POST", "/api/api-keys", Some(json!({ "name": "bad", "scopes": ["users:admin"] }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error_code"], "INVALID_API_KEY");
+}
+
+#[tokio::test]
+async fn test_refresh_tokens_rotate_and_reuse_revokes_the_session() {
+    let app = app();
+    let (_, login) = sign_up_and_log_in(&app, "rotate@example.com").await;
+    let first = login["refresh_token"].clone();
+    assert!(first.as_str().unwrap().starts_with("rnr_"));
+    assert!(login["refresh_expires_at"].is_string());
+
+    let (status, body) = send_refresh_token(&app, "/api/auth/refresh", &first).await;
+    assert_eq!(status, StatusCode::OK);
+    let second = body["data"]["refresh_token"].clone();
+    assert_ne!(first, second);
+    let bearer = format!("Bearer {}", body["data"]["access_token"].as_str().unwrap());
+    let (status, _, _) = send_with_headers(&app, "GET", "/api/auth/me", &[("authorization", &bearer)], None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Replaying the used token kills its successor too
+    let (status, body) = send_refresh_token(&app, "/api/auth/refresh", &first).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error_code"], "UNAUTHENTICATED");
+    let (status, _) = send_refresh_token(&app, "/api/auth/refresh", &second).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = send_refresh_token(&app, "/api/auth/refresh", &json!("rnr_not-a-token")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_logout_ends_only_its_own_session() {
+    let app = app();
+    let (_, phone) = sign_up_and_log_in(&app, "logout@example.com").await;
+    let (status, _, laptop) = send_with_headers(
+        &app,
+        "POST",
+        "/api/auth/login",
+        &[("authorization", "")],
+        Some(json!({ "email": "logout@example.com", "password": "correct horse" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = send_refresh_token(&app, "/api/auth/logout", &phone["refresh_token"]).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = send_refresh_token(&app, "/api/auth/logout", &phone["refresh_token"]).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = send_refresh_token(&app, "/api/auth/refresh", &phone["refresh_token"]).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = send_refresh_token(&app, "/api/auth/refresh", &laptop["data"]["refresh_token"]).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_admins_revoke_sessions_and_deleting_a_user_revokes_them() {
+    let app = app();
+    let (id, first) = sign_up_and_log_in(&app, "revoked@example.com").await;
+
+    let user_auth = format!("Bearer {}", first["access_token"].as_str().unwrap());
+    let (status, _, body) = send_with_headers(
+        &app,
+        "DELETE",
+        &format!("/api/users/{}/sessions", id),
+        &[("authorization", &user_auth)],
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error_code"], "FORBIDDEN");
+
+    let (status, _) = send(&app, "DELETE", &format!("/api/users/{}/sessions", id), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = send_refresh_token(&app, "/api/auth/refresh", &first["refresh_token"]).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = send(&app, "DELETE", &format!("/api/users/{}/sessions", uuid::Uuid::new_v4()), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _, second) = send_with_headers(
+        &app,
+        "POST",
+        "/api/auth/login",
+        &[("authorization", "")],
+        Some(json!({ "email": "revoked@example.com", "password": "correct horse" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, "DELETE", &format!("/api/users/{}", id), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = send(&app, "POST", &format!("/api/users/{}/restore", id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send_refresh_token(&app, "/api/auth/refresh", &second["data"]["refresh_token"]).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_email_verification_with_a_mailed_token() {
+    let app = app();
+    let (id, login) = sign_up_and_log_in(&app, "verify@example.com").await;
+    let user_auth = format!("Bearer {}", login["access_token"].as_str().unwrap());
+    let send_uri = format!("/api/users/{}/verify-email/send", id);
+
+    let (status, _, me) = send_with_headers(&app, "GET", "/api/auth/me", &[("authorization", &user_auth)], None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(me["data"]["email_verified_at"].is_null());
+
+    let (status, _, sent) = send_with_headers(&app, "POST", &send_uri, &[("authorization", &user_auth)], None).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!(sent["data"]["email"], "verify@example.com");
+    assert!(sent["data"].get("token").is_none());
+    assert_eq!(app.outbox.0.lock().unwrap()[0].to.as_str(), "verify@example.com");
+    let token = app.outbox.last_token("rnv_");
+
+    let confirm = |token: &str| json!({ "token": token });
+    let (status, _, body) = send_with_headers(
+        &app,
+        "POST",
+        "/api/verify-email/confirm",
+        &[("authorization", "")],
+        Some(confirm("rnv_not-a-token")),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error_code"], "INVALID_TOKEN");
+
+    let (status, _, verified) = send_with_headers(
+        &app,
+        "POST",
+        "/api/verify-email/confirm",
+        &[("authorization", "")],
+        Some(confirm(&token)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(verified["data"]["email_verified_at"].is_string());
+
+    // The verification is audited as the user's own change
+    let (_, history) = send(&app, "GET", &format!("/api/users/{}/history?limit=1", id), None).await;
+    let entry = &history["data"][0];
+    assert_eq!(entry["action"], "update");
+    assert_eq!(entry["actor_id"], id.as_str());
+    assert_eq!(entry["changes"][0]["field"], "email_verified_at");
+
+    // Tokens are single-use, and there is nothing left to verify
+    let (status, _, _) = send_with_headers(
+        &app,
+        "POST",
+        "/api/verify-email/confirm",
+        &[("authorization", "")],
+        Some(confirm(&token)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _, body) = send_with_headers(&app, "POST", &send_uri, &[("authorization", &user_auth)], None).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["error_code"], "EMAIL_ALREADY_VERIFIED");
+}
+
+#[tokio::test]
+async fn test_changing_email_needs_a_new_verification() {
+    let app = app();
+    let (id, _) = sign_up_and_log_in(&app, "old@example.com").await;
+    let (other_id, other_login) = sign_up_and_log_in(&app, "other@example.com").await;
+
+    // Users may only ask for their own address; admins for anyone
+    let other_auth = format!("Bearer {}", other_login["access_token"].as_str().unwrap());
+    let send_uri = format!("/api/users/{}/verify-email/send", id);
+    let (status, _, _) = send_with_headers(&app, "POST", &send_uri, &[("authorization", &other_auth)], None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(app.outbox.len(), 0);
+    let (status, _) = send(&app, "POST", &send_uri, None).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let stale_token = app.outbox.last_token("rnv_");
+
+    let merge = [("content-type", "application/merge-patch+json")];
+    let (status, _, _) = send_with_headers(
+        &app,
+        "PATCH",
+        &format!("/api/users/{}", id),
+        &merge,
+        Some(json!({ "email": "new@example.com" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _, body) = send_with_headers(
+        &app,
+        "POST",
+        "/api/verify-email/confirm",
+        &[("authorization", "")],
+        Some(json!({ "token": stale_token })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error_code"], "INVALID_TOKEN");
+
+    let (status, _) = send(&app, "POST", &send_uri, None).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!(app.outbox.0.lock().unwrap().last().unwrap().to.as_str(), "new@example.com");
+    let (status, _, _) = send_with_headers(
+        &app,
+        "POST",
+        "/api/verify-email/confirm",
+        &[("authorization", "")],
+        Some(json!({ "token": app.outbox.last_token("rnv_") })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // A verified address stays verified across unrelated updates
+    let (status, _, user) =
+        send_with_headers(&app, "PATCH", &format!("/api/users/{}", id), &merge, Some(json!({ "name": "Renamed" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(user["data"]["email_verified_at"].is_string());
+
+    // ...and may be cleared, but never set, by a patch
+    let json_patch = [("content-type", "application/json-patch+json")];
+    let forge = json!([{ "op": "replace", "path": "/email_verified_at", "value": "2020-01-01T00:00:00Z" }]);
+    let (status, _, body) =
+        send_with_headers(&app, "PATCH", &format!("/api/users/{}", id), &json_patch, Some(forge)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["error_code"], "INVALID_PATCH");
+    let clear = json!({ "email_verified_at": null });
+    let (status, _, user) = send_with_headers(&app, "PATCH", &format!("/api/users/{}", id), &merge, Some(clear)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(user["data"]["email_verified_at"].is_null());
+    assert_eq!(user["data"]["name"], "Renamed");
+    let (status, fetched) = send(&app, "GET", &format!("/api/users/{}", id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(fetched["data"]["email_verified_at"].is_null());
+    let (status, other) = send(&app, "GET", &format!("/api/users/{}", other_id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(other["data"]["email_verified_at"].is_null());
+}
+
+/// Wait for the mail a background task sends
+async fn wait_for_mail(app: &TestApp, count: usize) {
+    for _ in 0..100 {
+        if app.outbox.len() >= count {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    panic!("expected {} mails, got {}", count, app.outbox.len());
+}
+
+#[tokio::test]
+async fn test_password_reset_request_does_not_reveal_accounts() {
+    let app = app();
+    let no_token = [("authorization", "")];
+    for email in ["nobody@example.com", "not an email"] {
+        let (status, _, body) = send_with_headers(
+            &app,
+            "POST",
+            "/api/auth/password-reset/request",
+            &no_token,
+            Some(json!({ "email": email })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(body["success"], true);
+    }
+
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    assert_eq!(app.outbox.len(), 0);
+}
+
+#[tokio::test]
+async fn test_password_reset_sets_a_new_password_and_signs_out() {
+    let app = app();
+    let (_, login) = sign_up_and_log_in(&app, "forgetful@example.com").await;
+    let no_token = [("authorization", "")];
+
+    let (status, _, _) = send_with_headers(
+        &app,
+        "POST",
+        "/api/auth/password-reset/request",
+        &no_token,
+        Some(json!({ "email": "forgetful@example.com" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    wait_for_mail(&app, 1).await;
+    assert_eq!(app.outbox.0.lock().unwrap()[0].to.as_str(), "forgetful@example.com");
+    let token = app.outbox.last_token("rnp_");
+
+    let confirm = |new_password: &str| Some(json!({ "token": token.clone(), "new_password": new_password }));
+    // A password breaking the policy leaves the token usable
+    let (status, _, body) =
+        send_with_headers(&app, "POST", "/api/auth/password-reset/confirm", &no_token, confirm("too short")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error_code"], "INVALID_PASSWORD");
+
+    let (status, _, _) =
+        send_with_headers(&app, "POST", "/api/auth/password-reset/confirm", &no_token, confirm("brand new horse"))
+            .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    // Every session ended, and only the new password logs in
+    let (status, _) = send_refresh_token(&app, "/api/auth/refresh", &login["refresh_token"]).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    for (password, expected) in [("correct horse", StatusCode::UNAUTHORIZED), ("brand new horse", StatusCode::OK)] {
+        let (status, _, _) = send_with_headers(
+            &app,
+            "POST",
+            "/api/auth/login",
+            &no_token,
+            Some(json!({ "email": "forgetful@example.com", "password": password })),
+        )
+        .await;
+        assert_eq!(status, expected);
+    }
+
+    let (status, _, body) =
+        send_with_headers(&app, "POST", "/api/auth/password-reset/confirm", &no_token, confirm("another new horse"))
+            .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error_code"], "INVALID_TOKEN");
+}
+
+/// Budgets small enough to exhaust, refilling too slowly to matter in a test
+fn tight_limits(read_burst: u32, write_burst: u32) -> RateLimitConfig {
+    let slow = |burst| rust_nexus::infrastructure::Budget { burst, per_second: 0.01 };
+    RateLimitConfig {
+        read: slow(read_burst),
+        write: slow(write_burst),
+        trust_forwarded_for: true,
+        ..RateLimitConfig::default()
+    }
+}
+
+fn header_value(headers: &HeaderMap, name: &str) -> String {
+    headers.get(name).unwrap_or_else(|| panic!("missing {}", name)).to_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_rate_limit_rejects_with_retry_after_once_exhausted() {
+    let app = rate_limited_app(tight_limits(2, 1));
+
+    for remaining in ["1", "0"] {
+        let (status, headers, _) = send_with_headers(&app, "GET", "/api/users", &[], None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(header_value(&headers, "ratelimit-limit"), "2");
+        assert_eq!(header_value(&headers, "ratelimit-remaining"), remaining);
+        assert!(header_value(&headers, "ratelimit-reset").parse::<u64>().unwrap() > 0);
+    }
+
+    let (status, headers, body) = send_with_headers(&app, "GET", "/api/users", &[], None).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(body["error_code"], "RATE_LIMITED");
+    assert_eq!(body["retryable"], true);
+    assert!(header_value(&headers, "retry-after").parse::<u64>().unwrap() >= 1);
+    assert_eq!(header_value(&headers, "ratelimit-remaining"), "0");
+
+    // Writes have their own budget, and health checks are never limited
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/api/users",
+        Some(json!({ "name": "Jane Doe", "email": "jane@example.com" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = send(&app, "POST", "/api/users", Some(json!({ "name": "Jo", "email": "jo@example.com" }))).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    for _ in 0..3 {
+        let (status, _, _) = send_with_headers(&app, "GET", "/health", &[], None).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+}
+
+#[tokio::test]
+async fn test_rate_limits_are_kept_per_user_and_per_ip() {
+    let app = rate_limited_app(tight_limits(1, 1));
+    let (status, _, _) = send_with_headers(&app, "GET", "/api/users", &[], None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _, _) = send_with_headers(&app, "GET", "/api/users", &[], None).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+    // Another user has a full bucket, wherever the requests come from
+    let other = app.bearer_for(&uuid::Uuid::new_v4().to_string(), Role::Admin);
+    let auth = [("authorization", other.as_str()), ("x-forwarded-for", "203.0.113.7")];
+    let (status, _, _) = send_with_headers(&app, "GET", "/api/users", &auth, None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Anonymous callers, and callers with bad credentials, count against their IP
+    let first_ip = [("authorization", ""), ("x-forwarded-for", "198.51.100.1, 203.0.113.7")];
+    let (status, _, _) = send_with_headers(&app, "GET", "/api/users", &first_ip, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let forged = [("authorization", "Bearer forged"), ("x-forwarded-for", "203.0.113.7")];
+    let (status, _, _) = send_with_headers(&app, "GET", "/api/users", &forged, None).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    let second_ip = [("authorization", ""), ("x-forwarded-for", "198.51.100.2")];
+    let (status, _, _) = send_with_headers(&app, "GET", "/api/users", &second_ip, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_api_keys_from_an_exhausted_ip_are_not_looked_up() {
+    let app = rate_limited_app(tight_limits(2, 5));
+    let owner = json!({ "name": "Owner", "email": "owner@example.com" });
+    let (_, owner) = send(&app, "POST", "/api/users", Some(owner)).await;
+    let (status, body) = send(
+        &app,
+        "POST",
+        "/api/api-keys",
+        Some(json!({ "name": "reports", "owner_id": owner["data"]["id"], "scopes": ["users:read"] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let key = body["data"]["key"].as_str().unwrap().to_string();
+    let own_profile = format!("/api/users/{}", owner["data"]["id"].as_str().unwrap());
+
+    // Made-up keys spend the IP's budget
+    for attempt in 0..2 {
+        let made_up = format!("rnx_made-up-{}", attempt);
+        let headers = [("x-api-key", made_up.as_str()), ("x-forwarded-for", "198.51.100.9")];
+        let (status, _, _) = send_with_headers(&app, "GET", "/api/users", &headers, None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    // From there even a valid key waits for the IP's bucket to refill
+    let from_exhausted = [("x-api-key", key.as_str()), ("x-forwarded-for", "198.51.100.9")];
+    let (status, _, _) = send_with_headers(&app, "GET", &own_profile, &from_exhausted, None).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+    // Elsewhere the key has its own budget, which runs out like any other
+    let elsewhere = [("x-api-key", key.as_str()), ("x-forwarded-for", "198.51.100.10")];
+    for expected in [StatusCode::OK, StatusCode::OK, StatusCode::TOO_MANY_REQUESTS] {
+        let (status, _, _) = send_with_headers(&app, "GET", &own_profile, &elsewhere, None).await;
+        assert_eq!(status, expected);
+    }
+}
+
+#[tokio::test]
+async fn test_idempotency_key_replays_the_first_response() {
+    let app = app();
+    let body = json!({ "name": "Mobile User", "email": "mobile@example.com" });
+    let key = [("idempotency-key", "3f1c9a52-retry")];
+
+    let (status, headers, first) = send_with_headers(&app, "POST", "/api/users", &key, Some(body.clone())).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert!(headers.get("idempotent-replayed").is_none());
+
+    let (status, replayed_headers, replayed) =
+        send_with_headers(&app, "POST", "/api/users", &key, Some(body.clone())).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(replayed, first);
+    assert_eq!(header_value(&replayed_headers, "idempotent-replayed"), "true");
+    assert_eq!(header_value(&replayed_headers, "etag"), header_value(&headers, "etag"));
+    assert_eq!(header_value(&replayed_headers, "content-type"), "application/json");
+
+    let (_, listed) = send(&app, "GET", "/api/users", None).await;
+    assert_eq!(listed["pagination"]["total"], 1);
+
+    // Without a key a retry still runs, and conflicts
+    let (status, body) = send(&app, "POST", "/api/users", Some(body)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["error_code"], "EMAIL_ALREADY_EXISTS");
+}
+
+#[tokio::test]
+async fn test_idempotency_key_reuse_with_another_body_is_rejected() {
+    let app = app();
+    let key = [("idempotency-key", "signup-1")];
+    let (status, _, _) = send_with_headers(
+        &app,
+        "POST",
+        "/api/users",
+        &key,
+        Some(json!({ "name": "Jane Doe", "email": "jane@example.com" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, _, body) = send_with_headers(
+        &app,
+        "POST",
+        "/api/users",
+        &key,
+        Some(json!({ "name": "Jane Doe", "email": "jane.doe@example.com" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["error_code"], "IDEMPOTENCY_KEY_REUSED");
+
+    // Keys belong to their caller; an anonymous sign-up may use the same one
+    let anonymous = [("authorization", ""), ("idempotency-key", "signup-1")];
+    let (status, _, _) = send_with_headers(
+        &app,
+        "POST",
+        "/api/users",
+        &anonymous,
+        Some(json!({ "name": "Jane Doe", "email": "jane.doe@example.com" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // Anonymous sign-ups share one key space, where the same rule holds
+    let (status, _, body) = send_with_headers(
+        &app,
+        "POST",
+        "/api/users",
+        &anonymous,
+        Some(json!({ "name": "John Roe", "email": "john@example.com" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["error_code"], "IDEMPOTENCY_KEY_REUSED");
+    let (_, listed) = send(&app, "GET", "/api/users?email=john@example.com", None).await;
+    assert_eq!(listed["pagination"]["total"], 0);
+
+    let too_long = "k".repeat(256);
+    for bad_key in [too_long.as_str(), "has spaces inside"] {
+        let (status, _, body) = send_with_headers(
+            &app,
+            "POST",
+            "/api/users",
+            &[("idempotency-key", bad_key)],
+            Some(json!({ "name": "Jo Doe", "email": "jo@example.com" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error_code"], "INVALID_IDEMPOTENCY_KEY");
+    }
+}
+
+#[tokio::test]
+async fn test_idempotency_key_in_progress_and_failed_requests() {
+    let app = app();
+    let payload = CreateUserDto {
+        name: "Slow User".to_string(),
+        email: "slow@example.com".to_string(),
+        role: None,
+        password: None,
+    };
+    let fingerprint = IdempotencyService::fingerprint(&payload.fingerprint_fields()).unwrap();
+    let scope = IdempotencyService::scope(None);
+    // Only whether a password was sent is fingerprinted, never the password
+    let with_password = |password: &str| {
+        let payload = CreateUserDto {
+            name: "Slow User".to_string(),
+            email: "slow@example.com".to_string(),
+            role: None,
+            password: Some(password.to_string()),
+        };
+        IdempotencyService::fingerprint(&payload.fingerprint_fields()).unwrap()
+    };
+    assert_eq!(with_password("first-secret"), with_password("second-secret"));
+    assert_ne!(with_password("first-secret"), fingerprint);
+    let started = app.idempotency.start(&scope, "slow-1", &fingerprint).await.unwrap();
+    assert_eq!(started, IdempotentStart::Run);
+
+    let anonymous = [("authorization", ""), ("idempotency-key", "slow-1")];
+    let body = json!({ "name": "Slow User", "email": "slow@example.com" });
+    let (status, _, error) = send_with_headers(&app, "POST", "/api/users", &anonymous, Some(body.clone())).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(error["error_code"], "IDEMPOTENCY_KEY_IN_PROGRESS");
+    assert_eq!(error["retryable"], true);
+
+    // Once the first attempt gives up, the retry runs
+    app.idempotency.release(&scope, "slow-1").await.unwrap();
+    let (status, _, _) = send_with_headers(&app, "POST", "/api/users", &anonymous, Some(body)).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // Final errors are replayed like successes
+    let invalid = [("authorization", ""), ("idempotency-key", "bad-email")];
+    let body = json!({ "name": "Bad Email", "email": "not-an-email" });
+    for _ in 0..2 {
+        let (status, _, error) = send_with_headers(&app, "POST", "/api/users", &invalid, Some(body.clone())).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["error_code"], "INVALID_EMAIL");
+    }
+}
+
+#[tokio::test]
+async fn test_every_change_is_recorded_in_the_user_history() {
+    let app = app();
+    let (status, headers, created) = send_with_headers(
+        &app,
+        "POST",
+        "/api/users",
+        &[("x-request-id", "req-create-1")],
+        Some(json!({ "name": "Audited", "email": "audited@example.com" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(header_value(&headers, "x-request-id"), "req-create-1");
+    let id = created["data"]["id"].as_str().unwrap().to_string();
+    let uri = format!("/api/users/{}", id);
+
+    let merge = [("content-type", "application/merge-patch+json")];
+    let (status, headers, _) =
+        send_with_headers(&app, "PATCH", &uri, &merge, Some(json!({ "name": "Audited Again" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    let update_request_id = header_value(&headers, "x-request-id");
+    assert!(!update_request_id.is_empty());
+    let (status, _) = send(&app, "DELETE", &uri, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = send(&app, "POST", &format!("{}/restore", uri), None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, history) = send(&app, "GET", &format!("{}/history", uri), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let entries = history["data"].as_array().unwrap();
+    let actions: Vec<&str> = entries.iter().map(|e| e["action"].as_str().unwrap()).collect();
+    assert_eq!(actions, ["restore", "delete", "update", "create"]);
+    assert_eq!(history["pagination"]["total"], 4);
+
+    let (restore, delete, update, create) = (&entries[0], &entries[1], &entries[2], &entries[3]);
+    assert_eq!(create["request_id"], "req-create-1");
+    assert!(create["actor_id"].is_string());
+    assert_eq!(create["changes"][0], json!({ "field": "name", "before": null, "after": "Audited" }));
+    assert_eq!(update["request_id"], update_request_id);
+    assert_eq!(
+        update["changes"],
+        json!([{ "field": "name", "before": "Audited", "after": "Audited Again" }])
+    );
+    assert_eq!(delete["changes"][1], json!({ "field": "email", "before": "audited@example.com", "after": null }));
+    assert_eq!(restore["changes"][0]["after"], "Audited Again");
+
+    // Failed changes leave no entry
+    let (status, _) = send(&app, "PATCH", &uri, Some(json!({ "email": "not an email" }))).await;
+    assert!(status.is_client_error());
+    let (_, history) = send(&app, "GET", &format!("{}/history?limit=1", uri), None).await;
+    assert_eq!(history["pagination"]["total"], 4);
+    assert_eq!(history["pagination"]["has_more"], true);
+}
+
+#[tokio::test]
+async fn test_stored_changes_are_published_as_user_events() {
+    let app = app();
+    let mut events = app.events.subscribe();
+
+    let (status, created) =
+        send(&app, "POST", "/api/users", Some(json!({ "name": "Eventful", "email": "eventful@example.com" }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let id = created["data"]["id"].as_str().unwrap().to_string();
+    let uri = format!("/api/users/{}", id);
+    let merge = [("content-type", "application/merge-patch+json")];
+    let (status, _, _) =
+        send_with_headers(&app, "PATCH", &uri, &merge, Some(json!({ "name": "Eventful Again" }))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Rejected changes publish nothing
+    let twin = json!({ "name": "Twin", "email": "eventful@example.com" });
+    let (status, _) = send(&app, "POST", "/api/users", Some(twin)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let stale = [("if-match", "\"1\"")];
+    let (status, _, _) = send_with_headers(&app, "DELETE", &uri, &stale, None).await;
+    assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+
+    let (status, _) = send(&app, "DELETE", &uri, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = send(&app, "POST", &format!("{}/restore", uri), None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let UserEvent::Created(created) = next_event(&mut events).await else { panic!("expected user.created") };
+    assert_eq!(created.user.id().as_uuid().to_string(), id);
+    assert_eq!(created.user.name().as_str(), "Eventful");
+
+    let UserEvent::Updated(updated) = next_event(&mut events).await else { panic!("expected user.updated") };
+    assert_eq!(updated.user.name().as_str(), "Eventful Again");
+    assert_eq!(updated.changes.len(), 1);
+    assert_eq!(updated.changes[0].field, "name");
+    assert_eq!(updated.changes[0].before.as_deref(), Some("Eventful"));
+
+    let deleted = next_event(&mut events).await;
+    assert_eq!(deleted.event_type(), "user.deleted");
+    assert_eq!(deleted.user(), &updated.user);
+    assert_ne!(deleted.id(), updated.id);
+
+    let UserEvent::Restored(restored) = next_event(&mut events).await else { panic!("expected user.restored") };
+    assert_eq!(restored.user.name().as_str(), "Eventful Again");
+    assert!(!restored.user.is_deleted());
+    assert_eq!(restored.user.version(), updated.user.version() + 2);
+    assert!(events.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_outbox_relay_delivers_every_event_in_order_per_user() {
+    let app = app();
+    let sink = FlakySink::default();
+    let relay = OutboxRelay::new(Arc::new(app.event_outbox.clone()), Arc::new(sink.clone())).with_config(OutboxConfig {
+        retry_base: Duration::zero(),
+        ..OutboxConfig::default()
+    });
+
+    let (_, alice) =
+        send(&app, "POST", "/api/users", Some(json!({ "name": "Alice", "email": "alice.relay@example.com" }))).await;
+    let uri = format!("/api/users/{}", alice["data"]["id"].as_str().unwrap());
+    let merge = [("content-type", "application/merge-patch+json")];
+    let (status, _, _) = send_with_headers(&app, "PATCH", &uri, &merge, Some(json!({ "name": "Alice Again" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, bob) =
+        send(&app, "POST", "/api/users", Some(json!({ "name": "Bob", "email": "bob.relay@example.com" }))).await;
+
+    // Failed deliveries stay in the outbox and are retried
+    *sink.failing.lock().unwrap() = true;
+    let report = relay.relay_batch().await.unwrap();
+    assert_eq!((report.claimed, report.delivered, report.failed), (2, 0, 2));
+    *sink.failing.lock().unwrap() = false;
+
+    assert_eq!(relay.relay_batch().await.unwrap().delivered, 2);
+    assert_eq!(relay.relay_batch().await.unwrap().delivered, 1);
+    assert_eq!(relay.relay_batch().await.unwrap().claimed, 0);
+
+    let delivered = sink.delivered.lock().unwrap().clone();
+    let summary: Vec<(&str, &str)> = delivered
+        .iter()
+        .map(|event| (event.event_type(), event.user().name().as_str()))
+        .collect();
+    assert_eq!(summary, [("user.created", "Alice"), ("user.created", "Bob"), ("user.updated", "Alice Again")]);
+    assert_eq!(delivered[1].user_id().as_uuid().to_string(), bob["data"]["id"].as_str().unwrap());
+
+    assert_eq!(relay.purge_sent().await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_audit_log_is_filtered_and_only_open_to_admins() {
+    let app = app();
+    let (alice_id, _) = sign_up_and_log_in(&app, "alice.audit@example.com").await;
+    let (_, bob) = send(&app, "POST", "/api/users", Some(json!({ "name": "Bob", "email": "bob@example.com" }))).await;
+    let bob_id = bob["data"]["id"].as_str().unwrap();
+
+    let as_alice = app.bearer_for(&alice_id, Role::User);
+    let auth = [("authorization", as_alice.as_str())];
+    let merge = [("authorization", as_alice.as_str()), ("content-type", "application/merge-patch+json")];
+    let uri = format!("/api/users/{}", alice_id);
+    let (status, _, _) = send_with_headers(&app, "PATCH", &uri, &merge, Some(json!({ "name": "Alice" }))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Users see their own history, and nothing else
+    let (status, _, history) = send_with_headers(&app, "GET", &format!("{}/history", uri), &auth, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(history["data"][0]["actor_id"], alice_id.as_str());
+    assert!(history["data"][1]["actor_id"].is_null(), "sign-up has no actor");
+    let bob_history = format!("/api/users/{}/history", bob_id);
+    let (status, _, _) = send_with_headers(&app, "GET", &bob_history, &auth, None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _, _) = send_with_headers(&app, "GET", "/api/audit", &auth, None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, all) = send(&app, "GET", "/api/audit", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(all["pagination"]["total"], 3);
+
+    let (_, created) = send(&app, "GET", "/api/audit?action=create&limit=1", None).await;
+    assert_eq!(created["pagination"]["total"], 2);
+    assert_eq!(created["pagination"]["next"], "/api/audit?action=create&page=1&limit=1");
+
+    let (_, by_alice) = send(&app, "GET", &format!("/api/audit?actor_id={}", alice_id), None).await;
+    assert_eq!(by_alice["pagination"]["total"], 1);
+    assert_eq!(by_alice["data"][0]["user_id"], alice_id.as_str());
+
+    let (_, of_bob) = send(&app, "GET", &format!("/api/audit?user_id={}&action=update", bob_id), None).await;
+    assert_eq!(of_bob["pagination"]["total"], 0);
+
+    let (_, future) = send(&app, "GET", "/api/audit?from=2999-01-01T00:00:00Z", None).await;
+    assert_eq!(future["pagination"]["total"], 0);
+
+    let (status, body) = send(&app, "GET", "/api/audit?action=explode", None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error_code"], "INVALID_QUERY");
+}
+
+#[tokio::test]
+async fn test_webhooks_are_managed_by_admins_without_revealing_secrets() {
+    let app = app();
+    let (user_id, _) = sign_up_and_log_in(&app, "webhook.user@example.com").await;
+    let as_user = app.bearer_for(&user_id, Role::User);
+    let auth = [("authorization", as_user.as_str())];
+    let subscribe = json!({ "url": "https://partner.example.com/hooks" });
+    let (status, _, _) = send_with_headers(&app, "POST", "/api/webhooks", &auth, Some(subscribe.clone())).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, created) = send(&app, "POST", "/api/webhooks", Some(subscribe)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert!(created["data"]["secret"].as_str().unwrap().starts_with("whsec_"));
+    let all_types = json!(["user.created", "user.deleted", "user.restored", "user.updated"]);
+    assert_eq!(created["data"]["event_types"], all_types);
+    assert_eq!(created["data"]["active"], true);
+    let uri = format!("/api/webhooks/{}", created["data"]["id"].as_str().unwrap());
+
+    let (status, listed) = send(&app, "GET", "/api/webhooks", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed["data"].as_array().unwrap().len(), 1);
+    assert!(listed["data"][0].get("secret").is_none());
+
+    let change = json!({ "event_types": ["user.deleted"], "active": false });
+    let (status, updated) = send(&app, "PATCH", &uri, Some(change)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["data"]["event_types"], json!(["user.deleted"]));
+    assert_eq!(updated["data"]["active"], false);
+    assert!(updated["data"].get("secret").is_none());
+
+    for invalid in [
+        json!({ "url": "ftp://partner.example.com/hooks" }),
+        json!({ "url": "https://partner.example.com/hooks", "event_types": ["user.exploded"] }),
+        json!({ "url": "https://partner.example.com/hooks", "secret": "short" }),
+    ] {
+        let (status, body) = send(&app, "POST", "/api/webhooks", Some(invalid)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error_code"], "INVALID_WEBHOOK");
+    }
+
+    let (status, _) = send(&app, "DELETE", &uri, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, body) = send(&app, "GET", &uri, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error_code"], "WEBHOOK_NOT_FOUND");
+}
+
+#[tokio::test]
+async fn test_webhook_deliveries_are_signed_retried_dead_lettered_and_replayed() {
+    let app = app();
+    let receiver = Receiver::default();
+    let url = receiver.start().await;
+    let secret = "whsec_test_signing_secret";
+    let subscribe = json!({ "url": url, "event_types": ["user.created", "user.deleted"], "secret": secret });
+    let (status, created) = send(&app, "POST", "/api/webhooks", Some(subscribe)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let webhook_uri = format!("/api/webhooks/{}", created["data"]["id"].as_str().unwrap());
+    let relay = OutboxRelay::new(Arc::new(app.event_outbox.clone()), Arc::new(app.webhooks.clone()));
+    // Each pass relays the next event of every user
+    let drain = async || while relay.relay_batch().await.unwrap().claimed > 0 {};
+
+    let (_, user) =
+        send(&app, "POST", "/api/users", Some(json!({ "name": "Hooked", "email": "hooked@example.com" }))).await;
+    let user_uri = format!("/api/users/{}", user["data"]["id"].as_str().unwrap());
+    let merge = [("content-type", "application/merge-patch+json")];
+    let rename = json!({ "name": "Re-hooked" });
+    let (status, _, _) = send_with_headers(&app, "PATCH", &user_uri, &merge, Some(rename)).await;
+    assert_eq!(status, StatusCode::OK);
+    drain().await;
+
+    // Failed attempts are retried until the receiver takes the delivery
+    receiver.answer(&[500, 503]);
+    for expected in [(1, 0, 1), (1, 0, 1), (1, 1, 0)] {
+        let report = app.webhooks.dispatch_batch().await.unwrap();
+        assert_eq!((report.claimed, report.succeeded, report.retrying), expected);
+    }
+    assert_eq!(app.webhooks.dispatch_batch().await.unwrap().claimed, 0);
+    assert_eq!(receiver.len(), 3, "updates are not subscribed to");
+
+    let (headers, body) = receiver.last();
+    let header = |name: &str| headers[name].to_str().unwrap().to_string();
+    assert_eq!(header("content-type"), "application/json");
+    assert_eq!(header("x-webhook-event"), "user.created");
+    let signature = header("x-webhook-signature");
+    let timestamp: i64 = signature.strip_prefix("t=").unwrap().split(',').next().unwrap().parse().unwrap();
+    assert_eq!(signature, webhook_signature(secret, timestamp, &body));
+    let event: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(event["type"], "user.created");
+    assert_eq!(event["data"]["name"], "Hooked");
+
+    let delivered = format!("{}/deliveries/{}", webhook_uri, header("x-webhook-delivery"));
+    let (status, detail) = send(&app, "GET", &delivered, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(detail["data"]["status"], "succeeded");
+    assert_eq!(detail["data"]["payload"], event);
+    let log: Vec<&Value> = detail["data"]["attempt_log"].as_array().unwrap().iter().collect();
+    let outcomes: Vec<(i64, i64)> = log
+        .iter()
+        .map(|attempt| (attempt["attempt"].as_i64().unwrap(), attempt["response_status"].as_i64().unwrap()))
+        .collect();
+    assert_eq!(outcomes, [(1, 500), (2, 503), (3, 200)]);
+    assert_eq!(log[0]["error"], "receiver answered with status 500");
+
+    // After the last attempt fails, the delivery waits for a replay
+    let (status, _) = send(&app, "DELETE", &user_uri, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    drain().await;
+    receiver.answer(&[500, 500, 500]);
+    let mut dead_lettered = 0;
+    for _ in 0..3 {
+        dead_lettered += app.webhooks.dispatch_batch().await.unwrap().dead_lettered;
+    }
+    assert_eq!(dead_lettered, 1);
+    assert_eq!(app.webhooks.dispatch_batch().await.unwrap().claimed, 0);
+
+    let (status, dead) = send(&app, "GET", &format!("{}/deliveries?status=dead_lettered", webhook_uri), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(dead["pagination"]["total"], 1);
+    assert_eq!(dead["data"][0]["event_type"], "user.deleted");
+    assert_eq!(dead["data"][0]["attempts"], 3);
+    assert_eq!(dead["data"][0]["last_response_status"], 500);
+    assert!(dead["data"][0].get("next_attempt_at").is_none());
+    let (_, all) = send(&app, "GET", &format!("{}/deliveries?limit=1", webhook_uri), None).await;
+    assert_eq!(all["pagination"]["total"], 2);
+    assert_eq!(all["data"][0]["status"], "dead_lettered", "newest first");
+
+    let dead_id = dead["data"][0]["id"].as_str().unwrap();
+    let replay_uri = format!("{}/deliveries/{}/replay", webhook_uri, dead_id);
+    let (status, replay) = send(&app, "POST", &replay_uri, None).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!(replay["data"]["replay_of"], dead_id);
+    assert_eq!(replay["data"]["status"], "pending");
+    assert_eq!(app.webhooks.dispatch_batch().await.unwrap().succeeded, 1);
+    let (headers, body) = receiver.last();
+    assert_eq!(headers["x-webhook-delivery"].to_str().unwrap(), replay["data"]["id"].as_str().unwrap());
+    assert_eq!(serde_json::from_str::<Value>(&body).unwrap()["type"], "user.deleted");
+
+    let missing = format!("{}/deliveries/{}", webhook_uri, uuid::Uuid::new_v4());
+    let (status, body) = send(&app, "GET", &missing, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error_code"], "WEBHOOK_DELIVERY_NOT_FOUND");
+    let (status, body) = send(&app, "GET", &format!("{}/deliveries?status=lost", webhook_uri), None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error_code"], "INVALID_QUERY");
+}
+
+#[tokio::test]
+async fn test_user_event_stream_pushes_changes_and_resumes_after_the_last_event() {
+    let app = app();
+    // Streams end once their caller no longer exists, so watch as a real admin
+    let (_, admin) = sign_up_admin_and_log_in(&app, "admin.stream@example.com").await;
+    let bearer = format!("Bearer {}", admin["access_token"].as_str().unwrap());
+    let auth = ("authorization", bearer.as_str());
+    let (status, mut stream) = EventStreamReader::open(&app, "/api/users/events", &[auth]).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, alice) =
+        send(&app, "POST", "/api/users", Some(json!({ "name": "Alice", "email": "alice.stream@example.com" }))).await;
+    let alice_id = alice["data"]["id"].as_str().unwrap().to_string();
+    let alice_uri = format!("/api/users/{}", alice_id);
+    let merge = [("content-type", "application/merge-patch+json")];
+    let (status, _, _) =
+        send_with_headers(&app, "PATCH", &alice_uri, &merge, Some(json!({ "name": "Alice Again" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, bob) =
+        send(&app, "POST", "/api/users", Some(json!({ "name": "Bob", "email": "bob.stream@example.com" }))).await;
+    let bob_id = bob["data"]["id"].as_str().unwrap().to_string();
+    let (status, _) = send(&app, "DELETE", &alice_uri, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let mut messages = Vec::new();
+    for _ in 0..4 {
+        messages.push(stream.next_event().await);
+    }
+    let summary: Vec<(&str, String)> = messages
+        .iter()
+        .map(|message| {
+            let data: Value = serde_json::from_str(field(message, "data").unwrap()).unwrap();
+            assert_eq!(data["id"].as_str(), field(message, "id"));
+            assert_eq!(data["type"].as_str(), field(message, "event"));
+            (field(message, "event").unwrap(), data["data"]["name"].as_str().unwrap().to_string())
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("user.created", "Alice".to_string()),
+            ("user.updated", "Alice Again".to_string()),
+            ("user.created", "Bob".to_string()),
+            ("user.deleted", "Alice Again".to_string()),
+        ]
+    );
+    let updated: Value = serde_json::from_str(field(&messages[1], "data").unwrap()).unwrap();
+    assert_eq!(updated["data"]["id"], alice_id.as_str());
+    assert_eq!(updated["data"]["email"], "alice.stream@example.com");
+    assert_eq!(updated["changes"][0]["field"], "name");
+
+    // Idle streams get heartbeats
+    assert_eq!(stream.next().await, [(String::new(), String::new())]);
+
+    // Reconnecting resumes after the last event seen
+    let seen = field(&messages[1], "id").unwrap();
+    let (status, mut resumed) =
+        EventStreamReader::open(&app, "/api/users/events", &[auth, ("last-event-id", seen)]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(resumed.next().await, messages[2]);
+    assert_eq!(resumed.next().await, messages[3]);
+    assert_eq!(field(&resumed.next().await, ""), Some(""));
+
+    let only_bob = format!("/api/users/events?user_id={}", bob_id);
+    let (_, mut filtered) = EventStreamReader::open(&app, &only_bob, &[auth, ("last-event-id", seen)]).await;
+    assert_eq!(filtered.next().await, messages[2]);
+    let rename = json!({ "name": "Bob Again" });
+    let (status, _, _) =
+        send_with_headers(&app, "PATCH", &format!("/api/users/{}", bob_id), &merge, Some(rename)).await;
+    assert_eq!(status, StatusCode::OK);
+    let renamed = filtered.next_event().await;
+    assert_eq!(field(&renamed, "event"), Some("user.updated"));
+    assert!(field(&renamed, "data").unwrap().contains("Bob Again"));
+
+    // Events that left the buffer (or never were in it) cannot be resumed from
+    for n in 0..8 {
+        let filler = json!({ "name": format!("Filler {}", n), "email": format!("filler{}@example.com", n) });
+        let (status, _) = send(&app, "POST", "/api/users", Some(filler)).await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+    let mut newest = Vec::new();
+    for _ in 0..9 {
+        newest = stream.next_event().await;
+    }
+    for stale in [seen, "not-an-event-id"] {
+        let resume_from = [auth, ("last-event-id", stale)];
+        let (_, mut reset) = EventStreamReader::open(&app, "/api/users/events", &resume_from).await;
+        let message = reset.next().await;
+        assert_eq!(field(&message, "event"), Some("reset"));
+        assert_eq!(field(&message, "id"), field(&newest, "id"));
+    }
+}
+
+#[tokio::test]
+async fn test_user_event_stream_is_limited_to_what_the_caller_may_watch() {
+    let app = app();
+    let (alice_id, _) = sign_up_and_log_in(&app, "alice.watch@example.com").await;
+    let (bob_id, _) = sign_up_and_log_in(&app, "bob.watch@example.com").await;
+    let as_alice = app.bearer_for(&alice_id, Role::User);
+    let auth = [("authorization", as_alice.as_str())];
+
+    let (status, _) = EventStreamReader::open(&app, "/api/users/events", &[("authorization", "")]).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = EventStreamReader::open(&app, "/api/users/events", &auth).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = EventStreamReader::open(&app, &format!("/api/users/events?user_id={}", bob_id), &auth).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, mut own) =
+        EventStreamReader::open(&app, &format!("/api/users/events?user_id={}", alice_id), &auth).await;
+    assert_eq!(status, StatusCode::OK);
+    let merge = [("authorization", as_alice.as_str()), ("content-type", "application/merge-patch+json")];
+    let uri = format!("/api/users/{}", alice_id);
+    let (status, _, _) = send_with_headers(&app, "PATCH", &uri, &merge, Some(json!({ "name": "Alice" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    let changed = own.next_event().await;
+    assert_eq!(field(&changed, "event"), Some("user.updated"));
+
+    let as_reader = app.bearer_for(&bob_id, Role::ReadOnly);
+    let (status, _) =
+        EventStreamReader::open(&app, "/api/users/events", &[("authorization", as_reader.as_str())]).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = EventStreamReader::open(&app, "/api/users/events?user_id=nobody", &[]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_user_event_stream_ends_once_the_credentials_no_longer_hold() {
+    let app = app();
+    let (alice_id, tokens) = sign_up_and_log_in(&app, "alice.ended@example.com").await;
+    let bearer = format!("Bearer {}", tokens["access_token"].as_str().unwrap());
+    let own = format!("/api/users/events?user_id={}", alice_id);
+
+    // Signed out everywhere: the next heartbeat ends the stream
+    let (status, mut stream) = EventStreamReader::open(&app, &own, &[("authorization", bearer.as_str())]).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, "DELETE", &format!("/api/users/{}/sessions", alice_id), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert!(stream.ends().await);
+
+    // Key revoked
+    let (_, body) = send(
+        &app,
+        "POST",
+        "/api/api-keys",
+        Some(json!({ "name": "stream", "owner_id": alice_id, "scopes": ["users:read"] })),
+    )
+    .await;
+    let key = format!("ApiKey {}", body["data"]["key"].as_str().unwrap());
+    let (status, mut stream) = EventStreamReader::open(&app, &own, &[("authorization", key.as_str())]).await;
+    assert_eq!(status, StatusCode::OK);
+    for _ in 0..2 {
+        assert_eq!(stream.next().await, [(String::new(), String::new())], "valid keys keep the stream open");
+    }
+    let key_uri = format!("/api/api-keys/{}", body["data"]["id"].as_str().unwrap());
+    let (status, _) = send(&app, "DELETE", &key_uri, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert!(stream.ends().await);
+
+    // Token expired, while the session lives on
+    let tokens = log_in(&app, "alice.ended@example.com").await;
+    assert!(tokens["access_token"].is_string());
+    let short_lived = JwtAccessTokens::new(b"test-secret", "rust-nexus-tests", Duration::seconds(1));
+    let alice = Principal {
+        user_id: UserId::from_uuid(alice_id.parse().unwrap()),
+        role: Role::User,
+        scopes: None,
+        expires_at: None,
+    };
+    let bearer = format!("Bearer {}", short_lived.issue(&alice).unwrap().token);
+    let (status, mut stream) = EventStreamReader::open(&app, &own, &[("authorization", bearer.as_str())]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(stream.ends().await);
+}
+
+#[tokio::test]
+async fn test_websocket_subscriptions_push_changes_and_answer_queries() {
+    let app = app();
+    let (_, admin) = sign_up_admin_and_log_in(&app, "admin.ws@example.com").await;
+    let bearer = format!("Bearer {}", admin["access_token"].as_str().unwrap());
+    let mut ws = WsClient::connect(&app, Some(&bearer)).await.unwrap();
+
+    let reply = ws.request(json!({ "id": 1, "type": "subscribe" })).await;
+    assert_eq!(reply, json!({ "type": "result", "id": 1, "data": { "directory": true, "user_ids": [] } }));
+    let (_, alice) =
+        send(&app, "POST", "/api/users", Some(json!({ "name": "Alice", "email": "alice.ws@example.com" }))).await;
+    let alice_id = alice["data"]["id"].as_str().unwrap().to_string();
+    let created = ws.next().await.unwrap();
+    assert_eq!(created["type"], "event");
+    assert_eq!(created["event"]["type"], "user.created");
+    assert_eq!(created["event"]["data"]["id"], alice_id.as_str());
+
+    // Only the subscribed user's changes arrive once the directory is dropped
+    let reply = ws.request(json!({ "id": "a", "type": "unsubscribe" })).await;
+    assert_eq!(reply["data"], json!({ "directory": false, "user_ids": [] }));
+    let reply = ws.request(json!({ "id": "b", "type": "subscribe", "user_ids": [alice_id] })).await;
+    assert_eq!(reply["data"], json!({ "directory": false, "user_ids": [alice_id] }));
+    let (status, _) =
+        send(&app, "POST", "/api/users", Some(json!({ "name": "Bob", "email": "bob.ws@example.com" }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let merge = [("content-type", "application/merge-patch+json")];
+    let alice_uri = format!("/api/users/{}", alice_id);
+    let (status, _, _) =
+        send_with_headers(&app, "PATCH", &alice_uri, &merge, Some(json!({ "name": "Alice Again" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    let updated = ws.next().await.unwrap();
+    assert_eq!(updated["event"]["type"], "user.updated");
+    assert_eq!(updated["event"]["changes"][0]["field"], "name");
+
+    let reply = ws.request(json!({ "id": 2, "type": "get_user", "user_id": alice_id })).await;
+    assert_eq!(reply["id"], 2);
+    assert_eq!(reply["data"]["name"], "Alice Again");
+    let listed = ws.request(json!({ "id": 3, "type": "list_users", "limit": 1, "sort": "name:asc" })).await;
+    assert_eq!(listed["data"]["users"][0]["name"], "Alice Again");
+    assert_eq!(listed["data"]["pagination"]["total"], 3);
+
+    let reply = ws.request(json!({ "id": 4, "type": "unsubscribe", "user_ids": [alice_id] })).await;
+    assert_eq!(reply["data"], json!({ "directory": false, "user_ids": [] }));
+    let (status, _) = send(&app, "DELETE", &alice_uri, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    // The deletion was not pushed, so the next message is the reply. Failed
+    // requests get an error reply and the connection stays open.
+    let missing = ws.request(json!({ "id": 5, "type": "get_user", "user_id": uuid::Uuid::new_v4() })).await;
+    assert_eq!(missing["type"], "error");
+    assert_eq!(missing["id"], 5);
+    assert_eq!(missing["error_code"], "USER_NOT_FOUND");
+    assert_eq!(missing["retryable"], false);
+    let unknown = ws.request(json!({ "id": 6, "type": "shout" })).await;
+    assert_eq!((unknown["id"].clone(), unknown["error_code"].clone()), (json!(6), json!("INVALID_MESSAGE")));
+    ws.send(json!(null)).await;
+    let reply = ws.next().await.unwrap();
+    assert_eq!((reply["id"].clone(), reply["error_code"].clone()), (Value::Null, json!("INVALID_MESSAGE")));
+    let again = ws.request(json!({ "id": 7, "type": "authenticate", "token": admin["access_token"] })).await;
+    assert_eq!(again["error_code"], "INVALID_MESSAGE");
+}
+
+#[tokio::test]
+async fn test_websocket_authenticates_on_connect_and_is_limited_to_what_the_caller_may_watch() {
+    let app = app();
+    let (alice_id, tokens) = sign_up_and_log_in(&app, "alice.socket@example.com").await;
+    let (bob_id, _) = sign_up_and_log_in(&app, "bob.socket@example.com").await;
+
+    let rejected = WsClient::connect(&app, Some("Bearer not-a-token")).await.err().unwrap();
+    assert!(matches!(rejected, tungstenite::Error::Http(response) if response.status() == StatusCode::UNAUTHORIZED));
+
+    // Without credentials on the upgrade, the first message must authenticate
+    let mut ws = WsClient::connect(&app, None).await.unwrap();
+    let reply = ws.request(json!({ "id": 1, "type": "get_user", "user_id": alice_id })).await;
+    assert_eq!((reply["id"].clone(), reply["error_code"].clone()), (json!(1), json!("UNAUTHENTICATED")));
+    assert_eq!(ws.next().await, None);
+    let mut ws = WsClient::connect(&app, None).await.unwrap();
+    let reply = ws.request(json!({ "type": "authenticate", "token": "not-a-token" })).await;
+    assert_eq!(reply["type"], "error");
+    assert_eq!(ws.next().await, None);
+
+    let mut ws = WsClient::connect(&app, None).await.unwrap();
+    let reply = ws.request(json!({ "id": 1, "type": "authenticate", "token": tokens["access_token"] })).await;
+    assert_eq!(reply["data"], json!({ "user_id": alice_id, "role": "user" }));
+    for (id, subscribe) in [
+        (2, json!({ "id": 2, "type": "subscribe" })),
+        (3, json!({ "id": 3, "type": "subscribe", "user_ids": [alice_id, bob_id] })),
+    ] {
+        let reply = ws.request(subscribe).await;
+        assert_eq!((reply["id"].clone(), reply["error_code"].clone()), (json!(id), json!("FORBIDDEN")));
+    }
+    let reply = ws.request(json!({ "id": 4, "type": "get_user", "user_id": bob_id })).await;
+    assert_eq!(reply["error_code"], "FORBIDDEN");
+    let reply = ws.request(json!({ "id": 5, "type": "subscribe", "user_ids": [alice_id] })).await;
+    assert_eq!(reply["data"], json!({ "directory": false, "user_ids": [alice_id] }));
+
+    let as_alice = app.bearer_for(&alice_id, Role::User);
+    let merge = [("authorization", as_alice.as_str()), ("content-type", "application/merge-patch+json")];
+    let uri = format!("/api/users/{}", alice_id);
+    let (status, _, _) = send_with_headers(&app, "PATCH", &uri, &merge, Some(json!({ "name": "Alice" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    let changed = ws.next().await.unwrap();
+    assert_eq!(changed["event"]["type"], "user.updated");
+    assert_eq!(changed["event"]["data"]["id"], alice_id.as_str());
+}
+
+#[tokio::test]
+async fn test_websocket_closes_once_the_credentials_no_longer_hold() {
+    let app = app();
+    let (alice_id, tokens) = sign_up_and_log_in(&app, "alice.closed@example.com").await;
+    let bearer = format!("Bearer {}", tokens["access_token"].as_str().unwrap());
+
+    // Subscriptions by id are capped, before any is checked
+    let mut ws = WsClient::connect(&app, Some(&bearer)).await.unwrap();
+    let many: Vec<_> = (0..101).map(|_| uuid::Uuid::new_v4()).collect();
+    let reply = ws.request(json!({ "id": 1, "type": "subscribe", "user_ids": many })).await;
+    assert_eq!(reply["error_code"], "INVALID_MESSAGE");
+
+    // Signed out everywhere: the next heartbeat closes the connection
+    let (status, _) = send(&app, "DELETE", &format!("/api/users/{}/sessions", alice_id), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert_eq!(ws.next().await, None);
+
+    // Given another role
+    let tokens = log_in(&app, "alice.closed@example.com").await;
+    let bearer = format!("Bearer {}", tokens["access_token"].as_str().unwrap());
+    let mut ws = WsClient::connect(&app, Some(&bearer)).await.unwrap();
+    let merge = [("content-type", "application/merge-patch+json")];
+    let uri = format!("/api/users/{}", alice_id);
+    let (status, _, _) = send_with_headers(&app, "PATCH", &uri, &merge, Some(json!({ "role": "admin" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(ws.next().await, None);
+
+    // Key revoked
+    let (_, body) = send(
+        &app,
+        "POST",
+        "/api/api-keys",
+        Some(json!({ "name": "socket", "owner_id": alice_id, "scopes": ["users:read"] })),
+    )
+    .await;
+    let key = format!("ApiKey {}", body["data"]["key"].as_str().unwrap());
+    let mut ws = WsClient::connect(&app, Some(&key)).await.unwrap();
+    let reply = ws.request(json!({ "id": 1, "type": "get_user", "user_id": alice_id })).await;
+    assert_eq!(reply["type"], "result");
+    let key_uri = format!("/api/api-keys/{}", body["data"]["id"].as_str().unwrap());
+    let (status, _) = send(&app, "DELETE", &key_uri, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert_eq!(ws.next().await, None);
+
+    // Token expired, while the session lives on
+    let tokens = log_in(&app, "alice.closed@example.com").await;
+    assert!(tokens["access_token"].is_string());
+    let short_lived = JwtAccessTokens::new(b"test-secret", "rust-nexus-tests", Duration::seconds(1));
+    let alice = Principal {
+        user_id: UserId::from_uuid(alice_id.parse().unwrap()),
+        role: Role::Admin,
+        scopes: None,
+        expires_at: None,
+    };
+    let bearer = format!("Bearer {}", short_lived.issue(&alice).unwrap().token);
+    let mut ws = WsClient::connect(&app, Some(&bearer)).await.unwrap();
+    assert_eq!(ws.next().await, None);
+}
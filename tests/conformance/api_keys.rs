@@ -0,0 +1,142 @@
+//! Conformance checks for `ApiKeyRepositoryPort` implementations.
+//!
+//! Factories yield the key repository together with the user repository it
+//! belongs to, since keys require a stored owner:
+//!
+//! ```ignore
+//! api_key_repository_conformance!(in_memory, async {
+//!     let users = InMemoryUserRepository::new();
+//!     Some((users.clone(), InMemoryApiKeyRepository::new(users)))
+//! });
+//! ```
+
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use rust_nexus::domain::{
+    ApiKey, ApiKeyRepositoryPort, Scope, User, UserError, UserId, UserRepositoryPort, current_timestamp,
+};
+
+use super::{delete_user, email, name, save_user};
+
+fn api_key(owner: &User, secret: &str, created_at: DateTime<Utc>) -> ApiKey {
+    ApiKey {
+        id: Uuid::new_v4(),
+        owner_id: owner.id().clone(),
+        name: format!("{} key", secret),
+        prefix: "rnx_abcdefgh".to_string(),
+        key_hash: format!("{:0>64}", secret),
+        scopes: vec![Scope::UsersRead, Scope::UsersWrite],
+        created_at,
+        expires_at: Some(created_at + Duration::days(30)),
+        last_used_at: None,
+        revoked_at: None,
+    }
+}
+
+async fn owner<R: UserRepositoryPort>(users: &R, user_email: &str) -> User {
+    let user = User::new(name("Service Account"), email(user_email));
+    save_user(users, &user).await.unwrap();
+    user
+}
+
+pub async fn api_key_round_trip<R: UserRepositoryPort, K: ApiKeyRepositoryPort>(users: R, keys: K) {
+    let owner = owner(&users, "service@example.com").await;
+    let key = api_key(&owner, "a1", current_timestamp());
+    keys.save(&key).await.unwrap();
+
+    assert_eq!(keys.find_by_hash(&key.key_hash).await.unwrap(), Some(key.clone()));
+    assert_eq!(keys.find_by_hash(&format!("{:0>64}", "ff")).await.unwrap(), None);
+}
+
+pub async fn api_key_for_missing_owner_is_not_found<R: UserRepositoryPort, K: ApiKeyRepositoryPort>(
+    _users: R,
+    keys: K,
+) {
+    let ghost = User::new(name("Ghost"), email("ghost@example.com"));
+    assert!(matches!(
+        keys.save(&api_key(&ghost, "a1", current_timestamp())).await,
+        Err(UserError::NotFound)
+    ));
+}
+
+pub async fn find_all_filters_by_owner_newest_first<R: UserRepositoryPort, K: ApiKeyRepositoryPort>(
+    users: R,
+    keys: K,
+) {
+    let first = owner(&users, "first@example.com").await;
+    let second = owner(&users, "second@example.com").await;
+    let now = current_timestamp();
+    let older = api_key(&first, "a1", now - Duration::minutes(1));
+    let newer = api_key(&first, "a2", now);
+    let other = api_key(&second, "a3", now);
+    for key in [&older, &newer, &other] {
+        keys.save(key).await.unwrap();
+    }
+
+    let owned = keys.find_all(Some(first.id())).await.unwrap();
+    assert_eq!(owned, vec![newer, older]);
+    assert_eq!(keys.find_all(None).await.unwrap().len(), 3);
+    assert!(keys.find_all(Some(&UserId::new())).await.unwrap().is_empty());
+}
+
+pub async fn revoke_and_record_use<R: UserRepositoryPort, K: ApiKeyRepositoryPort>(users: R, keys: K) {
+    let owner = owner(&users, "service@example.com").await;
+    let key = api_key(&owner, "a1", current_timestamp());
+    keys.save(&key).await.unwrap();
+
+    let used_at = current_timestamp();
+    keys.record_use(key.id, used_at).await.unwrap();
+    let revoked_at = current_timestamp();
+    keys.revoke(key.id, revoked_at).await.unwrap();
+
+    let stored = keys.find_by_hash(&key.key_hash).await.unwrap().unwrap();
+    assert_eq!(stored.last_used_at, Some(used_at));
+    assert_eq!(stored.revoked_at, Some(revoked_at));
+    assert!(!stored.is_active(revoked_at));
+
+    // Revoking twice, or a key that never existed, finds nothing to revoke
+    assert!(matches!(keys.revoke(key.id, revoked_at).await, Err(UserError::ApiKeyNotFound)));
+    assert!(matches!(keys.revoke(Uuid::new_v4(), revoked_at).await, Err(UserError::ApiKeyNotFound)));
+}
+
+pub async fn purge_removes_api_keys<R: UserRepositoryPort, K: ApiKeyRepositoryPort>(users: R, keys: K) {
+    let owner = owner(&users, "service@example.com").await;
+    let key = api_key(&owner, "a1", current_timestamp());
+    keys.save(&key).await.unwrap();
+
+    delete_user(&users, &owner, None).await.unwrap();
+    users.purge_deleted(Utc::now() + Duration::seconds(1)).await.unwrap();
+
+    assert_eq!(keys.find_by_hash(&key.key_hash).await.unwrap(), None);
+    assert!(keys.find_all(None).await.unwrap().is_empty());
+}
+
+/// Expand the API key checks into `#[tokio::test]`s for one adapter
+#[macro_export]
+macro_rules! api_key_repository_conformance {
+    ($adapter:ident, $factory:expr) => {
+        mod $adapter {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $crate::api_key_repository_conformance!(@tests $factory;
+                api_key_round_trip,
+                api_key_for_missing_owner_is_not_found,
+                find_all_filters_by_owner_newest_first,
+                revoke_and_record_use,
+                purge_removes_api_keys,
+            );
+        }
+    };
+    (@tests $factory:expr; $($check:ident),+ $(,)?) => {
+        $(
+            #[tokio::test]
+            async fn $check() {
+                if let Some((users, keys)) = $factory.await {
+                    $crate::conformance::api_keys::$check(users, keys).await;
+                }
+            }
+        )+
+    };
+}
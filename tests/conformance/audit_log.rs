@@ -0,0 +1,206 @@
+//! Conformance checks for audited writes and `AuditLogRepositoryPort`
+//! implementations.
+//!
+//! Entries are written by the user repository, so factories yield both
+//! repositories over the same store:
+//!
+//! ```ignore
+//! audit_log_repository_conformance!(in_memory, async {
+//!     let users = InMemoryUserRepository::new();
+//!     Some((users.clone(), InMemoryAuditLogRepository::new(users)))
+//! });
+//! ```
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+use rust_nexus::domain::{
+    AuditAction, AuditContext, AuditEntry, AuditLogRepositoryPort, AuditQuery, User, UserError, UserEvent, UserId,
+    UserRepositoryPort,
+};
+
+use super::{email, name};
+
+fn context(actor: Option<&UserId>, request_id: &str) -> AuditContext {
+    AuditContext {
+        actor: actor.cloned(),
+        request_id: Some(request_id.to_string()),
+    }
+}
+
+/// Entry stamped `minutes` after a fixed instant, so ordering does not depend on the clock
+fn entry_at(
+    context: &AuditContext,
+    action: AuditAction,
+    before: Option<&User>,
+    after: Option<&User>,
+    minutes: i64,
+) -> AuditEntry {
+    AuditEntry {
+        created_at: base_time() + Duration::minutes(minutes),
+        ..AuditEntry::record(context, action, before, after)
+    }
+}
+
+fn base_time() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap()
+}
+
+fn of_user(user: &User) -> AuditQuery {
+    AuditQuery {
+        user_id: Some(user.id().clone()),
+        ..AuditQuery::default()
+    }
+}
+
+pub async fn audited_writes_append_entries<R: UserRepositoryPort, A: AuditLogRepositoryPort>(users: R, audit: A) {
+    let admin = UserId::new();
+    let ctx = context(Some(&admin), "req-1");
+
+    let created = User::new(name("Audited User"), email("audited@example.com"));
+    let create = entry_at(&ctx, AuditAction::Create, None, Some(&created), 0);
+    users.save_audited(&created, &create, &UserEvent::created(&created)).await.unwrap();
+
+    let mut updated = created.clone();
+    updated.update(Some(name("Renamed User")), None).unwrap();
+    let update = entry_at(&ctx, AuditAction::Update, Some(&created), Some(&updated), 1);
+    users.update_audited(&updated, &update, &UserEvent::updated(&created, &updated)).await.unwrap();
+
+    let delete = entry_at(&ctx, AuditAction::Delete, Some(&updated), None, 2);
+    let deleted = UserEvent::deleted(&updated);
+    users.delete_audited(updated.id(), Some(updated.version()), &delete, &deleted).await.unwrap();
+    assert_eq!(users.find_by_id(updated.id()).await.unwrap(), None);
+
+    let restore = entry_at(&context(None, "req-2"), AuditAction::Restore, None, Some(&updated), 3);
+    let restored = UserEvent::restored(&updated);
+    users.restore_audited(updated.id(), restore.created_at, &restore, &restored).await.unwrap();
+    assert!(users.find_by_id(updated.id()).await.unwrap().is_some());
+
+    assert_eq!(
+        audit.find(&of_user(&created), 0, 10).await.unwrap(),
+        vec![restore, delete, update.clone(), create]
+    );
+    assert_eq!(audit.count(&of_user(&created)).await.unwrap(), 4);
+    assert_eq!(update.changes.len(), 1);
+    assert_eq!(update.changes[0].field, "name");
+}
+
+pub async fn failed_writes_leave_no_entry<R: UserRepositoryPort, A: AuditLogRepositoryPort>(users: R, audit: A) {
+    let ctx = context(None, "req-1");
+    let user = User::new(name("Only User"), email("only@example.com"));
+    let create = entry_at(&ctx, AuditAction::Create, None, Some(&user), 0);
+    users.save_audited(&user, &create, &UserEvent::created(&user)).await.unwrap();
+
+    let twin = User::new(name("Twin User"), email("only@example.com"));
+    let create = entry_at(&ctx, AuditAction::Create, None, Some(&twin), 1);
+    let result = users.save_audited(&twin, &create, &UserEvent::created(&twin)).await;
+    assert!(matches!(result, Err(UserError::EmailAlreadyExists)));
+
+    // Stale version: the entity was never updated in memory
+    let mut stale = user.clone();
+    stale.update(Some(name("First Rename")), None).unwrap();
+    let first = entry_at(&ctx, AuditAction::Update, Some(&user), Some(&stale), 2);
+    users.update_audited(&stale, &first, &UserEvent::updated(&user, &stale)).await.unwrap();
+    let mut lost = user.clone();
+    lost.update(Some(name("Lost Rename")), None).unwrap();
+    let update = entry_at(&ctx, AuditAction::Update, Some(&user), Some(&lost), 2);
+    let result = users.update_audited(&lost, &update, &UserEvent::updated(&user, &lost)).await;
+    assert!(matches!(result, Err(UserError::Conflict(_))));
+
+    let delete = entry_at(&ctx, AuditAction::Delete, Some(&user), None, 3);
+    let deleted = UserEvent::deleted(&user);
+    let result = users.delete_audited(user.id(), Some(user.version()), &delete, &deleted).await;
+    assert!(matches!(result, Err(UserError::Conflict(_))));
+    let result = users.delete_audited(&UserId::new(), None, &delete, &deleted).await;
+    assert!(matches!(result, Err(UserError::NotFound)));
+    let restore = entry_at(&ctx, AuditAction::Restore, None, Some(&user), 4);
+    let result = users.restore_audited(user.id(), restore.created_at, &restore, &UserEvent::restored(&user)).await;
+    assert!(matches!(result, Err(UserError::NotFound)));
+
+    assert_eq!(audit.count(&AuditQuery::default()).await.unwrap(), 2);
+    assert_eq!(users.find_by_id(user.id()).await.unwrap().unwrap().name(), stale.name());
+}
+
+pub async fn queries_filter_and_page<R: UserRepositoryPort, A: AuditLogRepositoryPort>(users: R, audit: A) {
+    let (alice, bob) = (UserId::new(), UserId::new());
+    let first = User::new(name("First User"), email("first@example.com"));
+    let second = User::new(name("Second User"), email("second@example.com"));
+    let first_create = entry_at(&context(Some(&alice), "req-a"), AuditAction::Create, None, Some(&first), 0);
+    let second_create = entry_at(&context(Some(&bob), "req-b"), AuditAction::Create, None, Some(&second), 10);
+    users.save_audited(&first, &first_create, &UserEvent::created(&first)).await.unwrap();
+    users.save_audited(&second, &second_create, &UserEvent::created(&second)).await.unwrap();
+    let second_delete = entry_at(&context(Some(&alice), "req-c"), AuditAction::Delete, Some(&second), None, 20);
+    users.delete_audited(second.id(), None, &second_delete, &UserEvent::deleted(&second)).await.unwrap();
+
+    let everything = AuditQuery::default();
+    assert_eq!(
+        audit.find(&everything, 0, 10).await.unwrap(),
+        vec![second_delete.clone(), second_create.clone(), first_create.clone()]
+    );
+    assert_eq!(audit.find(&everything, 1, 1).await.unwrap(), vec![second_create.clone()]);
+    assert_eq!(audit.find(&everything, 3, 10).await.unwrap(), vec![]);
+
+    let by_alice = AuditQuery {
+        actor: Some(alice.clone()),
+        ..AuditQuery::default()
+    };
+    assert_eq!(audit.find(&by_alice, 0, 10).await.unwrap(), vec![second_delete.clone(), first_create.clone()]);
+    assert_eq!(audit.count(&by_alice).await.unwrap(), 2);
+
+    let deletes = AuditQuery {
+        action: Some(AuditAction::Delete),
+        ..AuditQuery::default()
+    };
+    assert_eq!(audit.find(&deletes, 0, 10).await.unwrap(), vec![second_delete.clone()]);
+
+    let by_request = AuditQuery {
+        request_id: Some("req-b".to_string()),
+        ..AuditQuery::default()
+    };
+    assert_eq!(audit.find(&by_request, 0, 10).await.unwrap(), vec![second_create.clone()]);
+
+    // `from` is inclusive, `to` exclusive
+    let window = AuditQuery {
+        from: Some(base_time() + Duration::minutes(10)),
+        to: Some(base_time() + Duration::minutes(20)),
+        ..AuditQuery::default()
+    };
+    assert_eq!(audit.find(&window, 0, 10).await.unwrap(), vec![second_create]);
+    assert_eq!(audit.count(&window).await.unwrap(), 1);
+
+    let combined = AuditQuery {
+        actor: Some(bob),
+        ..of_user(&first)
+    };
+    assert_eq!(audit.count(&combined).await.unwrap(), 0);
+
+    // History outlives the user
+    users.purge_deleted(Utc::now() + Duration::seconds(1)).await.unwrap();
+    assert_eq!(audit.count(&of_user(&second)).await.unwrap(), 2);
+}
+
+/// Expand the audit log checks into `#[tokio::test]`s for one adapter
+#[macro_export]
+macro_rules! audit_log_repository_conformance {
+    ($adapter:ident, $factory:expr) => {
+        mod $adapter {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $crate::audit_log_repository_conformance!(@tests $factory;
+                audited_writes_append_entries,
+                failed_writes_leave_no_entry,
+                queries_filter_and_page,
+            );
+        }
+    };
+    (@tests $factory:expr; $($check:ident),+ $(,)?) => {
+        $(
+            #[tokio::test]
+            async fn $check() {
+                if let Some((users, audit)) = $factory.await {
+                    $crate::conformance::audit_log::$check(users, audit).await;
+                }
+            }
+        )+
+    };
+}
@@ -0,0 +1,83 @@
+//! Conformance checks for `CredentialRepositoryPort` implementations.
+//!
+//! Factories yield the credential repository together with the user
+//! repository it belongs to, since credentials require a stored user:
+//!
+//! ```ignore
+//! credential_repository_conformance!(in_memory, async {
+//!     let users = InMemoryUserRepository::new();
+//!     Some((users.clone(), InMemoryCredentialRepository::new(users)))
+//! });
+//! ```
+
+use chrono::{Duration, Utc};
+
+use rust_nexus::domain::{
+    CredentialRepositoryPort, PasswordHash, User, UserError, UserId, UserRepositoryPort,
+};
+
+use super::{delete_user, email, name, save_user};
+
+fn hash(value: &str) -> PasswordHash {
+    PasswordHash::new(format!("$argon2id$v=19$m=19456,t=2,p=1${}", value))
+}
+
+pub async fn password_hash_round_trip<R: UserRepositoryPort, C: CredentialRepositoryPort>(users: R, credentials: C) {
+    let user = User::new(name("John Doe"), email("john.doe@example.com"));
+    save_user(&users, &user).await.unwrap();
+    assert_eq!(credentials.find_password_hash(user.id()).await.unwrap(), None);
+
+    credentials.set_password_hash(user.id(), &hash("first")).await.unwrap();
+    assert_eq!(credentials.find_password_hash(user.id()).await.unwrap(), Some(hash("first")));
+
+    credentials.set_password_hash(user.id(), &hash("second")).await.unwrap();
+    assert_eq!(credentials.find_password_hash(user.id()).await.unwrap(), Some(hash("second")));
+}
+
+pub async fn password_for_missing_user_is_not_found<R: UserRepositoryPort, C: CredentialRepositoryPort>(
+    _users: R,
+    credentials: C,
+) {
+    assert!(matches!(
+        credentials.set_password_hash(&UserId::new(), &hash("orphan")).await,
+        Err(UserError::NotFound)
+    ));
+}
+
+pub async fn purge_removes_credentials<R: UserRepositoryPort, C: CredentialRepositoryPort>(users: R, credentials: C) {
+    let user = User::new(name("John Doe"), email("john.doe@example.com"));
+    save_user(&users, &user).await.unwrap();
+    credentials.set_password_hash(user.id(), &hash("secret")).await.unwrap();
+
+    delete_user(&users, &user, None).await.unwrap();
+    users.purge_deleted(Utc::now() + Duration::seconds(1)).await.unwrap();
+
+    assert_eq!(credentials.find_password_hash(user.id()).await.unwrap(), None);
+}
+
+/// Expand the credential checks into `#[tokio::test]`s for one adapter
+#[macro_export]
+macro_rules! credential_repository_conformance {
+    ($adapter:ident, $factory:expr) => {
+        mod $adapter {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $crate::credential_repository_conformance!(@tests $factory;
+                password_hash_round_trip,
+                password_for_missing_user_is_not_found,
+                purge_removes_credentials,
+            );
+        }
+    };
+    (@tests $factory:expr; $($check:ident),+ $(,)?) => {
+        $(
+            #[tokio::test]
+            async fn $check() {
+                if let Some((users, credentials)) = $factory.await {
+                    $crate::conformance::credentials::$check(users, credentials).await;
+                }
+            }
+        )+
+    };
+}
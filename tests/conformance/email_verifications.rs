@@ -0,0 +1,125 @@
+//! Conformance checks for `EmailVerificationRepositoryPort` implementations.
+//!
+//! Factories yield the token repository together with the user repository it
+//! belongs to, since tokens require a stored user:
+//!
+//! ```ignore
+//! email_verification_repository_conformance!(in_memory, async {
+//!     let users = InMemoryUserRepository::new();
+//!     Some((users.clone(), InMemoryEmailVerificationRepository::new(users)))
+//! });
+//! ```
+
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use rust_nexus::domain::{
+    EmailVerificationRepositoryPort, EmailVerificationToken, User, UserError, UserRepositoryPort, current_timestamp,
+};
+
+use super::{delete_user, email, name, save_user};
+
+fn token(user: &User, secret: &str, expires_at: DateTime<Utc>) -> EmailVerificationToken {
+    EmailVerificationToken {
+        id: Uuid::new_v4(),
+        user_id: user.id().clone(),
+        email: user.email().clone(),
+        token_hash: format!("{:0>64}", secret),
+        created_at: current_timestamp(),
+        expires_at,
+        used_at: None,
+    }
+}
+
+async fn user<R: UserRepositoryPort>(users: &R, user_email: &str) -> User {
+    let user = User::new(name("Unverified User"), email(user_email));
+    save_user(users, &user).await.unwrap();
+    user
+}
+
+fn tomorrow() -> DateTime<Utc> {
+    current_timestamp() + Duration::days(1)
+}
+
+pub async fn verification_token_round_trip<R: UserRepositoryPort, T: EmailVerificationRepositoryPort>(
+    users: R,
+    tokens: T,
+) {
+    let user = user(&users, "verify@example.com").await;
+    let stored = token(&user, "a1", tomorrow());
+    tokens.save(&stored).await.unwrap();
+
+    assert_eq!(tokens.find_by_token_hash(&stored.token_hash).await.unwrap(), Some(stored));
+    assert_eq!(tokens.find_by_token_hash(&format!("{:0>64}", "ff")).await.unwrap(), None);
+
+    let ghost = User::new(name("Ghost"), email("ghost@example.com"));
+    assert!(matches!(tokens.save(&token(&ghost, "a2", tomorrow())).await, Err(UserError::NotFound)));
+}
+
+pub async fn mark_used_succeeds_once<R: UserRepositoryPort, T: EmailVerificationRepositoryPort>(
+    users: R,
+    tokens: T,
+) {
+    let user = user(&users, "verify@example.com").await;
+    let stored = token(&user, "a1", tomorrow());
+    tokens.save(&stored).await.unwrap();
+
+    let used_at = current_timestamp();
+    assert!(tokens.mark_used(stored.id, used_at).await.unwrap());
+    assert!(!tokens.mark_used(stored.id, used_at).await.unwrap());
+    assert!(!tokens.mark_used(Uuid::new_v4(), used_at).await.unwrap());
+
+    let found = tokens.find_by_token_hash(&stored.token_hash).await.unwrap().unwrap();
+    assert_eq!(found.used_at, Some(used_at));
+    assert!(!found.is_usable(used_at));
+}
+
+pub async fn purge_expired_and_deleted<R: UserRepositoryPort, T: EmailVerificationRepositoryPort>(
+    users: R,
+    tokens: T,
+) {
+    let kept_user = user(&users, "kept@example.com").await;
+    let gone_user = user(&users, "gone@example.com").await;
+    let now = current_timestamp();
+    let expired = token(&kept_user, "a1", now - Duration::minutes(1));
+    let live = token(&kept_user, "a2", tomorrow());
+    let orphaned = token(&gone_user, "a3", tomorrow());
+    for t in [&expired, &live, &orphaned] {
+        tokens.save(t).await.unwrap();
+    }
+
+    assert_eq!(tokens.purge_expired(now).await.unwrap(), 1);
+    assert_eq!(tokens.find_by_token_hash(&expired.token_hash).await.unwrap(), None);
+    assert!(tokens.find_by_token_hash(&live.token_hash).await.unwrap().is_some());
+
+    delete_user(&users, &gone_user, None).await.unwrap();
+    users.purge_deleted(Utc::now() + Duration::seconds(1)).await.unwrap();
+    assert_eq!(tokens.find_by_token_hash(&orphaned.token_hash).await.unwrap(), None);
+}
+
+/// Expand the email verification checks into `#[tokio::test]`s for one adapter
+#[macro_export]
+macro_rules! email_verification_repository_conformance {
+    ($adapter:ident, $factory:expr) => {
+        mod $adapter {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $crate::email_verification_repository_conformance!(@tests $factory;
+                verification_token_round_trip,
+                mark_used_succeeds_once,
+                purge_expired_and_deleted,
+            );
+        }
+    };
+    (@tests $factory:expr; $($check:ident),+ $(,)?) => {
+        $(
+            #[tokio::test]
+            async fn $check() {
+                if let Some((users, tokens)) = $factory.await {
+                    $crate::conformance::email_verifications::$check(users, tokens).await;
+                }
+            }
+        )+
+    };
+}
@@ -0,0 +1,129 @@
+//! Conformance checks for `IdempotencyRepositoryPort` implementations.
+//!
+//! Records stand alone, so factories yield just the repository:
+//!
+//! ```ignore
+//! idempotency_repository_conformance!(in_memory, async { Some(InMemoryIdempotencyRepository::new()) });
+//! ```
+
+use chrono::{DateTime, Duration, Utc};
+
+use rust_nexus::domain::{IdempotencyRecord, IdempotencyRepositoryPort, StoredResponse, current_timestamp};
+
+fn record(scope: &str, key: &str, fingerprint: &str, created_at: DateTime<Utc>) -> IdempotencyRecord {
+    IdempotencyRecord {
+        scope: scope.to_string(),
+        key: key.to_string(),
+        fingerprint: format!("{:0>64}", fingerprint),
+        response: None,
+        created_at,
+        expires_at: created_at + Duration::hours(24),
+    }
+}
+
+fn created() -> StoredResponse {
+    StoredResponse {
+        status: 201,
+        headers: vec![
+            ("content-type".to_string(), "application/json".to_string()),
+            ("etag".to_string(), "\"1\"".to_string()),
+        ],
+        body: r#"{"success":true}"#.to_string(),
+    }
+}
+
+fn long_ago() -> DateTime<Utc> {
+    current_timestamp() - Duration::minutes(5)
+}
+
+pub async fn claim_complete_and_find<T: IdempotencyRepositoryPort>(records: T) {
+    let now = current_timestamp();
+    let claimed = record("user:a", "key-1", "f1", now);
+    assert!(records.claim(&claimed, long_ago()).await.unwrap());
+    assert_eq!(records.find("user:a", "key-1").await.unwrap(), Some(claimed.clone()));
+    assert_eq!(records.find("user:b", "key-1").await.unwrap(), None);
+
+    records.complete("user:a", "key-1", &created()).await.unwrap();
+    let found = records.find("user:a", "key-1").await.unwrap().unwrap();
+    assert_eq!(found.response, Some(created()));
+    assert_eq!(found.fingerprint, claimed.fingerprint);
+}
+
+pub async fn claimed_keys_are_taken_per_scope<T: IdempotencyRepositoryPort>(records: T) {
+    let now = current_timestamp();
+    assert!(records.claim(&record("user:a", "key-1", "f1", now), long_ago()).await.unwrap());
+    assert!(!records.claim(&record("user:a", "key-1", "f2", now), long_ago()).await.unwrap());
+    assert!(records.claim(&record("user:b", "key-1", "f2", now), long_ago()).await.unwrap());
+
+    // A completed key stays taken however old its request is
+    records.complete("user:a", "key-1", &created()).await.unwrap();
+    assert!(!records.claim(&record("user:a", "key-1", "f1", now), now + Duration::hours(1)).await.unwrap());
+    assert_eq!(records.find("user:a", "key-1").await.unwrap().unwrap().response, Some(created()));
+}
+
+pub async fn expired_and_abandoned_keys_are_free<T: IdempotencyRepositoryPort>(records: T) {
+    let now = current_timestamp();
+    let day_ago = now - Duration::hours(25);
+    assert!(records.claim(&record("user:a", "expired", "f1", day_ago), long_ago()).await.unwrap());
+    records.complete("user:a", "expired", &created()).await.unwrap();
+    let abandoned = record("user:a", "abandoned", "f1", now - Duration::minutes(10));
+    assert!(records.claim(&abandoned, long_ago()).await.unwrap());
+
+    let retry = record("user:a", "expired", "f2", now);
+    assert!(records.claim(&retry, long_ago()).await.unwrap());
+    assert_eq!(records.find("user:a", "expired").await.unwrap(), Some(retry));
+    assert!(records.claim(&record("user:a", "abandoned", "f2", now), long_ago()).await.unwrap());
+}
+
+pub async fn release_frees_only_pending_keys<T: IdempotencyRepositoryPort>(records: T) {
+    let now = current_timestamp();
+    records.claim(&record("user:a", "pending", "f1", now), long_ago()).await.unwrap();
+    records.claim(&record("user:a", "done", "f1", now), long_ago()).await.unwrap();
+    records.complete("user:a", "done", &created()).await.unwrap();
+
+    records.release("user:a", "pending").await.unwrap();
+    records.release("user:a", "done").await.unwrap();
+    records.release("user:a", "unknown").await.unwrap();
+    assert_eq!(records.find("user:a", "pending").await.unwrap(), None);
+    assert!(records.find("user:a", "done").await.unwrap().is_some());
+    assert!(records.claim(&record("user:a", "pending", "f2", now), long_ago()).await.unwrap());
+}
+
+pub async fn purge_removes_only_expired_records<T: IdempotencyRepositoryPort>(records: T) {
+    let now = current_timestamp();
+    records.claim(&record("user:a", "old", "f1", now - Duration::hours(25)), long_ago()).await.unwrap();
+    records.claim(&record("user:a", "new", "f1", now), long_ago()).await.unwrap();
+
+    assert_eq!(records.purge_expired(now).await.unwrap(), 1);
+    assert_eq!(records.find("user:a", "old").await.unwrap(), None);
+    assert!(records.find("user:a", "new").await.unwrap().is_some());
+}
+
+/// Expand the idempotency checks into `#[tokio::test]`s for one adapter
+#[macro_export]
+macro_rules! idempotency_repository_conformance {
+    ($adapter:ident, $factory:expr) => {
+        mod $adapter {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $crate::idempotency_repository_conformance!(@tests $factory;
+                claim_complete_and_find,
+                claimed_keys_are_taken_per_scope,
+                expired_and_abandoned_keys_are_free,
+                release_frees_only_pending_keys,
+                purge_removes_only_expired_records,
+            );
+        }
+    };
+    (@tests $factory:expr; $($check:ident),+ $(,)?) => {
+        $(
+            #[tokio::test]
+            async fn $check() {
+                if let Some(records) = $factory.await {
+                    $crate::conformance::idempotency::$check(records).await;
+                }
+            }
+        )+
+    };
+}
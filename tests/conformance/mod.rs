@@ -0,0 +1,495 @@
+//! Conformance suite for `UserRepositoryPort` implementations.
+//!
+//! Every check takes a fresh, empty repository and asserts one part of the
+//! port contract. Adapters opt in with `user_repository_conformance!`, which
+//! expands to one `#[tokio::test]` per check:
+//!
+//! ```ignore
+//! user_repository_conformance!(in_memory, async { Some(InMemoryUserRepository::new()) });
+//! ```
+//!
+//! The factory expression is awaited once per test and must yield an isolated
+//! repository, because checks assume they start from an empty store. It yields
+//! `None` to skip the suite, e.g. when a backing service is not configured.
+
+pub mod api_keys;
+pub mod audit_log;
+pub mod credentials;
+pub mod email_verifications;
+pub mod idempotency;
+pub mod outbox;
+pub mod password_resets;
+pub mod sessions;
+pub mod webhooks;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+use rust_nexus::domain::{
+    AuditAction, AuditContext, AuditEntry, CountAccuracy, Email, Role, SortDirection, User, UserCursor, UserError,
+    UserEvent, UserId, UserName, UserQuery, UserRepositoryPort, UserSort, UserSortField, current_timestamp,
+};
+
+pub fn name(value: &str) -> UserName {
+    UserName::new(value.to_string()).unwrap()
+}
+
+pub fn email(value: &str) -> Email {
+    Email::new(value.to_string()).unwrap()
+}
+
+/// A user with explicit timestamps, so ordering does not depend on the clock
+pub fn user_created_at(user_name: &str, user_email: &str, created_at: DateTime<Utc>) -> User {
+    User::from_persistence(
+        UserId::new(),
+        name(user_name),
+        email(user_email),
+        None,
+        Role::User,
+        created_at,
+        created_at,
+        None,
+        1,
+    )
+}
+
+// Writes through the audited port methods, with the entry and event the
+// domain service would record, for checks about the stored users only
+
+pub async fn save_user<R: UserRepositoryPort>(repo: &R, user: &User) -> Result<(), UserError> {
+    let audit = AuditEntry::record(&AuditContext::default(), AuditAction::Create, None, Some(user));
+    repo.save_audited(user, &audit, &UserEvent::created(user)).await
+}
+
+/// Stores `user` as is, so the event lists no changes
+pub async fn update_user<R: UserRepositoryPort>(repo: &R, user: &User) -> Result<(), UserError> {
+    let audit = AuditEntry::record(&AuditContext::default(), AuditAction::Update, None, Some(user));
+    repo.update_audited(user, &audit, &UserEvent::updated(user, user)).await
+}
+
+pub async fn delete_user<R: UserRepositoryPort>(
+    repo: &R,
+    user: &User,
+    expected_version: Option<i64>,
+) -> Result<(), UserError> {
+    let audit = AuditEntry::record(&AuditContext::default(), AuditAction::Delete, Some(user), None);
+    repo.delete_audited(user.id(), expected_version, &audit, &UserEvent::deleted(user)).await
+}
+
+pub async fn restore_user<R: UserRepositoryPort>(repo: &R, user: &User) -> Result<(), UserError> {
+    let audit = AuditEntry::record(&AuditContext::default(), AuditAction::Restore, None, Some(user));
+    repo.restore_audited(user.id(), audit.created_at, &audit, &UserEvent::restored(user)).await
+}
+
+/// No filters, default (newest first) order
+fn all() -> UserQuery {
+    UserQuery::default()
+}
+
+fn base_time() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap()
+}
+
+pub async fn save_then_find_round_trip<R: UserRepositoryPort>(repo: R) {
+    let user = User::new(name("John Doe"), email("john.doe@example.com"));
+    save_user(&repo, &user).await.unwrap();
+
+    let found = repo.find_by_id(user.id()).await.unwrap();
+    assert_eq!(found, Some(user));
+}
+
+pub async fn find_missing_returns_none<R: UserRepositoryPort>(repo: R) {
+    assert_eq!(repo.find_by_id(&UserId::new()).await.unwrap(), None);
+}
+
+pub async fn find_by_email_skips_deleted_users<R: UserRepositoryPort>(repo: R) {
+    let user = User::new(name("John Doe"), email("john.doe@example.com"));
+    save_user(&repo, &user).await.unwrap();
+    assert_eq!(repo.find_by_email(user.email()).await.unwrap(), Some(user.clone()));
+    assert_eq!(repo.find_by_email(&email("someone@example.com")).await.unwrap(), None);
+
+    delete_user(&repo, &user, None).await.unwrap();
+    assert_eq!(repo.find_by_email(user.email()).await.unwrap(), None);
+}
+
+pub async fn update_round_trip<R: UserRepositoryPort>(repo: R) {
+    let mut user = User::new(name("John Doe"), email("john.doe@example.com"));
+    save_user(&repo, &user).await.unwrap();
+
+    user.update(Some(name("Jane Doe")), Some(email("jane.doe@example.com")))
+        .unwrap();
+    update_user(&repo, &user).await.unwrap();
+
+    let found = repo.find_by_id(user.id()).await.unwrap().unwrap();
+    assert_eq!(found.name().as_str(), "Jane Doe");
+    assert_eq!(found.email().as_str(), "jane.doe@example.com");
+    assert_eq!(found.updated_at(), user.updated_at());
+}
+
+pub async fn update_missing_is_not_found<R: UserRepositoryPort>(repo: R) {
+    let user = User::new(name("Ghost"), email("ghost@example.com"));
+    assert!(matches!(update_user(&repo, &user).await, Err(UserError::NotFound)));
+}
+
+pub async fn delete_removes_user<R: UserRepositoryPort>(repo: R) {
+    let user = User::new(name("John Doe"), email("john.doe@example.com"));
+    save_user(&repo, &user).await.unwrap();
+
+    delete_user(&repo, &user, None).await.unwrap();
+
+    assert_eq!(repo.find_by_id(user.id()).await.unwrap(), None);
+    assert!(!repo.exists_by_email(user.email()).await.unwrap());
+}
+
+pub async fn delete_missing_is_not_found<R: UserRepositoryPort>(repo: R) {
+    let ghost = User::new(name("Ghost"), email("ghost@example.com"));
+    assert!(matches!(delete_user(&repo, &ghost, None).await, Err(UserError::NotFound)));
+}
+
+pub async fn duplicate_email_is_rejected<R: UserRepositoryPort>(repo: R) {
+    let first = User::new(name("John Doe"), email("john.doe@example.com"));
+    save_user(&repo, &first).await.unwrap();
+
+    let second = User::new(name("John Clone"), email("John.Doe@Example.COM"));
+    assert!(matches!(save_user(&repo, &second).await, Err(UserError::EmailAlreadyExists)));
+}
+
+pub async fn exists_by_email_ignores_case<R: UserRepositoryPort>(repo: R) {
+    let user = User::new(name("John Doe"), email("john.doe@example.com"));
+    save_user(&repo, &user).await.unwrap();
+
+    assert!(repo.exists_by_email(&email("JOHN.DOE@example.com")).await.unwrap());
+    assert!(!repo.exists_by_email(&email("someone@example.com")).await.unwrap());
+}
+
+pub async fn update_to_taken_email_is_rejected<R: UserRepositoryPort>(repo: R) {
+    let john = User::new(name("John Doe"), email("john.doe@example.com"));
+    let mut jane = User::new(name("Jane Doe"), email("jane.doe@example.com"));
+    save_user(&repo, &john).await.unwrap();
+    save_user(&repo, &jane).await.unwrap();
+
+    jane.update(None, Some(email("John.Doe@example.com"))).unwrap();
+    assert!(matches!(update_user(&repo, &jane).await, Err(UserError::EmailAlreadyExists)));
+}
+
+pub async fn find_all_orders_newest_first_and_paginates<R: UserRepositoryPort>(repo: R) {
+    let oldest = user_created_at("Oldest", "oldest@example.com", base_time());
+    let middle = user_created_at("Middle", "middle@example.com", base_time() + Duration::minutes(1));
+    let newest = user_created_at("Newest", "newest@example.com", base_time() + Duration::minutes(2));
+    for user in [&middle, &oldest, &newest] {
+        save_user(&repo, user).await.unwrap();
+    }
+
+    let first_page = repo.find_all(&all(), 0, 2).await.unwrap();
+    assert_eq!(first_page, vec![newest.clone(), middle.clone()]);
+
+    let second_page = repo.find_all(&all(), 2, 2).await.unwrap();
+    assert_eq!(second_page, vec![oldest.clone()]);
+
+    assert!(repo.find_all(&all(), 3, 2).await.unwrap().is_empty());
+}
+
+pub async fn find_after_walks_keyset_without_gaps<R: UserRepositoryPort>(repo: R) {
+    // Two users share a timestamp, so the id tie-breaker is exercised
+    let mut expected = vec![
+        user_created_at("Newest", "newest@example.com", base_time() + Duration::minutes(2)),
+        user_created_at("Tie A", "tie.a@example.com", base_time() + Duration::minutes(1)),
+        user_created_at("Tie B", "tie.b@example.com", base_time() + Duration::minutes(1)),
+        user_created_at("Oldest", "oldest@example.com", base_time()),
+    ];
+    for user in &expected {
+        save_user(&repo, user).await.unwrap();
+    }
+    expected.sort_by(|a, b| {
+        b.created_at()
+            .cmp(&a.created_at())
+            .then_with(|| a.id().as_uuid().cmp(&b.id().as_uuid()))
+    });
+
+    let first_page = repo.find_after(&all(), None, 2).await.unwrap();
+    assert_eq!(first_page, expected[..2]);
+
+    // A user inserted at the head must not shift the following page
+    let late = user_created_at("Late", "late@example.com", base_time() + Duration::minutes(3));
+    save_user(&repo, &late).await.unwrap();
+
+    let cursor = UserCursor::after(first_page.last().unwrap(), UserSort::default());
+    let second_page = repo.find_after(&all(), Some(&cursor), 2).await.unwrap();
+    assert_eq!(second_page, expected[2..]);
+
+    let cursor = UserCursor::after(second_page.last().unwrap(), UserSort::default());
+    assert!(repo.find_after(&all(), Some(&cursor), 2).await.unwrap().is_empty());
+}
+
+pub async fn count_tracks_saves_and_deletes<R: UserRepositoryPort>(repo: R) {
+    assert_eq!(repo.count(&all(), CountAccuracy::Exact).await.unwrap(), 0);
+
+    let john = User::new(name("John Doe"), email("john.doe@example.com"));
+    let jane = User::new(name("Jane Doe"), email("jane.doe@example.com"));
+    save_user(&repo, &john).await.unwrap();
+    save_user(&repo, &jane).await.unwrap();
+    assert_eq!(repo.count(&all(), CountAccuracy::Exact).await.unwrap(), 2);
+
+    delete_user(&repo, &john, None).await.unwrap();
+    assert_eq!(repo.count(&all(), CountAccuracy::Exact).await.unwrap(), 1);
+
+    // Estimates may lag, but must never fail
+    assert!(repo.count(&all(), CountAccuracy::Estimated).await.unwrap() >= 0);
+}
+
+pub async fn filters_narrow_listing_and_count<R: UserRepositoryPort>(repo: R) {
+    let alice = user_created_at("Alice Smith", "alice@example.com", base_time());
+    let alfred = user_created_at("Alfred Jones", "alfred@corp.io", base_time() + Duration::minutes(1));
+    let bob = user_created_at("Bob Alison", "bob@example.com", base_time() + Duration::minutes(2));
+    // Wildcards in input must match literally
+    let percent = user_created_at("100% Real", "percent@corp.io", base_time() + Duration::minutes(3));
+    for user in [&alice, &alfred, &bob, &percent] {
+        save_user(&repo, user).await.unwrap();
+    }
+
+    let cases = [
+        (UserQuery { name_prefix: Some("AL".into()), ..all() }, vec![&alfred, &alice]),
+        (UserQuery { name_contains: Some("alI".into()), ..all() }, vec![&bob, &alice]),
+        (UserQuery { name_contains: Some("0%".into()), ..all() }, vec![&percent]),
+        (UserQuery { name_prefix: Some("_".into()), ..all() }, vec![]),
+        (UserQuery { email: Some(email("bob@example.com")), ..all() }, vec![&bob]),
+        (UserQuery { email_domain: Some("corp.io".into()), ..all() }, vec![&percent, &alfred]),
+        (
+            UserQuery {
+                created_after: Some(base_time() + Duration::minutes(1)),
+                created_before: Some(base_time() + Duration::minutes(3)),
+                ..all()
+            },
+            vec![&bob, &alfred],
+        ),
+        (
+            UserQuery { updated_before: Some(base_time() + Duration::minutes(1)), ..all() },
+            vec![&alice],
+        ),
+    ];
+
+    for (query, expected) in cases {
+        let expected: Vec<User> = expected.into_iter().cloned().collect();
+        assert_eq!(repo.find_all(&query, 0, 10).await.unwrap(), expected, "{:?}", query);
+        assert_eq!(
+            repo.count(&query, CountAccuracy::Exact).await.unwrap(),
+            expected.len() as i64,
+            "{:?}",
+            query
+        );
+    }
+}
+
+pub async fn sort_orders_and_keyset_follow_query<R: UserRepositoryPort>(repo: R) {
+    let carol = user_created_at("carol", "carol@example.com", base_time());
+    let alice = user_created_at("Alice", "alice@example.com", base_time() + Duration::minutes(1));
+    let bob = user_created_at("Bob", "bob@example.com", base_time() + Duration::minutes(2));
+    for user in [&carol, &alice, &bob] {
+        save_user(&repo, user).await.unwrap();
+    }
+
+    let by_name = UserQuery {
+        sort: UserSort { field: UserSortField::Name, direction: SortDirection::Asc },
+        ..all()
+    };
+    let expected = vec![alice.clone(), bob.clone(), carol.clone()];
+    assert_eq!(repo.find_all(&by_name, 0, 10).await.unwrap(), expected);
+
+    let first = repo.find_after(&by_name, None, 2).await.unwrap();
+    assert_eq!(first, expected[..2]);
+    let cursor = UserCursor::after(first.last().unwrap(), by_name.sort);
+    assert_eq!(repo.find_after(&by_name, Some(&cursor), 2).await.unwrap(), expected[2..]);
+
+    let oldest_first = UserQuery {
+        sort: UserSort { field: UserSortField::CreatedAt, direction: SortDirection::Asc },
+        ..all()
+    };
+    assert_eq!(repo.find_all(&oldest_first, 0, 10).await.unwrap(), vec![carol, alice, bob]);
+}
+
+pub async fn timestamps_are_preserved<R: UserRepositoryPort>(repo: R) {
+    let created_at = base_time() + Duration::microseconds(123_456);
+    let user = user_created_at("John Doe", "john.doe@example.com", created_at);
+    save_user(&repo, &user).await.unwrap();
+
+    let mut updated = repo.find_by_id(user.id()).await.unwrap().unwrap();
+    assert_eq!(updated.created_at(), created_at);
+    assert_eq!(updated.updated_at(), created_at);
+
+    updated.update(Some(name("Jane Doe")), None).unwrap();
+    update_user(&repo, &updated).await.unwrap();
+
+    let found = repo.find_by_id(user.id()).await.unwrap().unwrap();
+    assert_eq!(found.created_at(), created_at, "update must not touch created_at");
+    assert_eq!(found.updated_at(), updated.updated_at());
+}
+
+pub async fn soft_deleted_user_is_hidden_and_restorable<R: UserRepositoryPort>(repo: R) {
+    let user = User::new(name("John Doe"), email("john.doe@example.com"));
+    save_user(&repo, &user).await.unwrap();
+    delete_user(&repo, &user, None).await.unwrap();
+
+    assert_eq!(repo.find_by_id(user.id()).await.unwrap(), None);
+    assert!(repo.find_all(&all(), 0, 10).await.unwrap().is_empty());
+    assert_eq!(repo.count(&all(), CountAccuracy::Exact).await.unwrap(), 0);
+    assert!(matches!(delete_user(&repo, &user, None).await, Err(UserError::NotFound)));
+    assert!(matches!(update_user(&repo, &user).await, Err(UserError::NotFound)));
+
+    let deleted = repo.find_by_id_including_deleted(user.id()).await.unwrap().unwrap();
+    assert!(deleted.is_deleted());
+    let with_deleted = UserQuery { include_deleted: true, ..all() };
+    assert_eq!(repo.find_all(&with_deleted, 0, 10).await.unwrap(), vec![deleted]);
+    assert_eq!(repo.count(&with_deleted, CountAccuracy::Exact).await.unwrap(), 1);
+
+    restore_user(&repo, &user).await.unwrap();
+    let restored = repo.find_by_id(user.id()).await.unwrap().unwrap();
+    assert_eq!(restored.deleted_at(), None);
+    assert_eq!(restored.created_at(), user.created_at());
+    assert!(matches!(restore_user(&repo, &user).await, Err(UserError::NotFound)));
+}
+
+pub async fn deleted_email_is_reusable_until_restore<R: UserRepositoryPort>(repo: R) {
+    let original = User::new(name("John Doe"), email("john.doe@example.com"));
+    save_user(&repo, &original).await.unwrap();
+    delete_user(&repo, &original, None).await.unwrap();
+
+    let successor = User::new(name("John Again"), email("John.Doe@example.com"));
+    save_user(&repo, &successor).await.unwrap();
+    assert!(repo.exists_by_email(original.email()).await.unwrap());
+
+    assert!(matches!(restore_user(&repo, &original).await, Err(UserError::EmailAlreadyExists)));
+    assert!(repo.find_by_id(original.id()).await.unwrap().is_none());
+}
+
+pub async fn purge_removes_only_expired_deletions<R: UserRepositoryPort>(repo: R) {
+    let kept = User::new(name("Kept"), email("kept@example.com"));
+    let deleted = User::new(name("Deleted"), email("deleted@example.com"));
+    save_user(&repo, &kept).await.unwrap();
+    save_user(&repo, &deleted).await.unwrap();
+    delete_user(&repo, &deleted, None).await.unwrap();
+
+    // Deleted just now, so still inside any retention window
+    let an_hour_ago = Utc::now() - Duration::hours(1);
+    assert_eq!(repo.purge_deleted(an_hour_ago).await.unwrap(), 0);
+    assert!(repo.find_by_id_including_deleted(deleted.id()).await.unwrap().is_some());
+
+    let later = Utc::now() + Duration::seconds(1);
+    assert_eq!(repo.purge_deleted(later).await.unwrap(), 1);
+    assert_eq!(repo.find_by_id_including_deleted(deleted.id()).await.unwrap(), None);
+    assert!(repo.find_by_id(kept.id()).await.unwrap().is_some());
+    assert!(matches!(restore_user(&repo, &deleted).await, Err(UserError::NotFound)));
+}
+
+pub async fn stale_update_is_a_conflict<R: UserRepositoryPort>(repo: R) {
+    let user = User::new(name("John Doe"), email("john.doe@example.com"));
+    save_user(&repo, &user).await.unwrap();
+
+    // Two writers load the same version; the second one to write loses
+    let mut first = repo.find_by_id(user.id()).await.unwrap().unwrap();
+    let mut second = first.clone();
+    first.update(Some(name("First Writer")), None).unwrap();
+    second.update(Some(name("Second Writer")), None).unwrap();
+
+    update_user(&repo, &first).await.unwrap();
+    assert!(matches!(update_user(&repo, &second).await, Err(UserError::Conflict(_))));
+
+    let stored = repo.find_by_id(user.id()).await.unwrap().unwrap();
+    assert_eq!(stored.name().as_str(), "First Writer");
+    assert_eq!(stored.version(), user.version() + 1);
+}
+
+pub async fn versioned_delete_requires_current_version<R: UserRepositoryPort>(repo: R) {
+    let mut user = User::new(name("John Doe"), email("john.doe@example.com"));
+    save_user(&repo, &user).await.unwrap();
+    let loaded_version = user.version();
+    user.update(Some(name("Jane Doe")), None).unwrap();
+    update_user(&repo, &user).await.unwrap();
+
+    assert!(matches!(
+        delete_user(&repo, &user, Some(loaded_version)).await,
+        Err(UserError::Conflict(_))
+    ));
+    delete_user(&repo, &user, Some(user.version())).await.unwrap();
+
+    // Deleting and restoring are changes too
+    let deleted = repo.find_by_id_including_deleted(user.id()).await.unwrap().unwrap();
+    assert_eq!(deleted.version(), user.version() + 1);
+    restore_user(&repo, &user).await.unwrap();
+    let restored = repo.find_by_id(user.id()).await.unwrap().unwrap();
+    assert_eq!(restored.version(), user.version() + 2);
+}
+
+pub async fn role_is_stored_and_updated<R: UserRepositoryPort>(repo: R) {
+    let mut user = User::new(name("John Doe"), email("john.doe@example.com"));
+    user.set_role(Role::ReadOnly);
+    save_user(&repo, &user).await.unwrap();
+    assert_eq!(repo.find_by_id(user.id()).await.unwrap().unwrap().role(), Role::ReadOnly);
+
+    user.set_role(Role::Admin);
+    user.update(None, None).unwrap();
+    update_user(&repo, &user).await.unwrap();
+    assert_eq!(repo.find_by_id(user.id()).await.unwrap().unwrap().role(), Role::Admin);
+}
+
+pub async fn email_verification_is_stored_and_reset<R: UserRepositoryPort>(repo: R) {
+    let mut user = User::new(name("John Doe"), email("john.doe@example.com"));
+    save_user(&repo, &user).await.unwrap();
+    assert_eq!(repo.find_by_id(user.id()).await.unwrap().unwrap().email_verified_at(), None);
+
+    let verified_at = current_timestamp();
+    user.mark_email_verified(verified_at);
+    update_user(&repo, &user).await.unwrap();
+    let found = repo.find_by_id(user.id()).await.unwrap().unwrap();
+    assert_eq!(found.email_verified_at(), Some(verified_at));
+    assert_eq!(found.version(), user.version());
+
+    user.update(None, Some(email("john.doe@example.net"))).unwrap();
+    update_user(&repo, &user).await.unwrap();
+    assert_eq!(repo.find_by_id(user.id()).await.unwrap().unwrap().email_verified_at(), None);
+}
+
+/// Expand the conformance suite into `#[tokio::test]`s for one adapter
+#[macro_export]
+macro_rules! user_repository_conformance {
+    ($adapter:ident, $factory:expr) => {
+        mod $adapter {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $crate::user_repository_conformance!(@tests $factory;
+                save_then_find_round_trip,
+                find_missing_returns_none,
+                find_by_email_skips_deleted_users,
+                update_round_trip,
+                update_missing_is_not_found,
+                delete_removes_user,
+                delete_missing_is_not_found,
+                duplicate_email_is_rejected,
+                exists_by_email_ignores_case,
+                update_to_taken_email_is_rejected,
+                find_all_orders_newest_first_and_paginates,
+                find_after_walks_keyset_without_gaps,
+                count_tracks_saves_and_deletes,
+                filters_narrow_listing_and_count,
+                sort_orders_and_keyset_follow_query,
+                timestamps_are_preserved,
+                soft_deleted_user_is_hidden_and_restorable,
+                deleted_email_is_reusable_until_restore,
+                purge_removes_only_expired_deletions,
+                stale_update_is_a_conflict,
+                versioned_delete_requires_current_version,
+                role_is_stored_and_updated,
+                email_verification_is_stored_and_reset,
+            );
+        }
+    };
+    (@tests $factory:expr; $($check:ident),+ $(,)?) => {
+        $(
+            #[tokio::test]
+            async fn $check() {
+                if let Some(repo) = $factory.await {
+                    $crate::conformance::$check(repo).await;
+                }
+            }
+        )+
+    };
+}
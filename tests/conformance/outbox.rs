@@ -0,0 +1,294 @@
+//! Conformance checks for the transactional outbox: events enqueued by the
+//! `*_audited` writes of `UserRepositoryPort` and `OutboxRepositoryPort`
+//! implementations.
+//!
+//! Events are enqueued by the user repository, so factories yield both
+//! repositories over the same store:
+//!
+//! ```ignore
+//! outbox_repository_conformance!(in_memory, async {
+//!     let users = InMemoryUserRepository::new();
+//!     Some((users.clone(), InMemoryOutboxRepository::new(users)))
+//! });
+//! ```
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+use rust_nexus::domain::{
+    AuditAction, AuditContext, AuditEntry, OutboxMessage, OutboxRepositoryPort, User, UserCreated, UserDeleted,
+    UserError, UserEvent, UserRepositoryPort, UserRestored, UserUpdated,
+};
+
+use super::{email, name};
+
+/// Events occur at fixed instants, so comparisons do not depend on the
+/// clock or on timestamp precision
+fn base_time() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap()
+}
+
+fn at(minutes: i64) -> DateTime<Utc> {
+    base_time() + Duration::minutes(minutes)
+}
+
+fn stamped(event: UserEvent, minutes: i64) -> UserEvent {
+    match event {
+        UserEvent::Created(created) => UserEvent::Created(UserCreated {
+            occurred_at: at(minutes),
+            ..created
+        }),
+        UserEvent::Updated(updated) => UserEvent::Updated(UserUpdated {
+            occurred_at: at(minutes),
+            ..updated
+        }),
+        UserEvent::Deleted(deleted) => UserEvent::Deleted(UserDeleted {
+            occurred_at: at(minutes),
+            ..deleted
+        }),
+        UserEvent::Restored(restored) => UserEvent::Restored(UserRestored {
+            occurred_at: at(minutes),
+            ..restored
+        }),
+    }
+}
+
+fn audit(action: AuditAction, before: Option<&User>, after: Option<&User>) -> AuditEntry {
+    AuditEntry::record(&AuditContext::default(), action, before, after)
+}
+
+async fn create<R: UserRepositoryPort>(
+    users: &R,
+    user_name: &str,
+    user_email: &str,
+    minutes: i64,
+) -> (User, UserEvent) {
+    let user = User::new(name(user_name), email(user_email));
+    let event = stamped(UserEvent::created(&user), minutes);
+    users.save_audited(&user, &audit(AuditAction::Create, None, Some(&user)), &event).await.unwrap();
+    (user, event)
+}
+
+fn events(messages: &[OutboxMessage]) -> Vec<UserEvent> {
+    messages.iter().map(|message| message.event.clone()).collect()
+}
+
+pub async fn audited_writes_enqueue_events_in_order<R: UserRepositoryPort, O: OutboxRepositoryPort>(
+    users: R,
+    outbox: O,
+) {
+    let lease = Duration::minutes(1);
+    let (alice, alice_created) = create(&users, "Alice Outbox", "alice.outbox@example.com", 0).await;
+
+    let mut renamed = alice.clone();
+    renamed.update(Some(name("Alice Renamed")), None).unwrap();
+    let alice_updated = stamped(UserEvent::updated(&alice, &renamed), 1);
+    let update = audit(AuditAction::Update, Some(&alice), Some(&renamed));
+    users.update_audited(&renamed, &update, &alice_updated).await.unwrap();
+
+    let (_, bob_created) = create(&users, "Bob Outbox", "bob.outbox@example.com", 2).await;
+
+    let alice_deleted = stamped(UserEvent::deleted(&renamed), 3);
+    let delete = audit(AuditAction::Delete, Some(&renamed), None);
+    users.delete_audited(renamed.id(), None, &delete, &alice_deleted).await.unwrap();
+
+    let mut back = users.find_by_id_including_deleted(renamed.id()).await.unwrap().unwrap();
+    back.restore(at(4));
+    let alice_restored = stamped(UserEvent::restored(&back), 4);
+    let restore = audit(AuditAction::Restore, None, Some(&renamed));
+    users.restore_audited(renamed.id(), at(4), &restore, &alice_restored).await.unwrap();
+    // The event carries the user as stored
+    assert_eq!(users.find_by_id(renamed.id()).await.unwrap().as_ref(), Some(alice_restored.user()));
+
+    // Only the oldest undelivered event of each user is claimable
+    let first = outbox.claim(at(10), lease, 10).await.unwrap();
+    assert_eq!(events(&first), vec![alice_created, bob_created.clone()]);
+    assert!(first.iter().all(|message| message.attempts == 1));
+    assert!(first[0].seq < first[1].seq);
+    assert_eq!(outbox.claim(at(10), lease, 10).await.unwrap(), vec![]);
+
+    outbox.mark_sent(first[0].seq, at(10)).await.unwrap();
+    outbox.mark_sent(first[1].seq, at(10)).await.unwrap();
+    let second = outbox.claim(at(10), lease, 10).await.unwrap();
+    assert_eq!(events(&second), vec![alice_updated.clone()]);
+    if let UserEvent::Updated(updated) = &second[0].event {
+        assert_eq!(updated.changes[0].field, "name");
+    }
+
+    outbox.mark_sent(second[0].seq, at(10)).await.unwrap();
+    let third = outbox.claim(at(10), lease, 10).await.unwrap();
+    assert_eq!(events(&third), vec![alice_deleted]);
+    outbox.mark_sent(third[0].seq, at(10)).await.unwrap();
+
+    let fourth = outbox.claim(at(10), lease, 10).await.unwrap();
+    assert_eq!(events(&fourth), vec![alice_restored]);
+    outbox.mark_sent(fourth[0].seq, at(10)).await.unwrap();
+
+    assert_eq!(outbox.claim(at(100), lease, 10).await.unwrap(), vec![]);
+}
+
+pub async fn failed_writes_enqueue_nothing<R: UserRepositoryPort, O: OutboxRepositoryPort>(users: R, outbox: O) {
+    let (user, created) = create(&users, "Only User", "only.outbox@example.com", 0).await;
+
+    let twin = User::new(name("Twin User"), email("only.outbox@example.com"));
+    let create = audit(AuditAction::Create, None, Some(&twin));
+    let result = users.save_audited(&twin, &create, &UserEvent::created(&twin)).await;
+    assert!(matches!(result, Err(UserError::EmailAlreadyExists)));
+
+    // Stale version: the entity was never updated in memory
+    let mut stale = user.clone();
+    stale.update(Some(name("First Rename")), None).unwrap();
+    let renamed = stamped(UserEvent::updated(&user, &stale), 1);
+    let first = audit(AuditAction::Update, Some(&user), Some(&stale));
+    users.update_audited(&stale, &first, &renamed).await.unwrap();
+    let mut lost = user.clone();
+    lost.update(Some(name("Lost Rename")), None).unwrap();
+    let update = audit(AuditAction::Update, Some(&user), Some(&lost));
+    let result = users.update_audited(&lost, &update, &UserEvent::updated(&user, &lost)).await;
+    assert!(matches!(result, Err(UserError::Conflict(_))));
+
+    let delete = audit(AuditAction::Delete, Some(&user), None);
+    let result = users.delete_audited(user.id(), Some(user.version()), &delete, &UserEvent::deleted(&user)).await;
+    assert!(matches!(result, Err(UserError::Conflict(_))));
+
+    // Not deleted, so there is nothing to restore
+    let restore = audit(AuditAction::Restore, None, Some(&user));
+    let result = users.restore_audited(user.id(), at(1), &restore, &UserEvent::restored(&user)).await;
+    assert!(matches!(result, Err(UserError::NotFound)));
+
+    // One event per user at a time, in order
+    for expected in [created, renamed] {
+        let claimed = outbox.claim(at(10), Duration::minutes(1), 10).await.unwrap();
+        assert_eq!(events(&claimed), vec![expected]);
+        outbox.mark_sent(claimed[0].seq, at(10)).await.unwrap();
+    }
+    assert_eq!(outbox.claim(at(100), Duration::minutes(1), 10).await.unwrap(), vec![]);
+}
+
+pub async fn leases_expire_and_failures_back_off<R: UserRepositoryPort, O: OutboxRepositoryPort>(
+    users: R,
+    outbox: O,
+) {
+    let lease = Duration::minutes(1);
+    let (alice, alice_created) = create(&users, "Alice Retry", "alice.retry@example.com", 0).await;
+    let mut renamed = alice.clone();
+    renamed.update(Some(name("Alice Retried")), None).unwrap();
+    let update = audit(AuditAction::Update, Some(&alice), Some(&renamed));
+    users.update_audited(&renamed, &update, &stamped(UserEvent::updated(&alice, &renamed), 1)).await.unwrap();
+
+    // Never marked: delivered again once the lease runs out
+    let claimed = outbox.claim(at(10), lease, 10).await.unwrap();
+    assert_eq!(events(&claimed), vec![alice_created.clone()]);
+    assert_eq!(outbox.claim(at(10) + lease - Duration::seconds(1), lease, 10).await.unwrap(), vec![]);
+    let reclaimed = outbox.claim(at(10) + lease, lease, 10).await.unwrap();
+    assert_eq!(events(&reclaimed), vec![alice_created.clone()]);
+    assert_eq!(reclaimed[0].seq, claimed[0].seq);
+    assert_eq!(reclaimed[0].attempts, 2);
+
+    // A failed head holds back the later events of its user, not those of others
+    outbox.mark_failed(claimed[0].seq, "sink unavailable", at(30)).await.unwrap();
+    let (_, bob_created) = create(&users, "Bob Retry", "bob.retry@example.com", 2).await;
+    let others = outbox.claim(at(20), lease, 10).await.unwrap();
+    assert_eq!(events(&others), vec![bob_created]);
+    outbox.mark_sent(others[0].seq, at(20)).await.unwrap();
+
+    let retried = outbox.claim(at(30), lease, 10).await.unwrap();
+    assert_eq!(events(&retried), vec![alice_created]);
+    assert_eq!(retried[0].attempts, 3);
+    outbox.mark_sent(retried[0].seq, at(30)).await.unwrap();
+
+    // Marking a delivered message again changes nothing
+    outbox.mark_failed(retried[0].seq, "late failure", at(30)).await.unwrap();
+    let next = outbox.claim(at(30), lease, 10).await.unwrap();
+    assert_eq!(next.len(), 1);
+    assert!(matches!(next[0].event, UserEvent::Updated(_)));
+}
+
+pub async fn claims_are_limited_and_disjoint<R: UserRepositoryPort, O: OutboxRepositoryPort>(users: R, outbox: O) {
+    let lease = Duration::minutes(1);
+    for i in 0..4 {
+        create(&users, &format!("Claimed User {}", i), &format!("claimed{}@example.com", i), i).await;
+    }
+
+    let first = outbox.claim(at(10), lease, 1).await.unwrap();
+    assert_eq!(first.len(), 1);
+
+    // Concurrent relays never claim the same message
+    let (left, right) = tokio::join!(outbox.claim(at(10), lease, 10), outbox.claim(at(10), lease, 10));
+    let (left, right) = (left.unwrap(), right.unwrap());
+    let seqs: HashSet<i64> = left.iter().chain(&right).chain(&first).map(|message| message.seq).collect();
+    assert_eq!(left.len() + right.len(), 3);
+    assert_eq!(seqs.len(), 4);
+}
+
+pub async fn purge_removes_only_delivered_messages<R: UserRepositoryPort, O: OutboxRepositoryPort>(
+    users: R,
+    outbox: O,
+) {
+    let lease = Duration::minutes(1);
+    create(&users, "Sent User", "sent@example.com", 0).await;
+    let (_, pending) = create(&users, "Pending User", "pending@example.com", 1).await;
+
+    let claimed = outbox.claim(at(10), lease, 1).await.unwrap();
+    outbox.mark_sent(claimed[0].seq, at(10)).await.unwrap();
+
+    assert_eq!(outbox.purge_sent(at(10)).await.unwrap(), 0);
+    assert_eq!(outbox.purge_sent(at(11)).await.unwrap(), 1);
+    assert_eq!(outbox.purge_sent(at(11)).await.unwrap(), 0);
+    assert_eq!(events(&outbox.claim(at(10), lease, 10).await.unwrap()), vec![pending]);
+}
+
+pub async fn readers_follow_every_message_by_seq<R: UserRepositoryPort, O: OutboxRepositoryPort>(
+    users: R,
+    outbox: O,
+) {
+    assert_eq!(outbox.last_seq().await.unwrap(), 0);
+    assert!(outbox.find_after(0, 10).await.unwrap().is_empty());
+    let (_, first) = create(&users, "Read User", "read@example.com", 0).await;
+    let (_, second) = create(&users, "Other Read User", "other.read@example.com", 1).await;
+    let (_, third) = create(&users, "Third Read User", "third.read@example.com", 2).await;
+
+    // Delivered or claimed messages are still read, and reading claims nothing
+    let claimed = outbox.claim(at(10), Duration::minutes(1), 1).await.unwrap();
+    outbox.mark_sent(claimed[0].seq, at(10)).await.unwrap();
+    let all = outbox.find_after(0, 10).await.unwrap();
+    assert_eq!(events(&all), vec![first, second.clone(), third.clone()]);
+    assert!(all.windows(2).all(|pair| pair[0].seq < pair[1].seq));
+    assert_eq!(outbox.last_seq().await.unwrap(), all[2].seq);
+
+    assert_eq!(events(&outbox.find_after(all[0].seq, 1).await.unwrap()), vec![second]);
+    assert_eq!(events(&outbox.find_after(all[1].seq, 10).await.unwrap()), vec![third]);
+    assert!(outbox.find_after(all[2].seq, 10).await.unwrap().is_empty());
+    assert_eq!(outbox.claim(at(10), Duration::minutes(1), 10).await.unwrap().len(), 2);
+}
+
+/// Expand the outbox checks into `#[tokio::test]`s for one adapter
+#[macro_export]
+macro_rules! outbox_repository_conformance {
+    ($adapter:ident, $factory:expr) => {
+        mod $adapter {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $crate::outbox_repository_conformance!(@tests $factory;
+                audited_writes_enqueue_events_in_order,
+                failed_writes_enqueue_nothing,
+                leases_expire_and_failures_back_off,
+                claims_are_limited_and_disjoint,
+                purge_removes_only_delivered_messages,
+                readers_follow_every_message_by_seq,
+            );
+        }
+    };
+    (@tests $factory:expr; $($check:ident),+ $(,)?) => {
+        $(
+            #[tokio::test]
+            async fn $check() {
+                if let Some((users, outbox)) = $factory.await {
+                    $crate::conformance::outbox::$check(users, outbox).await;
+                }
+            }
+        )+
+    };
+}
@@ -0,0 +1,142 @@
+//! Conformance checks for `PasswordResetRepositoryPort` implementations.
+//!
+//! Factories yield the token repository together with the user repository it
+//! belongs to, since tokens require a stored user:
+//!
+//! ```ignore
+//! password_reset_repository_conformance!(in_memory, async {
+//!     let users = InMemoryUserRepository::new();
+//!     Some((users.clone(), InMemoryPasswordResetRepository::new(users)))
+//! });
+//! ```
+
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use rust_nexus::domain::{
+    PasswordResetRepositoryPort, PasswordResetToken, User, UserError, UserRepositoryPort, current_timestamp,
+};
+
+use super::{delete_user, email, name, save_user};
+
+fn token(user: &User, secret: &str, expires_at: DateTime<Utc>) -> PasswordResetToken {
+    PasswordResetToken {
+        id: Uuid::new_v4(),
+        user_id: user.id().clone(),
+        token_hash: format!("{:0>64}", secret),
+        created_at: current_timestamp(),
+        expires_at,
+        used_at: None,
+    }
+}
+
+async fn user<R: UserRepositoryPort>(users: &R, user_email: &str) -> User {
+    let user = User::new(name("Forgetful User"), email(user_email));
+    save_user(users, &user).await.unwrap();
+    user
+}
+
+fn in_an_hour() -> DateTime<Utc> {
+    current_timestamp() + Duration::hours(1)
+}
+
+pub async fn reset_token_round_trip<R: UserRepositoryPort, T: PasswordResetRepositoryPort>(users: R, tokens: T) {
+    let user = user(&users, "reset@example.com").await;
+    let stored = token(&user, "b1", in_an_hour());
+    tokens.save(&stored).await.unwrap();
+
+    assert_eq!(tokens.find_by_token_hash(&stored.token_hash).await.unwrap(), Some(stored));
+    assert_eq!(tokens.find_by_token_hash(&format!("{:0>64}", "ff")).await.unwrap(), None);
+
+    let ghost = User::new(name("Ghost"), email("ghost@example.com"));
+    assert!(matches!(tokens.save(&token(&ghost, "b2", in_an_hour())).await, Err(UserError::NotFound)));
+}
+
+pub async fn mark_used_succeeds_once<R: UserRepositoryPort, T: PasswordResetRepositoryPort>(users: R, tokens: T) {
+    let user = user(&users, "reset@example.com").await;
+    let stored = token(&user, "b1", in_an_hour());
+    tokens.save(&stored).await.unwrap();
+
+    let used_at = current_timestamp();
+    assert!(tokens.mark_used(stored.id, used_at).await.unwrap());
+    assert!(!tokens.mark_used(stored.id, used_at).await.unwrap());
+    assert!(!tokens.mark_used(Uuid::new_v4(), used_at).await.unwrap());
+
+    let found = tokens.find_by_token_hash(&stored.token_hash).await.unwrap().unwrap();
+    assert_eq!(found.used_at, Some(used_at));
+    assert!(!found.is_usable(used_at));
+}
+
+pub async fn mark_all_used_for_user_spares_others<R: UserRepositoryPort, T: PasswordResetRepositoryPort>(
+    users: R,
+    tokens: T,
+) {
+    let user_a = user(&users, "a@example.com").await;
+    let user_b = user(&users, "b@example.com").await;
+    let used = token(&user_a, "b1", in_an_hour());
+    let first = token(&user_a, "b2", in_an_hour());
+    let second = token(&user_a, "b3", in_an_hour());
+    let other = token(&user_b, "b4", in_an_hour());
+    for t in [&used, &first, &second, &other] {
+        tokens.save(t).await.unwrap();
+    }
+    let earlier = current_timestamp() - Duration::minutes(5);
+    tokens.mark_used(used.id, earlier).await.unwrap();
+
+    let now = current_timestamp();
+    assert_eq!(tokens.mark_all_used_for_user(user_a.id(), now).await.unwrap(), 2);
+    for t in [&first, &second] {
+        assert_eq!(tokens.find_by_token_hash(&t.token_hash).await.unwrap().unwrap().used_at, Some(now));
+    }
+    // Already used tokens keep their original timestamp
+    assert_eq!(tokens.find_by_token_hash(&used.token_hash).await.unwrap().unwrap().used_at, Some(earlier));
+    assert!(tokens.find_by_token_hash(&other.token_hash).await.unwrap().unwrap().is_usable(now));
+}
+
+pub async fn purge_expired_and_deleted<R: UserRepositoryPort, T: PasswordResetRepositoryPort>(users: R, tokens: T) {
+    let kept_user = user(&users, "kept@example.com").await;
+    let gone_user = user(&users, "gone@example.com").await;
+    let now = current_timestamp();
+    let expired = token(&kept_user, "b1", now - Duration::minutes(1));
+    let live = token(&kept_user, "b2", in_an_hour());
+    let orphaned = token(&gone_user, "b3", in_an_hour());
+    for t in [&expired, &live, &orphaned] {
+        tokens.save(t).await.unwrap();
+    }
+
+    assert_eq!(tokens.purge_expired(now).await.unwrap(), 1);
+    assert_eq!(tokens.find_by_token_hash(&expired.token_hash).await.unwrap(), None);
+    assert!(tokens.find_by_token_hash(&live.token_hash).await.unwrap().is_some());
+
+    delete_user(&users, &gone_user, None).await.unwrap();
+    users.purge_deleted(Utc::now() + Duration::seconds(1)).await.unwrap();
+    assert_eq!(tokens.find_by_token_hash(&orphaned.token_hash).await.unwrap(), None);
+}
+
+/// Expand the password reset checks into `#[tokio::test]`s for one adapter
+#[macro_export]
+macro_rules! password_reset_repository_conformance {
+    ($adapter:ident, $factory:expr) => {
+        mod $adapter {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $crate::password_reset_repository_conformance!(@tests $factory;
+                reset_token_round_trip,
+                mark_used_succeeds_once,
+                mark_all_used_for_user_spares_others,
+                purge_expired_and_deleted,
+            );
+        }
+    };
+    (@tests $factory:expr; $($check:ident),+ $(,)?) => {
+        $(
+            #[tokio::test]
+            async fn $check() {
+                if let Some((users, tokens)) = $factory.await {
+                    $crate::conformance::password_resets::$check(users, tokens).await;
+                }
+            }
+        )+
+    };
+}
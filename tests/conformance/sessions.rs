@@ -0,0 +1,174 @@
+//! Conformance checks for `SessionRepositoryPort` implementations.
+//!
+//! Factories yield the session repository together with the user repository
+//! it belongs to, since sessions require a stored user:
+//!
+//! ```ignore
+//! session_repository_conformance!(in_memory, async {
+//!     let users = InMemoryUserRepository::new();
+//!     Some((users.clone(), InMemorySessionRepository::new(users)))
+//! });
+//! ```
+
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use rust_nexus::domain::{Session, SessionRepositoryPort, User, UserError, UserRepositoryPort, current_timestamp};
+
+use super::{delete_user, email, name, save_user};
+
+fn session(user: &User, family_id: Uuid, secret: &str, expires_at: DateTime<Utc>) -> Session {
+    Session {
+        id: Uuid::new_v4(),
+        family_id,
+        user_id: user.id().clone(),
+        token_hash: format!("{:0>64}", secret),
+        created_at: current_timestamp(),
+        expires_at,
+        rotated_at: None,
+        revoked_at: None,
+    }
+}
+
+async fn user<R: UserRepositoryPort>(users: &R, user_email: &str) -> User {
+    let user = User::new(name("Session User"), email(user_email));
+    save_user(users, &user).await.unwrap();
+    user
+}
+
+fn tomorrow() -> DateTime<Utc> {
+    current_timestamp() + Duration::days(1)
+}
+
+pub async fn session_round_trip<R: UserRepositoryPort, S: SessionRepositoryPort>(users: R, sessions: S) {
+    let user = user(&users, "session@example.com").await;
+    let stored = session(&user, Uuid::new_v4(), "a1", tomorrow());
+    sessions.save(&stored).await.unwrap();
+
+    assert_eq!(sessions.find_by_token_hash(&stored.token_hash).await.unwrap(), Some(stored));
+    assert_eq!(sessions.find_by_token_hash(&format!("{:0>64}", "ff")).await.unwrap(), None);
+
+    let ghost = User::new(name("Ghost"), email("ghost@example.com"));
+    assert!(matches!(
+        sessions.save(&session(&ghost, Uuid::new_v4(), "a2", tomorrow())).await,
+        Err(UserError::NotFound)
+    ));
+}
+
+pub async fn mark_rotated_succeeds_once<R: UserRepositoryPort, S: SessionRepositoryPort>(users: R, sessions: S) {
+    let user = user(&users, "session@example.com").await;
+    let stored = session(&user, Uuid::new_v4(), "a1", tomorrow());
+    sessions.save(&stored).await.unwrap();
+
+    let rotated_at = current_timestamp();
+    assert!(sessions.mark_rotated(stored.id, rotated_at).await.unwrap());
+    assert!(!sessions.mark_rotated(stored.id, rotated_at).await.unwrap());
+    assert!(!sessions.mark_rotated(Uuid::new_v4(), rotated_at).await.unwrap());
+
+    let found = sessions.find_by_token_hash(&stored.token_hash).await.unwrap().unwrap();
+    assert_eq!(found.rotated_at, Some(rotated_at));
+    assert!(!found.is_active(rotated_at));
+
+    // A revoked session cannot be rotated either
+    let revoked = session(&user, Uuid::new_v4(), "a2", tomorrow());
+    sessions.save(&revoked).await.unwrap();
+    sessions.revoke_family(revoked.family_id, rotated_at).await.unwrap();
+    assert!(!sessions.mark_rotated(revoked.id, rotated_at).await.unwrap());
+}
+
+pub async fn revoke_family_and_user<R: UserRepositoryPort, S: SessionRepositoryPort>(users: R, sessions: S) {
+    let first = user(&users, "first@example.com").await;
+    let second = user(&users, "second@example.com").await;
+    let family = Uuid::new_v4();
+    let older = session(&first, family, "a1", tomorrow());
+    let newer = session(&first, family, "a2", tomorrow());
+    let other_device = session(&first, Uuid::new_v4(), "a3", tomorrow());
+    let other_user = session(&second, Uuid::new_v4(), "a4", tomorrow());
+    for s in [&older, &newer, &other_device, &other_user] {
+        sessions.save(s).await.unwrap();
+    }
+
+    let now = current_timestamp();
+    assert_eq!(sessions.revoke_family(family, now).await.unwrap(), 2);
+    assert_eq!(sessions.revoke_family(family, now).await.unwrap(), 0);
+    let found = sessions.find_by_token_hash(&newer.token_hash).await.unwrap().unwrap();
+    assert_eq!(found.revoked_at, Some(now));
+    assert!(sessions.find_by_token_hash(&other_device.token_hash).await.unwrap().unwrap().revoked_at.is_none());
+
+    assert_eq!(sessions.revoke_all_for_user(first.id(), now).await.unwrap(), 1);
+    assert!(sessions.find_by_token_hash(&other_device.token_hash).await.unwrap().unwrap().revoked_at.is_some());
+    assert!(sessions.find_by_token_hash(&other_user.token_hash).await.unwrap().unwrap().revoked_at.is_none());
+}
+
+pub async fn has_active_ignores_ended_sessions<R: UserRepositoryPort, S: SessionRepositoryPort>(
+    users: R,
+    sessions: S,
+) {
+    let owner = user(&users, "active@example.com").await;
+    let other = user(&users, "other@example.com").await;
+    let now = current_timestamp();
+    assert!(!sessions.has_active(owner.id(), now).await.unwrap());
+
+    let expired = session(&owner, Uuid::new_v4(), "a1", now - Duration::minutes(1));
+    let rotated = session(&owner, Uuid::new_v4(), "a2", tomorrow());
+    let live = session(&owner, Uuid::new_v4(), "a3", tomorrow());
+    for s in [&expired, &rotated, &live] {
+        sessions.save(s).await.unwrap();
+    }
+    sessions.mark_rotated(rotated.id, now).await.unwrap();
+    assert!(sessions.has_active(owner.id(), now).await.unwrap());
+    assert!(!sessions.has_active(other.id(), now).await.unwrap());
+    assert!(!sessions.has_active(owner.id(), live.expires_at).await.unwrap());
+
+    sessions.revoke_family(live.family_id, now).await.unwrap();
+    assert!(!sessions.has_active(owner.id(), now).await.unwrap());
+}
+
+pub async fn purge_expired_and_deleted<R: UserRepositoryPort, S: SessionRepositoryPort>(users: R, sessions: S) {
+    let kept_user = user(&users, "kept@example.com").await;
+    let gone_user = user(&users, "gone@example.com").await;
+    let now = current_timestamp();
+    let expired = session(&kept_user, Uuid::new_v4(), "a1", now - Duration::minutes(1));
+    let live = session(&kept_user, Uuid::new_v4(), "a2", tomorrow());
+    let orphaned = session(&gone_user, Uuid::new_v4(), "a3", tomorrow());
+    for s in [&expired, &live, &orphaned] {
+        sessions.save(s).await.unwrap();
+    }
+
+    assert_eq!(sessions.purge_expired(now).await.unwrap(), 1);
+    assert_eq!(sessions.find_by_token_hash(&expired.token_hash).await.unwrap(), None);
+    assert!(sessions.find_by_token_hash(&live.token_hash).await.unwrap().is_some());
+
+    delete_user(&users, &gone_user, None).await.unwrap();
+    users.purge_deleted(Utc::now() + Duration::seconds(1)).await.unwrap();
+    assert_eq!(sessions.find_by_token_hash(&orphaned.token_hash).await.unwrap(), None);
+}
+
+/// Expand the session checks into `#[tokio::test]`s for one adapter
+#[macro_export]
+macro_rules! session_repository_conformance {
+    ($adapter:ident, $factory:expr) => {
+        mod $adapter {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $crate::session_repository_conformance!(@tests $factory;
+                session_round_trip,
+                mark_rotated_succeeds_once,
+                revoke_family_and_user,
+                has_active_ignores_ended_sessions,
+                purge_expired_and_deleted,
+            );
+        }
+    };
+    (@tests $factory:expr; $($check:ident),+ $(,)?) => {
+        $(
+            #[tokio::test]
+            async fn $check() {
+                if let Some((users, sessions)) = $factory.await {
+                    $crate::conformance::sessions::$check(users, sessions).await;
+                }
+            }
+        )+
+    };
+}
@@ -0,0 +1,296 @@
+//! Conformance checks for `WebhookRepositoryPort` implementations.
+//!
+//! Subscriptions stand alone, so factories yield just the repository:
+//!
+//! ```ignore
+//! webhook_repository_conformance!(in_memory, async { Some(InMemoryWebhookRepository::new()) });
+//! ```
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use uuid::Uuid;
+
+use rust_nexus::domain::{
+    DeliveryStatus, UserError, WebhookAttempt, WebhookDelivery, WebhookRepositoryPort, WebhookSubscription,
+};
+
+/// Rows get fixed instants, so comparisons do not depend on the clock or on
+/// timestamp precision
+fn at(minutes: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap() + Duration::minutes(minutes)
+}
+
+fn subscription(url: &str, minutes: i64) -> WebhookSubscription {
+    let event_types = ["user.created".to_string(), "user.deleted".to_string()];
+    WebhookSubscription {
+        created_at: at(minutes),
+        updated_at: at(minutes),
+        ..WebhookSubscription::new(url, &event_types, "whsec_conformance_secret".to_string()).unwrap()
+    }
+}
+
+fn delivery(subscription: &WebhookSubscription, event_id: Uuid, minutes: i64) -> WebhookDelivery {
+    WebhookDelivery {
+        id: Uuid::new_v4(),
+        subscription_id: subscription.id,
+        event_id,
+        event_type: "user.created".to_string(),
+        payload: format!(r#"{{"id":"{}"}}"#, event_id),
+        status: DeliveryStatus::Pending,
+        attempts: 0,
+        next_attempt_at: at(minutes),
+        last_response_status: None,
+        last_error: None,
+        replay_of: None,
+        created_at: at(minutes),
+        updated_at: at(minutes),
+    }
+}
+
+async fn stored<T: WebhookRepositoryPort>(webhooks: &T, url: &str, minutes: i64) -> WebhookSubscription {
+    let subscription = subscription(url, minutes);
+    webhooks.save_subscription(&subscription).await.unwrap();
+    subscription
+}
+
+async fn enqueued<T: WebhookRepositoryPort>(
+    webhooks: &T,
+    subscription: &WebhookSubscription,
+    minutes: i64,
+) -> WebhookDelivery {
+    let delivery = delivery(subscription, Uuid::new_v4(), minutes);
+    assert!(webhooks.enqueue_delivery(&delivery).await.unwrap());
+    delivery
+}
+
+fn ids(deliveries: &[WebhookDelivery]) -> Vec<Uuid> {
+    deliveries.iter().map(|delivery| delivery.id).collect()
+}
+
+/// The stored state of `delivery` after the outcome of attempt `attempt`
+fn attempted(
+    delivery: &WebhookDelivery,
+    attempt: i32,
+    status: DeliveryStatus,
+    response_status: Option<i32>,
+    minutes: i64,
+) -> (WebhookDelivery, WebhookAttempt) {
+    let error = (status != DeliveryStatus::Succeeded).then(|| "receiver answered with status 500".to_string());
+    let delivery = WebhookDelivery {
+        status,
+        attempts: attempt,
+        next_attempt_at: at(minutes + 10),
+        last_response_status: response_status,
+        last_error: error.clone(),
+        updated_at: at(minutes),
+        ..delivery.clone()
+    };
+    let attempt = WebhookAttempt {
+        delivery_id: delivery.id,
+        attempt,
+        response_status,
+        error,
+        attempted_at: at(minutes),
+    };
+    (delivery, attempt)
+}
+
+pub async fn subscriptions_round_trip<T: WebhookRepositoryPort>(webhooks: T) {
+    let first = stored(&webhooks, "https://partner.example.com/hooks", 0).await;
+    let second = stored(&webhooks, "http://localhost:9000/events", 1).await;
+
+    assert_eq!(webhooks.find_subscription(first.id).await.unwrap(), Some(first.clone()));
+    assert_eq!(webhooks.find_subscription(Uuid::new_v4()).await.unwrap(), None);
+    assert_eq!(webhooks.find_subscriptions().await.unwrap(), vec![first.clone(), second.clone()]);
+
+    let mut changed = first.clone();
+    changed.url = "https://partner.example.com/v2/hooks".to_string();
+    changed.event_types = vec!["user.updated".to_string()];
+    changed.secret = "whsec_rotated_secret_value".to_string();
+    changed.active = false;
+    changed.updated_at = at(5);
+    webhooks.update_subscription(&changed).await.unwrap();
+    assert_eq!(webhooks.find_subscription(first.id).await.unwrap(), Some(changed));
+
+    let missing = subscription("https://missing.example.com/", 2);
+    assert!(matches!(webhooks.update_subscription(&missing).await, Err(UserError::WebhookNotFound)));
+
+    webhooks.delete_subscription(second.id).await.unwrap();
+    assert!(matches!(webhooks.delete_subscription(second.id).await, Err(UserError::WebhookNotFound)));
+    assert_eq!(webhooks.find_subscriptions().await.unwrap().len(), 1);
+}
+
+pub async fn events_are_enqueued_once_per_subscription<T: WebhookRepositoryPort>(webhooks: T) {
+    let partner = stored(&webhooks, "https://partner.example.com/hooks", 0).await;
+    let other = stored(&webhooks, "https://other.example.com/hooks", 1).await;
+    let event_id = Uuid::new_v4();
+
+    let original = delivery(&partner, event_id, 2);
+    assert!(webhooks.enqueue_delivery(&original).await.unwrap());
+    assert_eq!(webhooks.find_delivery(original.id).await.unwrap(), Some(original.clone()));
+
+    // The relay may hand over the same event again
+    assert!(!webhooks.enqueue_delivery(&delivery(&partner, event_id, 3)).await.unwrap());
+    assert!(webhooks.enqueue_delivery(&delivery(&other, event_id, 3)).await.unwrap());
+
+    // Replays re-send the event as often as asked
+    let replay = WebhookDelivery {
+        id: Uuid::new_v4(),
+        replay_of: Some(original.id),
+        ..original.clone()
+    };
+    assert!(webhooks.enqueue_delivery(&replay).await.unwrap());
+    assert!(webhooks.enqueue_delivery(&WebhookDelivery { id: Uuid::new_v4(), ..replay.clone() }).await.unwrap());
+    assert_eq!(webhooks.find_delivery(replay.id).await.unwrap(), Some(replay));
+    assert_eq!(webhooks.count_deliveries(partner.id, None).await.unwrap(), 3);
+
+    let orphan = delivery(&subscription("https://gone.example.com/", 4), Uuid::new_v4(), 4);
+    assert!(matches!(webhooks.enqueue_delivery(&orphan).await, Err(UserError::WebhookNotFound)));
+}
+
+pub async fn claims_lease_due_deliveries<T: WebhookRepositoryPort>(webhooks: T) {
+    let lease = Duration::minutes(1);
+    let partner = stored(&webhooks, "https://partner.example.com/hooks", 0).await;
+    let first = enqueued(&webhooks, &partner, 1).await;
+    let second = enqueued(&webhooks, &partner, 2).await;
+    let later = enqueued(&webhooks, &partner, 30).await;
+
+    let claimed = webhooks.claim_deliveries(at(10), lease, 10).await.unwrap();
+    assert_eq!(ids(&claimed), vec![first.id, second.id]);
+    assert!(claimed.iter().all(|delivery| delivery.attempts == 1));
+    assert_eq!(webhooks.claim_deliveries(at(10), lease, 10).await.unwrap(), vec![]);
+
+    // Never recorded: attempted again once the lease runs out
+    let expiry = at(10) + lease;
+    assert_eq!(webhooks.claim_deliveries(expiry - Duration::seconds(1), lease, 10).await.unwrap(), vec![]);
+    let reclaimed = webhooks.claim_deliveries(expiry, lease, 1).await.unwrap();
+    assert_eq!(ids(&reclaimed), vec![first.id]);
+    assert_eq!(reclaimed[0].attempts, 2);
+
+    // Oldest first, whenever each became due
+    let due = webhooks.claim_deliveries(at(30), lease, 10).await.unwrap();
+    assert_eq!(ids(&due), vec![first.id, second.id, later.id]);
+}
+
+pub async fn concurrent_claims_are_disjoint<T: WebhookRepositoryPort>(webhooks: T) {
+    let lease = Duration::minutes(1);
+    let partner = stored(&webhooks, "https://partner.example.com/hooks", 0).await;
+    for minutes in 0..6 {
+        enqueued(&webhooks, &partner, minutes).await;
+    }
+
+    let (left, right) = tokio::join!(
+        webhooks.claim_deliveries(at(10), lease, 4),
+        webhooks.claim_deliveries(at(10), lease, 4)
+    );
+    let (left, right) = (left.unwrap(), right.unwrap());
+    let claimed: HashSet<Uuid> = left.iter().chain(&right).map(|delivery| delivery.id).collect();
+    assert_eq!(left.len() + right.len(), 6);
+    assert_eq!(claimed.len(), 6);
+}
+
+pub async fn attempts_are_logged_with_their_outcome<T: WebhookRepositoryPort>(webhooks: T) {
+    let lease = Duration::minutes(1);
+    let partner = stored(&webhooks, "https://partner.example.com/hooks", 0).await;
+    let flaky = enqueued(&webhooks, &partner, 1).await;
+    let dead = enqueued(&webhooks, &partner, 2).await;
+
+    // A failed attempt schedules a retry
+    let claimed = webhooks.claim_deliveries(at(10), lease, 10).await.unwrap();
+    let (retry, failure) = attempted(&claimed[0], 1, DeliveryStatus::Pending, Some(500), 10);
+    webhooks.record_attempt(&retry, &failure).await.unwrap();
+    let (dead_letter, last_failure) = attempted(&claimed[1], 1, DeliveryStatus::DeadLettered, None, 10);
+    webhooks.record_attempt(&dead_letter, &last_failure).await.unwrap();
+    assert_eq!(webhooks.find_delivery(flaky.id).await.unwrap(), Some(retry.clone()));
+    assert_eq!(webhooks.find_delivery(dead.id).await.unwrap(), Some(dead_letter.clone()));
+    assert_eq!(webhooks.claim_deliveries(at(19), lease, 10).await.unwrap(), vec![]);
+
+    let retried = webhooks.claim_deliveries(at(20), lease, 10).await.unwrap();
+    assert_eq!(ids(&retried), vec![flaky.id]);
+    assert_eq!(retried[0].attempts, 2);
+    let (succeeded, success) = attempted(&retried[0], 2, DeliveryStatus::Succeeded, Some(204), 20);
+    webhooks.record_attempt(&succeeded, &success).await.unwrap();
+    assert_eq!(webhooks.find_attempts(flaky.id).await.unwrap(), vec![failure, success]);
+    assert_eq!(webhooks.find_attempts(dead.id).await.unwrap(), vec![last_failure]);
+    assert_eq!(webhooks.find_attempts(Uuid::new_v4()).await.unwrap(), vec![]);
+
+    // A late outcome for a finished delivery changes nothing
+    let (late, late_attempt) = attempted(&retried[0], 2, DeliveryStatus::Pending, Some(500), 30);
+    webhooks.record_attempt(&late, &late_attempt).await.unwrap();
+    assert_eq!(webhooks.find_delivery(flaky.id).await.unwrap(), Some(succeeded));
+    assert_eq!(webhooks.find_attempts(flaky.id).await.unwrap().len(), 2);
+    assert_eq!(webhooks.claim_deliveries(at(100), lease, 10).await.unwrap(), vec![]);
+}
+
+pub async fn deliveries_are_filtered_and_paged<T: WebhookRepositoryPort>(webhooks: T) {
+    let partner = stored(&webhooks, "https://partner.example.com/hooks", 0).await;
+    let other = stored(&webhooks, "https://other.example.com/hooks", 0).await;
+    let oldest = enqueued(&webhooks, &partner, 1).await;
+    let middle = enqueued(&webhooks, &partner, 2).await;
+    let newest = enqueued(&webhooks, &partner, 3).await;
+    enqueued(&webhooks, &other, 4).await;
+
+    let claimed = webhooks.claim_deliveries(at(10), Duration::minutes(1), 1).await.unwrap();
+    let (dead_letter, failure) = attempted(&claimed[0], 1, DeliveryStatus::DeadLettered, Some(410), 10);
+    webhooks.record_attempt(&dead_letter, &failure).await.unwrap();
+
+    let all = webhooks.find_deliveries(partner.id, None, 0, 10).await.unwrap();
+    assert_eq!(ids(&all), vec![newest.id, middle.id, oldest.id]);
+    assert_eq!(ids(&webhooks.find_deliveries(partner.id, None, 1, 1).await.unwrap()), vec![middle.id]);
+    assert_eq!(webhooks.count_deliveries(partner.id, None).await.unwrap(), 3);
+
+    let dead = Some(DeliveryStatus::DeadLettered);
+    assert_eq!(ids(&webhooks.find_deliveries(partner.id, dead, 0, 10).await.unwrap()), vec![oldest.id]);
+    assert_eq!(webhooks.count_deliveries(partner.id, dead).await.unwrap(), 1);
+    assert_eq!(webhooks.count_deliveries(partner.id, Some(DeliveryStatus::Pending)).await.unwrap(), 2);
+    assert_eq!(webhooks.count_deliveries(partner.id, Some(DeliveryStatus::Succeeded)).await.unwrap(), 0);
+    assert_eq!(webhooks.count_deliveries(Uuid::new_v4(), None).await.unwrap(), 0);
+}
+
+pub async fn deleting_a_subscription_removes_its_deliveries<T: WebhookRepositoryPort>(webhooks: T) {
+    let partner = stored(&webhooks, "https://partner.example.com/hooks", 0).await;
+    let other = stored(&webhooks, "https://other.example.com/hooks", 0).await;
+    let doomed = enqueued(&webhooks, &partner, 1).await;
+    let kept = enqueued(&webhooks, &other, 2).await;
+
+    let claimed = webhooks.claim_deliveries(at(10), Duration::minutes(1), 1).await.unwrap();
+    let (retry, failure) = attempted(&claimed[0], 1, DeliveryStatus::Pending, Some(503), 10);
+    webhooks.record_attempt(&retry, &failure).await.unwrap();
+
+    webhooks.delete_subscription(partner.id).await.unwrap();
+    assert_eq!(webhooks.find_delivery(doomed.id).await.unwrap(), None);
+    assert_eq!(webhooks.find_attempts(doomed.id).await.unwrap(), vec![]);
+    assert_eq!(webhooks.find_delivery(kept.id).await.unwrap(), Some(kept));
+}
+
+/// Expand the webhook checks into `#[tokio::test]`s for one adapter
+#[macro_export]
+macro_rules! webhook_repository_conformance {
+    ($adapter:ident, $factory:expr) => {
+        mod $adapter {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $crate::webhook_repository_conformance!(@tests $factory;
+                subscriptions_round_trip,
+                events_are_enqueued_once_per_subscription,
+                claims_lease_due_deliveries,
+                concurrent_claims_are_disjoint,
+                attempts_are_logged_with_their_outcome,
+                deliveries_are_filtered_and_paged,
+                deleting_a_subscription_removes_its_deliveries,
+            );
+        }
+    };
+    (@tests $factory:expr; $($check:ident),+ $(,)?) => {
+        $(
+            #[tokio::test]
+            async fn $check() {
+                if let Some(webhooks) = $factory.await {
+                    $crate::conformance::webhooks::$check(webhooks).await;
+                }
+            }
+        )+
+    };
+}
@@ -0,0 +1,127 @@
+//! Mail adapters against local sinks: a minimal SMTP server and a drop directory.
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpListener,
+    sync::oneshot,
+};
+
+use rust_nexus::{
+    domain::{Email, MailMessage, MailerPort, UserError},
+    infrastructure::{LogMailer, SmtpMailer, SmtpTls},
+};
+
+fn message() -> MailMessage {
+    MailMessage {
+        to: Email::new("jane.doe@example.com".to_string()).unwrap(),
+        subject: "Verify your email address".to_string(),
+        body: "Use this token:\n\nrnv_secret\n".to_string(),
+    }
+}
+
+/// What the sink received in one SMTP session
+#[derive(Debug, Default)]
+struct Received {
+    mail_from: String,
+    rcpt_to: Vec<String>,
+    data: String,
+}
+
+/// Accept one SMTP session on a free port and report what was sent
+async fn smtp_sink() -> (u16, oneshot::Receiver<Received>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (done, received) = oneshot::channel();
+
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        let mut received = Received::default();
+
+        writer.write_all(b"220 sink ESMTP\r\n").await.unwrap();
+        while let Some(line) = lines.next_line().await.unwrap() {
+            let command = line.to_ascii_uppercase();
+            let reply: &[u8] = if command.starts_with("EHLO") || command.starts_with("HELO") {
+                b"250 sink\r\n"
+            } else if command.starts_with("MAIL FROM:") {
+                received.mail_from = line[10..].trim().to_string();
+                b"250 OK\r\n"
+            } else if command.starts_with("RCPT TO:") {
+                received.rcpt_to.push(line[8..].trim().to_string());
+                b"250 OK\r\n"
+            } else if command == "DATA" {
+                writer.write_all(b"354 End data with <CR><LF>.<CR><LF>\r\n").await.unwrap();
+                while let Some(data_line) = lines.next_line().await.unwrap() {
+                    if data_line == "." {
+                        break;
+                    }
+                    received.data.push_str(&data_line);
+                    received.data.push('\n');
+                }
+                b"250 OK: queued\r\n"
+            } else if command == "QUIT" {
+                writer.write_all(b"221 Bye\r\n").await.unwrap();
+                break;
+            } else {
+                b"502 Command not implemented\r\n"
+            };
+            writer.write_all(reply).await.unwrap();
+        }
+        let _ = done.send(received);
+    });
+
+    (port, received)
+}
+
+#[tokio::test]
+async fn test_smtp_mailer_delivers_to_a_local_sink() {
+    let (port, received) = smtp_sink().await;
+    let mailer = SmtpMailer::new("127.0.0.1", port, SmtpTls::None, None, "Rust Nexus <no-reply@example.com>").unwrap();
+
+    mailer.send(&message()).await.unwrap();
+
+    let received = received.await.unwrap();
+    assert_eq!(received.mail_from, "<no-reply@example.com>");
+    assert_eq!(received.rcpt_to, vec!["<jane.doe@example.com>"]);
+    assert!(received.data.contains("Subject: Verify your email address"));
+    assert!(received.data.contains("To: jane.doe@example.com"));
+    assert!(received.data.contains("rnv_secret"));
+}
+
+#[tokio::test]
+async fn test_smtp_mailer_reports_an_unreachable_server_as_unavailable() {
+    // Bind and drop to find a port nobody listens on
+    let port = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+    let mailer = SmtpMailer::new("127.0.0.1", port, SmtpTls::None, None, "no-reply@example.com").unwrap();
+
+    let err = mailer.send(&message()).await.unwrap_err();
+    assert!(matches!(err, UserError::MailUnavailable(_)));
+    assert!(err.is_retryable());
+}
+
+#[tokio::test]
+async fn test_smtp_mailer_rejects_an_invalid_sender() {
+    let result = SmtpMailer::new("127.0.0.1", 25, SmtpTls::None, None, "not an address");
+    assert!(matches!(result, Err(UserError::Internal(_))));
+}
+
+#[tokio::test]
+async fn test_log_mailer_drops_messages_as_files() {
+    let dir = std::env::temp_dir().join(format!("rust-nexus-mail-{}", uuid::Uuid::new_v4().simple()));
+    let mailer = LogMailer::new().with_drop_dir(&dir);
+
+    mailer.send(&message()).await.unwrap();
+    mailer.send(&message()).await.unwrap();
+
+    let mut files = std::fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().path()).collect::<Vec<_>>();
+    files.sort();
+    assert_eq!(files.len(), 2);
+    assert!(files.iter().all(|path| path.extension().is_some_and(|ext| ext == "eml")));
+    let contents = std::fs::read_to_string(&files[0]).unwrap();
+    assert!(contents.contains("To: jane.doe@example.com\r\n"));
+    assert!(contents.contains("Subject: Verify your email address\r\n"));
+    assert!(contents.contains("rnv_secret"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,243 @@
+//! Runs the repository conformance suites against every adapter.
+//!
+//! Postgres checks need `TEST_DATABASE_URL`; each test gets its own schema so
+//! they can run in parallel against one database. Without the variable they
+//! pass vacuously on a developer machine, but fail in CI (when `CI` is set)
+//! so a pipeline without a database cannot report them green.
+
+mod conformance;
+
+use rust_nexus::infrastructure::{
+    InMemoryApiKeyRepository, InMemoryAuditLogRepository, InMemoryCredentialRepository,
+    InMemoryEmailVerificationRepository, InMemoryIdempotencyRepository, InMemoryOutboxRepository,
+    InMemoryPasswordResetRepository, InMemorySessionRepository, InMemoryUserRepository, InMemoryWebhookRepository,
+    PostgresApiKeyRepository, PostgresAuditLogRepository, PostgresCredentialRepository,
+    PostgresEmailVerificationRepository, PostgresIdempotencyRepository, PostgresOutboxRepository,
+    PostgresPasswordResetRepository, PostgresSessionRepository, PostgresUserRepository, PostgresWebhookRepository,
+};
+use sqlx::PgPool;
+
+user_repository_conformance!(in_memory, async { Some(InMemoryUserRepository::new()) });
+
+credential_repository_conformance!(in_memory_credentials, async {
+    let users = InMemoryUserRepository::new();
+    Some((users.clone(), InMemoryCredentialRepository::new(users)))
+});
+
+api_key_repository_conformance!(in_memory_api_keys, async {
+    let users = InMemoryUserRepository::new();
+    Some((users.clone(), InMemoryApiKeyRepository::new(users)))
+});
+
+session_repository_conformance!(in_memory_sessions, async {
+    let users = InMemoryUserRepository::new();
+    Some((users.clone(), InMemorySessionRepository::new(users)))
+});
+
+email_verification_repository_conformance!(in_memory_email_verifications, async {
+    let users = InMemoryUserRepository::new();
+    Some((users.clone(), InMemoryEmailVerificationRepository::new(users)))
+});
+
+password_reset_repository_conformance!(in_memory_password_resets, async {
+    let users = InMemoryUserRepository::new();
+    Some((users.clone(), InMemoryPasswordResetRepository::new(users)))
+});
+
+idempotency_repository_conformance!(in_memory_idempotency, async { Some(InMemoryIdempotencyRepository::new()) });
+
+audit_log_repository_conformance!(in_memory_audit_log, async {
+    let users = InMemoryUserRepository::new();
+    Some((users.clone(), InMemoryAuditLogRepository::new(users)))
+});
+
+outbox_repository_conformance!(in_memory_outbox, async {
+    let users = InMemoryUserRepository::new();
+    Some((users.clone(), InMemoryOutboxRepository::new(users)))
+});
+
+webhook_repository_conformance!(in_memory_webhooks, async { Some(InMemoryWebhookRepository::new()) });
+
+user_repository_conformance!(postgres, async { postgres_pool().await.map(PostgresUserRepository::new) });
+
+credential_repository_conformance!(postgres_credentials, async {
+    postgres_pool()
+        .await
+        .map(|pool| (PostgresUserRepository::new(pool.clone()), PostgresCredentialRepository::new(pool)))
+});
+
+api_key_repository_conformance!(postgres_api_keys, async {
+    postgres_pool()
+        .await
+        .map(|pool| (PostgresUserRepository::new(pool.clone()), PostgresApiKeyRepository::new(pool)))
+});
+
+session_repository_conformance!(postgres_sessions, async {
+    postgres_pool()
+        .await
+        .map(|pool| (PostgresUserRepository::new(pool.clone()), PostgresSessionRepository::new(pool)))
+});
+
+email_verification_repository_conformance!(postgres_email_verifications, async {
+    postgres_pool()
+        .await
+        .map(|pool| (PostgresUserRepository::new(pool.clone()), PostgresEmailVerificationRepository::new(pool)))
+});
+
+password_reset_repository_conformance!(postgres_password_resets, async {
+    postgres_pool()
+        .await
+        .map(|pool| (PostgresUserRepository::new(pool.clone()), PostgresPasswordResetRepository::new(pool)))
+});
+
+idempotency_repository_conformance!(postgres_idempotency, async {
+    postgres_pool().await.map(PostgresIdempotencyRepository::new)
+});
+
+audit_log_repository_conformance!(postgres_audit_log, async {
+    postgres_pool()
+        .await
+        .map(|pool| (PostgresUserRepository::new(pool.clone()), PostgresAuditLogRepository::new(pool)))
+});
+
+outbox_repository_conformance!(postgres_outbox, async {
+    postgres_pool()
+        .await
+        .map(|pool| (PostgresUserRepository::new(pool.clone()), PostgresOutboxRepository::new(pool)))
+});
+
+webhook_repository_conformance!(postgres_webhooks, async {
+    postgres_pool().await.map(PostgresWebhookRepository::new)
+});
+
+async fn postgres_pool() -> Option<PgPool> {
+    use sqlx::{Executor, postgres::PgPoolOptions};
+
+    let Ok(url) = std::env::var("TEST_DATABASE_URL") else {
+        assert!(
+            std::env::var_os("CI").is_none(),
+            "TEST_DATABASE_URL must be set in CI to run the Postgres conformance tests"
+        );
+        return None;
+    };
+    let schema = format!("conformance_{}", uuid::Uuid::new_v4().simple());
+
+    // The uuid extension is database-wide; install it once in `public` so every
+    // per-test schema can resolve it through its search_path
+    let setup = PgPoolOptions::new().max_connections(1).connect(&url).await.unwrap();
+    setup
+        .execute(format!(r#"CREATE EXTENSION IF NOT EXISTS "uuid-ossp"; CREATE SCHEMA {schema}"#).as_str())
+        .await
+        .unwrap();
+    setup.close().await;
+
+    let search_path = format!("SET search_path TO {schema}, public");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .after_connect(move |conn, _| {
+            let search_path = search_path.clone();
+            Box::pin(async move {
+                conn.execute(search_path.as_str()).await?;
+                Ok(())
+            })
+        })
+        .connect(&url)
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+    Some(pool)
+}
+
+#[cfg(feature = "sqlite")]
+user_repository_conformance!(sqlite, async {
+    sqlite_pool().await.map(rust_nexus::infrastructure::SqliteUserRepository::new)
+});
+
+#[cfg(feature = "sqlite")]
+credential_repository_conformance!(sqlite_credentials, async {
+    use rust_nexus::infrastructure::{SqliteCredentialRepository, SqliteUserRepository};
+
+    sqlite_pool()
+        .await
+        .map(|pool| (SqliteUserRepository::new(pool.clone()), SqliteCredentialRepository::new(pool)))
+});
+
+#[cfg(feature = "sqlite")]
+api_key_repository_conformance!(sqlite_api_keys, async {
+    use rust_nexus::infrastructure::{SqliteApiKeyRepository, SqliteUserRepository};
+
+    sqlite_pool()
+        .await
+        .map(|pool| (SqliteUserRepository::new(pool.clone()), SqliteApiKeyRepository::new(pool)))
+});
+
+#[cfg(feature = "sqlite")]
+session_repository_conformance!(sqlite_sessions, async {
+    use rust_nexus::infrastructure::{SqliteSessionRepository, SqliteUserRepository};
+
+    sqlite_pool()
+        .await
+        .map(|pool| (SqliteUserRepository::new(pool.clone()), SqliteSessionRepository::new(pool)))
+});
+
+#[cfg(feature = "sqlite")]
+email_verification_repository_conformance!(sqlite_email_verifications, async {
+    use rust_nexus::infrastructure::{SqliteEmailVerificationRepository, SqliteUserRepository};
+
+    sqlite_pool()
+        .await
+        .map(|pool| (SqliteUserRepository::new(pool.clone()), SqliteEmailVerificationRepository::new(pool)))
+});
+
+#[cfg(feature = "sqlite")]
+password_reset_repository_conformance!(sqlite_password_resets, async {
+    use rust_nexus::infrastructure::{SqlitePasswordResetRepository, SqliteUserRepository};
+
+    sqlite_pool()
+        .await
+        .map(|pool| (SqliteUserRepository::new(pool.clone()), SqlitePasswordResetRepository::new(pool)))
+});
+
+#[cfg(feature = "sqlite")]
+idempotency_repository_conformance!(sqlite_idempotency, async {
+    sqlite_pool().await.map(rust_nexus::infrastructure::SqliteIdempotencyRepository::new)
+});
+
+#[cfg(feature = "sqlite")]
+audit_log_repository_conformance!(sqlite_audit_log, async {
+    use rust_nexus::infrastructure::{SqliteAuditLogRepository, SqliteUserRepository};
+
+    sqlite_pool()
+        .await
+        .map(|pool| (SqliteUserRepository::new(pool.clone()), SqliteAuditLogRepository::new(pool)))
+});
+
+#[cfg(feature = "sqlite")]
+outbox_repository_conformance!(sqlite_outbox, async {
+    use rust_nexus::infrastructure::{SqliteOutboxRepository, SqliteUserRepository};
+
+    sqlite_pool()
+        .await
+        .map(|pool| (SqliteUserRepository::new(pool.clone()), SqliteOutboxRepository::new(pool)))
+});
+
+#[cfg(feature = "sqlite")]
+webhook_repository_conformance!(sqlite_webhooks, async {
+    sqlite_pool().await.map(rust_nexus::infrastructure::SqliteWebhookRepository::new)
+});
+
+#[cfg(feature = "sqlite")]
+async fn sqlite_pool() -> Option<sqlx::SqlitePool> {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    // Every connection to `sqlite::memory:` is a separate database, so keep one
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations/sqlite").run(&pool).await.unwrap();
+
+    Some(pool)
+}